@@ -1,6 +1,10 @@
 use std::fmt::{self, Write};
 
-use crate::Snes;
+use crate::{Snes, ppu::PpuVariant};
+
+use dsp::Dsp;
+
+pub mod dsp;
 
 #[rustfmt::skip]
 static BOOT_ROM: [u8; 64] = [
@@ -14,6 +18,46 @@ static BOOT_ROM: [u8; 64] = [
     /* FFF8 */ 0x5D, 0xD0, 0xDB, 0x1F, 0x00, 0x00, 0xC0, 0xFF,
 ];
 
+/// Frequency of the APU's own oscillator in Hz, independent of the master clock. The SPC700 runs
+/// at a 24th of it, the timers and the DSP are derived from it as well.
+pub const CLOCK_HZ: u64 = 24_576_000;
+
+/// Converts a number of master clock cycles into cycles of the APU's oscillator.
+pub fn master_to_apu_cycles(master_cycles: u64, variant: PpuVariant) -> u64 {
+    // Master clock frequency in Hz as a fraction, so that no rounding errors accumulate
+    let (numerator, denominator): (u128, u128) = match variant {
+        PpuVariant::Ntsc => (236_250_000, 11),
+        PpuVariant::Pal => (21_281_370, 1),
+    };
+    (u128::from(master_cycles) * u128::from(CLOCK_HZ) * denominator / numerator) as u64
+}
+
+/// Number of oscillator cycles per SPC700 cycle.
+const CLOCK_DIVIDER: u64 = 24;
+
+/// Number of SPC700 cycles taken by every instruction, without the two extra cycles of a taken
+/// branch.
+#[rustfmt::skip]
+static CYCLES: [u8; 256] = [
+    /*       0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F */
+    /* 0 */  2, 8, 4, 5, 3, 4, 3, 6, 2, 6, 5, 4, 5, 4, 6, 8,
+    /* 1 */  2, 8, 4, 5, 4, 5, 5, 6, 5, 5, 6, 5, 2, 2, 4, 6,
+    /* 2 */  2, 8, 4, 5, 3, 4, 3, 6, 2, 6, 5, 4, 5, 4, 5, 2,
+    /* 3 */  2, 8, 4, 5, 4, 5, 5, 6, 5, 5, 6, 5, 2, 2, 3, 8,
+    /* 4 */  2, 8, 4, 5, 3, 4, 3, 6, 2, 6, 4, 4, 5, 4, 6, 6,
+    /* 5 */  2, 8, 4, 5, 4, 5, 5, 6, 5, 5, 4, 5, 2, 2, 4, 3,
+    /* 6 */  2, 8, 4, 5, 3, 4, 3, 6, 2, 6, 4, 4, 5, 4, 5, 5,
+    /* 7 */  2, 8, 4, 5, 4, 5, 5, 6, 5, 5, 5, 5, 2, 2, 3, 6,
+    /* 8 */  2, 8, 4, 5, 3, 4, 3, 6, 2, 6, 5, 4, 5, 2, 4, 5,
+    /* 9 */  2, 8, 4, 5, 4, 5, 5, 6, 5, 5, 5, 5, 2, 2,12, 5,
+    /* A */  3, 8, 4, 5, 3, 4, 3, 6, 2, 6, 4, 4, 5, 2, 4, 4,
+    /* B */  2, 8, 4, 5, 4, 5, 5, 6, 5, 5, 5, 5, 2, 2, 3, 4,
+    /* C */  3, 8, 4, 5, 4, 5, 4, 7, 2, 5, 6, 4, 5, 2, 4, 9,
+    /* D */  2, 8, 4, 5, 5, 6, 6, 7, 4, 5, 5, 5, 2, 2, 6, 3,
+    /* E */  2, 8, 4, 5, 3, 4, 3, 6, 2, 4, 5, 3, 4, 3, 4, 3,
+    /* F */  2, 8, 4, 5, 4, 5, 5, 6, 3, 4, 5, 4, 2, 2, 4, 3,
+];
+
 pub struct Apu {
     pub cpuio_in: [u8; 4],
     pub cpuio_out: [u8; 4],
@@ -21,6 +65,8 @@ pub struct Apu {
     pub ram: Box<[u8; 0x10000]>,
     reset: bool,
     pub timers: [Timer; 3],
+    pub dsp: Dsp,
+    dsp_addr: u8,
 
     pub a: u8,
     pub x: u8,
@@ -31,7 +77,14 @@ pub struct Apu {
     cycles: u64,
     cycles_8khz_clock: u64,
     cycles_64khz_clock: u64,
+    cycles_32khz_clock: u64,
     stopped: bool,
+    unimplemented_feature: Option<&'static str>,
+    samples: Vec<[i16; 2]>,
+    /// Debug option: Keep the APU from running alongside the main CPU, so that the CPU can be
+    /// stepped on its own. The APU can still be stepped with [`step`], and it catches up on the
+    /// time it missed once the option is cleared.
+    pub paused: bool,
 }
 
 impl Default for Apu {
@@ -43,6 +96,8 @@ impl Default for Apu {
             ram: Box::new([0; 0x10000]),
             reset: false,
             timers: [Timer::default(); 3],
+            dsp: Dsp::default(),
+            dsp_addr: 0,
 
             a: 0,
             x: 0,
@@ -53,7 +108,11 @@ impl Default for Apu {
             cycles: 0,
             cycles_8khz_clock: 0,
             cycles_64khz_clock: 0,
+            cycles_32khz_clock: 0,
             stopped: false,
+            unimplemented_feature: None,
+            samples: Vec::new(),
+            paused: false,
         }
     }
 }
@@ -71,7 +130,7 @@ pub struct Psw {
 }
 
 impl Psw {
-    fn set_from_bits(&mut self, bits: u8) {
+    pub fn set_from_bits(&mut self, bits: u8) {
         self.c = bits & 0x01 != 0;
         self.z = bits & 0x02 != 0;
         self.i = bits & 0x04 != 0;
@@ -83,7 +142,7 @@ impl Psw {
     }
 
     #[allow(clippy::identity_op)]
-    fn to_bits(&self) -> u8 {
+    pub fn to_bits(&self) -> u8 {
         (self.c as u8) << 0
             | (self.z as u8) << 1
             | (self.i as u8) << 2
@@ -210,12 +269,33 @@ impl Target for AddressingMode {
 #[derive(Default, Clone, Copy)]
 pub struct Timer {
     pub enabled: bool,
+    /// Number of clock ticks per increment of `out`, 0 counts as 256
     pub divider: u8,
+    /// 4 bit counter, cleared when read by the SPC700
     pub out: u8,
     counter: u8,
 }
 
+impl Timer {
+    /// Advances the timer by one tick of its 8 or 64 kHz clock. Stopped timers keep their output.
+    fn tick(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        self.counter = self.counter.wrapping_add(1);
+        if self.counter == self.divider {
+            self.counter = 0;
+            self.out = (self.out + 1) & 0x0F;
+        }
+    }
+}
+
 impl Apu {
+    /// Native output sample rate of the S-DSP in Hz.
+    pub const SAMPLE_RATE: u32 = 32000;
+
+    /// Reads one of the four APU ports. `addr` must already be masked to 0x2140..=0x2143. The ports
+    /// are always driven, so this never returns open bus.
     pub fn cpu_read_pure(&self, addr: u16) -> Option<u8> {
         Some(self.cpuio_out[usize::from(addr - 0x2140)])
     }
@@ -234,6 +314,8 @@ impl Apu {
 
     pub fn read_pure(&self, addr: u16) -> u8 {
         match addr {
+            0x00F2 => self.dsp_addr,
+            0x00F3 => self.dsp.read(self.dsp_addr),
             0x00F4 => self.cpuio_in[0],
             0x00F5 => self.cpuio_in[1],
             0x00F6 => self.cpuio_in[2],
@@ -249,6 +331,8 @@ impl Apu {
     fn read(&mut self, addr: u16) -> u8 {
         self.run_timers();
         match addr {
+            0x00F2 => self.dsp_addr,
+            0x00F3 => self.dsp.read(self.dsp_addr),
             0x00F4 => self.cpuio_in[0],
             0x00F5 => self.cpuio_in[1],
             0x00F6 => self.cpuio_in[2],
@@ -277,18 +361,17 @@ impl Apu {
         self.run_timers();
         self.ram[usize::from(addr)] = value;
         match addr {
-            0x00F0 => todo!(),
+            // Games write the power-on value 0x0A, which we can safely ignore
+            0x00F0 if value != 0x0A => self.unimplemented_feature = Some("APU TEST register"),
             0x00F1 => {
                 for (i, timer) in self.timers.iter_mut().enumerate() {
                     let enabled = (value >> i) & 0x01 != 0;
-                    if timer.enabled == enabled {
-                        continue;
-                    }
-                    timer.enabled = enabled;
-                    if !enabled {
+                    // Only starting a timer resets it, stopping it keeps the output
+                    if enabled && !timer.enabled {
                         timer.out = 0;
                         timer.counter = 0;
                     }
+                    timer.enabled = enabled;
                 }
                 if value & 0x10 != 0 {
                     self.cpuio_in[0..=1].fill(0);
@@ -298,6 +381,8 @@ impl Apu {
                 }
                 self.rom_enable = value & 0x80 != 0;
             }
+            0x00F2 => self.dsp_addr = value,
+            0x00F3 => self.dsp.write(self.dsp_addr, value),
             0x00F4 => self.cpuio_out[0] = value,
             0x00F5 => self.cpuio_out[1] = value,
             0x00F6 => self.cpuio_out[2] = value,
@@ -318,6 +403,18 @@ impl Apu {
         self.a = value as u8;
     }
 
+    /// Number of elapsed cycles of the APU's oscillator, see [`CLOCK_HZ`] and
+    /// [`master_to_apu_cycles`].
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Removes and returns all stereo samples which were output since the last call, at a rate of
+    /// [`Apu::SAMPLE_RATE`].
+    pub fn drain_samples(&mut self) -> std::vec::Drain<'_, [i16; 2]> {
+        self.samples.drain(..)
+    }
+
     fn next_instr_byte(&mut self) -> u8 {
         let pc = self.pc;
         self.pc = self.pc.wrapping_add(1);
@@ -340,7 +437,7 @@ impl Apu {
             AddressingMode::DirectX => Pointer::new8(self.psw.p as u8, self.x),
             AddressingMode::DirectXInc => {
                 let pointer = self.read_pointer(AddressingMode::DirectX);
-                self.x += 1;
+                self.x = self.x.wrapping_add(1);
                 pointer
             }
             AddressingMode::DirectY => Pointer::new8(self.psw.p as u8, self.y),
@@ -359,7 +456,8 @@ impl Apu {
                 let pointer = self.read_pointer(AddressingMode::Absolute8);
                 let ll = self.read(pointer.at(0)) as u16;
                 let hh = self.read(pointer.at(1)) as u16;
-                // TODO: Should this add wrap at the byte boundary?
+                // Only the pointer wraps within the direct page, the indexed address wraps at 16
+                // bits
                 Pointer::new16((hh << 8 | ll).wrapping_add(self.y as u16))
             }
             AddressingMode::IndirectX => {
@@ -431,6 +529,8 @@ impl Apu {
         self.read(0x0100 | self.sp as u16)
     }
 
+    /// Loads into A, X and Y (including `MOV X, SP`) update N and Z, while stores to memory,
+    /// `MOV SP, X` and `MOV dp, dp` leave the flags untouched.
     fn inst_mov(&mut self, dst: impl Target, src: impl Target, update_flags: bool) {
         let src_op = src.resolve(self);
         let dst_op = dst.resolve(self);
@@ -465,14 +565,14 @@ impl Apu {
 
     fn inst_movw_with_dummy_read(
         &mut self,
-
         dst: impl Target,
         src: impl Target,
         update_flags: bool,
     ) {
         let src_op = src.resolve(self);
         let dst_op = dst.resolve(self);
-        self.get_operand_u16(src_op);
+        // Only the low byte of the destination is read before the store
+        self.get_operand_u8(dst_op);
         self.inst_movw(dst_op, src_op, update_flags);
     }
 
@@ -845,10 +945,16 @@ impl Apu {
         self.psw.z = value == self.a;
     }
 
+    /// Jumps to the relative offset `rr`, which takes two additional cycles.
+    fn branch(&mut self, rr: i8) {
+        self.pc = self.pc.wrapping_add_signed(rr as i16);
+        self.cycles += 2 * CLOCK_DIVIDER;
+    }
+
     fn inst_bra(&mut self, branch: bool) {
         let rr = self.next_instr_byte() as i8;
         if branch {
-            self.pc = self.pc.wrapping_add_signed(rr as i16);
+            self.branch(rr);
         }
     }
 
@@ -857,7 +963,7 @@ impl Apu {
         let rr = self.next_instr_byte() as i8;
         let value = self.get_operand_u8(op);
         if value & (1 << bit) != 0 {
-            self.pc = self.pc.wrapping_add_signed(rr as i16);
+            self.branch(rr);
         }
     }
 
@@ -866,7 +972,7 @@ impl Apu {
         let rr = self.next_instr_byte() as i8;
         let value = self.get_operand_u8(op);
         if value & (1 << bit) == 0 {
-            self.pc = self.pc.wrapping_add_signed(rr as i16);
+            self.branch(rr);
         }
     }
 
@@ -875,7 +981,7 @@ impl Apu {
         let rr = self.next_instr_byte() as i8;
         let value = self.get_operand_u8(op);
         if value != self.a {
-            self.pc = self.pc.wrapping_add_signed(rr as i16);
+            self.branch(rr);
         }
     }
 
@@ -885,7 +991,7 @@ impl Apu {
         let value = self.get_operand_u8(op).wrapping_sub(1);
         self.set_operand_u8(op, value);
         if value != 0 {
-            self.pc = self.pc.wrapping_add_signed(rr as i16);
+            self.branch(rr);
         }
     }
 
@@ -948,42 +1054,37 @@ impl Apu {
         self.psw.i = false;
     }
 
+    /// Advances the timers and the DSP up to the current cycle.
     fn run_timers(&mut self) {
         while self.cycles_8khz_clock < self.cycles {
             self.cycles_8khz_clock += 3072;
 
-            for timer in &mut self.timers[0..=1] {
-                timer.counter = timer.counter.wrapping_add(1);
-                if timer.counter == timer.divider {
-                    timer.counter = 0;
-                    timer.out = (timer.out + 1) & 0x0F;
-                }
-            }
+            self.timers[0].tick();
+            self.timers[1].tick();
         }
 
         while self.cycles_64khz_clock < self.cycles {
             self.cycles_64khz_clock += 384;
+            self.timers[2].tick();
+        }
 
-            let timer = &mut self.timers[2];
-            timer.counter = timer.counter.wrapping_add(1);
-            if timer.counter == timer.divider {
-                timer.counter = 0;
-                timer.out = (timer.out + 1) & 0x0F;
-            }
+        while self.cycles_32khz_clock < self.cycles {
+            self.cycles_32khz_clock += 768;
+            let sample = self.dsp.sample(&mut self.ram);
+            self.samples.push(sample);
         }
     }
 
 
     #[rustfmt::skip]
     fn step(&mut self) {
-
-        self.cycles += 24;
-
         if self.stopped {
+            self.cycles += CLOCK_DIVIDER;
             return;
         }
 
         let opcode = self.next_instr_byte();
+        self.cycles += u64::from(CYCLES[usize::from(opcode)]) * CLOCK_DIVIDER;
 
         match opcode {
             0xE8 => self.inst_mov(Operand::A, AddressingMode::Immediate, true),
@@ -1275,7 +1376,7 @@ impl Apu {
     }
 }
 
-pub fn catch_up(emu: &mut Snes) {
+fn process_reset(emu: &mut Snes) {
     if emu.apu.reset {
         emu.apu.rom_enable = true;
         emu.apu.cpuio_in.fill(0);
@@ -1286,15 +1387,44 @@ pub fn catch_up(emu: &mut Snes) {
         emu.apu.cycles = 0;
         emu.apu.cycles_8khz_clock = 0;
         emu.apu.cycles_64khz_clock = 0;
+        emu.apu.cycles_32khz_clock = 0;
+        emu.apu.dsp.reset();
         emu.apu.reset = false;
     }
+}
+
+fn report_unimplemented(emu: &mut Snes) {
+    if let Some(feature) = emu.apu.unimplemented_feature.take() {
+        emu.report_unimplemented(feature);
+    }
+}
+
+pub fn catch_up(emu: &mut Snes) {
+    process_reset(emu);
+    if emu.apu.paused {
+        return;
+    }
+
+    let target = master_to_apu_cycles(emu.cpu.cycles(), emu.ppu.variant);
+    while emu.apu.cycles < target {
+        emu.apu.step();
+    }
+    emu.apu.run_timers();
+
+    report_unimplemented(emu);
+}
+
+/// Executes `count` instructions without advancing the main CPU. The APU gets ahead of the CPU
+/// this way, so it simply waits in [`catch_up`] until the CPU has caught up again.
+pub fn step(emu: &mut Snes, count: u32) {
+    process_reset(emu);
 
-    // TODO: The APU has a separate clock which runs a little faster, but this should suffice for
-    // now
-    while emu.apu.cycles < emu.cpu.cycles() {
+    for _ in 0..count {
         emu.apu.step();
     }
     emu.apu.run_timers();
+
+    report_unimplemented(emu);
 }
 
 pub mod disasm {
@@ -0,0 +1,560 @@
+//! The S-DSP, which generates the audio output of the APU.
+//!
+//! It mixes 8 voices playing BRR compressed samples from APU RAM into a stereo stream at 32 kHz.
+//! Emulation happens at sample granularity, but the order of operations within a sample follows
+//! the hardware, so effects like pitch modulation, KON/KOFF polling and echo feedback behave the
+//! same way.
+
+// Global registers
+const MVOLL: usize = 0x0C;
+const EVOLL: usize = 0x2C;
+const KON: usize = 0x4C;
+const KOFF: usize = 0x5C;
+const FLG: usize = 0x6C;
+const ENDX: usize = 0x7C;
+const EFB: usize = 0x0D;
+const PMON: usize = 0x2D;
+const NON: usize = 0x3D;
+const EON: usize = 0x4D;
+const DIR: usize = 0x5D;
+const ESA: usize = 0x6D;
+const EDL: usize = 0x7D;
+/// The echo FIR filter coefficients are at `FIR + i * 0x10`.
+const FIR: usize = 0x0F;
+
+// Voice registers, relative to `voice * 0x10`
+const VOLL: usize = 0x00;
+const PITCHL: usize = 0x02;
+const PITCHH: usize = 0x03;
+const SRCN: usize = 0x04;
+const ADSR1: usize = 0x05;
+const ADSR2: usize = 0x06;
+const GAIN: usize = 0x07;
+const ENVX: usize = 0x08;
+const OUTX: usize = 0x09;
+
+const BRR_BLOCK_SIZE: u16 = 9;
+
+/// The global counter counts down from this value, it is divisible by all of [`COUNTER_RATES`].
+const COUNTER_RANGE: u16 = 2048 * 5 * 3;
+
+/// Number of samples between two envelope or noise updates for each rate.
+#[rustfmt::skip]
+static COUNTER_RATES: [u16; 32] = [
+    COUNTER_RANGE + 1, // never
+          2048, 1536,
+    1280, 1024,  768,
+     640,  512,  384,
+     320,  256,  192,
+     160,  128,   96,
+      80,   64,   48,
+      40,   32,   24,
+      20,   16,   12,
+      10,    8,    6,
+       5,    4,    3,
+             2,
+             1,
+];
+
+#[rustfmt::skip]
+static COUNTER_OFFSETS: [u16; 32] = [
+      1, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+    536, 0, 1040,
+         0,
+         0,
+];
+
+/// Coefficients of the gaussian interpolation.
+#[rustfmt::skip]
+static GAUSS: [i16; 512] = [
+    0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+    1,   1,   1,   1,   1,   1,   1,   1,   1,   1,   1,   2,   2,   2,   2,   2,
+    2,   2,   3,   3,   3,   3,   3,   4,   4,   4,   4,   4,   5,   5,   5,   5,
+    6,   6,   6,   6,   7,   7,   7,   8,   8,   8,   9,   9,   9,  10,  10,  10,
+    11,  11,  11,  12,  12,  13,  13,  14,  14,  15,  15,  15,  16,  16,  17,  17,
+    18,  19,  19,  20,  20,  21,  21,  22,  23,  23,  24,  24,  25,  26,  27,  27,
+    28,  29,  29,  30,  31,  32,  32,  33,  34,  35,  36,  36,  37,  38,  39,  40,
+    41,  42,  43,  44,  45,  46,  47,  48,  49,  50,  51,  52,  53,  54,  55,  56,
+    58,  59,  60,  61,  62,  64,  65,  66,  67,  69,  70,  71,  73,  74,  76,  77,
+    78,  80,  81,  83,  84,  86,  87,  89,  90,  92,  94,  95,  97,  99, 100, 102,
+    104, 106, 107, 109, 111, 113, 115, 117, 118, 120, 122, 124, 126, 128, 130, 132,
+    134, 137, 139, 141, 143, 145, 147, 150, 152, 154, 156, 159, 161, 163, 166, 168,
+    171, 173, 175, 178, 180, 183, 186, 188, 191, 193, 196, 199, 201, 204, 207, 210,
+    212, 215, 218, 221, 224, 227, 230, 233, 236, 239, 242, 245, 248, 251, 254, 257,
+    260, 263, 267, 270, 273, 276, 280, 283, 286, 290, 293, 297, 300, 304, 307, 311,
+    314, 318, 321, 325, 328, 332, 336, 339, 343, 347, 351, 354, 358, 362, 366, 370,
+    374, 378, 381, 385, 389, 393, 397, 401, 405, 410, 414, 418, 422, 426, 430, 434,
+    439, 443, 447, 451, 456, 460, 464, 469, 473, 477, 482, 486, 491, 495, 499, 504,
+    508, 513, 517, 522, 527, 531, 536, 540, 545, 550, 554, 559, 563, 568, 573, 577,
+    582, 587, 592, 596, 601, 606, 611, 615, 620, 625, 630, 635, 640, 644, 649, 654,
+    659, 664, 669, 674, 678, 683, 688, 693, 698, 703, 708, 713, 718, 723, 728, 732,
+    737, 742, 747, 752, 757, 762, 767, 772, 777, 782, 787, 792, 797, 802, 806, 811,
+    816, 821, 826, 831, 836, 841, 846, 851, 855, 860, 865, 870, 875, 880, 884, 889,
+    894, 899, 904, 908, 913, 918, 923, 927, 932, 937, 941, 946, 951, 955, 960, 965,
+    969, 974, 978, 983, 988, 992, 997,1001,1005,1010,1014,1019,1023,1027,1032,1036,
+    1040,1045,1049,1053,1057,1061,1066,1070,1074,1078,1082,1086,1090,1094,1098,1102,
+    1106,1109,1113,1117,1121,1125,1128,1132,1136,1139,1143,1146,1150,1153,1157,1160,
+    1164,1167,1170,1174,1177,1180,1183,1186,1190,1193,1196,1199,1202,1205,1207,1210,
+    1213,1216,1219,1221,1224,1227,1229,1232,1234,1237,1239,1241,1244,1246,1248,1251,
+    1253,1255,1257,1259,1261,1263,1265,1267,1269,1270,1272,1274,1275,1277,1279,1280,
+    1282,1283,1284,1286,1287,1288,1290,1291,1292,1293,1294,1295,1296,1297,1297,1298,
+    1299,1300,1300,1301,1302,1302,1303,1303,1303,1304,1304,1304,1304,1304,1305,1305,
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum EnvelopeMode {
+    #[default]
+    Release,
+    Attack,
+    Decay,
+    Sustain,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Voice {
+    /// Ring buffer of the last 12 decoded BRR samples.
+    buf: [i32; 12],
+    buf_pos: usize,
+    /// 4.12 fixed point position of the oldest interpolated sample, relative to `buf_pos`.
+    interp_pos: i32,
+    brr_addr: u16,
+    /// Offset of the next two bytes to decode within the current BRR block.
+    brr_offset: u16,
+    /// Number of samples until a voice which was keyed on starts playing.
+    kon_delay: u8,
+    env_mode: EnvelopeMode,
+    env: i32,
+    /// Envelope value before clamping and rate limiting, the bent line GAIN mode depends on it.
+    hidden_env: i32,
+}
+
+pub struct Dsp {
+    regs: [u8; 128],
+    voices: [Voice; 8],
+    /// Global counter which decides when envelopes and the noise generator are updated.
+    counter: u16,
+    noise: u16,
+    /// KON and KOFF are only polled every other sample.
+    every_other_sample: bool,
+    /// Voices which were keyed on since the last poll.
+    new_kon: u8,
+    echo_offset: u16,
+    echo_length: u16,
+    /// The last 8 samples read from the echo buffer, used as input of the FIR filter.
+    echo_history: [[i32; 2]; 8],
+    echo_history_pos: usize,
+}
+
+impl Default for Dsp {
+    fn default() -> Self {
+        let mut dsp = Self {
+            regs: [0; 128],
+            voices: [Voice::default(); 8],
+            counter: 0,
+            noise: 0,
+            every_other_sample: false,
+            new_kon: 0,
+            echo_offset: 0,
+            echo_length: 0,
+            echo_history: [[0; 2]; 8],
+            echo_history_pos: 0,
+        };
+        dsp.reset();
+        dsp
+    }
+}
+
+impl Dsp {
+    /// Reads a DSP register. 0x80..=0xFF mirror 0x00..=0x7F.
+    ///
+    /// All registers read back the last written value, except for ENVX, OUTX and ENDX. These are
+    /// updated by the DSP on every sample, so a written value is only visible until the next one.
+    pub fn read(&self, addr: u8) -> u8 {
+        self.regs[usize::from(addr & 0x7F)]
+    }
+
+    /// Writes a DSP register. Writes to 0x80..=0xFF are ignored.
+    pub fn write(&mut self, addr: u8, value: u8) {
+        let addr = usize::from(addr);
+        if addr >= 0x80 {
+            return;
+        }
+
+        self.regs[addr] = value;
+        match addr {
+            KON => self.new_kon = value,
+            // Writing any value clears all bits
+            ENDX => self.regs[ENDX] = 0,
+            _ => (),
+        }
+    }
+
+    /// Silences all voices and disables the echo, like after power-on.
+    pub fn reset(&mut self) {
+        // Soft reset, mute and echo write disable
+        self.regs[FLG] = 0xE0;
+        for voice in &mut self.voices {
+            voice.env_mode = EnvelopeMode::Release;
+            voice.env = 0;
+            voice.kon_delay = 0;
+        }
+        self.counter = 0;
+        self.noise = 0x4000;
+        self.every_other_sample = false;
+        self.new_kon = 0;
+        self.echo_offset = 0;
+    }
+
+    /// Generates the next stereo sample. Voices read their samples from, and the echo buffer
+    /// lives in, `ram`.
+    pub fn sample(&mut self, ram: &mut [u8; 0x10000]) -> [i16; 2] {
+        self.counter = self.counter.checked_sub(1).unwrap_or(COUNTER_RANGE - 1);
+
+        let flg = self.regs[FLG];
+        if rate_tick(self.counter, flg & 0x1F) {
+            let feedback = self.noise << 13 ^ self.noise << 14;
+            self.noise = feedback & 0x4000 ^ self.noise >> 1;
+        }
+
+        self.every_other_sample = !self.every_other_sample;
+        let (kon, koff) = if self.every_other_sample {
+            (std::mem::take(&mut self.new_kon), self.regs[KOFF])
+        } else {
+            (0, 0)
+        };
+
+        // Voice 0 can't be modulated, because there is no previous voice
+        let pmon = self.regs[PMON] & !0x01;
+        let non = self.regs[NON];
+        let eon = self.regs[EON];
+        let dir = u16::from(self.regs[DIR]) << 8;
+        let mut endx = self.regs[ENDX];
+
+        let mut main_out = [0; 2];
+        let mut echo_out = [0; 2];
+        let mut output = 0;
+
+        for (i, voice) in self.voices.iter_mut().enumerate() {
+            let bit = 1 << i;
+            let regs = &mut self.regs[i << 4..(i + 1) << 4];
+
+            let mut pitch = i32::from(u16::from_le_bytes([regs[PITCHL], regs[PITCHH]]) & 0x3FFF);
+            if pmon & bit != 0 {
+                // `output` still belongs to the previous voice
+                pitch += ((output >> 5) * pitch) >> 10;
+            }
+
+            let dir_entry = dir.wrapping_add(u16::from(regs[SRCN]) * 4);
+            let mut header = ram[usize::from(voice.brr_addr)];
+
+            if voice.kon_delay > 0 {
+                if voice.kon_delay == 5 {
+                    voice.brr_addr = read_u16(ram, dir_entry);
+                    voice.brr_offset = 1;
+                    voice.buf_pos = 0;
+                    header = 0;
+                }
+                voice.env = 0;
+                voice.hidden_env = 0;
+                voice.kon_delay -= 1;
+                // Decodes the first 12 samples during the delay
+                voice.interp_pos = if voice.kon_delay & 3 != 0 { 0x4000 } else { 0 };
+                pitch = 0;
+            }
+
+            let sample = if non & bit != 0 {
+                i32::from((self.noise << 1) as i16)
+            } else {
+                voice.interpolate()
+            };
+            output = (sample * voice.env) >> 11 & !1;
+            regs[ENVX] = (voice.env >> 4) as u8;
+            regs[OUTX] = (output >> 8) as u8;
+
+            // Voices are silenced immediately at the end of a sample which doesn't loop
+            if flg & 0x80 != 0 || header & 0x03 == 0x01 {
+                voice.env_mode = EnvelopeMode::Release;
+                voice.env = 0;
+            }
+
+            if koff & bit != 0 {
+                voice.env_mode = EnvelopeMode::Release;
+            }
+            if kon & bit != 0 {
+                voice.kon_delay = 5;
+                voice.env_mode = EnvelopeMode::Attack;
+            }
+
+            if voice.kon_delay == 0 {
+                voice.run_envelope(regs[ADSR1], regs[ADSR2], regs[GAIN], self.counter);
+            }
+
+            for ch in 0..2 {
+                let amp = (output * i32::from(regs[VOLL + ch] as i8)) >> 7;
+                main_out[ch] = clamp16(main_out[ch] + amp);
+                if eon & bit != 0 {
+                    echo_out[ch] = clamp16(echo_out[ch] + amp);
+                }
+            }
+
+            // Decode the next 4 samples once the oldest ones aren't needed anymore
+            if voice.interp_pos >= 0x4000 {
+                voice.decode_brr(ram);
+                voice.brr_offset += 2;
+                if voice.brr_offset >= BRR_BLOCK_SIZE {
+                    voice.brr_addr = voice.brr_addr.wrapping_add(BRR_BLOCK_SIZE);
+                    if header & 0x01 != 0 {
+                        voice.brr_addr = read_u16(ram, dir_entry.wrapping_add(2));
+                        endx |= bit;
+                    }
+                    voice.brr_offset = 1;
+                }
+            }
+            voice.interp_pos = ((voice.interp_pos & 0x3FFF) + pitch).min(0x7FFF);
+
+            if voice.kon_delay == 5 {
+                endx &= !bit;
+            }
+        }
+
+        self.regs[ENDX] = endx;
+
+        self.echo_history_pos = (self.echo_history_pos + 1) % 8;
+        let echo_addr = (u16::from(self.regs[ESA]) << 8).wrapping_add(self.echo_offset);
+        let mut echo_in = [0; 2];
+        for (ch, echo_in) in echo_in.iter_mut().enumerate() {
+            let value = read_u16(ram, echo_addr.wrapping_add(ch as u16 * 2)) as i16;
+            self.echo_history[self.echo_history_pos][ch] = i32::from(value) >> 1;
+
+            // The first coefficient applies to the oldest sample
+            let tap = |i: usize| {
+                let sample = self.echo_history[(self.echo_history_pos + 1 + i) % 8][ch];
+                (sample * i32::from(self.regs[FIR + i * 0x10] as i8)) >> 6
+            };
+            let sum = (0..7).map(tap).sum::<i32>();
+            *echo_in = clamp16(i32::from(sum as i16) + tap(7)) & !1;
+        }
+
+        let mut out = [0; 2];
+        for ch in 0..2 {
+            let main = (main_out[ch] * i32::from(self.regs[MVOLL + ch * 0x10] as i8)) >> 7;
+            let echo = (echo_in[ch] * i32::from(self.regs[EVOLL + ch * 0x10] as i8)) >> 7;
+            out[ch] = clamp16(main + echo) as i16;
+
+            let feedback = (echo_in[ch] * i32::from(self.regs[EFB] as i8)) >> 7;
+            echo_out[ch] = clamp16(echo_out[ch] + i32::from(feedback as i16)) & !1;
+        }
+
+        if flg & 0x20 == 0 {
+            for (ch, value) in echo_out.into_iter().enumerate() {
+                let [low, high] = (value as i16).to_le_bytes();
+                let addr = echo_addr.wrapping_add(ch as u16 * 2);
+                ram[usize::from(addr)] = low;
+                ram[usize::from(addr.wrapping_add(1))] = high;
+            }
+        }
+
+        // The buffer size is only updated when the end of the current buffer is reached
+        if self.echo_offset == 0 {
+            self.echo_length = u16::from(self.regs[EDL] & 0x0F) * 0x800;
+        }
+        self.echo_offset += 4;
+        if self.echo_offset >= self.echo_length {
+            self.echo_offset = 0;
+        }
+
+        if flg & 0x40 != 0 { [0; 2] } else { out }
+    }
+}
+
+impl Voice {
+    /// Decodes the next 4 samples of the current BRR block into the sample buffer.
+    fn decode_brr(&mut self, ram: &[u8; 0x10000]) {
+        let header = ram[usize::from(self.brr_addr)];
+        let shift = header >> 4;
+        let filter = header >> 2 & 0x03;
+        let nibbles = read_u16(ram, self.brr_addr.wrapping_add(self.brr_offset)).swap_bytes();
+
+        for i in 0..4 {
+            let nibble = i32::from((nibbles << (i * 4)) as i16 >> 12);
+            let mut s = match shift {
+                0..=12 => (nibble << shift) >> 1,
+                // Invalid shift values
+                _ if nibble < 0 => -2048,
+                _ => 0,
+            };
+
+            let p1 = self.buf[(self.buf_pos + 11) % 12];
+            let p2 = self.buf[(self.buf_pos + 10) % 12] >> 1;
+            s += match filter {
+                0 => 0,
+                // p1 * 15/16
+                1 => (p1 >> 1) + (-p1 >> 5),
+                // p1 * 61/32 - p2 * 15/16
+                2 => p1 - p2 + (p2 >> 4) + ((p1 * -3) >> 6),
+                // p1 * 115/64 - p2 * 13/16
+                _ => p1 - p2 + ((p1 * -13) >> 7) + ((p2 * 3) >> 4),
+            };
+
+            self.buf[self.buf_pos] = i32::from((clamp16(s) * 2) as i16);
+            self.buf_pos = (self.buf_pos + 1) % 12;
+        }
+    }
+
+    fn interpolate(&self) -> i32 {
+        let offset = (self.interp_pos >> 4 & 0xFF) as usize;
+        let sample =
+            |i: usize| self.buf[(self.buf_pos + (self.interp_pos >> 12) as usize + i) % 12];
+
+        let mut out = (i32::from(GAUSS[255 - offset]) * sample(0)) >> 11;
+        out += (i32::from(GAUSS[511 - offset]) * sample(1)) >> 11;
+        out += (i32::from(GAUSS[256 + offset]) * sample(2)) >> 11;
+        out = i32::from(out as i16);
+        out += (i32::from(GAUSS[offset]) * sample(3)) >> 11;
+        clamp16(out) & !1
+    }
+
+    fn run_envelope(&mut self, adsr1: u8, adsr2: u8, gain: u8, counter: u16) {
+        let mut env = self.env;
+        if self.env_mode == EnvelopeMode::Release {
+            self.env = (env - 8).max(0);
+            return;
+        }
+
+        let rate;
+        let env_data;
+        if adsr1 & 0x80 != 0 {
+            env_data = adsr2;
+            if self.env_mode == EnvelopeMode::Attack {
+                rate = (adsr1 & 0x0F) * 2 + 1;
+                env += if rate < 31 { 0x20 } else { 0x400 };
+            } else {
+                env -= 1;
+                env -= env >> 8;
+                rate = match self.env_mode {
+                    EnvelopeMode::Decay => (adsr1 >> 3 & 0x0E) + 0x10,
+                    _ => adsr2 & 0x1F,
+                };
+            }
+        } else {
+            env_data = gain;
+            if gain & 0x80 == 0 {
+                // Direct
+                env = i32::from(gain) * 0x10;
+                rate = 31;
+            } else {
+                rate = gain & 0x1F;
+                match gain >> 5 & 0x03 {
+                    // Linear decrease
+                    0 => env -= 0x20,
+                    // Exponential decrease
+                    1 => {
+                        env -= 1;
+                        env -= env >> 8;
+                    }
+                    // Linear increase
+                    2 => env += 0x20,
+                    // Bent line increase
+                    _ => {
+                        env += if self.hidden_env as u32 >= 0x600 {
+                            0x08
+                        } else {
+                            0x20
+                        }
+                    }
+                }
+            }
+        }
+
+        // Sustain level
+        if self.env_mode == EnvelopeMode::Decay && env >> 8 == i32::from(env_data >> 5) {
+            self.env_mode = EnvelopeMode::Sustain;
+        }
+
+        self.hidden_env = env;
+
+        if !(0..=0x7FF).contains(&env) {
+            env = env.clamp(0, 0x7FF);
+            if self.env_mode == EnvelopeMode::Attack {
+                self.env_mode = EnvelopeMode::Decay;
+            }
+        }
+
+        if rate_tick(counter, rate) {
+            self.env = env;
+        }
+    }
+}
+
+/// Returns whether an event with the given rate (0..=31) happens during the current sample.
+fn rate_tick(counter: u16, rate: u8) -> bool {
+    let rate = usize::from(rate);
+    (counter + COUNTER_OFFSETS[rate]).is_multiple_of(COUNTER_RATES[rate])
+}
+
+fn read_u16(ram: &[u8; 0x10000], addr: u16) -> u16 {
+    u16::from_le_bytes([
+        ram[usize::from(addr)],
+        ram[usize::from(addr.wrapping_add(1))],
+    ])
+}
+
+fn clamp16(value: i32) -> i32 {
+    value.clamp(i16::MIN.into(), i16::MAX.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Voice;
+
+    /// Decodes the 16 samples of the block at `addr`, continuing from the history in `voice`.
+    fn decode_block(voice: &mut Voice, ram: &[u8; 0x10000], addr: u16) -> [i32; 16] {
+        voice.brr_addr = addr;
+        let mut samples = [0; 16];
+        for (i, chunk) in samples.chunks_exact_mut(4).enumerate() {
+            voice.brr_offset = 1 + i as u16 * 2;
+            voice.decode_brr(ram);
+            for (j, sample) in chunk.iter_mut().enumerate() {
+                *sample = voice.buf[(voice.buf_pos + 8 + j) % 12];
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn decode_brr_blocks() {
+        let mut ram = Box::new([0; 0x10000]);
+        // Shift 12 without filter: every nibble from 0 to 7 and -8 to -1
+        ram[0x1000..0x1009]
+            .copy_from_slice(&[0xC0, 0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
+        // Shift 0 with filter 1: silence decays from the last sample by 15/16 per sample
+        ram[0x1009] = 0x04;
+        // Shift 13 is invalid: negative nibbles decode to -2048, positive ones to 0
+        ram[0x1012..0x101B].copy_from_slice(&[0xD0, 0x7F, 0x7F, 0, 0, 0, 0, 0, 0]);
+
+        // Samples are stored doubled, i.e. as 16 bit values with 15 bits of precision
+        let mut voice = Voice::default();
+        let expected: [i32; 16] = std::array::from_fn(|i| (i as i32 ^ 8) - 8).map(|n| n * 4096);
+        assert_eq!(decode_block(&mut voice, &ram, 0x1000), expected);
+
+        let samples = decode_block(&mut voice, &ram, 0x1009);
+        assert_eq!(samples[..3], [-3840, -3600, -3376]);
+        assert!(
+            samples
+                .windows(2)
+                .all(|pair| pair[0] < pair[1] && pair[1] < 0)
+        );
+
+        let samples = decode_block(&mut voice, &ram, 0x1012);
+        assert_eq!(samples[..4], [0, -4096, 0, -4096]);
+        assert_eq!(samples[4..], [0; 12]);
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::{Cursor, Read};
+
+use zip::{ZipArchive, result::ZipResult};
+
+const ROM_EXTENSIONS: &[&str] = &["sfc", "smc"];
+
+pub fn is_zip(data: &[u8]) -> bool {
+    data.starts_with(b"PK\x03\x04")
+}
+
+/// Returns the names of all files in the archive which look like SNES ROMs.
+pub fn list_roms(data: &[u8]) -> ZipResult<Vec<String>> {
+    let archive = ZipArchive::new(Cursor::new(data))?;
+
+    let roms = archive
+        .file_names()
+        .filter(|name| {
+            name.rsplit_once('.').is_some_and(|(_, ext)| {
+                ROM_EXTENSIONS
+                    .iter()
+                    .any(|rom_ext| ext.eq_ignore_ascii_case(rom_ext))
+            })
+        })
+        .map(str::to_owned)
+        .collect();
+
+    Ok(roms)
+}
+
+pub fn extract(data: &[u8], name: &str) -> ZipResult<Box<[u8]>> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut file = archive.by_name(name)?;
+
+    let mut rom = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut rom)?;
+    Ok(rom.into_boxed_slice())
+}
@@ -0,0 +1,77 @@
+//! Conversion of the audio output of the APU to the sample rate of the host. Only sample buffers
+//! leave the emulator, playing them is up to the frontend.
+
+/// Linear resampler for interleaved 16 bit stereo samples.
+///
+/// Positions are tracked exactly: one input sample period is `output_rate` units long and one
+/// output sample period `input_rate` units, so no error accumulates over time.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    input_rate: u32,
+    output_rate: u32,
+    /// Position of the next output sample after `prev`, in the units described above.
+    phase: u32,
+    prev: [i16; 2],
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        assert!(
+            input_rate > 0 && output_rate > 0,
+            "sample rates must not be zero"
+        );
+        Self {
+            input_rate,
+            output_rate,
+            phase: 0,
+            prev: [0; 2],
+        }
+    }
+
+    pub fn input_rate(&self) -> u32 {
+        self.input_rate
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Changes the output rate without losing the position within the input, so that the rate can
+    /// be adjusted continuously without clicks.
+    pub fn set_output_rate(&mut self, output_rate: u32) {
+        assert!(output_rate > 0, "sample rates must not be zero");
+        let phase = u64::from(self.phase) * u64::from(output_rate) / u64::from(self.output_rate);
+        self.phase = phase as u32;
+        self.output_rate = output_rate;
+    }
+
+    /// Resamples `samples` and appends the result to `out`, left channel first. Output samples
+    /// are interpolated between two input samples, so they lag behind by one input sample.
+    pub fn process(&mut self, samples: impl IntoIterator<Item = [i16; 2]>, out: &mut Vec<i16>) {
+        let period = i64::from(self.output_rate);
+        for next in samples {
+            while self.phase < self.output_rate {
+                let phase = i64::from(self.phase);
+                for (prev, next) in self.prev.into_iter().zip(next) {
+                    let (prev, next) = (i64::from(prev), i64::from(next));
+                    out.push((prev + (next - prev) * phase / period) as i16);
+                }
+                self.phase += self.input_rate;
+            }
+            self.phase -= self.output_rate;
+            self.prev = next;
+        }
+    }
+}
+
+/// Hashes interleaved samples with 64 bit FNV-1a, the audio counterpart to
+/// [`Screenshot::hash`](crate::screenshot::Screenshot::hash). The result is stable across
+/// platforms and Rust versions, so it can be stored in regression tests.
+pub fn hash_samples(samples: &[i16]) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for byte in samples.iter().flat_map(|sample| sample.to_le_bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+    hash
+}
@@ -0,0 +1,120 @@
+//! Plays the audio output of the emulator on the default output device of the host.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use cpal::{
+    FromSample, Sample, SampleFormat, SizedSample,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+
+/// Samples beyond this amount are dropped, so the latency can't build up if emulation runs ahead
+/// of the output device.
+const MAX_LATENCY_MS: u32 = 100;
+
+pub struct AudioOutput {
+    /// Playback stops when the stream is dropped.
+    _stream: cpal::Stream,
+    sample_rate: u32,
+    /// Interleaved stereo samples which have not been played yet
+    queue: Arc<Mutex<VecDeque<i16>>>,
+}
+
+impl AudioOutput {
+    /// Starts playback on the default output device. Returns `None` if there is no usable device.
+    pub fn open() -> Option<Self> {
+        let Some(device) = cpal::default_host().default_output_device() else {
+            tracing::warn!("No audio output device found");
+            return None;
+        };
+        let supported_config = match device.default_output_config() {
+            Ok(config) => config,
+            Err(err) => {
+                tracing::error!("Failed to get the audio output configuration: {err}");
+                return None;
+            }
+        };
+
+        let config = supported_config.config();
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let result = match supported_config.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, Arc::clone(&queue)),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, Arc::clone(&queue)),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, Arc::clone(&queue)),
+            format => {
+                tracing::error!("Unsupported audio sample format {format:?}");
+                return None;
+            }
+        };
+        let stream = match result {
+            Ok(stream) => stream,
+            Err(err) => {
+                tracing::error!("Failed to open the audio output stream: {err}");
+                return None;
+            }
+        };
+        if let Err(err) = stream.play() {
+            tracing::error!("Failed to start audio playback: {err}");
+            return None;
+        }
+
+        Some(Self {
+            _stream: stream,
+            sample_rate: config.sample_rate.0,
+            queue,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Queues interleaved stereo samples for playback.
+    pub fn push_samples(&self, samples: &[i16]) {
+        let max_len = (self.sample_rate * MAX_LATENCY_MS / 1000) as usize * 2;
+        let mut queue = self.queue.lock().unwrap();
+        queue.extend(samples);
+        if queue.len() > max_len {
+            let excess = queue.len() - max_len;
+            queue.drain(..excess);
+        }
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: Arc<Mutex<VecDeque<i16>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<i16>,
+{
+    let channels = usize::from(config.channels);
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut queue = queue.lock().unwrap();
+            for frame in data.chunks_exact_mut(channels) {
+                // Plays silence when the queue runs dry
+                let left = queue.pop_front().unwrap_or(0);
+                let right = queue.pop_front().unwrap_or(0);
+                match frame {
+                    [mono] => {
+                        let mixed = (i32::from(left) + i32::from(right)) / 2;
+                        *mono = T::from_sample(mixed as i16);
+                    }
+                    [out_left, out_right, rest @ ..] => {
+                        *out_left = T::from_sample(left);
+                        *out_right = T::from_sample(right);
+                        rest.fill(T::EQUILIBRIUM);
+                    }
+                    [] => (),
+                }
+            }
+        },
+        |err| tracing::error!("Audio output error: {err}"),
+        None,
+    )
+}
@@ -0,0 +1,219 @@
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use snes_emu::{
+    Snes,
+    input::{
+        InputDevice, Joypad, JoypadState, Mouse, MouseState, Multitap, SuperScope, SuperScopeState,
+    },
+};
+
+#[derive(Default)]
+pub struct Input {
+    pub start: bool,
+    pub select: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub a: bool,
+    pub b: bool,
+    pub x: bool,
+    pub y: bool,
+    pub l: bool,
+    pub r: bool,
+    /// The pixel below the mouse pointer, if it is inside the game view.
+    pub aim: Option<(u16, u16)>,
+    /// Mouse movement in points which hasn't been reported to the emulated mouse yet.
+    pub mouse_motion: (f32, f32),
+    pub pointer_primary: bool,
+    pub pointer_secondary: bool,
+    pub turbo: bool,
+    pub pause: bool,
+}
+
+/// The device connected to one of the two controller ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControllerType {
+    None,
+    Joypad,
+    Mouse,
+    Multitap,
+    SuperScope,
+}
+
+impl ControllerType {
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::Joypad,
+        Self::Mouse,
+        Self::Multitap,
+        Self::SuperScope,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Joypad => "Standard Controller",
+            Self::Mouse => "Mouse",
+            Self::Multitap => "Multitap",
+            Self::SuperScope => "Super Scope",
+        }
+    }
+
+    /// Creates the device, which reads its state from the keyboard and mouse input.
+    pub fn create_device(self, current_input: &Arc<RwLock<Input>>) -> Option<Box<dyn InputDevice>> {
+        match self {
+            Self::None => None,
+            Self::Joypad => Some(create_joypad(current_input)),
+            Self::Mouse => {
+                let current_input = Arc::clone(current_input);
+                Some(Box::new(Mouse::new(move || {
+                    let mut current_input = current_input.write().unwrap();
+                    // The mouse reports the movement since it was last read
+                    let (dx, dy) = std::mem::take(&mut current_input.mouse_motion);
+                    MouseState {
+                        dx: dx.round() as i32,
+                        dy: dy.round() as i32,
+                        button_left: current_input.pointer_primary,
+                        button_right: current_input.pointer_secondary,
+                    }
+                })))
+            }
+            // Only the first controller of the multitap is mapped to the keyboard
+            Self::Multitap => Some(Box::new(Multitap::new([
+                Some(create_joypad(current_input)),
+                None,
+                None,
+                None,
+            ]))),
+            Self::SuperScope => {
+                let current_input = Arc::clone(current_input);
+                Some(Box::new(SuperScope::new(move || {
+                    let current_input = current_input.read().unwrap();
+                    // Aiming outside of the game view points the gun away from the screen
+                    let (x, y) = current_input.aim.unwrap_or((u16::MAX, u16::MAX));
+                    SuperScopeState {
+                        x,
+                        y,
+                        trigger: current_input.pointer_primary,
+                        cursor: current_input.pointer_secondary,
+                        turbo: current_input.turbo,
+                        pause: current_input.pause,
+                    }
+                })))
+            }
+        }
+    }
+}
+
+fn create_joypad(current_input: &Arc<RwLock<Input>>) -> Box<dyn InputDevice> {
+    let current_input = Arc::clone(current_input);
+    Box::new(Joypad::new(move || {
+        let current_input = current_input.read().unwrap();
+        JoypadState {
+            button_b: current_input.b,
+            button_y: current_input.y,
+            button_select: current_input.select,
+            button_start: current_input.start,
+            dpad_up: current_input.up,
+            dpad_down: current_input.down,
+            dpad_left: current_input.left,
+            dpad_right: current_input.right,
+            button_a: current_input.a,
+            button_x: current_input.x,
+            button_l: current_input.l,
+            button_r: current_input.r,
+        }
+    }))
+}
+
+pub fn connect_controllers(
+    snes: &mut Snes,
+    controller_types: [ControllerType; 2],
+    current_input: &Arc<RwLock<Input>>,
+) {
+    snes.set_input1(controller_types[0].create_device(current_input));
+    snes.set_input2(controller_types[1].create_device(current_input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Latches the device and returns the first 32 bits of data line 1, in the order they are read.
+    fn read_bits(device: &mut dyn InputDevice) -> u32 {
+        device.strobe();
+        device.end_strobe();
+        (0..32).fold(0, |bits, i| bits | (device.read_data1() as u32) << i)
+    }
+
+    fn create(controller_type: ControllerType, input: Input) -> Option<Box<dyn InputDevice>> {
+        controller_type.create_device(&Arc::new(RwLock::new(input)))
+    }
+
+    #[test]
+    fn none_has_no_device() {
+        assert!(create(ControllerType::None, Input::default()).is_none());
+    }
+
+    #[test]
+    fn joypad_reads_keyboard() {
+        let input = Input {
+            b: true,
+            r: true,
+            ..Input::default()
+        };
+        let mut device = create(ControllerType::Joypad, input).unwrap();
+        // Buttons in bits 0-11, signature 0000 in bits 12-15, then only ones
+        assert_eq!(read_bits(device.as_mut()), 0xFFFF_0801);
+    }
+
+    #[test]
+    fn mouse_reports_motion_once() {
+        let input = Arc::new(RwLock::new(Input {
+            mouse_motion: (-3.0, 200.0),
+            pointer_primary: true,
+            ..Input::default()
+        }));
+        let mut device = ControllerType::Mouse.create_device(&input).unwrap();
+
+        let bits = read_bits(device.as_mut());
+        assert_eq!(bits & 0xFFFF, 0x8200, "left button and mouse signature");
+        // Y is clamped to 127 downwards, X is 3 to the left, both sent MSB first
+        assert_eq!((bits >> 16) as u8, 0x7Fu8.reverse_bits());
+        assert_eq!((bits >> 24) as u8, 0x83u8.reverse_bits());
+
+        // The motion was consumed by the first read
+        assert_eq!(input.read().unwrap().mouse_motion, (0.0, 0.0));
+        assert_eq!(read_bits(device.as_mut()) >> 16, 0);
+    }
+
+    #[test]
+    fn multitap_connects_keyboard_to_first_port() {
+        let input = Input {
+            b: true,
+            ..Input::default()
+        };
+        let mut device = create(ControllerType::Multitap, input).unwrap();
+
+        device.strobe();
+        assert!(device.read_data2(), "multitap signature while latched");
+        device.end_strobe();
+        device.set_pin6(true);
+        assert!(device.read_data1(), "B on the first controller");
+        assert!(!device.read_data2(), "nothing on the second controller");
+    }
+
+    #[test]
+    fn super_scope_aims_offscreen_without_pointer() {
+        let input = Input {
+            pointer_primary: true,
+            ..Input::default()
+        };
+        let mut device = create(ControllerType::SuperScope, input).unwrap();
+        // Trigger in bit 0, offscreen in bit 6 and the signature in the high byte
+        assert_eq!(read_bits(device.as_mut()) & 0xFFFF, 0xFF41);
+        assert_eq!(device.aim_position(), None);
+    }
+}
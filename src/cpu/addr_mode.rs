@@ -2,7 +2,7 @@ use crate::Snes;
 
 use super::{
     Pointer,
-    memory::{next_instr_byte, read},
+    memory::{idle_cycle, next_instr_byte, read},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -51,15 +51,44 @@ fn read_absolute(emu: &mut Snes) -> Pointer {
     Pointer::new24(dbr << 16 | addr_hh << 8 | addr_ll)
 }
 
+/// Adding an index takes an extra cycle if the index registers are 16 bits wide or if it crosses a
+/// page. Writes always take that cycle, see [`indexed_write_penalty`].
+fn index_penalty(emu: &mut Snes, pointer: Pointer, index: u16) {
+    if !emu.cpu.regs.p.x || (pointer.low as u8) < index as u8 {
+        idle_cycle(emu);
+    }
+}
+
+/// Store and read-modify-write instructions using `a,X`, `a,Y` or `(d),Y` always spend the extra
+/// indexing cycle, even if the index registers are 8 bits wide and no page is crossed.
+pub fn indexed_write_penalty(emu: &mut Snes, mode: AddressingMode, pointer: Pointer) {
+    let index = match mode {
+        AddressingMode::AbsoluteX => emu.cpu.regs.x.get(),
+        AddressingMode::AbsoluteY | AddressingMode::DirectYParens => emu.cpu.regs.y.get(),
+        _ => return,
+    };
+    if emu.cpu.regs.p.x && (pointer.low as u8) >= index as u8 {
+        idle_cycle(emu);
+    }
+}
+
 // FIXME: Is this (and the other functions where X and Y are used) affected by the x flag?
 fn read_absolute_x(emu: &mut Snes) -> Pointer {
-    read_absolute(emu).with_offset(emu.cpu.regs.x.get())
+    let x = emu.cpu.regs.x.get();
+    let pointer = read_absolute(emu).with_offset(x);
+    index_penalty(emu, pointer, x);
+    pointer
 }
 
 fn read_absolute_y(emu: &mut Snes) -> Pointer {
-    read_absolute(emu).with_offset(emu.cpu.regs.y.get())
+    let y = emu.cpu.regs.y.get();
+    let pointer = read_absolute(emu).with_offset(y);
+    index_penalty(emu, pointer, y);
+    pointer
 }
 
+/// `(a)`: The pointer is always read from bank 0 and wraps around within it, so `JMP ($FFFF)` reads
+/// its high byte from $00:0000. Unlike on the 6502, there is no wrap within the page.
 fn read_absolute_indirect_jmp(emu: &mut Snes) -> Pointer {
     let pointer_ll = next_instr_byte(emu) as u16;
     let pointer_hh = next_instr_byte(emu) as u16;
@@ -72,6 +101,8 @@ fn read_absolute_indirect_jmp(emu: &mut Snes) -> Pointer {
     Pointer::new16(emu.cpu.regs.k, data_hh << 8 | data_ll)
 }
 
+/// `[a]`: Like [`read_absolute_indirect_jmp`], all three bytes of the pointer are read from bank 0,
+/// so `JMP [$FFFE]` reads them from $00:FFFE, $00:FFFF and $00:0000.
 fn read_absolute_indirect_long_jmp(emu: &mut Snes) -> Pointer {
     let pointer_ll = next_instr_byte(emu) as u16;
     let pointer_hh = next_instr_byte(emu) as u16;
@@ -91,6 +122,7 @@ fn read_absolute_x_indirect_jmp(emu: &mut Snes) -> Pointer {
     let pointer_hh = next_instr_byte(emu) as u16;
     let x = emu.cpu.regs.x.get();
     let k = emu.cpu.regs.k as u32;
+    idle_cycle(emu);
 
     let partial_pointer = (pointer_hh << 8 | pointer_ll).wrapping_add(x);
     let pointer_lo = k << 16 | partial_pointer as u32;
@@ -101,8 +133,16 @@ fn read_absolute_x_indirect_jmp(emu: &mut Snes) -> Pointer {
     Pointer::new16(emu.cpu.regs.k, data_hi << 8 | data_lo)
 }
 
+/// Adding a non-zero DL to the direct page offset takes one extra cycle.
+fn direct_page_penalty(emu: &mut Snes) {
+    if emu.cpu.regs.d.getl() != 0 {
+        idle_cycle(emu);
+    }
+}
+
 fn read_direct_old(emu: &mut Snes) -> Pointer {
     let ll = next_instr_byte(emu);
+    direct_page_penalty(emu);
 
     if emu.cpu.regs.d.getl() == 0 && emu.cpu.regs.p.e {
         let dh = emu.cpu.regs.d.geth();
@@ -115,12 +155,15 @@ fn read_direct_old(emu: &mut Snes) -> Pointer {
 
 fn read_direct_new(emu: &mut Snes) -> Pointer {
     let ll = next_instr_byte(emu);
+    direct_page_penalty(emu);
     let d = emu.cpu.regs.d.get();
     Pointer::new16(0, d.wrapping_add(ll as u16))
 }
 
 fn read_direct_x(emu: &mut Snes) -> Pointer {
     let ll = next_instr_byte(emu);
+    direct_page_penalty(emu);
+    idle_cycle(emu);
     if emu.cpu.regs.d.getl() == 0 && emu.cpu.regs.p.e {
         let dh = emu.cpu.regs.d.geth();
         let x = emu.cpu.regs.x.getl();
@@ -134,6 +177,8 @@ fn read_direct_x(emu: &mut Snes) -> Pointer {
 
 fn read_direct_y(emu: &mut Snes) -> Pointer {
     let ll = next_instr_byte(emu);
+    direct_page_penalty(emu);
+    idle_cycle(emu);
     if emu.cpu.regs.d.getl() == 0 && emu.cpu.regs.p.e {
         let dh = emu.cpu.regs.d.geth();
         let y = emu.cpu.regs.y.getl();
@@ -145,6 +190,8 @@ fn read_direct_y(emu: &mut Snes) -> Pointer {
     }
 }
 
+/// `(d)`: In emulation mode with DL = 0, the pointer is read with 6502 semantics, i.e. its high byte
+/// wraps around within the direct page. Otherwise it wraps around within bank 0.
 fn read_direct_indirect(emu: &mut Snes) -> Pointer {
     let pointer = read_pointer(emu, AddressingMode::DirectOld);
     let data_lo = read(emu, pointer.low) as u32;
@@ -155,6 +202,7 @@ fn read_direct_indirect(emu: &mut Snes) -> Pointer {
 
 fn read_direct_indirect_long(emu: &mut Snes) -> Pointer {
     let ll = next_instr_byte(emu);
+    direct_page_penalty(emu);
     let addr = emu.cpu.regs.d.get().wrapping_add(ll as u16);
     let data_lo = read(emu, addr as u32) as u32;
     let data_mid = read(emu, addr.wrapping_add(1) as u32) as u32;
@@ -162,17 +210,22 @@ fn read_direct_indirect_long(emu: &mut Snes) -> Pointer {
     Pointer::new24(data_hi << 16 | data_mid << 8 | data_lo)
 }
 
+/// `(d,X)`: Wraps around the same way as [`read_direct_indirect`]. The page wrap only applies in
+/// emulation mode with DL = 0, in native mode the high byte of the pointer may cross into the next
+/// page.
 fn read_direct_x_indirect(emu: &mut Snes) -> Pointer {
-    let target_lo = read_pointer(emu, AddressingMode::DirectX).low;
-    let target_hi = (target_lo & 0xFFFFFF00) | (target_lo as u8).wrapping_add(1) as u32;
-    let data_lo = read(emu, target_lo) as u32;
-    let data_hi = read(emu, target_hi) as u32;
+    let pointer = read_pointer(emu, AddressingMode::DirectX);
+    let data_lo = read(emu, pointer.low) as u32;
+    let data_hi = read(emu, pointer.high) as u32;
     let dbr = emu.cpu.regs.dbr as u32;
     Pointer::new24(dbr << 16 | data_hi << 8 | data_lo)
 }
 
 fn read_direct_y_indirect(emu: &mut Snes) -> Pointer {
-    read_direct_indirect(emu).with_offset(emu.cpu.regs.y.get())
+    let y = emu.cpu.regs.y.get();
+    let pointer = read_direct_indirect(emu).with_offset(y);
+    index_penalty(emu, pointer, y);
+    pointer
 }
 
 fn read_direct_y_indirect_long(emu: &mut Snes) -> Pointer {
@@ -227,14 +280,18 @@ fn read_relative_16(emu: &mut Snes) -> Pointer {
 
 fn read_stack_s(emu: &mut Snes) -> Pointer {
     let ll = next_instr_byte(emu) as u16;
+    idle_cycle(emu);
     let s = emu.cpu.regs.s.get();
     Pointer::new16(0, ll.wrapping_add(s))
 }
 
+/// `(d,S),Y`: The stack relative address and the pointer stored there both wrap around within bank
+/// 0, while adding Y to the final address may cross into the next bank.
 fn read_stack_s_y_indirect(emu: &mut Snes) -> Pointer {
     let pointer = read_pointer(emu, AddressingMode::StackS);
     let data_ll = read(emu, pointer.low) as u32;
     let data_hh = read(emu, pointer.high) as u32;
+    idle_cycle(emu);
     let dbr = emu.cpu.regs.dbr as u32;
     let y = emu.cpu.regs.y.get();
     Pointer::new24(dbr << 16 | data_hh << 8 | data_ll).with_offset(y)
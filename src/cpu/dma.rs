@@ -1,6 +1,6 @@
 use crate::Snes;
 
-use super::memory;
+use super::{AccessKind, memory};
 
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub enum TransferDirection {
@@ -48,7 +48,7 @@ pub struct DMAP {
 }
 
 impl DMAP {
-    fn from_bits(bits: u8) -> Self {
+    pub fn from_bits(bits: u8) -> Self {
         let transfer_direction = match bits >> 7 & 0x01 {
             0 => TransferDirection::AToB,
             1 => TransferDirection::BToA,
@@ -86,7 +86,7 @@ impl DMAP {
         }
     }
 
-    fn to_bits(self) -> u8 {
+    pub fn to_bits(self) -> u8 {
         let transfer_direction = match self.transfer_direction {
             TransferDirection::AToB => 0,
             TransferDirection::BToA => 1,
@@ -235,9 +235,7 @@ pub fn process_mdma(emu: &mut Snes) {
 
     let idx = emu.cpu.mdmaen.trailing_zeros() as usize;
 
-    if emu.cpu.dma.channels[idx].das > 0 {
-        mdma_transfer(emu, idx);
-    }
+    mdma_transfer(emu, idx);
 
     if emu.cpu.dma.channels[idx].das == 0 {
         emu.cpu.mdmaen &= emu.cpu.mdmaen - 1;
@@ -255,7 +253,7 @@ pub fn reload_hdma(emu: &mut Snes) {
         channel.a2a = channel.a1t;
 
         let table_addr = channel.next_address();
-        emu.cpu.cycles += 8;
+        hdma_cycle(emu);
         let ntrl = memory::read_with_cycle_counting(emu, table_addr, false);
 
         let channel = &mut emu.cpu.dma.channels[i];
@@ -264,9 +262,9 @@ pub fn reload_hdma(emu: &mut Snes) {
         if channel.dmap.addressing_mode == super::dma::AddressingMode::IndirectTable {
             let das_addr_l = channel.next_address();
             let das_addr_h = channel.next_address();
-            emu.cpu.cycles += 8;
+            hdma_cycle(emu);
             let dasl = memory::read_with_cycle_counting(emu, das_addr_l, false);
-            emu.cpu.cycles += 8;
+            hdma_cycle(emu);
             let dash = memory::read_with_cycle_counting(emu, das_addr_h, false);
             emu.cpu.dma.channels[i].das = (dash as u16) << 8 | (dasl as u16);
         }
@@ -308,7 +306,7 @@ pub fn process_hdma(emu: &mut Snes) {
 
         if channel.ntrl & 0x7F == 0 {
             let ntrl_addr = channel.next_address();
-            emu.cpu.cycles += 8;
+            hdma_cycle(emu);
             let ntrl = memory::read_with_cycle_counting(emu, ntrl_addr, false);
             let channel = &mut emu.cpu.dma.channels[i];
             channel.ntrl = ntrl;
@@ -316,9 +314,9 @@ pub fn process_hdma(emu: &mut Snes) {
             if channel.dmap.addressing_mode == super::dma::AddressingMode::IndirectTable {
                 let das_addr_l = channel.next_address();
                 let das_addr_h = channel.next_address();
-                emu.cpu.cycles += 8;
+                hdma_cycle(emu);
                 let dasl = memory::read_with_cycle_counting(emu, das_addr_l, false);
-                emu.cpu.cycles += 8;
+                hdma_cycle(emu);
                 let dash = memory::read_with_cycle_counting(emu, das_addr_h, false);
                 emu.cpu.dma.channels[i].das = (dash as u16) << 8 | (dasl as u16);
             }
@@ -334,66 +332,68 @@ pub fn process_hdma(emu: &mut Snes) {
     emu.cpu.dma.paused |= emu.cpu.dma.stopped;
 }
 
-#[derive(Clone, Copy)]
-struct DmaPattern {
-    step: u8,
-    mask: u8,
-    count: u16,
+impl TransferUnitSelect {
+    /// Offsets of the B-Bus registers (relative to BBADx) which are accessed by a single transfer
+    /// unit, in order.
+    fn b_bus_offsets(self) -> &'static [u8] {
+        match self {
+            Self::WO1Bytes1Regs => &[0],
+            Self::WO2Bytes2Regs => &[0, 1],
+            Self::WT2Bytes1Regs | Self::WT2Bytes1RegsAgain => &[0, 0],
+            Self::WT4Bytes2Regs | Self::WT4Bytes2RegsAgain => &[0, 0, 1, 1],
+            Self::WO4Bytes4Regs => &[0, 1, 2, 3],
+            Self::WO4Bytes2Regs => &[0, 1, 0, 1],
+        }
+    }
 }
 
-impl DmaPattern {
-    fn from_transfer_unit_select(tus: TransferUnitSelect) -> Self {
-        let (step, mask, count) = match tus {
-            TransferUnitSelect::WO1Bytes1Regs => (0, 0, 1),
-            TransferUnitSelect::WO2Bytes2Regs => (2, 2, 2),
-            TransferUnitSelect::WT2Bytes1Regs | TransferUnitSelect::WT2Bytes1RegsAgain => (0, 0, 2),
-            TransferUnitSelect::WT4Bytes2Regs | TransferUnitSelect::WT4Bytes2RegsAgain => (1, 3, 4),
-            TransferUnitSelect::WO4Bytes4Regs => (2, 6, 4),
-            TransferUnitSelect::WO4Bytes2Regs => (2, 2, 4),
-        };
+fn mdma_cycle(emu: &mut Snes) {
+    emu.cpu.cycles += 8;
+    emu.cpu.debug.cycle_usage.mdma += 8;
+}
 
-        Self { step, mask, count }
-    }
+fn hdma_cycle(emu: &mut Snes) {
+    emu.cpu.cycles += 8;
+    emu.cpu.debug.cycle_usage.hdma += 8;
 }
 
+/// Transfers a single unit. Stops early if the byte counter reaches zero in the middle of the unit.
 fn mdma_transfer(emu: &mut Snes, channel_idx: usize) {
-    let channel = &emu.cpu.dma.channels[channel_idx];
-    let tus = channel.dmap.transfer_unit_select;
-    let pattern = DmaPattern::from_transfer_unit_select(tus);
+    let tus = emu.cpu.dma.channels[channel_idx].dmap.transfer_unit_select;
 
-    let mut offset = 0;
-    for _ in 0..u16::min(pattern.count, channel.das) {
+    for &offset in tus.b_bus_offsets() {
         let channel = &mut emu.cpu.dma.channels[channel_idx];
 
         let mut src_addr = (channel.a1b as u32) << 16 | (channel.a1t as u32);
-        let mut dst_addr = 0x2100 | ((channel.bbad.wrapping_add(offset >> 1)) as u32);
+        let mut dst_addr = 0x2100 | ((channel.bbad.wrapping_add(offset)) as u32);
 
         if channel.dmap.transfer_direction == super::dma::TransferDirection::BToA {
             std::mem::swap(&mut src_addr, &mut dst_addr);
         }
 
-        offset = (offset + pattern.step) & pattern.mask;
-
         match channel.dmap.a_bus_address_step {
             ABusAddressStep::Increment => channel.a1t = channel.a1t.wrapping_add(1),
             ABusAddressStep::Decrement => channel.a1t = channel.a1t.wrapping_sub(1),
             ABusAddressStep::Fixed1 | ABusAddressStep::Fixed2 => (),
         }
 
-        channel.das -= 1;
+        // A byte count of zero transfers 0x10000 bytes
+        channel.das = channel.das.wrapping_sub(1);
+        let finished = channel.das == 0;
+
+        mdma_cycle(emu);
+        transfer_byte(emu, src_addr, dst_addr);
 
-        emu.cpu.cycles += 8;
-        let byte = memory::read_with_cycle_counting(emu, src_addr, false);
-        memory::write_with_cycle_counting(emu, dst_addr, byte, false);
+        if finished {
+            break;
+        }
     }
 }
 
 fn hdma_transfer(emu: &mut Snes, channel_idx: usize) {
     let tus = emu.cpu.dma.channels[channel_idx].dmap.transfer_unit_select;
-    let pattern = DmaPattern::from_transfer_unit_select(tus);
 
-    let mut offset = 0;
-    for _ in 0..pattern.count {
+    for &offset in tus.b_bus_offsets() {
         let channel = &mut emu.cpu.dma.channels[channel_idx];
 
         let mut src_addr = match channel.dmap.addressing_mode {
@@ -405,16 +405,23 @@ fn hdma_transfer(emu: &mut Snes, channel_idx: usize) {
             }
         };
 
-        let mut dst_addr = 0x2100 | ((channel.bbad.wrapping_add(offset >> 1)) as u32);
+        let mut dst_addr = 0x2100 | ((channel.bbad.wrapping_add(offset)) as u32);
 
         if channel.dmap.transfer_direction == super::dma::TransferDirection::BToA {
             std::mem::swap(&mut src_addr, &mut dst_addr);
         }
 
-        offset = (offset + pattern.step) & pattern.mask;
+        hdma_cycle(emu);
+        transfer_byte(emu, src_addr, dst_addr);
+    }
+}
 
-        emu.cpu.cycles += 8;
-        let byte = memory::read_with_cycle_counting(emu, src_addr, false);
-        memory::write_with_cycle_counting(emu, dst_addr, byte, false);
+/// Copies a byte from `src_addr` to `dst_addr` without counting the cycles of the accesses.
+fn transfer_byte(emu: &mut Snes, src_addr: u32, dst_addr: u32) {
+    let byte = memory::read_with_cycle_counting(emu, src_addr, false);
+    memory::write_with_cycle_counting(emu, dst_addr, byte, false);
+    if emu.cpu.debug.watch_dma {
+        memory::check_watchpoints(emu, src_addr, byte, AccessKind::Read);
+        memory::check_watchpoints(emu, dst_addr, byte, AccessKind::Write);
     }
 }
@@ -2,11 +2,11 @@ use crate::Snes;
 
 use super::{
     Operand,
-    addr_mode::{AddressingMode, read_pointer},
+    addr_mode::{AddressingMode, indexed_write_penalty, read_pointer},
     memory::{
-        get_operand_u8, get_operand_u16, next_instr_byte, pull8new, pull8old, pull16new, pull16old,
-        push8new, push8old, push16new, push16old, read, read_operand, set_operand_u8,
-        set_operand_u16, skip_instr_byte, write,
+        get_operand_u8, get_operand_u16, idle_cycle, next_instr_byte, pull8new, pull8old,
+        pull16new, pull16old, push8new, push8old, push16new, push16old, read, read_operand,
+        set_operand_u8, set_operand_u16, skip_instr_byte, write,
     },
 };
 
@@ -168,6 +168,7 @@ fn inst_cmp(emu: &mut Snes, op1: Operand, addr_mode: AddressingMode) {
 
 fn inst_inc(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if op.is_not_wide(emu.cpu.regs.p) {
         let result = get_operand_u8(emu, op).wrapping_add(1);
         set_operand_u8(emu, op, result);
@@ -183,6 +184,7 @@ fn inst_inc(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_dec(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if op.is_not_wide(emu.cpu.regs.p) {
         let result = get_operand_u8(emu, op).wrapping_sub(1);
         set_operand_u8(emu, op, result);
@@ -241,6 +243,8 @@ fn inst_ora(emu: &mut Snes, addr_mode: AddressingMode) {
     }
 }
 
+// BIT #imm only affects Z, the other addressing modes also copy the operand's top two bits into
+// N and V. In both cases Z is computed over the full accumulator width.
 fn inst_bit(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
     if emu.cpu.regs.p.m {
@@ -264,6 +268,7 @@ fn inst_bit(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_trb(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if emu.cpu.regs.p.m {
         let val = get_operand_u8(emu, op);
         let mask = emu.cpu.regs.a.getl();
@@ -279,6 +284,7 @@ fn inst_trb(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_tsb(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if emu.cpu.regs.p.m {
         let val = get_operand_u8(emu, op);
         let mask = emu.cpu.regs.a.getl();
@@ -294,6 +300,7 @@ fn inst_tsb(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_asl(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if emu.cpu.regs.p.m {
         let val = get_operand_u8(emu, op);
         let result = val << 1;
@@ -313,6 +320,7 @@ fn inst_asl(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_lsr(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if emu.cpu.regs.p.m {
         let val = get_operand_u8(emu, op);
         let result = val >> 1;
@@ -332,6 +340,7 @@ fn inst_lsr(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_rol(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if emu.cpu.regs.p.m {
         let val = get_operand_u8(emu, op);
         let carry = emu.cpu.regs.p.c;
@@ -353,6 +362,7 @@ fn inst_rol(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_ror(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    modify_cycles(emu, addr_mode, op);
     if emu.cpu.regs.p.m {
         let val = get_operand_u8(emu, op);
         let carry = emu.cpu.regs.p.c;
@@ -372,15 +382,22 @@ fn inst_ror(emu: &mut Snes, addr_mode: AddressingMode) {
     }
 }
 
+/// A taken branch takes an extra cycle, and in emulation mode yet another one if it crosses a page.
 fn inst_branch(emu: &mut Snes, condition: bool) {
     let addr = read_pointer(emu, AddressingMode::Relative8).low;
     if condition {
+        idle_cycle(emu);
+        let pc = emu.cpu.regs.pc.get();
+        if emu.cpu.regs.p.e && (pc ^ addr as u16) & 0xFF00 != 0 {
+            idle_cycle(emu);
+        }
         emu.cpu.regs.pc.set(addr as u16);
     }
 }
 
 fn inst_brl(emu: &mut Snes) {
     let addr = read_pointer(emu, AddressingMode::Relative16).low;
+    idle_cycle(emu);
     emu.cpu.regs.k = (addr >> 16) as u8;
     emu.cpu.regs.pc.set(addr as u16);
 }
@@ -393,6 +410,7 @@ fn inst_jmp(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_jsl(emu: &mut Snes) {
     push8new(emu, emu.cpu.regs.k);
+    idle_cycle(emu);
     let ret = emu.cpu.regs.pc.get().wrapping_add(2);
     push16new(emu, ret);
     inst_jmp(emu, AddressingMode::Long);
@@ -401,18 +419,31 @@ fn inst_jsl(emu: &mut Snes) {
 
 fn inst_jsr_old(emu: &mut Snes, addr_mode: AddressingMode) {
     let ret = emu.cpu.regs.pc.get().wrapping_add(1);
+    idle_cycle(emu);
     push16old(emu, ret);
     inst_jmp(emu, addr_mode);
 }
 
-fn inst_jsr_new(emu: &mut Snes, addr_mode: AddressingMode) {
-    let ret = emu.cpu.regs.pc.get().wrapping_add(1);
+/// JSR (a,x) pushes the return address between fetching the low and the high byte of the
+/// pointer, so it can't use [`read_pointer`].
+fn inst_jsr_absolute_x_indirect(emu: &mut Snes) {
+    let pointer_ll = next_instr_byte(emu) as u16;
+    let ret = emu.cpu.regs.pc.get();
     push16new(emu, ret);
-    inst_jmp(emu, addr_mode);
+    let pointer_hh = next_instr_byte(emu) as u16;
+    idle_cycle(emu);
+
+    let k = emu.cpu.regs.k as u32;
+    let partial_pointer = (pointer_hh << 8 | pointer_ll).wrapping_add(emu.cpu.regs.x.get());
+    let data_lo = read(emu, k << 16 | partial_pointer as u32) as u16;
+    let data_hi = read(emu, k << 16 | partial_pointer.wrapping_add(1) as u32) as u16;
+    emu.cpu.regs.pc.set(data_hi << 8 | data_lo);
     stack_modified_new(emu);
 }
 
 fn inst_rtl(emu: &mut Snes) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     let pc = pull16new(emu);
     emu.cpu.regs.pc.set(pc.wrapping_add(1));
     emu.cpu.regs.k = pull8new(emu);
@@ -420,12 +451,17 @@ fn inst_rtl(emu: &mut Snes) {
 }
 
 fn inst_rts(emu: &mut Snes) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     let pc = pull16old(emu);
     emu.cpu.regs.pc.set(pc.wrapping_add(1));
+    idle_cycle(emu);
 }
 
 fn inst_rti(emu: &mut Snes) {
     let is_native = !emu.cpu.regs.p.e;
+    idle_cycle(emu);
+    idle_cycle(emu);
 
     let p = pull8old(emu);
     emu.cpu.regs.p.set_from_bits(p);
@@ -446,6 +482,7 @@ fn inst_rti(emu: &mut Snes) {
 fn inst_rep(emu: &mut Snes) {
     let op = read_operand(emu, AddressingMode::Immediate8);
     let mask = get_operand_u8(emu, op);
+    idle_cycle(emu);
     let value = emu.cpu.regs.p.to_bits();
     emu.cpu.regs.p.set_from_bits(value & !mask);
     flags_updated(emu);
@@ -454,6 +491,7 @@ fn inst_rep(emu: &mut Snes) {
 fn inst_sep(emu: &mut Snes) {
     let op = read_operand(emu, AddressingMode::Immediate8);
     let mask = get_operand_u8(emu, op);
+    idle_cycle(emu);
     let value = emu.cpu.regs.p.to_bits();
     emu.cpu.regs.p.set_from_bits(value | mask);
     flags_updated(emu);
@@ -506,6 +544,9 @@ fn inst_ldy(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_sta(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    if let Operand::Memory(pointer) = op {
+        indexed_write_penalty(emu, addr_mode, pointer);
+    }
     if emu.cpu.regs.p.m {
         set_operand_u8(emu, op, emu.cpu.regs.a.getl());
     } else {
@@ -533,6 +574,9 @@ fn inst_sty(emu: &mut Snes, addr_mode: AddressingMode) {
 
 fn inst_stz(emu: &mut Snes, addr_mode: AddressingMode) {
     let op = read_operand(emu, addr_mode);
+    if let Operand::Memory(pointer) = op {
+        indexed_write_penalty(emu, addr_mode, pointer);
+    }
     if emu.cpu.regs.p.m {
         set_operand_u8(emu, op, 0);
     } else {
@@ -553,6 +597,8 @@ fn inst_mvn_mvp(emu: &mut Snes, step: i16) {
     emu.cpu.regs.dbr = dst_bank;
     let value = read(emu, src);
     write(emu, dst, value);
+    idle_cycle(emu);
+    idle_cycle(emu);
 
     let mut next_x = src_offset.wrapping_add_signed(step);
     let mut next_y = dst_offset.wrapping_add_signed(step);
@@ -588,13 +634,18 @@ fn inst_pei(emu: &mut Snes) {
     stack_modified_new(emu);
 }
 
+/// PER pushes PC + 3 + offset, i.e. the offset is relative to the following instruction. This
+/// falls out of the Relative16 addressing mode, since the PC has already been advanced past both
+/// operand bytes when the offset is added.
 fn inst_per(emu: &mut Snes) {
     let pointer = read_pointer(emu, AddressingMode::Relative16);
+    idle_cycle(emu);
     push16new(emu, pointer.low as u16);
     stack_modified_new(emu);
 }
 
 fn inst_push_reg(emu: &mut Snes, op: Operand) {
+    idle_cycle(emu);
     if op.is_not_wide(emu.cpu.regs.p) {
         let value = get_operand_u8(emu, op);
         push8old(emu, value);
@@ -605,6 +656,8 @@ fn inst_push_reg(emu: &mut Snes, op: Operand) {
 }
 
 fn inst_pull_reg(emu: &mut Snes, op: Operand) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     if op.is_not_wide(emu.cpu.regs.p) {
         let value = pull8old(emu);
         set_operand_u8(emu, op, value);
@@ -619,25 +672,31 @@ fn inst_pull_reg(emu: &mut Snes, op: Operand) {
 }
 
 fn inst_phb(emu: &mut Snes) {
+    idle_cycle(emu);
     push8new(emu, emu.cpu.regs.dbr);
     stack_modified_new(emu);
 }
 
 fn inst_phd(emu: &mut Snes) {
+    idle_cycle(emu);
     push16new(emu, emu.cpu.regs.d.get());
     stack_modified_new(emu);
 }
 
 fn inst_phk(emu: &mut Snes) {
+    idle_cycle(emu);
     push8new(emu, emu.cpu.regs.k);
     stack_modified_new(emu);
 }
 
 fn inst_php(emu: &mut Snes) {
+    idle_cycle(emu);
     push8old(emu, emu.cpu.regs.p.to_bits());
 }
 
 fn inst_plb(emu: &mut Snes) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     let value = pull8new(emu);
     stack_modified_new(emu);
     emu.cpu.regs.dbr = value;
@@ -646,6 +705,8 @@ fn inst_plb(emu: &mut Snes) {
 }
 
 fn inst_pld(emu: &mut Snes) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     let value = pull16new(emu);
     stack_modified_new(emu);
     emu.cpu.regs.d.set(value);
@@ -654,12 +715,15 @@ fn inst_pld(emu: &mut Snes) {
 }
 
 fn inst_plp(emu: &mut Snes) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     let value = pull8old(emu);
     emu.cpu.regs.p.set_from_bits(value);
     flags_updated(emu);
 }
 
 fn inst_transfer(emu: &mut Snes, src: Operand, dst: Operand) {
+    idle_cycle(emu);
     if dst.is_not_wide(emu.cpu.regs.p) {
         let value = get_operand_u8(emu, src);
         set_operand_u8(emu, dst, value);
@@ -674,6 +738,7 @@ fn inst_transfer(emu: &mut Snes, src: Operand, dst: Operand) {
 }
 
 fn inst_tsx(emu: &mut Snes) {
+    idle_cycle(emu);
     if emu.cpu.regs.p.x {
         let value = emu.cpu.regs.s.getl();
         emu.cpu.regs.x.setl(value);
@@ -688,6 +753,7 @@ fn inst_tsx(emu: &mut Snes) {
 }
 
 fn inst_txs(emu: &mut Snes) {
+    idle_cycle(emu);
     if emu.cpu.regs.p.e {
         emu.cpu.regs.s.setl(emu.cpu.regs.x.getl());
     } else {
@@ -696,6 +762,7 @@ fn inst_txs(emu: &mut Snes) {
 }
 
 fn inst_tcd(emu: &mut Snes) {
+    idle_cycle(emu);
     let value = emu.cpu.regs.a.get();
     emu.cpu.regs.d.set(value);
     emu.cpu.regs.p.n = value & 0x8000 != 0;
@@ -703,6 +770,7 @@ fn inst_tcd(emu: &mut Snes) {
 }
 
 fn inst_tcs(emu: &mut Snes) {
+    idle_cycle(emu);
     if emu.cpu.regs.p.e {
         emu.cpu.regs.s.setl(emu.cpu.regs.a.getl());
     } else {
@@ -711,6 +779,7 @@ fn inst_tcs(emu: &mut Snes) {
 }
 
 fn inst_tdc(emu: &mut Snes) {
+    idle_cycle(emu);
     let value = emu.cpu.regs.d.get();
     emu.cpu.regs.a.set(value);
     emu.cpu.regs.p.n = value & 0x8000 != 0;
@@ -718,6 +787,7 @@ fn inst_tdc(emu: &mut Snes) {
 }
 
 fn inst_tsc(emu: &mut Snes) {
+    idle_cycle(emu);
     let value = emu.cpu.regs.s.get();
     emu.cpu.regs.a.set(value);
     emu.cpu.regs.p.n = value & 0x8000 != 0;
@@ -725,6 +795,8 @@ fn inst_tsc(emu: &mut Snes) {
 }
 
 fn inst_xba(emu: &mut Snes) {
+    idle_cycle(emu);
+    idle_cycle(emu);
     let swapped = emu.cpu.regs.a.get().swap_bytes();
     emu.cpu.regs.a.set(swapped);
     emu.cpu.regs.p.n = swapped & 0x0080 != 0;
@@ -732,23 +804,37 @@ fn inst_xba(emu: &mut Snes) {
 }
 
 fn inst_xce(emu: &mut Snes) {
+    idle_cycle(emu);
     std::mem::swap(&mut emu.cpu.regs.p.c, &mut emu.cpu.regs.p.e);
     flags_updated(emu);
 }
 
 fn flags_updated(emu: &mut Snes) {
-    if emu.cpu.regs.p.e {
-        emu.cpu.regs.p.m = true;
-        emu.cpu.regs.p.x = true;
-        emu.cpu.regs.s.seth(0x01);
-    }
+    emu.cpu.regs.flags_updated();
+}
 
-    if emu.cpu.regs.p.x {
-        emu.cpu.regs.x.seth(0x00);
-        emu.cpu.regs.y.seth(0x00);
+/// Read-modify-write instructions spend an internal cycle to modify their operand. For register
+/// operands, [`read_operand`] has already accounted for it.
+fn modify_cycles(emu: &mut Snes, addr_mode: AddressingMode, op: Operand) {
+    if let Operand::Memory(pointer) = op {
+        indexed_write_penalty(emu, addr_mode, pointer);
+        idle_cycle(emu);
     }
 }
 
+/// Implied instructions take a single internal cycle after fetching the opcode.
+fn inst_implied(emu: &mut Snes, op: impl FnOnce(&mut super::Cpu)) {
+    idle_cycle(emu);
+    op(&mut emu.cpu);
+}
+
+/// STP and WAI take one more internal cycle than other implied instructions before the CPU halts.
+fn inst_halt(emu: &mut Snes, op: impl FnOnce(&mut super::Cpu)) {
+    idle_cycle(emu);
+    idle_cycle(emu);
+    op(&mut emu.cpu);
+}
+
 fn stack_modified_new(emu: &mut Snes) {
     if emu.cpu.regs.p.e {
         emu.cpu.regs.s.seth(0x01);
@@ -950,7 +1036,7 @@ pub fn exec_next_inst(emu: &mut Snes) {
         0x22 => inst_jsl(emu),
         // JSR
         0x20 => inst_jsr_old(emu, AddressingMode::AbsoluteJmp),
-        0xFC => inst_jsr_new(emu, AddressingMode::AbsoluteXParensJmp),
+        0xFC => inst_jsr_absolute_x_indirect(emu),
         // RTL
         0x6B => inst_rtl(emu),
         // RTS
@@ -962,19 +1048,19 @@ pub fn exec_next_inst(emu: &mut Snes) {
         // RTI
         0x40 => inst_rti(emu),
         // CLC
-        0x18 => emu.cpu.regs.p.c = false,
+        0x18 => inst_implied(emu, |cpu| cpu.regs.p.c = false),
         // CLD
-        0xD8 => emu.cpu.regs.p.d = false,
+        0xD8 => inst_implied(emu, |cpu| cpu.regs.p.d = false),
         // CLI
-        0x58 => emu.cpu.regs.p.i = false,
+        0x58 => inst_implied(emu, |cpu| cpu.regs.p.i = false),
         // CLV
-        0xB8 => emu.cpu.regs.p.v = false,
+        0xB8 => inst_implied(emu, |cpu| cpu.regs.p.v = false),
         // SEC
-        0x38 => emu.cpu.regs.p.c = true,
+        0x38 => inst_implied(emu, |cpu| cpu.regs.p.c = true),
         // SED
-        0xF8 => emu.cpu.regs.p.d = true,
+        0xF8 => inst_implied(emu, |cpu| cpu.regs.p.d = true),
         // SEI
-        0x78 => emu.cpu.regs.p.i = true,
+        0x78 => inst_implied(emu, |cpu| cpu.regs.p.i = true),
         // REP
         0xC2 => inst_rep(emu),
         // SEP
@@ -1040,7 +1126,7 @@ pub fn exec_next_inst(emu: &mut Snes) {
         // MVP
         0x44 => inst_mvn_mvp(emu, -1),
         // NOP
-        0xEA => (),
+        0xEA => idle_cycle(emu),
         // WDM
         0x42 => skip_instr_byte(emu),
         // PEA
@@ -1076,9 +1162,9 @@ pub fn exec_next_inst(emu: &mut Snes) {
         // PLP
         0x28 => inst_plp(emu),
         // STP
-        0xDB => emu.cpu.stopped = true,
+        0xDB => inst_halt(emu, |cpu| cpu.stopped = true),
         // WAI
-        0xCB => emu.cpu.waiting = true,
+        0xCB => inst_halt(emu, |cpu| cpu.waiting = true),
         // TAX
         0xAA => inst_transfer(emu, Operand::A, Operand::X),
         // TAY
@@ -1,8 +1,10 @@
-use crate::{Snes, apu, ppu};
+use serde::{Deserialize, Serialize};
 
-use super::{Operand, addr_mode, addr_mode::AddressingMode};
+use crate::{Snes, apu, joypad, ppu, srtc};
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+use super::{AccessKind, Operand, addr_mode, addr_mode::AddressingMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MappingMode {
     LoRom,
     HiRom,
@@ -20,6 +22,21 @@ pub enum BusDevice {
     WRamAccess,
     Dma,
     Joypad,
+    Expansion,
+}
+
+/// An access to an address which is not mapped to any device, or a read of a device register which
+/// does not drive the data bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenBusAccess {
+    pub addr: u32,
+    pub write: bool,
+}
+
+fn report_open_bus(emu: &mut Snes, addr: u32, write: bool) {
+    if emu.pause_on_open_bus && emu.open_bus_access.is_none() {
+        emu.open_bus_access = Some(OpenBusAccess { addr, write });
+    }
 }
 
 fn resolve_cartridge_addr(addr: u32, mapping_mode: MappingMode) -> Option<(BusDevice, u32)> {
@@ -43,13 +60,24 @@ fn resolve_cartridge_addr(addr: u32, mapping_mode: MappingMode) -> Option<(BusDe
             if ((addr >> 16) & 0x7F) >= 0x40 || offset >= 0x8000 {
                 Some((BusDevice::Rom, addr & 0x3FFFFF))
             } else if offset >= 0x6000 {
-                let mapped_addr = (offset as u32 - 0x6000) | ((bank as u32) & 0xF) << 14;
+                let mapped_addr = (offset as u32 - 0x6000) | ((bank as u32) & 0xF) << 13;
+                Some((BusDevice::SRam, mapped_addr))
+            } else {
+                None
+            }
+        }
+        // Like HiRom, but banks 0x80-0xFF map the first 4 MiB and banks 0x00-0x7D the upper 4 MiB
+        MappingMode::ExHiRom => {
+            let upper_half = if bank & 0x80 == 0 { 0x400000 } else { 0 };
+            if ((addr >> 16) & 0x7F) >= 0x40 || offset >= 0x8000 {
+                Some((BusDevice::Rom, upper_half | addr & 0x3FFFFF))
+            } else if offset >= 0x6000 && (bank & 0x7F) >= 0x20 {
+                let mapped_addr = (offset as u32 - 0x6000) | ((bank as u32) & 0xF) << 13;
                 Some((BusDevice::SRam, mapped_addr))
             } else {
                 None
             }
         }
-        _ => todo!(),
     }
 }
 
@@ -68,10 +96,11 @@ fn resolve_addr(addr: u32, mapping_mode: MappingMode) -> Option<(BusDevice, u32)
             0x0000..=0x1FFF => Some((BusDevice::WRam, offset as u32)),
             0x2000..=0x20FF => None,
             0x2100..=0x213F => Some((BusDevice::Ppu, offset as u32)),
+            // The four APU ports are mirrored across the whole range, e.g. 0x2144 is 0x2140
             0x2140..=0x217F => Some((BusDevice::Apu, (offset & 0xFFC3) as u32)),
             0x2180..=0x2183 => Some((BusDevice::WRamAccess, offset as u32)),
-            0x2184..=0x21FF => None, // Open Bus / Expansion (B-Bus)
-            0x2200..=0x3FFF => None, // Open Bus / Expansion (A-Bus)
+            0x2184..=0x21FF => Some((BusDevice::Expansion, offset as u32)), // B-Bus
+            0x2200..=0x3FFF => Some((BusDevice::Expansion, offset as u32)), // A-Bus
             0x4000..=0x4015 => None,
             0x4016..=0x4017 => Some((BusDevice::Joypad, offset as u32)),
             0x4018..=0x41FF => None,
@@ -88,6 +117,40 @@ fn resolve_addr(addr: u32, mapping_mode: MappingMode) -> Option<(BusDevice, u32)
     resolve_cartridge_addr(addr, mapping_mode)
 }
 
+/// The SRAM is mirrored across its whole address window. Returns `None` if the cartridge doesn't
+/// have any SRAM.
+fn sram_index(emu: &Snes, device_addr: u32) -> Option<usize> {
+    let len = emu.sram.len();
+    (len != 0).then(|| device_addr as usize & (len - 1))
+}
+
+/// The ROM is mirrored across its whole address window. A ROM whose size isn't a power of two
+/// consists of a power of two sized part and a smaller rest, the rest is mirrored up to the same
+/// size as the first part. E.g. a 3 MiB ROM appears as 2 MiB + 1 MiB + the last 1 MiB again.
+/// Returns `None` if there is no ROM.
+fn rom_index(emu: &Snes, device_addr: u32) -> Option<usize> {
+    let mut addr = device_addr as usize;
+    let mut size = emu.rom.len();
+    if size == 0 {
+        return None;
+    }
+    // Mirrors of the whole ROM first, then of the rest within the upper part, and so on
+    let mut base = 0;
+    let mut mask = 1 << 23;
+    while addr >= size {
+        while addr & mask == 0 {
+            mask >>= 1;
+        }
+        addr -= mask;
+        if size > mask {
+            size -= mask;
+            base += mask;
+        }
+        mask >>= 1;
+    }
+    Some(base + addr)
+}
+
 pub fn read_pure(emu: &Snes, addr: u32) -> Option<u8> {
     let (device, device_addr) = resolve_addr(addr, emu.cpu.mapping_mode)?;
 
@@ -99,34 +162,60 @@ pub fn read_pure(emu: &Snes, addr: u32) -> Option<u8> {
         BusDevice::Joypad => emu.joypad.read_pure(device_addr),
         BusDevice::CpuIo => emu.cpu.read_pure(device_addr),
         BusDevice::Dma => emu.cpu.dma.read_pure(device_addr),
-        BusDevice::Rom => {
-            // TODO: Implement correct wrapping behavior
-            let wrapped = (device_addr as usize) & !0 >> (emu.rom.len() - 1).leading_zeros();
-            Some(emu.rom.get(wrapped).copied().unwrap_or(0))
-        }
-        BusDevice::SRam => Some(emu.sram[device_addr as usize]),
+        BusDevice::Expansion => match &emu.srtc {
+            Some(srtc) if srtc::REGISTERS.contains(&device_addr) => {
+                srtc.read_pure(device_addr as u16)
+            }
+            _ => emu.expansion.as_ref()?.read_pure(device_addr as u16),
+        },
+        BusDevice::Rom => rom_index(emu, device_addr).map(|i| emu.rom[i]),
+        BusDevice::SRam => sram_index(emu, device_addr).map(|i| emu.sram[i]),
+    }
+}
+
+/// Returns the number of master cycles an access to `addr` takes. Most of the address space is
+/// slow (8 cycles), the joypad registers at 0x4000..=0x41FF are extra slow (12 cycles) and the
+/// remaining I/O registers are fast (6 cycles). ROM in banks 0x80..=0xFF is fast as well if FastROM
+/// was enabled through MEMSEL.
+fn access_cycles(emu: &Snes, addr: u32) -> u64 {
+    let bank = (addr >> 16) as u8;
+    let fast_rom = bank & 0x80 != 0 && emu.cpu.memsel & 0x01 != 0;
+    match (bank & 0x7F, addr as u16) {
+        (0x40.., _) if fast_rom => 6,
+        (0x40.., _) => 8,
+        (_, 0x0000..=0x1FFF) => 8,
+        (_, 0x2000..=0x3FFF) => 6,
+        (_, 0x4000..=0x41FF) => 12,
+        (_, 0x4200..=0x5FFF) => 6,
+        (_, 0x6000..=0x7FFF) => 8,
+        (_, 0x8000..) if fast_rom => 6,
+        (_, 0x8000..) => 8,
     }
 }
 
 pub fn read(emu: &mut Snes, addr: u32) -> u8 {
-    read_with_cycle_counting(emu, addr, true)
+    let value = read_with_cycle_counting(emu, addr, true);
+    check_watchpoints(emu, addr, value, AccessKind::Read);
+    value
 }
 
 pub fn read_with_cycle_counting(emu: &mut Snes, addr: u32, count_cycles: bool) -> u8 {
-    let Some((device, device_addr)) = resolve_addr(addr, emu.cpu.mapping_mode) else {
-        emu.cpu.cycles += 6;
-        return emu.cpu.mdr;
-    };
-
     if count_cycles {
-        // TODO: Check whether we are accessing slow or fast memory and increment by 6 or 8 accordingly
         // TODO: Should we increment the `cycles` counter before or after reading?
-        emu.cpu.cycles += 6;
+        emu.cpu.cycles += access_cycles(emu, addr);
     }
     super::run_timer(emu);
 
+    let Some((device, device_addr)) = resolve_addr(addr, emu.cpu.mapping_mode) else {
+        report_open_bus(emu, addr, false);
+        return emu.cpu.mdr;
+    };
+
     let value = match device {
-        BusDevice::WRam => Some(emu.wram.data[device_addr as usize]),
+        BusDevice::WRam => {
+            emu.wram.record_read(device_addr);
+            Some(emu.wram.data[device_addr as usize])
+        }
         BusDevice::Ppu => {
             ppu::catch_up(emu);
             emu.ppu.read(device_addr).or_else(|| {
@@ -142,14 +231,24 @@ pub fn read_with_cycle_counting(emu: &mut Snes, addr: u32, count_cycles: bool) -
         BusDevice::Joypad => emu.joypad.read(device_addr),
         BusDevice::CpuIo => emu.cpu.read(device_addr),
         BusDevice::Dma => emu.cpu.dma.read(device_addr),
-        BusDevice::Rom => {
-            let wrapped = (device_addr as usize) & !0 >> (emu.rom.len() - 1).leading_zeros();
-            Some(emu.rom.get(wrapped).copied().unwrap_or(0))
-        }
-        BusDevice::SRam => Some(emu.sram[device_addr as usize]),
+        BusDevice::Expansion => match &mut emu.srtc {
+            Some(srtc) if srtc::REGISTERS.contains(&device_addr) => {
+                srtc.sync(emu.cpu.cycles, emu.ppu.variant.master_clock_hz());
+                srtc.read(device_addr as u16)
+            }
+            _ => emu
+                .expansion
+                .as_mut()
+                .and_then(|device| device.read(device_addr as u16)),
+        },
+        BusDevice::Rom => rom_index(emu, device_addr).map(|i| emu.rom[i]),
+        BusDevice::SRam => sram_index(emu, device_addr).map(|i| emu.sram[i]),
     };
 
-    let value = value.unwrap_or(emu.cpu.mdr);
+    let Some(value) = value else {
+        report_open_bus(emu, addr, false);
+        return emu.cpu.mdr;
+    };
 
     emu.cpu.mdr = value;
 
@@ -158,24 +257,42 @@ pub fn read_with_cycle_counting(emu: &mut Snes, addr: u32, count_cycles: bool) -
 
 pub fn write(emu: &mut Snes, addr: u32, value: u8) {
     write_with_cycle_counting(emu, addr, value, true);
+    check_watchpoints(emu, addr, value, AccessKind::Write);
+}
+
+/// Remembers the first access in the current step which matches a watchpoint, it is reported by
+/// [`super::step`].
+pub(super) fn check_watchpoints(emu: &mut Snes, addr: u32, value: u8, kind: AccessKind) {
+    let debug = &mut emu.cpu.debug;
+    if debug.watchpoint_hit.is_none()
+        && debug
+            .watchpoints
+            .iter()
+            .any(|wp| wp.matches(addr, value, kind))
+    {
+        debug.watchpoint_hit = Some(super::WatchpointHit { addr, value, kind });
+    }
 }
 
 pub fn write_with_cycle_counting(emu: &mut Snes, addr: u32, value: u8, count_cycles: bool) {
     emu.cpu.mdr = value;
 
-    let Some((device, device_addr)) = resolve_addr(addr, emu.cpu.mapping_mode) else {
-        return;
-    };
-
     if count_cycles {
-        // TODO: Check whether we are accessing slow or fast memory and increment by 6 or 8 accordingly
         // TODO: Should we increment the `cycles` counter before or after writing?
-        emu.cpu.cycles += 6;
+        emu.cpu.cycles += access_cycles(emu, addr);
     }
     super::run_timer(emu);
 
+    let Some((device, device_addr)) = resolve_addr(addr, emu.cpu.mapping_mode) else {
+        report_open_bus(emu, addr, true);
+        return;
+    };
+
     match device {
-        BusDevice::WRam => emu.wram.data[device_addr as usize] = value,
+        BusDevice::WRam => {
+            emu.wram.record_write(device_addr);
+            emu.wram.data[device_addr as usize] = value;
+        }
         BusDevice::Ppu => {
             ppu::catch_up(emu);
             emu.ppu.write(device_addr, value)
@@ -186,10 +303,26 @@ pub fn write_with_cycle_counting(emu: &mut Snes, addr: u32, value: u8, count_cyc
         }
         BusDevice::WRamAccess => emu.wram.write(device_addr, value),
         BusDevice::Joypad => emu.joypad.write(device_addr, value),
-        BusDevice::CpuIo => emu.cpu.write(device_addr, value),
+        BusDevice::CpuIo => {
+            emu.cpu.write(device_addr, value);
+            if device_addr == 0x4201 {
+                joypad::update_pin6(emu);
+            }
+        }
         BusDevice::Dma => emu.cpu.dma.write(device_addr, value),
+        BusDevice::Expansion => match (&mut emu.srtc, &mut emu.expansion) {
+            (Some(srtc), _) if srtc::REGISTERS.contains(&device_addr) => {
+                srtc.sync(emu.cpu.cycles, emu.ppu.variant.master_clock_hz());
+                srtc.write(device_addr as u16, value)
+            }
+            (_, Some(device)) => device.write(device_addr as u16, value),
+            (_, None) => report_open_bus(emu, addr, true),
+        },
         BusDevice::Rom => (),
-        BusDevice::SRam => emu.sram[device_addr as usize] = value,
+        BusDevice::SRam => match sram_index(emu, device_addr) {
+            Some(i) => emu.sram[i] = value,
+            None => report_open_bus(emu, addr, true),
+        },
     }
 }
 
@@ -199,18 +332,28 @@ pub fn next_instr_byte(emu: &mut Snes) -> u8 {
     read(emu, (emu.cpu.regs.k as u32) << 16 | pc as u32)
 }
 
-pub fn skip_instr_byte(emu: &mut Snes) {
-    emu.cpu.regs.pc.set(emu.cpu.regs.pc.get().wrapping_add(1));
+/// An internal operation cycle of the CPU, during which the bus is not accessed.
+pub fn idle_cycle(emu: &mut Snes) {
     emu.cpu.cycles += 6;
+    super::run_timer(emu);
+}
+
+pub fn skip_instr_byte(emu: &mut Snes) {
+    let pc = emu.cpu.regs.pc.get();
+    emu.cpu.regs.pc.set(pc.wrapping_add(1));
+    emu.cpu.cycles += access_cycles(emu, (emu.cpu.regs.k as u32) << 16 | pc as u32);
 }
 
 pub fn read_operand(emu: &mut Snes, mode: AddressingMode) -> Operand {
-    match mode {
+    let register = match mode {
         AddressingMode::Accumulator => Operand::A,
         AddressingMode::X => Operand::X,
         AddressingMode::Y => Operand::Y,
-        _ => Operand::Memory(addr_mode::read_pointer(emu, mode)),
-    }
+        _ => return Operand::Memory(addr_mode::read_pointer(emu, mode)),
+    };
+    // Operating on a register takes an internal cycle instead of a memory access
+    idle_cycle(emu);
+    register
 }
 
 pub fn get_operand_u8(emu: &mut Snes, operand: Operand) -> u8 {
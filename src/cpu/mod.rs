@@ -1,8 +1,13 @@
-use std::fmt::{self, Write};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    io,
+    ops::RangeInclusive,
+};
 
 use arbitrary_int::*;
 
-use crate::{RomHeader, Snes, apu, cpu::memory::MappingMode, ppu};
+use crate::{RomHeader, Snes, apu, cpu::memory::MappingMode, joypad, ppu};
 
 mod addr_mode;
 pub mod disasm;
@@ -66,6 +71,29 @@ pub struct Registers {
     pub y: Register16,
 }
 
+impl Registers {
+    /// Must be called whenever P was modified as a whole (REP, SEP, PLP, RTI, XCE). Setting x
+    /// truncates X and Y right away, so clearing it again later exposes zeroed high bytes.
+    pub fn flags_updated(&mut self) {
+        if self.p.e {
+            self.p.m = true;
+            self.p.x = true;
+            self.s.seth(0x01);
+        }
+
+        if self.p.x {
+            self.x.seth(0x00);
+            self.y.seth(0x00);
+        }
+    }
+
+    /// Sets P like PLP does, including the side effects on the other registers.
+    pub fn set_p(&mut self, bits: u8) {
+        self.p.set_from_bits(bits);
+        self.flags_updated();
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Flags {
     /// Carry
@@ -174,41 +202,52 @@ pub struct Pointer {
 }
 
 impl Pointer {
+    /// Creates a pointer to a 16 bit value which wraps around within the bank.
     fn new16(hh: u8, mmll: u16) -> Self {
         let low = (hh as u32) << 16 | (mmll as u32);
-        let high = (hh as u32) << 16 | (mmll as u32).wrapping_add(1);
+        let high = (hh as u32) << 16 | (mmll.wrapping_add(1) as u32);
         Self { low, high }
     }
 
+    /// Creates a pointer to a 16 bit value which wraps around within the page.
     fn new8(hh: u8, mm: u8, ll: u8) -> Self {
         let low = (hh as u32) << 16 | (mm as u32) << 8 | (ll as u32);
-        let high = (hh as u32) << 16 | (mm as u32) << 8 | (ll as u32).wrapping_add(1);
+        let high = (hh as u32) << 16 | (mm as u32) << 8 | (ll.wrapping_add(1) as u32);
         Self { low, high }
     }
 
+    /// Creates a pointer to a 16 bit value which may cross into the next bank.
     fn new24(hhmmll: u32) -> Self {
         assert!(hhmmll & 0xFF00_0000 == 0);
         let low = hhmmll;
-        let high = hhmmll.wrapping_add(1);
+        let high = hhmmll.wrapping_add(1) & 0xFF_FFFF;
         Self { low, high }
     }
 
+    /// Offsets both halves of the pointer, crossing bank boundaries if necessary.
     fn with_offset(self, offset: u16) -> Self {
         Self {
-            low: self.low.wrapping_add(u32::from(offset)),
-            high: self.high.wrapping_add(u32::from(offset)),
+            low: self.low.wrapping_add(u32::from(offset)) & 0xFF_FFFF,
+            high: self.high.wrapping_add(u32::from(offset)) & 0xFF_FFFF,
         }
     }
 }
 
-// NOTE: When multiple interrupts are raised at the same time, they are handled in the same order
-// as they are defined here.
-// TODO: Is that even correct?
+// NOTE: Hardware interrupts are sampled between instructions. When multiple of them are pending at
+// the same time, they are handled in the same order as they are defined here (Reset, NMI, ABORT,
+// IRQ). COP and BRK are not pending interrupts, they are software interrupts which are entered
+// directly while executing the corresponding instruction. A hardware interrupt which is pending
+// when a BRK or COP is about to be executed is therefore always handled first, and the BRK or COP
+// only executes after its handler returns.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Interrupt {
     Reset,
     Nmi,
+    /// The ABORT pin is not connected on the SNES, so this is only raised through
+    /// [`Cpu::raise_interrupt`]. Since it is sampled between instructions, it aborts the next
+    /// instruction before it had any effect: The pushed return address points to that instruction,
+    /// so it is executed again after the handler returns.
     Abort,
     Irq,
     Cop,
@@ -219,8 +258,6 @@ const INT_RESET: u8 = Interrupt::Reset as u8;
 const INT_NMI: u8 = Interrupt::Nmi as u8;
 const INT_ABORT: u8 = Interrupt::Abort as u8;
 const INT_IRQ: u8 = Interrupt::Irq as u8;
-const INT_COP: u8 = Interrupt::Cop as u8;
-const INT_BREAK: u8 = Interrupt::Break as u8;
 
 #[derive(PartialEq, Eq)]
 pub enum HvIrq {
@@ -234,13 +271,211 @@ pub enum HvIrq {
 pub enum StepResult {
     Stepped,
     BreakpointHit,
+    /// Emulation hit a feature which is not implemented yet. The emulator keeps running with
+    /// some fallback behavior, but the output is likely wrong.
+    Unimplemented(&'static str),
+    /// The CPU accessed open bus while [`Snes::pause_on_open_bus`] was enabled.
+    OpenBus(memory::OpenBusAccess),
+    /// The last instruction accessed an address with a matching [`Watchpoint`].
+    WatchpointHit(WatchpointHit),
+    /// The budget set with [`Snes::set_instruction_budget`] is used up.
+    BudgetExhausted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// Stops emulation after the CPU accessed an address in `start..=end`. `read` and `write` select
+/// the kinds of accesses which trigger the watchpoint. If `value` is set, only accesses of exactly
+/// that value trigger it.
+///
+/// Addresses are compared as the CPU sees them, mirrors have to be watched separately. Accesses by
+/// DMA and HDMA are only checked if [`CpuDebug::watch_dma`] is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub start: u32,
+    pub end: u32,
+    pub read: bool,
+    pub write: bool,
+    pub value: Option<u8>,
+}
+
+impl Watchpoint {
+    pub fn matches(&self, addr: u32, value: u8, kind: AccessKind) -> bool {
+        let kind_matches = match kind {
+            AccessKind::Read => self.read,
+            AccessKind::Write => self.write,
+        };
+        kind_matches
+            && (self.start..=self.end).contains(&addr)
+            && self.value.is_none_or(|expected| expected == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchpointHit {
+    pub addr: u32,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingEventKind {
+    VBlankStart,
+    VBlankEnd,
+    Nmi,
+    HvIrq,
+    HdmaReload,
+    HdmaTransfer,
+}
+
+/// Something noteworthy that happened at a specific dot of the frame.
+#[derive(Debug, Clone, Copy)]
+pub struct TimingEvent {
+    pub kind: TimingEventKind,
+    pub h: u16,
+    pub v: u16,
+}
+
+/// Number of master cycles spent on different tasks during a frame.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CycleUsage {
+    /// Length of the whole frame.
+    pub frame: u64,
+    pub mdma: u64,
+    pub hdma: u64,
+}
+
+impl CycleUsage {
+    /// Cycles which were left for the CPU to execute instructions.
+    pub fn cpu(&self) -> u64 {
+        self.frame.saturating_sub(self.mdma + self.hdma)
+    }
+}
+
+/// Number of times every opcode and every instruction address was executed, for finding hot code.
+#[derive(Clone)]
+pub struct InstructionStats {
+    pub opcodes: Box<[u64; 256]>,
+    mnemonics: Box<[[u8; 3]; 256]>,
+    /// Execution counts by address, along with the last instruction executed there. Holds at most
+    /// [`Self::MAX_PCS`] addresses.
+    pcs: HashMap<u32, (u64, disasm::Instruction)>,
+    /// Executions at addresses which weren't counted because `pcs` was full.
+    pub untracked: u64,
+}
+
+impl Default for InstructionStats {
+    fn default() -> Self {
+        Self {
+            opcodes: Box::new([0; 256]),
+            mnemonics: Box::new([*b"???"; 256]),
+            pcs: HashMap::new(),
+            untracked: 0,
+        }
+    }
+}
+
+impl InstructionStats {
+    /// Maximum number of distinct addresses to count, so code running wild through the whole
+    /// address space can't use up all memory.
+    pub const MAX_PCS: usize = 0x10000;
+
+    fn record(&mut self, instruction: &disasm::Instruction) {
+        self.opcodes[usize::from(instruction.opcode)] += 1;
+        self.mnemonics[usize::from(instruction.opcode)] = instruction.mnemonic;
+        let pc = instruction.address();
+        if let Some(entry) = self.pcs.get_mut(&pc) {
+            entry.0 += 1;
+            entry.1 = *instruction;
+        } else if self.pcs.len() < Self::MAX_PCS {
+            self.pcs.insert(pc, (1, *instruction));
+        } else {
+            self.untracked += 1;
+        }
+    }
+
+    /// Total number of executed instructions.
+    pub fn total(&self) -> u64 {
+        self.opcodes.iter().sum()
+    }
+
+    /// Returns up to `count` executed opcodes with their mnemonic and execution count, most
+    /// executed first.
+    pub fn top_opcodes(&self, count: usize) -> Vec<(u8, &str, u64)> {
+        let mut opcodes: Vec<_> = (0..=255u8)
+            .filter(|&op| self.opcodes[usize::from(op)] != 0)
+            .map(|op| {
+                let mnemonic = std::str::from_utf8(&self.mnemonics[usize::from(op)]).unwrap();
+                (op, mnemonic, self.opcodes[usize::from(op)])
+            })
+            .collect();
+        opcodes.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        opcodes.truncate(count);
+        opcodes
+    }
+
+    /// Returns up to `count` instruction addresses with their execution count and instruction, most
+    /// executed first.
+    pub fn hottest_pcs(&self, count: usize) -> Vec<(u32, u64, disasm::Instruction)> {
+        let mut pcs: Vec<_> = self
+            .pcs
+            .iter()
+            .map(|(&pc, &(count, instruction))| (pc, count, instruction))
+            .collect();
+        pcs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        pcs.truncate(count);
+        pcs
+    }
+
+    /// Writes all opcodes and addresses sorted by execution count as plain text.
+    pub fn dump(&self, out: &mut impl io::Write) -> io::Result<()> {
+        let total = self.total().max(1) as f64;
+        writeln!(out, "Opcodes ({} instructions):", self.total())?;
+        for (op, mnemonic, count) in self.top_opcodes(256) {
+            let percent = count as f64 / total * 100.0;
+            writeln!(out, "  {op:02X} {mnemonic} {count:>12} {percent:6.2}%")?;
+        }
+        writeln!(out)?;
+        writeln!(out, "Addresses:")?;
+        for (pc, count, instruction) in self.hottest_pcs(usize::MAX) {
+            writeln!(out, "  ${pc:06X} {count:>12}  {instruction}")?;
+        }
+        if self.untracked != 0 {
+            writeln!(out, "  Other   {:>12}", self.untracked)?;
+        }
+        Ok(())
+    }
 }
 
 pub struct CpuDebug {
     pub execution_history: Box<[disasm::Instruction]>,
     pub execution_history_pos: usize,
     pub breakpoints: Vec<u32>,
+    pub watchpoints: Vec<Watchpoint>,
+    /// Also check the watchpoints against the transfers of DMA and HDMA channels.
+    pub watch_dma: bool,
+    watchpoint_hit: Option<WatchpointHit>,
     pub encountered_instructions: Box<[Option<disasm::Instruction>; 0x1000000]>,
+    /// Events of the frame that is currently being emulated.
+    pub timing_events: Vec<TimingEvent>,
+    /// Events of the last fully emulated frame.
+    pub last_frame_timing_events: Vec<TimingEvent>,
+    /// DMA cycles of the frame that is currently being emulated. `frame` is only filled in once
+    /// the frame is finished.
+    pub cycle_usage: CycleUsage,
+    pub last_frame_cycle_usage: CycleUsage,
+    frame_start_cycles: u64,
+    /// Execution counts of all instructions, only collected while set to `Some`.
+    pub instruction_stats: Option<InstructionStats>,
+    /// Receives a line for every executed instruction while set, see [`write_trace_line`]. It is
+    /// removed if writing fails.
+    pub trace: Option<Box<dyn io::Write>>,
+    /// Only instructions at these addresses are written to the trace log, if set.
+    pub trace_range: Option<RangeInclusive<u32>>,
 }
 
 impl Default for CpuDebug {
@@ -249,9 +484,20 @@ impl Default for CpuDebug {
             execution_history: vec![disasm::Instruction::default(); 256].into_boxed_slice(),
             execution_history_pos: 0,
             breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            watch_dma: false,
+            watchpoint_hit: None,
             encountered_instructions: vec![None; 0x1000000]
                 .try_into()
                 .unwrap_or_else(|_| panic!()),
+            timing_events: Vec::new(),
+            last_frame_timing_events: Vec::new(),
+            cycle_usage: CycleUsage::default(),
+            last_frame_cycle_usage: CycleUsage::default(),
+            frame_start_cycles: 0,
+            instruction_stats: None,
+            trace: None,
+            trace_range: None,
         }
     }
 }
@@ -362,9 +608,17 @@ impl Cpu {
     }
 
     pub fn raise_interrupt(&mut self, interrupt: Interrupt) {
+        debug_assert!(
+            !matches!(interrupt, Interrupt::Cop | Interrupt::Break),
+            "software interrupts can only be raised by executing COP or BRK",
+        );
         self.pending_interrupts |= 1 << interrupt as u8;
     }
 
+    pub fn is_interrupt_pending(&self, interrupt: Interrupt) -> bool {
+        self.pending_interrupts & (1 << interrupt as u8) != 0
+    }
+
     pub fn dismiss_interrupt(&mut self, interrupt: Interrupt) {
         self.pending_interrupts &= !(1 << interrupt as u8);
     }
@@ -372,6 +626,7 @@ impl Cpu {
     pub fn set_vblank_nmi_enable(&mut self, enable: bool) {
         if enable && !self.nmitimen_vblank_nmi_enable && self.rdnmi_vblank_nmi_flag {
             self.raise_interrupt(Interrupt::Nmi);
+            self.record_timing_event(TimingEventKind::Nmi);
         }
 
         self.nmitimen_vblank_nmi_enable = enable;
@@ -380,6 +635,7 @@ impl Cpu {
     pub fn set_vblank_nmi_flag(&mut self, nmi: bool) {
         if nmi && !self.rdnmi_vblank_nmi_flag && self.nmitimen_vblank_nmi_enable {
             self.raise_interrupt(Interrupt::Nmi);
+            self.record_timing_event(TimingEventKind::Nmi);
         }
 
         self.rdnmi_vblank_nmi_flag = nmi;
@@ -408,6 +664,17 @@ impl Cpu {
         self.v_counter = 0;
         self.cycles = 0;
         self.hv_counter_cycles = 0;
+        self.debug.cycle_usage = CycleUsage::default();
+        self.debug.frame_start_cycles = 0;
+        self.stopped = false;
+        self.waiting = false;
+    }
+
+    /// Continues execution at the 24 bit address `addr`, waking up the CPU if it was halted by WAI
+    /// or STP.
+    pub fn set_pc(&mut self, addr: u32) {
+        self.regs.k = (addr >> 16) as u8;
+        self.regs.pc.set(addr as u16);
         self.stopped = false;
         self.waiting = false;
     }
@@ -539,21 +806,54 @@ impl Cpu {
     pub fn cycles(&self) -> u64 {
         self.cycles
     }
+
+    pub fn h_counter(&self) -> u16 {
+        self.h_counter
+    }
+
+    pub fn v_counter(&self) -> u16 {
+        self.v_counter
+    }
+
+    fn record_timing_event(&mut self, kind: TimingEventKind) {
+        self.debug.timing_events.push(TimingEvent {
+            kind,
+            h: self.h_counter,
+            v: self.v_counter,
+        });
+    }
 }
 
 fn int_reset(emu: &mut Snes) {
-    emu.cpu.regs.p = Flags::default();
-    emu.cpu.regs.x.seth(0x00);
-    emu.cpu.regs.y.seth(0x00);
-    emu.cpu.regs.s.seth(0x01);
-    // FIXME: should this happen before or after resetting the registers?
-    enter_interrupt_handler(emu, Interrupt::Reset);
+    let regs = &mut emu.cpu.regs;
+    regs.p = Flags {
+        i: true,
+        ..Flags::default()
+    };
+    regs.d.set(0x0000);
+    regs.dbr = 0x00;
+    regs.x.seth(0x00);
+    regs.y.seth(0x00);
+    regs.s.seth(0x01);
+
+    // Reset goes through the same sequence as the other interrupts, except that the three stack
+    // accesses are reads. S is decremented as if PC and P were pushed, but nothing is written.
+    memory::idle_cycle(emu);
+    memory::idle_cycle(emu);
+    for _ in 0..3 {
+        let s = emu.cpu.regs.s;
+        memory::read(emu, s.get().into());
+        emu.cpu.regs.s.setl(s.getl().wrapping_sub(1));
+    }
+    jump_to_vector(emu, 0xFFFC);
 
     emu.cpu.reset();
     emu.ppu.reset();
     emu.apu.reset();
+    joypad::update_pin6(emu);
 }
 
+// BRK and COP skip their signature byte before pushing PC, so RTI returns past it
 fn int_break(emu: &mut Snes) {
     memory::skip_instr_byte(emu);
     enter_interrupt_handler(emu, Interrupt::Break);
@@ -565,16 +865,30 @@ fn int_cop(emu: &mut Snes) {
 }
 
 fn enter_interrupt_handler(emu: &mut Snes, interrupt: Interrupt) {
+    // Hardware interrupts spend two internal cycles in place of fetching an opcode and operand
+    if !matches!(interrupt, Interrupt::Break | Interrupt::Cop) {
+        memory::idle_cycle(emu);
+        memory::idle_cycle(emu);
+    }
+
     if !emu.cpu.regs.p.e {
         memory::push8old(emu, emu.cpu.regs.k);
     }
 
-    // FIXME: Apparently there are "new" and "old" interrupts with different wrapping behavior here.
+    // Interrupts push like the "old" 6502 instructions, and RTI pulls the same way: The stack only
+    // wraps within page 1 in emulation mode. In native mode S is decremented as a whole, so a stack
+    // near $0100 continues into page 0.
     let ret = emu.cpu.regs.pc.get();
     memory::push16old(emu, ret);
+    // In emulation mode bit 4 is the B flag instead of x (which is always set there). It is only
+    // pushed as set for software interrupts, so that handlers can tell BRK and IRQ apart since
+    // both share the same vector.
     let mut p_bits = emu.cpu.regs.p.to_bits();
-    if emu.cpu.regs.p.e && interrupt == Interrupt::Break {
-        p_bits |= 0x10;
+    if emu.cpu.regs.p.e {
+        match interrupt {
+            Interrupt::Break | Interrupt::Cop => p_bits |= 0x10,
+            _ => p_bits &= !0x10,
+        }
     }
     memory::push8old(emu, p_bits);
 
@@ -586,7 +900,7 @@ fn enter_interrupt_handler(emu: &mut Snes, interrupt: Interrupt) {
             Interrupt::Cop => 0xFFF4,
             Interrupt::Abort => 0xFFF8,
             Interrupt::Nmi => 0xFFFA,
-            Interrupt::Reset => 0xFFFC,
+            Interrupt::Reset => unreachable!("reset is handled by int_reset"),
             Interrupt::Irq | Interrupt::Break => 0xFFFE,
         }
     } else {
@@ -595,11 +909,15 @@ fn enter_interrupt_handler(emu: &mut Snes, interrupt: Interrupt) {
             Interrupt::Break => 0xFFE6,
             Interrupt::Abort => 0xFFE8,
             Interrupt::Nmi => 0xFFEA,
-            Interrupt::Reset => panic!("cannot enter reset interrupt handler in native mode"),
+            Interrupt::Reset => unreachable!("reset is handled by int_reset"),
             Interrupt::Irq => 0xFFEE,
         }
     };
+    jump_to_vector(emu, vector_addr);
+}
 
+/// Continues execution in bank 0 at the address stored at `vector_addr`.
+fn jump_to_vector(emu: &mut Snes, vector_addr: u32) {
     let target_ll = memory::read(emu, vector_addr);
     let target_hh = memory::read(emu, vector_addr + 1);
     let target = (target_hh as u16) << 8 | target_ll as u16;
@@ -609,6 +927,8 @@ fn enter_interrupt_handler(emu: &mut Snes, interrupt: Interrupt) {
 
 #[cold]
 fn process_interrupt(emu: &mut Snes) {
+    // While waiting (WAI), an IRQ wakes up the CPU even if it is masked by the I flag. It is not
+    // serviced in that case though, execution just resumes after the WAI (see the INT_IRQ arm).
     let mask = !(((emu.cpu.regs.p.i & !emu.cpu.waiting) as u8) << INT_IRQ);
 
     let interrupt = (emu.cpu.pending_interrupts & mask).trailing_zeros();
@@ -624,14 +944,12 @@ fn process_interrupt(emu: &mut Snes) {
     match interrupt as u8 {
         INT_RESET => int_reset(emu),
         INT_NMI => enter_interrupt_handler(emu, Interrupt::Nmi),
-        INT_ABORT => todo!(),
+        INT_ABORT => enter_interrupt_handler(emu, Interrupt::Abort),
         INT_IRQ => {
             if !emu.cpu.regs.p.i {
                 enter_interrupt_handler(emu, Interrupt::Irq);
             }
         }
-        INT_COP => int_cop(emu),
-        INT_BREAK => int_break(emu),
         _ => unreachable!(),
     }
 }
@@ -673,16 +991,92 @@ fn do_step(emu: &mut Snes, ignore_breakpoints: bool) -> StepResult {
 
     let pc = (emu.cpu.regs.k as u32) << 16 | emu.cpu.regs.pc.get() as u32;
     emu.cpu.debug.encountered_instructions[pc as usize] = Some(instruction[0]);
+    if let Some(stats) = &mut emu.cpu.debug.instruction_stats {
+        stats.record(&instruction[0]);
+    }
+    let in_trace_range = match &emu.cpu.debug.trace_range {
+        Some(range) => range.contains(&pc),
+        None => true,
+    };
+    if in_trace_range && let Some(mut trace) = emu.cpu.debug.trace.take() {
+        match write_trace_line(emu, &instruction[0], &mut trace) {
+            Ok(()) => emu.cpu.debug.trace = Some(trace),
+            Err(err) => tracing::error!("Failed to write the trace log: {err}"),
+        }
+    }
 
     instructions::exec_next_inst(emu);
+    if let Some(budget) = &mut emu.instruction_budget {
+        *budget = budget.saturating_sub(1);
+    }
 
     StepResult::Stepped
 }
 
+/// Writes the state before executing `instruction` in the format of the trace loggers of bsnes
+/// and Mesen, so traces can be diffed against theirs:
+///
+/// ```text
+/// 008003 REP #$30        A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzC V:  0 H:  44
+/// ```
+///
+/// Set flags are upper case. H is the position within the scanline in master cycles.
+pub fn write_trace_line(
+    emu: &Snes,
+    instruction: &disasm::Instruction,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    let regs = &emu.cpu.regs;
+    let mut flags = String::with_capacity(8);
+    for (set, ch) in [
+        (regs.p.n, 'n'),
+        (regs.p.v, 'v'),
+        (regs.p.m, 'm'),
+        (regs.p.x, 'x'),
+        (regs.p.d, 'd'),
+        (regs.p.i, 'i'),
+        (regs.p.z, 'z'),
+        (regs.p.c, 'c'),
+    ] {
+        flags.push(if set { ch.to_ascii_uppercase() } else { ch });
+    }
+    let address = instruction.address();
+    let instruction = instruction.to_string();
+    write!(out, "{address:06X} {instruction:<15} ")?;
+    writeln!(
+        out,
+        "A:{:04X} X:{:04X} Y:{:04X} S:{:04X} D:{:04X} DB:{:02X} P:{flags} V:{:3} H:{:4}",
+        regs.a.get(),
+        regs.x.get(),
+        regs.y.get(),
+        regs.s.get(),
+        regs.d.get(),
+        regs.dbr,
+        emu.cpu.v_counter,
+        emu.cpu.h_counter * 4,
+    )
+}
+
 pub fn step(emu: &mut Snes, ignore_breakpoints: bool) -> StepResult {
+    // Nothing happens at all once the budget is used up, no matter how often this is called
+    if emu.instruction_budget == Some(0) {
+        return StepResult::BudgetExhausted;
+    }
     let result = do_step(emu, ignore_breakpoints);
     run_timer(emu);
-    result
+    if let Some(feature) = emu.unimplemented_feature.take() {
+        return StepResult::Unimplemented(feature);
+    }
+    if let Some(hit) = emu.cpu.debug.watchpoint_hit.take() {
+        return StepResult::WatchpointHit(hit);
+    }
+    if emu.instruction_budget == Some(0) {
+        return StepResult::BudgetExhausted;
+    }
+    match emu.open_bus_access.take() {
+        Some(access) => StepResult::OpenBus(access),
+        None => result,
+    }
 }
 
 fn run_timer(emu: &mut Snes) {
@@ -700,9 +1094,12 @@ fn run_timer(emu: &mut Snes) {
             emu.cpu.h_counter = 0;
             emu.cpu.v_counter += 1;
 
-            if emu.cpu.v_counter == 2 {
+            if emu.cpu.v_counter == 1 {
+                emu.cpu.record_timing_event(TimingEventKind::VBlankEnd);
+            } else if emu.cpu.v_counter == 2 {
                 emu.cpu.set_vblank_nmi_flag(false);
             } else if emu.cpu.v_counter == output_height + 1 {
+                emu.cpu.record_timing_event(TimingEventKind::VBlankStart);
                 emu.cpu.set_vblank_nmi_flag(true);
             }
 
@@ -710,15 +1107,51 @@ fn run_timer(emu: &mut Snes) {
             // a NTSC or PAL console. (at least I think so ..)
             if emu.cpu.v_counter > max_vpos {
                 emu.cpu.v_counter = 0;
+
+                let debug = &mut emu.cpu.debug;
+                std::mem::swap(
+                    &mut debug.timing_events,
+                    &mut debug.last_frame_timing_events,
+                );
+                debug.timing_events.clear();
+
+                debug.cycle_usage.frame = emu.cpu.hv_counter_cycles - debug.frame_start_cycles;
+                debug.last_frame_cycle_usage = std::mem::take(&mut debug.cycle_usage);
+                debug.frame_start_cycles = emu.cpu.hv_counter_cycles;
             }
         }
 
         match (emu.cpu.h_counter, emu.cpu.v_counter) {
-            (4, 0) => dma::reload_hdma(emu),
-            (278, 0..225) => dma::process_hdma(emu),
+            (4, 0) => {
+                emu.cpu.record_timing_event(TimingEventKind::HdmaReload);
+                dma::reload_hdma(emu);
+            }
+            // Transfers take place at the end of every visible scanline, including line 0 whose
+            // transfer affects the first visible line
+            (278, v_counter) if v_counter <= output_height => {
+                emu.cpu.record_timing_event(TimingEventKind::HdmaTransfer);
+                dma::process_hdma(emu);
+            }
             _ => (),
         }
 
+        if emu.cpu.hvbjoy_auto_joypad_read_busy_flag {
+            joypad::run_auto_read(emu);
+        } else if emu.cpu.h_counter == 33
+            && emu.cpu.v_counter == output_height + 1
+            && emu.cpu.nmitimen_joypad_enable
+        {
+            joypad::start_auto_read(emu);
+        }
+
+        // A light gun only sees the beam if something is displayed at all
+        if emu.joypad.light_gun_latch == Some((emu.cpu.h_counter, emu.cpu.v_counter))
+            && emu.cpu.wrio_joypad2_pin6
+            && !emu.ppu.inidisp_forced_blanking
+        {
+            emu.ppu.latch_counters(emu.cpu.h_counter, emu.cpu.v_counter);
+        }
+
         let hblank = emu.cpu.h_counter < 22 || emu.cpu.h_counter > 277;
         let vblank = emu.cpu.v_counter < 1 || emu.cpu.v_counter > output_height;
 
@@ -739,6 +1172,7 @@ fn run_timer(emu: &mut Snes) {
         // Set the IRQ flag only when the condition *becomes* true.
         if hv_irq_cond & !emu.cpu.hv_irq_cond {
             emu.cpu.raise_interrupt(Interrupt::Irq);
+            emu.cpu.record_timing_event(TimingEventKind::HvIrq);
         }
         emu.cpu.hv_irq_cond = hv_irq_cond;
 
@@ -755,5 +1189,9 @@ fn run_timer(emu: &mut Snes) {
         assert_eq!(emu.cpu.h_counter, emu.ppu.hpos);
         assert_eq!(emu.cpu.v_counter, emu.ppu.vpos);
         emu.frame_finished = true;
+
+        if let Some(heatmap) = &mut emu.wram.heatmap {
+            heatmap.decay();
+        }
     }
 }
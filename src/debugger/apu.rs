@@ -1,5 +1,14 @@
-#[derive(Default)]
-pub struct ApuTab;
+use snes_emu::apu::dsp::Dsp;
+
+pub struct ApuTab {
+    step_count: u32,
+}
+
+impl Default for ApuTab {
+    fn default() -> Self {
+        Self { step_count: 1 }
+    }
+}
 
 impl super::Tab for ApuTab {
     fn title(&self) -> &str {
@@ -8,6 +17,7 @@ impl super::Tab for ApuTab {
 
     fn ui(&mut self, emulation_state: &mut crate::EmulationState, ui: &mut egui::Ui) {
         let snes = &mut emulation_state.snes;
+        let mut step_result = None;
 
         ui.horizontal(|ui| {
             ui.vertical(|ui| {
@@ -48,6 +58,20 @@ impl super::Tab for ApuTab {
                     });
                     ui.monospace(format!("{:?}", snes.apu.psw));
                 });
+
+                // Steps the APU on its own, the main CPU stays where it is
+                ui.horizontal(|ui| {
+                    if ui.button("Step APU").clicked() {
+                        step_result = Some(snes.step_apu(self.step_count));
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut self.step_count)
+                            .range(1..=100000)
+                            .suffix(" instructions"),
+                    );
+                });
+                // And the other way around, the APU stays where it is while the CPU runs
+                ui.checkbox(&mut snes.apu.paused, "Pause APU");
             });
 
             ui.vertical(|ui| {
@@ -66,6 +90,21 @@ impl super::Tab for ApuTab {
 
             ui.checkbox(&mut snes.apu.rom_enable, "ROM");
 
+            ui.vertical(|ui| {
+                let cpu_cycles = snes.cpu.cycles();
+                let apu_cycles = snes.apu.cycles();
+                let expected = snes_emu::apu::master_to_apu_cycles(cpu_cycles, snes.ppu.variant);
+                ui.monospace(format!("CPU cycles: {cpu_cycles}"));
+                ui.monospace(format!("APU cycles: {apu_cycles}"));
+                ui.monospace(format!("Drift: {:+}", apu_cycles as i64 - expected as i64));
+                if cpu_cycles != 0 {
+                    ui.monospace(format!(
+                        "Ratio: {:.6}",
+                        apu_cycles as f64 / cpu_cycles as f64
+                    ));
+                }
+            });
+
             ui.vertical(|ui| {
                 egui::Grid::new("cpu-disasm").striped(true).show(ui, |ui| {
                     let mut off = 0;
@@ -80,6 +119,10 @@ impl super::Tab for ApuTab {
                 });
             });
         });
+
+        if let Some(result) = step_result {
+            emulation_state.handle_step_result(result);
+        }
     }
 }
 
@@ -110,3 +153,95 @@ impl super::Tab for ApuRamTab {
         );
     }
 }
+
+/// Names of the ten registers of each voice, voice n uses the addresses n0-n9.
+const VOICE_REGISTERS: [&str; 10] = [
+    "VOLL", "VOLR", "PITCHL", "PITCHH", "SRCN", "ADSR1", "ADSR2", "GAIN", "ENVX", "OUTX",
+];
+
+const GLOBAL_REGISTERS: [(&str, u8); 15] = [
+    ("MVOLL", 0x0C),
+    ("MVOLR", 0x1C),
+    ("EVOLL", 0x2C),
+    ("EVOLR", 0x3C),
+    ("KON", 0x4C),
+    ("KOFF", 0x5C),
+    ("FLG", 0x6C),
+    ("ENDX", 0x7C),
+    ("EFB", 0x0D),
+    ("PMON", 0x2D),
+    ("NON", 0x3D),
+    ("EON", 0x4D),
+    ("DIR", 0x5D),
+    ("ESA", 0x6D),
+    ("EDL", 0x7D),
+];
+
+/// Shows the register file of the DSP. Edited values are written just like by the SPC700, so e.g.
+/// setting a bit in KON keys on the voice.
+#[derive(Default)]
+pub struct DspTab;
+
+impl super::Tab for DspTab {
+    fn title(&self) -> &str {
+        "DSP"
+    }
+
+    fn ui(&mut self, emulation_state: &mut crate::EmulationState, ui: &mut egui::Ui) {
+        // Only written when edited, writing KON or ENDX has side effects
+        fn show_reg(ui: &mut egui::Ui, dsp: &mut Dsp, addr: u8) {
+            let mut value = dsp.read(addr);
+            let drag_value = egui::DragValue::new(&mut value).hexadecimal(2, false, true);
+            if ui.add(drag_value).changed() {
+                dsp.write(addr, value);
+            }
+        }
+
+        let dsp = &mut emulation_state.snes.apu.dsp;
+
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("dsp-voices").striped(true).show(ui, |ui| {
+                ui.label("Voice");
+                for name in VOICE_REGISTERS {
+                    ui.label(name);
+                }
+                ui.end_row();
+
+                for voice in 0..8u8 {
+                    ui.label(voice.to_string());
+                    for reg in 0..VOICE_REGISTERS.len() as u8 {
+                        show_reg(ui, dsp, voice << 4 | reg);
+                    }
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+
+            ui.horizontal_top(|ui| {
+                egui::Grid::new("dsp-globals").striped(true).show(ui, |ui| {
+                    for (idx, (name, addr)) in GLOBAL_REGISTERS.into_iter().enumerate() {
+                        ui.label(name);
+                        show_reg(ui, dsp, addr);
+                        if idx % 4 == 3 {
+                            ui.end_row();
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                // Coefficients of the echo FIR filter
+                egui::Grid::new("dsp-fir").striped(true).show(ui, |ui| {
+                    for coefficient in 0..8u8 {
+                        ui.label(format!("C{coefficient}"));
+                        show_reg(ui, dsp, coefficient << 4 | 0x0F);
+                        if coefficient % 4 == 3 {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+        });
+    }
+}
@@ -1,16 +1,207 @@
 use std::{cmp, ops::RangeInclusive};
 
 use egui::{Ui, Widget};
-use snes_emu::{Snes, cpu::HvIrq};
+use snes_emu::{
+    Snes,
+    cpu::{CpuDebug, HvIrq, InstructionStats, Watchpoint},
+};
 
 use crate::EmulationState;
 
 use super::Tab;
 
-#[derive(Default)]
 pub struct CpuTab {
     create_addr_input: String,
     create_addr: Option<u32>,
+    watch_addr_input: String,
+    watch_end_input: String,
+    watch_value_input: String,
+    watch_read: bool,
+    watch_write: bool,
+    pc_input: String,
+    trace_start_input: String,
+    trace_end_input: String,
+}
+
+impl Default for CpuTab {
+    fn default() -> Self {
+        Self {
+            create_addr_input: String::new(),
+            create_addr: None,
+            watch_addr_input: String::new(),
+            watch_end_input: String::new(),
+            watch_value_input: String::new(),
+            watch_read: false,
+            watch_write: true,
+            pc_input: String::new(),
+            trace_start_input: String::new(),
+            trace_end_input: String::new(),
+        }
+    }
+}
+
+impl CpuTab {
+    /// Starts writing the trace log to a file, see [`snes_emu::cpu::write_trace_line`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn trace_ui(&mut self, debug: &mut CpuDebug, ui: &mut Ui) {
+        // Empty addresses leave that end of the range open, both empty trace everything
+        let start = match self.trace_start_input.is_empty() {
+            true => Some(0x000000),
+            false => u32::from_str_radix(&self.trace_start_input, 16).ok(),
+        };
+        let end = match self.trace_end_input.is_empty() {
+            true => Some(0xFFFFFF),
+            false => u32::from_str_radix(&self.trace_end_input, 16)
+                .ok()
+                .filter(|&end| start.is_none_or(|start| start <= end)),
+        };
+
+        ui.horizontal(|ui| {
+            let mut start_edit = egui::TextEdit::singleline(&mut self.trace_start_input)
+                .hint_text("Start")
+                .desired_width(100.0);
+            if start.is_none() {
+                start_edit = start_edit.text_color(egui::Color32::LIGHT_RED);
+            }
+            start_edit.ui(ui);
+
+            let mut end_edit = egui::TextEdit::singleline(&mut self.trace_end_input)
+                .hint_text("End")
+                .desired_width(100.0);
+            if end.is_none() {
+                end_edit = end_edit.text_color(egui::Color32::LIGHT_RED);
+            }
+            end_edit.ui(ui);
+        });
+
+        if let (Some(start), Some(end)) = (start, end) {
+            debug.trace_range =
+                match self.trace_start_input.is_empty() && self.trace_end_input.is_empty() {
+                    true => None,
+                    false => Some(start..=end),
+                };
+        }
+
+        if debug.trace.is_some() {
+            // Dropping the writer flushes it
+            if ui.button("Stop Trace").clicked() {
+                debug.trace = None;
+            }
+            return;
+        }
+        if ui.button("Trace to File").clicked()
+            && let Some(path) = rfd::FileDialog::new()
+                .add_filter("Trace log", &["log", "txt"])
+                .set_file_name("trace.log")
+                .save_file()
+        {
+            match std::fs::File::create(&path) {
+                Ok(file) => debug.trace = Some(Box::new(std::io::BufWriter::new(file))),
+                Err(err) => tracing::error!("Failed to create {}: {err}", path.display()),
+            }
+        }
+    }
+
+    fn watchpoints_ui(&mut self, debug: &mut CpuDebug, ui: &mut Ui) {
+        let start = u32::from_str_radix(&self.watch_addr_input, 16).ok();
+        // An empty end address watches a single address
+        let end = match self.watch_end_input.is_empty() {
+            true => start,
+            false => u32::from_str_radix(&self.watch_end_input, 16)
+                .ok()
+                .filter(|&end| start.is_none_or(|start| start <= end)),
+        };
+        // An empty value matches any access
+        let value = match self.watch_value_input.is_empty() {
+            true => Some(None),
+            false => u8::from_str_radix(&self.watch_value_input, 16)
+                .ok()
+                .map(Some),
+        };
+
+        ui.horizontal(|ui| {
+            let mut addr_edit = egui::TextEdit::singleline(&mut self.watch_addr_input)
+                .hint_text("Address")
+                .desired_width(100.0);
+            if start.is_none() {
+                addr_edit = addr_edit.text_color(egui::Color32::LIGHT_RED);
+            }
+            addr_edit.ui(ui);
+
+            let mut end_edit = egui::TextEdit::singleline(&mut self.watch_end_input)
+                .hint_text("End")
+                .desired_width(100.0);
+            if end.is_none() {
+                end_edit = end_edit.text_color(egui::Color32::LIGHT_RED);
+            }
+            end_edit.ui(ui);
+
+            let mut value_edit = egui::TextEdit::singleline(&mut self.watch_value_input)
+                .hint_text("Value")
+                .desired_width(40.0);
+            if value.is_none() {
+                value_edit = value_edit.text_color(egui::Color32::LIGHT_RED);
+            }
+            value_edit.ui(ui);
+
+            ui.checkbox(&mut self.watch_read, "R");
+            ui.checkbox(&mut self.watch_write, "W");
+
+            if ui.button("Create Watchpoint").clicked()
+                && let (Some(start), Some(end), Some(value)) = (start, end, value)
+                && (self.watch_read || self.watch_write)
+            {
+                let watchpoint = Watchpoint {
+                    start,
+                    end,
+                    read: self.watch_read,
+                    write: self.watch_write,
+                    value,
+                };
+                if !debug.watchpoints.contains(&watchpoint) {
+                    debug.watchpoints.push(watchpoint);
+                }
+                self.watch_addr_input.clear();
+                self.watch_end_input.clear();
+                self.watch_value_input.clear();
+            }
+        });
+        ui.checkbox(&mut debug.watch_dma, "Include DMA and HDMA transfers");
+
+        let mut delete_watchpoint = None;
+
+        egui::Grid::new("cpu-watchpoints")
+            .striped(true)
+            .show(ui, |ui| {
+                for (idx, watchpoint) in debug.watchpoints.iter().enumerate() {
+                    match watchpoint.start == watchpoint.end {
+                        true => ui.monospace(format!("${:06X}", watchpoint.start)),
+                        false => ui.monospace(format!(
+                            "${:06X}-${:06X}",
+                            watchpoint.start, watchpoint.end
+                        )),
+                    };
+                    let kind = match (watchpoint.read, watchpoint.write) {
+                        (true, true) => "RW",
+                        (true, false) => "R",
+                        _ => "W",
+                    };
+                    ui.monospace(kind);
+                    match watchpoint.value {
+                        Some(value) => ui.monospace(format!("= ${value:02X}")),
+                        None => ui.monospace("any"),
+                    };
+                    if ui.small_button("-").clicked() {
+                        delete_watchpoint = Some(idx);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(delete_watchpoint) = delete_watchpoint {
+            debug.watchpoints.remove(delete_watchpoint);
+        }
+    }
 }
 
 impl Tab for CpuTab {
@@ -62,15 +253,14 @@ impl Tab for CpuTab {
 
                 ui.horizontal(|ui| {
                     if ui.button("Step CPU").clicked() {
-                        assert_eq!(
-                            emulation_state.snes.step(),
-                            snes_emu::cpu::StepResult::Stepped
-                        );
+                        let result = emulation_state.snes.step();
+                        emulation_state.handle_step_result(result);
                         emulation_state.update_displayed_image();
                     }
 
                     if ui.button("Step Frame").clicked() {
-                        emulation_state.snes.run();
+                        let result = emulation_state.snes.run();
+                        emulation_state.handle_step_result(result);
                         emulation_state.update_displayed_image();
                     }
 
@@ -88,16 +278,54 @@ impl Tab for CpuTab {
                 });
 
                 ui.horizontal(|ui| {
-                    let cpu = &mut emulation_state.snes.cpu;
-
-                    if ui.button("Reset").clicked() {
-                        cpu.raise_interrupt(snes_emu::cpu::Interrupt::Reset);
+                    let addr = u32::from_str_radix(&self.pc_input, 16)
+                        .ok()
+                        .filter(|&addr| addr <= 0xFF_FFFF);
+
+                    let mut pc_edit = egui::TextEdit::singleline(&mut self.pc_input)
+                        .hint_text("Address")
+                        .desired_width(100.0);
+                    if addr.is_none() {
+                        pc_edit = pc_edit.text_color(egui::Color32::LIGHT_RED);
                     }
-                    if ui.button("IRQ").clicked() {
-                        cpu.raise_interrupt(snes_emu::cpu::Interrupt::Irq);
+                    pc_edit.ui(ui);
+
+                    if ui
+                        .button("Set PC")
+                        .on_hover_text("Continues execution at the given 24 bit address")
+                        .clicked()
+                        && let Some(addr) = addr
+                    {
+                        emulation_state.snes.set_pc(addr);
+                        self.pc_input.clear();
                     }
-                    if ui.button("NMI").clicked() {
-                        cpu.raise_interrupt(snes_emu::cpu::Interrupt::Nmi);
+                });
+
+                ui.horizontal(|ui| {
+                    use snes_emu::cpu::Interrupt;
+
+                    let cpu = &mut emulation_state.snes.cpu;
+
+                    ui.label("Raise:");
+                    for (interrupt, name, hint) in [
+                        (Interrupt::Reset, "Reset", "Resets the CPU, PPU and APU"),
+                        (
+                            Interrupt::Irq,
+                            "IRQ",
+                            "Stays pending until acknowledged by reading TIMEUP ($4211) and is \
+                             masked by the I flag",
+                        ),
+                        (
+                            Interrupt::Nmi,
+                            "NMI",
+                            "Serviced before the next instruction",
+                        ),
+                    ] {
+                        let pending = cpu.is_interrupt_pending(interrupt);
+                        let button = egui::Button::new(name).selected(pending);
+                        if ui.add(button).on_hover_text(hint).clicked() {
+                            cpu.raise_interrupt(interrupt);
+                        }
                     }
                 });
             });
@@ -161,6 +389,9 @@ impl Tab for CpuTab {
                 if let Some(delete_breakpoint) = delete_breakpoint {
                     breakpoints.remove(delete_breakpoint);
                 }
+
+                ui.separator();
+                self.watchpoints_ui(&mut emulation_state.snes.cpu.debug, ui);
             });
 
             ui.vertical(|ui| {
@@ -176,6 +407,8 @@ impl Tab for CpuTab {
             });
 
             ui.vertical(|ui| {
+                #[cfg(not(target_arch = "wasm32"))]
+                self.trace_ui(&mut emulation_state.snes.cpu.debug, ui);
                 egui::ScrollArea::vertical()
                     .id_salt("cpu-history-scroll-area")
                     .show(ui, |ui| {
@@ -407,3 +640,117 @@ fn dump_instructions(snes: &Snes) {
         writeln!(writer, "{hor_line_end} {instr}").unwrap();
     }
 }
+
+#[derive(Default)]
+pub struct InstructionStatsTab {
+    status: Option<Result<String, String>>,
+}
+
+impl InstructionStatsTab {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export(stats: &InstructionStats) -> Result<String, String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .set_file_name("instructions.txt")
+            .save_file()
+        else {
+            return Err("Export cancelled".to_owned());
+        };
+
+        let mut out = Vec::new();
+        stats.dump(&mut out).unwrap();
+        std::fs::write(&path, out)
+            .map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+        Ok(format!("Exported statistics to {}", path.display()))
+    }
+}
+
+impl Tab for InstructionStatsTab {
+    fn title(&self) -> &str {
+        "Instructions"
+    }
+
+    fn ui(&mut self, emulation_state: &mut EmulationState, ui: &mut Ui) {
+        let stats = &mut emulation_state.snes.cpu.debug.instruction_stats;
+
+        ui.horizontal(|ui| {
+            let mut enabled = stats.is_some();
+            if ui.checkbox(&mut enabled, "Collect").changed() {
+                *stats = enabled.then(InstructionStats::default);
+            }
+            if let Some(stats) = stats.as_mut() {
+                if ui.button("Reset").clicked() {
+                    *stats = InstructionStats::default();
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Export").clicked() {
+                    self.status = Some(Self::export(stats));
+                }
+            }
+        });
+
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(message)) => {
+                ui.colored_label(ui.visuals().error_fg_color, message);
+            }
+            None => {}
+        }
+
+        let Some(stats) = stats else {
+            return;
+        };
+
+        ui.label(format!("{} instructions executed", stats.total()));
+        if stats.untracked != 0 {
+            ui.label(format!(
+                "{} executed at addresses beyond the first {}, which aren't listed",
+                stats.untracked,
+                InstructionStats::MAX_PCS,
+            ));
+        }
+        ui.separator();
+
+        ui.horizontal_top(|ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("instruction-stats-opcodes")
+                .show(ui, |ui| {
+                    egui::Grid::new("instruction-stats-opcodes")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Opcode");
+                            ui.label("Count");
+                            ui.end_row();
+                            for (opcode, mnemonic, count) in stats.top_opcodes(64) {
+                                ui.monospace(format!("{opcode:02X} {mnemonic}"));
+                                ui.monospace(count.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            egui::ScrollArea::vertical()
+                .id_salt("instruction-stats-pcs")
+                .show(ui, |ui| {
+                    egui::Grid::new("instruction-stats-pcs")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Address");
+                            ui.label("Count");
+                            ui.label("Instruction");
+                            ui.end_row();
+                            for (pc, count, instruction) in stats.hottest_pcs(64) {
+                                ui.monospace(format!("${pc:06X}"));
+                                ui.monospace(count.to_string());
+                                ui.monospace(instruction.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+
+        ui.ctx().request_repaint();
+    }
+}
@@ -11,7 +11,7 @@ impl super::Tab for DmaTab {
 
         fn show_channel(ui: &mut egui::Ui, snes: &mut snes_emu::Snes, idx: usize) {
             use snes_emu::cpu::dma::{
-                ABusAddressStep, AddressingMode, TransferDirection, TransferUnitSelect,
+                ABusAddressStep, AddressingMode, DMAP, TransferDirection, TransferUnitSelect,
             };
 
             fn show_reg_u8(ui: &mut egui::Ui, value: &mut u8) {
@@ -26,6 +26,10 @@ impl super::Tab for DmaTab {
 
             let channel = &mut snes.cpu.dma.channels[idx];
 
+            let mut dmap = channel.dmap.to_bits();
+            show_reg_u8(ui, &mut dmap);
+            channel.dmap = DMAP::from_bits(dmap);
+
             enum_combobox!(
                 ui,
                 egui::Id::new("transfer-direction").with(idx),
@@ -89,10 +93,14 @@ impl super::Tab for DmaTab {
             ui.end_row();
         }
 
+        show_cycle_usage(ui, &snes.cpu.debug.last_frame_cycle_usage);
+        ui.separator();
+
         egui::Grid::new("dma-channels")
             .striped(true)
             .show(ui, |ui| {
                 ui.label("");
+                ui.label("DMAP");
                 ui.label("Direction");
                 ui.label("Mode");
                 ui.label("Step");
@@ -112,5 +120,36 @@ impl super::Tab for DmaTab {
                     show_channel(ui, snes, idx);
                 }
             });
+
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Shows how the master cycles of the last frame were split between the CPU and (H)DMA as a bar.
+fn show_cycle_usage(ui: &mut egui::Ui, usage: &snes_emu::cpu::CycleUsage) {
+    let parts = [
+        ("CPU", usage.cpu(), egui::Color32::from_gray(96)),
+        ("DMA", usage.mdma, egui::Color32::ORANGE),
+        ("HDMA", usage.hdma, egui::Color32::LIGHT_GREEN),
+    ];
+    let frame = usage.frame.max(1) as f32;
+
+    let size = egui::vec2(ui.available_width(), 16.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+
+    let mut x = rect.min.x;
+    for (_, cycles, color) in parts {
+        let width = cycles as f32 / frame * rect.width();
+        let part = egui::Rect::from_min_size(egui::pos2(x, rect.min.y), egui::vec2(width, 16.0));
+        painter.rect_filled(part, 0.0, color);
+        x += width;
     }
+
+    ui.horizontal(|ui| {
+        for (label, cycles, color) in parts {
+            let percent = cycles as f32 / frame * 100.0;
+            ui.colored_label(color, format!("{label}: {cycles} ({percent:.1}%)"));
+        }
+    });
 }
@@ -43,7 +43,81 @@ impl Tab for BusTab {
             ui,
             &mut emulation_state.snes,
             |emu, addr| cpu::memory::read_pure(emu, addr as u32),
-            |emu, addr, value| cpu::memory::write(emu, addr as u32, value),
+            // Edits by the user don't take any cycles and must not trigger watchpoints
+            |emu, addr, value| {
+                cpu::memory::write_with_cycle_counting(emu, addr as u32, value, false)
+            },
         );
     }
 }
+
+#[derive(Default)]
+pub struct WRamHeatmapTab {
+    texture: Option<egui::TextureHandle>,
+}
+
+impl WRamHeatmapTab {
+    const WIDTH: usize = 512;
+    const HEIGHT: usize = 0x20000 / Self::WIDTH;
+}
+
+impl Tab for WRamHeatmapTab {
+    fn title(&self) -> &str {
+        "WRAM Heatmap"
+    }
+
+    fn ui(&mut self, emulation_state: &mut EmulationState, ui: &mut Ui) {
+        let snes = &mut emulation_state.snes;
+
+        let mut enabled = snes.wram_heatmap().is_some();
+        if ui.checkbox(&mut enabled, "Track Accesses").changed() {
+            snes.set_wram_heatmap_enabled(enabled);
+        }
+        ui.label("Green: reads, red: writes. Each pixel is one byte, 512 bytes per row.");
+
+        let Some(heatmap) = snes.wram_heatmap() else {
+            self.texture = None;
+            return;
+        };
+
+        let mut image = egui::ColorImage::filled([Self::WIDTH, Self::HEIGHT], egui::Color32::BLACK);
+        for (pixel, (&reads, &writes)) in image
+            .pixels
+            .iter_mut()
+            .zip(heatmap.reads.iter().zip(heatmap.writes.iter()))
+        {
+            *pixel = egui::Color32::from_rgb(writes, reads, 0);
+        }
+
+        let options = egui::TextureOptions::NEAREST;
+        let texture = match self.texture.take() {
+            Some(mut texture) => {
+                texture.set(image, options);
+                self.texture.insert(texture)
+            }
+            None => self
+                .texture
+                .insert(ui.ctx().load_texture("wram-heatmap", image, options)),
+        };
+
+        let response = ui.image(egui::load::SizedTexture::new(
+            texture.id(),
+            texture.size_vec2(),
+        ));
+
+        if let Some(pos) = response.hover_pos() {
+            let rel = (pos - response.rect.min) / response.rect.size();
+            let x = ((rel.x * Self::WIDTH as f32) as usize).min(Self::WIDTH - 1);
+            let y = ((rel.y * Self::HEIGHT as f32) as usize).min(Self::HEIGHT - 1);
+            let addr = y * Self::WIDTH + x;
+            response.on_hover_text(format!(
+                "${:06X}: {} reads, {} writes",
+                0x7E0000 + addr,
+                heatmap.reads[addr],
+                heatmap.writes[addr],
+            ));
+        }
+
+        ui.ctx().request_repaint();
+    }
+}
@@ -1,14 +1,15 @@
 use egui::{Id, Ui};
 use egui_dock::{DockArea, DockState, NodeIndex, NodePath, TabViewer};
 
-use apu::{ApuRamTab, ApuTab};
-use cpu::CpuTab;
+use apu::{ApuRamTab, ApuTab, DspTab};
+use cpu::{CpuTab, InstructionStatsTab};
 use dma::DmaTab;
-use mem::BusTab;
+use mem::{BusTab, WRamHeatmapTab};
 use ppu::{
     PpuBackgroundsTab, PpuCgRamTab, PpuMiscTab, PpuOamTab, PpuObjectsTab, PpuScreensTab,
-    PpuSpritesTab, PpuVRamTab, PpuWindowsTab,
+    PpuSpritesTab, PpuTilemapTab, PpuTimingTab, PpuVRamTab, PpuWindowsTab,
 };
+use script::ScriptTab;
 
 use crate::{EmulationState, game_view::GameView};
 
@@ -48,6 +49,8 @@ mod cpu;
 mod dma;
 mod mem;
 mod ppu;
+mod script;
+mod tile_io;
 
 struct TabWithId {
     tab: Box<dyn Tab>,
@@ -101,6 +104,7 @@ impl<'a> TabViewer for DebugTabViewer<'a> {
         }
 
         tab_button::<CpuTab>("CPU", &mut self.added_tabs, path, ui);
+        tab_button::<InstructionStatsTab>("Instructions", &mut self.added_tabs, path, ui);
         ui.menu_button("Memory", |ui| {
             egui::menu::menu_style(ui.style_mut());
             tab_button::<BusTab>("CPU", &mut self.added_tabs, path, ui);
@@ -109,17 +113,22 @@ impl<'a> TabViewer for DebugTabViewer<'a> {
             tab_button::<PpuVRamTab>("VRAM", &mut self.added_tabs, path, ui);
             tab_button::<PpuCgRamTab>("CGRAM", &mut self.added_tabs, path, ui);
             tab_button::<PpuSpritesTab>("Sprites", &mut self.added_tabs, path, ui);
+            tab_button::<WRamHeatmapTab>("WRAM Heatmap", &mut self.added_tabs, path, ui);
         });
         tab_button::<DmaTab>("DMA", &mut self.added_tabs, path, ui);
         ui.menu_button("PPU", |ui| {
             egui::menu::menu_style(ui.style_mut());
             tab_button::<PpuMiscTab>("Misc.", &mut self.added_tabs, path, ui);
             tab_button::<PpuBackgroundsTab>("Backgrounds", &mut self.added_tabs, path, ui);
+            tab_button::<PpuTilemapTab>("Tilemap", &mut self.added_tabs, path, ui);
             tab_button::<PpuObjectsTab>("Objects", &mut self.added_tabs, path, ui);
             tab_button::<PpuScreensTab>("Screens", &mut self.added_tabs, path, ui);
             tab_button::<PpuWindowsTab>("Windows", &mut self.added_tabs, path, ui);
+            tab_button::<PpuTimingTab>("Timing", &mut self.added_tabs, path, ui);
         });
         tab_button::<ApuTab>("APU", &mut self.added_tabs, path, ui);
+        tab_button::<DspTab>("DSP", &mut self.added_tabs, path, ui);
+        tab_button::<ScriptTab>("Script", &mut self.added_tabs, path, ui);
     }
 }
 
@@ -188,7 +197,11 @@ impl Default for Debugger {
             ],
         );
 
-        tree.split_below(right, 0.75, vec![generator.create(Box::new(ApuTab))]);
+        tree.split_below(
+            right,
+            0.75,
+            vec![generator.create(Box::new(ApuTab::default()))],
+        );
 
         Self {
             generator,
@@ -1,7 +1,12 @@
 use arbitrary_int::{traits::Integer, u3, u4, u6};
 use egui::Widget;
 use egui_memory_editor::MemoryEditor;
-use snes_emu::ppu::{MathEnable, PpuVariant, WindowMaskLogic};
+use snes_emu::{
+    cpu::TimingEventKind,
+    ppu::{Background, MathEnable, Ppu, PpuVariant, WindowMaskLogic},
+};
+
+use super::tile_io;
 
 #[derive(Default)]
 pub struct PpuMiscTab;
@@ -161,6 +166,147 @@ impl super::Tab for PpuBackgroundsTab {
     }
 }
 
+pub struct PpuTilemapTab {
+    background: usize,
+    show_viewport: bool,
+    texture: Option<egui::TextureHandle>,
+    /// The state the texture was decoded from, to only decode it again when something changed.
+    source: Option<TilemapSource>,
+}
+
+impl Default for PpuTilemapTab {
+    fn default() -> Self {
+        Self {
+            background: 0,
+            show_viewport: true,
+            texture: None,
+            source: None,
+        }
+    }
+}
+
+/// Everything [`Ppu::render_tilemap`] depends on.
+struct TilemapSource {
+    background_num: usize,
+    mode: u3,
+    background: Background,
+    vram: Box<[u8; 0x10000]>,
+    cgram: Box<[u8; 0x200]>,
+}
+
+impl TilemapSource {
+    fn capture(ppu: &Ppu, background_num: usize) -> Self {
+        Self {
+            background_num,
+            mode: ppu.backgrounds.mode,
+            background: Self::tilemap_registers(ppu, background_num),
+            vram: ppu.vram.clone(),
+            cgram: ppu.cgram.clone(),
+        }
+    }
+
+    /// The registers of the background, without the scroll offsets which don't change the tile map.
+    fn tilemap_registers(ppu: &Ppu, background_num: usize) -> Background {
+        Background {
+            h_offset: 0,
+            v_offset: 0,
+            ..ppu.backgrounds.backgrounds[background_num]
+        }
+    }
+
+    fn matches(&self, ppu: &Ppu, background_num: usize) -> bool {
+        self.background_num == background_num
+            && self.mode == ppu.backgrounds.mode
+            && self.background == Self::tilemap_registers(ppu, background_num)
+            && self.vram == ppu.vram
+            && self.cgram == ppu.cgram
+    }
+}
+
+impl super::Tab for PpuTilemapTab {
+    fn title(&self) -> &str {
+        "PPU - Tilemap"
+    }
+
+    fn ui(&mut self, emulation_state: &mut crate::EmulationState, ui: &mut egui::Ui) {
+        let ppu = &emulation_state.snes.ppu;
+
+        ui.horizontal(|ui| {
+            for bg_num in 0..4 {
+                ui.selectable_value(&mut self.background, bg_num, format!("BG{}", bg_num + 1));
+            }
+            ui.checkbox(&mut self.show_viewport, "Show Viewport");
+        });
+
+        if ppu.backgrounds.mode.value() == 7 {
+            ui.label("Mode 7 isn't supported");
+            return;
+        }
+
+        let up_to_date = self
+            .source
+            .as_ref()
+            .is_some_and(|source| source.matches(ppu, self.background));
+        if !up_to_date {
+            let Some((width, height, rgb)) = ppu.render_tilemap(self.background) else {
+                ui.label(format!(
+                    "BG{} isn't used in Mode {}",
+                    self.background + 1,
+                    ppu.backgrounds.mode
+                ));
+                self.source = None;
+                return;
+            };
+
+            let image = egui::ColorImage::from_rgb([usize::from(width), usize::from(height)], &rgb);
+            let options = egui::TextureOptions::NEAREST;
+            match &mut self.texture {
+                Some(texture) => texture.set(image, options),
+                None => {
+                    self.texture = Some(ui.ctx().load_texture("tilemap-preview", image, options));
+                }
+            }
+            self.source = Some(TilemapSource::capture(ppu, self.background));
+        }
+        let texture = self.texture.as_ref().unwrap();
+        let [width, height] = texture.size().map(|size| size as u16);
+
+        egui::ScrollArea::both()
+            .id_salt("ppu-tilemap-scroll-area")
+            .show(ui, |ui| {
+                let response = ui.image(egui::load::SizedTexture::new(
+                    texture.id(),
+                    texture.size_vec2(),
+                ));
+                if !self.show_viewport {
+                    return;
+                }
+
+                // The scroll offsets count in pixels of the regular resolution even in the hires
+                // modes, and the first visible scanline is line 1
+                let bg = &ppu.backgrounds.backgrounds[self.background];
+                let scale = match matches!(ppu.backgrounds.mode.value(), 5 | 6) {
+                    true => 2,
+                    false => 1,
+                };
+                let x = (bg.h_offset & 0x3FF) * scale % width;
+                let y = (bg.v_offset & 0x3FF).wrapping_add(1) % height;
+                let size = egui::vec2(f32::from(256 * scale), f32::from(ppu.output_height()));
+
+                // The viewport wraps around the tile map, so it is drawn once for every copy of the
+                // tile map it overlaps
+                let painter = ui.painter_at(response.rect);
+                let stroke = egui::Stroke::new(1.0, egui::Color32::RED);
+                for (dx, dy) in [(0, 0), (width, 0), (0, height), (width, height)] {
+                    let min = response.rect.min
+                        + egui::vec2(f32::from(x) - f32::from(dx), f32::from(y) - f32::from(dy));
+                    let rect = egui::Rect::from_min_size(min, size);
+                    painter.rect_stroke(rect, 0.0, stroke, egui::StrokeKind::Inside);
+                }
+            });
+    }
+}
+
 #[derive(Default)]
 pub struct PpuObjectsTab;
 
@@ -315,10 +461,10 @@ impl super::Tab for PpuWindowsTab {
                 }
 
                 ui.label("OBJ");
-                bitfield_checkbox(&mut ppuio.windows.w1en, 5, ui);
-                bitfield_checkbox(&mut ppuio.windows.w1inv, 5, ui);
-                bitfield_checkbox(&mut ppuio.windows.w2en, 5, ui);
-                bitfield_checkbox(&mut ppuio.windows.w2inv, 5, ui);
+                bitfield_checkbox(&mut ppuio.windows.w1en, 4, ui);
+                bitfield_checkbox(&mut ppuio.windows.w1inv, 4, ui);
+                bitfield_checkbox(&mut ppuio.windows.w2en, 4, ui);
+                bitfield_checkbox(&mut ppuio.windows.w2inv, 4, ui);
                 enum_combobox!(
                     ui,
                     egui::Id::new("ppu-window-logic").with(5),
@@ -329,15 +475,15 @@ impl super::Tab for PpuWindowsTab {
                     WindowMaskLogic::Xor => "xor",
                     WindowMaskLogic::Xnor => "xnor",
                 );
-                bitfield_checkbox(&mut ppuio.windows.tmw, 5, ui);
-                bitfield_checkbox(&mut ppuio.windows.tsw, 5, ui);
+                bitfield_checkbox(&mut ppuio.windows.tmw, 4, ui);
+                bitfield_checkbox(&mut ppuio.windows.tsw, 4, ui);
                 ui.end_row();
 
                 ui.label("Math");
-                bitfield_checkbox(&mut ppuio.windows.w1en, 6, ui);
-                bitfield_checkbox(&mut ppuio.windows.w1inv, 6, ui);
-                bitfield_checkbox(&mut ppuio.windows.w2en, 6, ui);
-                bitfield_checkbox(&mut ppuio.windows.w2inv, 6, ui);
+                bitfield_checkbox(&mut ppuio.windows.w1en, 5, ui);
+                bitfield_checkbox(&mut ppuio.windows.w1inv, 5, ui);
+                bitfield_checkbox(&mut ppuio.windows.w2en, 5, ui);
+                bitfield_checkbox(&mut ppuio.windows.w2inv, 5, ui);
                 enum_combobox!(
                     ui,
                     egui::Id::new("ppu-window-logic").with(6),
@@ -372,15 +518,188 @@ impl super::Tab for PpuWindowsTab {
     }
 }
 
+#[derive(Default)]
+pub struct PpuTimingTab;
+
+impl super::Tab for PpuTimingTab {
+    fn title(&self) -> &str {
+        "PPU - Timing"
+    }
+
+    fn ui(&mut self, emulation_state: &mut crate::EmulationState, ui: &mut egui::Ui) {
+        const DOTS_PER_LINE: u16 = 340;
+        const SCALE: f32 = 2.0;
+
+        fn event_color(kind: TimingEventKind) -> egui::Color32 {
+            match kind {
+                TimingEventKind::VBlankStart => egui::Color32::LIGHT_BLUE,
+                TimingEventKind::VBlankEnd => egui::Color32::BLUE,
+                TimingEventKind::Nmi => egui::Color32::RED,
+                TimingEventKind::HvIrq => egui::Color32::YELLOW,
+                TimingEventKind::HdmaReload => egui::Color32::LIGHT_GREEN,
+                TimingEventKind::HdmaTransfer => egui::Color32::DARK_GREEN,
+            }
+        }
+
+        let snes = &emulation_state.snes;
+        let h = snes.cpu.h_counter();
+        let v = snes.cpu.v_counter();
+        let lines = snes.ppu.max_vpos() + 1;
+        let output_height = snes.ppu.output_height();
+        let events = &snes.cpu.debug.last_frame_timing_events;
+
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.monospace(format!("H: {h:3}  V: {v:3}"));
+                ui.monospace(format!("Cycles: {}", snes.cpu.cycles()));
+
+                let size = egui::vec2(f32::from(DOTS_PER_LINE) * SCALE, f32::from(lines) * SCALE);
+                let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+                let origin = response.rect.min;
+                let dot_pos = |h: u16, v: u16| {
+                    origin + egui::vec2(f32::from(h) * SCALE, f32::from(v) * SCALE)
+                };
+
+                painter.rect_filled(response.rect, 0.0, egui::Color32::from_gray(16));
+                painter.rect_filled(
+                    egui::Rect::from_min_max(dot_pos(22, 1), dot_pos(278, output_height + 1)),
+                    0.0,
+                    egui::Color32::from_gray(48),
+                );
+
+                for event in events {
+                    painter.rect_filled(
+                        egui::Rect::from_min_size(
+                            dot_pos(event.h, event.v),
+                            egui::Vec2::splat(SCALE),
+                        ),
+                        0.0,
+                        event_color(event.kind),
+                    );
+                }
+
+                let stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+                painter.line_segment([dot_pos(0, v), dot_pos(DOTS_PER_LINE, v)], stroke);
+                painter.line_segment([dot_pos(h, 0), dot_pos(h, lines)], stroke);
+
+                if let Some(pos) = response.hover_pos() {
+                    let rel = (pos - origin) / SCALE;
+                    response.on_hover_text(format!("H: {}  V: {}", rel.x as u16, rel.y as u16));
+                }
+            });
+
+            ui.vertical(|ui| {
+                for kind in [
+                    TimingEventKind::VBlankStart,
+                    TimingEventKind::VBlankEnd,
+                    TimingEventKind::Nmi,
+                    TimingEventKind::HvIrq,
+                    TimingEventKind::HdmaReload,
+                    TimingEventKind::HdmaTransfer,
+                ] {
+                    ui.colored_label(event_color(kind), format!("{kind:?}"));
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("ppu-timing-events")
+                    .show(ui, |ui| {
+                        egui::Grid::new("ppu-timing-events-grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for event in events
+                                    .iter()
+                                    .filter(|event| event.kind != TimingEventKind::HdmaTransfer)
+                                {
+                                    ui.monospace(format!("{:3},{:3}", event.h, event.v));
+                                    ui.colored_label(
+                                        event_color(event.kind),
+                                        format!("{:?}", event.kind),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        });
+
+        ui.ctx().request_repaint();
+    }
+}
+
 pub struct PpuOamTab {
     memory_editor: MemoryEditor,
+    selected_object: Option<usize>,
 }
 
 impl Default for PpuOamTab {
     fn default() -> Self {
         let memory_editor = MemoryEditor::new().with_address_range("*", 0x0000..0x0220);
 
-        Self { memory_editor }
+        Self {
+            memory_editor,
+            selected_object: None,
+        }
+    }
+}
+
+impl PpuOamTab {
+    /// Lists the decoded objects, the same way the PPU reads them from OAM.
+    fn objects_ui(&mut self, ppu: &snes_emu::ppu::Ppu, ui: &mut egui::Ui) {
+        let sizes = ppu.obsel_size_selection.sizes();
+        let highlight = ui.visuals().selection.stroke.color;
+
+        egui::Grid::new("ppu-oam-objects")
+            .striped(true)
+            .show(ui, |ui| {
+                for header in ["#", "X", "Y", "Tile", "Pal", "Prio", "Flip", "Size"] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for i in 0..128 {
+                    let entry = &ppu.oam[i * 4..][..4];
+                    let flags1 = entry[3];
+                    let flags2 = ppu.oam[512 + i / 4] >> (i % 4 * 2);
+
+                    let x = (entry[0] as u16) | (flags2 as u16 & 0x01) << 8;
+                    let y = entry[1];
+                    let tile_number = (entry[2] as u16) | (flags1 as u16 & 0x01) << 8;
+                    let palette = flags1 >> 1 & 0x07;
+                    let priority = flags1 >> 4 & 0x03;
+                    let x_flip = flags1 >> 6 & 0x01 != 0;
+                    let y_flip = flags1 >> 7 & 0x01 != 0;
+                    let (width, height) = sizes[usize::from(flags2 >> 1 & 0x01)];
+
+                    let selected = self.selected_object == Some(i);
+                    if ui.selectable_label(selected, format!("{i:3}")).clicked() {
+                        self.selected_object = (!selected).then_some(i);
+                    }
+
+                    let cell = |ui: &mut egui::Ui, text: String| {
+                        let text = egui::RichText::new(text).monospace();
+                        match selected {
+                            true => ui.label(text.color(highlight)),
+                            false => ui.label(text),
+                        };
+                    };
+                    cell(ui, format!("{x:3}"));
+                    cell(ui, format!("{y:3}"));
+                    cell(ui, format!("{tile_number:03X}"));
+                    cell(ui, format!("{palette}"));
+                    cell(ui, format!("{priority}"));
+                    let flip = match (x_flip, y_flip) {
+                        (false, false) => "--",
+                        (true, false) => "H-",
+                        (false, true) => "-V",
+                        (true, true) => "HV",
+                    };
+                    cell(ui, flip.to_string());
+                    cell(ui, format!("{width}x{height}"));
+                    ui.end_row();
+                }
+            });
     }
 }
 
@@ -392,28 +711,22 @@ impl super::Tab for PpuOamTab {
     fn ui(&mut self, emulation_state: &mut crate::EmulationState, ui: &mut egui::Ui) {
         let ppuio = &mut emulation_state.snes.ppu;
 
-        /*
-        egui::DragValue::new(&mut self.current_object)
-            .clamp_range(0..=127)
-            .ui(ui);
-
-        let oam_offset =
-            usize::from(ppu.obsel.base_address().value()) + usize::from(self.current_object) * 4;
-
-        let aux = ppu.oam[512 + usize::from(self.current_object / 4)] >> (self.current_object * 2);
+        ui.horizontal_top(|ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("ppu-oam-objects-scroll-area")
+                .show(ui, |ui| self.objects_ui(ppuio, ui));
 
-        let attrs = ppu.oam[oam_offset + 3];
-        let x_coord = (ppu.oam[oam_offset] as u16) | ((aux & 1) as u16) << 8;
-        let y_coord = ppu.oam[oam_offset + 1];
-        let tile_number = (ppu.oam[oam_offset + 2] as u16) | ((attrs & 1) as u16) << 8;
-        */
+            ui.separator();
 
-        self.memory_editor.draw_editor_contents(
-            ui,
-            &mut ppuio.oam,
-            |mem, addr| Some(mem[addr]),
-            |mem, addr, value| mem[addr] = value,
-        );
+            ui.vertical(|ui| {
+                self.memory_editor.draw_editor_contents(
+                    ui,
+                    &mut ppuio.oam,
+                    |mem, addr| Some(mem[addr]),
+                    |mem, addr, value| mem[addr] = value,
+                );
+            });
+        });
     }
 }
 
@@ -477,6 +790,10 @@ pub struct PpuSpritesTab {
     bits_per_pixel: u8,
     direct_color: bool,
     texture: Option<egui::TextureHandle>,
+    palette: u8,
+    first_tile: u16,
+    num_tiles: u16,
+    status: Option<Result<String, String>>,
 }
 
 impl Default for PpuSpritesTab {
@@ -485,10 +802,78 @@ impl Default for PpuSpritesTab {
             bits_per_pixel: 2,
             direct_color: false,
             texture: None,
+            palette: 0,
+            first_tile: 0,
+            num_tiles: 256,
+            status: None,
         }
     }
 }
 
+impl PpuSpritesTab {
+    /// Number of tiles of the selected format which fit into VRAM.
+    fn vram_tiles(&self) -> u16 {
+        (0x10000 / (usize::from(self.bits_per_pixel) * 8)) as u16
+    }
+
+    /// Number of palettes of the selected format in CGRAM, including the ones used by objects.
+    fn cgram_palettes(&self) -> u8 {
+        (256 >> self.bits_per_pixel) as u8
+    }
+
+    /// Returns the VRAM byte range covered by the selected tiles.
+    fn tile_range(&self) -> std::ops::Range<usize> {
+        let bytes_per_tile = usize::from(self.bits_per_pixel) * 8;
+        let num_tiles = usize::from(self.vram_tiles());
+        let first_tile = usize::from(self.first_tile).min(num_tiles - 1);
+        let last_tile = (first_tile + usize::from(self.num_tiles)).min(num_tiles);
+        first_tile * bytes_per_tile..last_tile * bytes_per_tile
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn export_tiles(&self, ppu: &snes_emu::ppu::Ppu) -> Result<String, String> {
+        let palette = tile_io::palette_rgb(&ppu.cgram[..], self.bits_per_pixel, self.palette);
+        let tiles = &ppu.vram[self.tile_range()];
+        let png = tile_io::export_png(tiles, self.bits_per_pixel, &palette)
+            .map_err(|err| format!("Failed to encode PNG: {err}"))?;
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name("tiles.png")
+            .save_file()
+        else {
+            return Err("Export cancelled".to_owned());
+        };
+
+        std::fs::write(&path, png)
+            .map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+        Ok(format!("Exported tiles to {}", path.display()))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_tiles(&self, ppu: &mut snes_emu::ppu::Ppu) -> Result<String, String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .pick_file()
+        else {
+            return Err("Import cancelled".to_owned());
+        };
+
+        let png = std::fs::read(&path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let palette = tile_io::palette_rgb(&ppu.cgram[..], self.bits_per_pixel, self.palette);
+
+        // Decode into a copy first, so a malformed image doesn't leave VRAM partially overwritten
+        let range = self.tile_range();
+        let mut tiles = ppu.vram[range.clone()].to_vec();
+        tile_io::import_png(&png, self.bits_per_pixel, &palette, &mut tiles)
+            .map_err(|err| format!("Failed to import {}: {err}", path.display()))?;
+        ppu.vram[range].copy_from_slice(&tiles);
+
+        Ok(format!("Imported tiles from {}", path.display()))
+    }
+}
+
 impl super::Tab for PpuSpritesTab {
     fn title(&self) -> &str {
         "PPU - Sprites"
@@ -535,6 +920,11 @@ impl super::Tab for PpuSpritesTab {
                 if self.bits_per_pixel != 8 {
                     self.direct_color = false;
                 }
+                let vram_tiles = self.vram_tiles();
+                let cgram_palettes = self.cgram_palettes();
+                self.palette = self.palette.min(cgram_palettes - 1);
+                self.first_tile = self.first_tile.min(vram_tiles - 1);
+                self.num_tiles = self.num_tiles.min(vram_tiles);
 
                 changed |= ui
                     .add_enabled(
@@ -545,7 +935,51 @@ impl super::Tab for PpuSpritesTab {
 
                 changed |= ui.button("Update").clicked();
 
-                if changed {
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    egui::DragValue::new(&mut self.palette)
+                        .range(0..=cgram_palettes - 1)
+                        .ui(ui);
+                    ui.label("Palette");
+                });
+                ui.horizontal(|ui| {
+                    egui::DragValue::new(&mut self.first_tile)
+                        .hexadecimal(3, false, true)
+                        .range(0..=vram_tiles - 1)
+                        .ui(ui);
+                    ui.label("First Tile");
+                });
+                ui.horizontal(|ui| {
+                    egui::DragValue::new(&mut self.num_tiles)
+                        .range(1..=vram_tiles)
+                        .ui(ui);
+                    ui.label("Tile Count");
+                });
+
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.horizontal(|ui| {
+                    if ui.button("Export PNG").clicked() {
+                        self.status = Some(self.export_tiles(&emulation_state.snes.ppu));
+                    }
+                    if ui.button("Import PNG").clicked() {
+                        let result = self.import_tiles(&mut emulation_state.snes.ppu);
+                        changed |= result.is_ok();
+                        self.status = Some(result);
+                    }
+                });
+
+                match &self.status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(message)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, message);
+                    }
+                    None => {}
+                }
+
+                if changed && let Some(texture) = &mut self.texture {
                     let vram = emulation_state.snes.ppu.vram.as_mut();
                     let image = compute_vram_image(vram, self.bits_per_pixel);
                     texture.set(image, options);
@@ -572,22 +1006,7 @@ fn compute_vram_image(vram: &[u8], bpp: u8) -> egui::ColorImage {
         let sprite_y = sprite_idx * 8 / image_size[0] * 8;
         let vram_offset = sprite_idx * bytes_per_sprite;
 
-        let mut sprite = [0; 64];
-
-        for plane_offset in (0..(bpp as usize)).step_by(2) {
-            for y in 0..8 {
-                let line = &mut sprite[(y * 8)..][..8];
-
-                let plane1 = vram[vram_offset + y * 2 + plane_offset * 8];
-                let plane2 = vram[vram_offset + y * 2 + plane_offset * 8 + 1];
-
-                for (x, line) in line.iter_mut().enumerate().take(8) {
-                    let bit1 = plane1.rotate_left(x as u32 + 1) & 1;
-                    let bit2 = plane2.rotate_left(x as u32 + 1) & 1;
-                    *line = *line << 2 | bit2 << 1 | bit1;
-                }
-            }
-        }
+        let sprite = tile_io::decode_tile(&vram[vram_offset..], bpp);
 
         for y in 0..8 {
             for x in 0..8 {
@@ -0,0 +1,71 @@
+use crate::scripting::ScriptHost;
+
+#[derive(Default)]
+pub struct ScriptTab {
+    source: String,
+    error: Option<String>,
+}
+
+impl super::Tab for ScriptTab {
+    fn title(&self) -> &str {
+        "Script"
+    }
+
+    fn ui(&mut self, emulation_state: &mut crate::EmulationState, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Run").clicked() {
+                match ScriptHost::new(&self.source, &mut emulation_state.snes) {
+                    Ok(script) => {
+                        emulation_state.script = Some(script);
+                        self.error = None;
+                    }
+                    Err(err) => {
+                        emulation_state.script = None;
+                        self.error = Some(err.to_string());
+                    }
+                }
+            }
+
+            let running = emulation_state.script.is_some();
+            if ui.add_enabled(running, egui::Button::new("Stop")).clicked() {
+                emulation_state.script = None;
+            }
+
+            if let Some(script) = &emulation_state.script
+                && ui.button("Clear Log").clicked()
+            {
+                script.clear_log();
+            }
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("script-source")
+            .max_height(ui.available_height() * 0.7)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.source)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(20),
+                );
+            });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("script-log")
+            .stick_to_bottom(true)
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                if let Some(script) = &emulation_state.script {
+                    for line in script.log().iter() {
+                        ui.monospace(line);
+                    }
+                }
+            });
+    }
+}
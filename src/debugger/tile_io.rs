@@ -0,0 +1,253 @@
+//! Conversion between planar SNES tiles and indexed PNG images.
+
+use std::{error::Error, io::Cursor};
+
+/// Number of tiles per row in exported images.
+const TILES_PER_ROW: usize = 16;
+
+/// Decodes a single 8x8 tile in the planar SNES format into one palette index per pixel.
+pub fn decode_tile(tile: &[u8], bpp: u8) -> [u8; 64] {
+    let mut pixels = [0; 64];
+
+    for plane_offset in (0..usize::from(bpp)).step_by(2) {
+        for y in 0..8 {
+            let plane1 = tile[y * 2 + plane_offset * 8];
+            let plane2 = tile[y * 2 + plane_offset * 8 + 1];
+
+            for x in 0..8 {
+                let bit1 = plane1 >> (7 - x) & 1;
+                let bit2 = plane2 >> (7 - x) & 1;
+                pixels[y * 8 + x] |= (bit1 | bit2 << 1) << plane_offset;
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Encodes palette indices into a tile in the planar SNES format. This is the inverse of
+/// [`decode_tile`].
+pub fn encode_tile(pixels: &[u8; 64], bpp: u8, tile: &mut [u8]) {
+    for plane_offset in (0..usize::from(bpp)).step_by(2) {
+        for y in 0..8 {
+            let mut plane1 = 0;
+            let mut plane2 = 0;
+
+            for x in 0..8 {
+                let value = pixels[y * 8 + x] >> plane_offset;
+                plane1 |= (value & 1) << (7 - x);
+                plane2 |= (value >> 1 & 1) << (7 - x);
+            }
+
+            tile[y * 2 + plane_offset * 8] = plane1;
+            tile[y * 2 + plane_offset * 8 + 1] = plane2;
+        }
+    }
+}
+
+/// Converts the colors of the given palette from CGRAM into RGB triples.
+pub fn palette_rgb(cgram: &[u8], bpp: u8, palette: u8) -> Vec<[u8; 3]> {
+    let num_colors = 1usize << bpp;
+    let first_color = usize::from(palette) * num_colors % 256;
+
+    (first_color..first_color + num_colors)
+        .map(|i| {
+            let color = u16::from_le_bytes([cgram[i * 2], cgram[i * 2 + 1]]);
+            [0, 5, 10].map(|shift| {
+                let c = (color >> shift & 0x1F) as u8;
+                c << 3 | c >> 2
+            })
+        })
+        .collect()
+}
+
+/// Encodes the given tiles into an indexed PNG with 16 tiles per row.
+pub fn export_png(tiles: &[u8], bpp: u8, palette: &[[u8; 3]]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes_per_tile = usize::from(bpp) * 8;
+    let num_tiles = tiles.len() / bytes_per_tile;
+    let width = TILES_PER_ROW * 8;
+    let height = num_tiles.div_ceil(TILES_PER_ROW) * 8;
+
+    let mut indices = vec![0; width * height];
+    for (i, tile) in tiles.chunks_exact(bytes_per_tile).enumerate() {
+        let pixels = decode_tile(tile, bpp);
+        let tile_x = i % TILES_PER_ROW * 8;
+        let tile_y = i / TILES_PER_ROW * 8;
+        for (y, row) in pixels.chunks_exact(8).enumerate() {
+            let start = (tile_y + y) * width + tile_x;
+            indices[start..start + 8].copy_from_slice(row);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.as_flattened());
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&indices)?;
+    writer.finish()?;
+
+    Ok(png)
+}
+
+/// Decodes a PNG (as created by [`export_png`]) back into tiles, overwriting `tiles`.
+///
+/// Indexed images are used as is, other images must only contain colors from the palette.
+pub fn import_png(
+    png: &[u8],
+    bpp: u8,
+    palette: &[[u8; 3]],
+    tiles: &mut [u8],
+) -> Result<(), Box<dyn Error>> {
+    let indices = decode_png_indices(png, palette)?;
+    let width = TILES_PER_ROW * 8;
+    let bytes_per_tile = usize::from(bpp) * 8;
+
+    for (i, tile) in tiles.chunks_exact_mut(bytes_per_tile).enumerate() {
+        let tile_x = i % TILES_PER_ROW * 8;
+        let tile_y = i / TILES_PER_ROW * 8;
+
+        let mut pixels = [0; 64];
+        for (y, row) in pixels.chunks_exact_mut(8).enumerate() {
+            let start = (tile_y + y) * width + tile_x;
+            let Some(src) = indices.get(start..start + 8) else {
+                return Err("image is too small".into());
+            };
+            row.copy_from_slice(src);
+        }
+
+        if pixels
+            .iter()
+            .any(|&index| usize::from(index) >= palette.len())
+        {
+            return Err(format!("tile {i} uses colors outside of the palette").into());
+        }
+
+        encode_tile(&pixels, bpp, tile);
+    }
+
+    Ok(())
+}
+
+/// Returns one palette index per pixel of an image which is 16 tiles wide.
+fn decode_png_indices(png: &[u8], palette: &[[u8; 3]]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (info, buf) = decode_png(png, png::Transformations::IDENTITY)?;
+
+    if info.width as usize != TILES_PER_ROW * 8 {
+        return Err(format!("image must be {} pixels wide", TILES_PER_ROW * 8).into());
+    }
+
+    // Indexed images can be used directly, everything else has to be mapped to the palette by color
+    if info.color_type == png::ColorType::Indexed && info.bit_depth == png::BitDepth::Eight {
+        return Ok(buf);
+    }
+
+    let transformations = png::Transformations::EXPAND | png::Transformations::STRIP_16;
+    let (info, buf) = decode_png(png, transformations)?;
+    let channels = match info.color_type {
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        _ => return Err("image must either be indexed or RGB(A)".into()),
+    };
+
+    buf.chunks_exact(channels)
+        .map(|pixel| {
+            palette
+                .iter()
+                .position(|color| color[..] == pixel[..3])
+                .map(|index| index as u8)
+                .ok_or_else(|| format!("color {:02X?} is not in the palette", &pixel[..3]).into())
+        })
+        .collect()
+}
+
+fn decode_png(
+    png: &[u8],
+    transformations: png::Transformations,
+) -> Result<(png::OutputInfo, Vec<u8>), Box<dyn Error>> {
+    let mut decoder = png::Decoder::new(Cursor::new(png));
+    decoder.set_transformations(transformations);
+    let mut reader = decoder.read_info()?;
+
+    let mut buf = vec![0; reader.output_buffer_size().ok_or("image is too large")?];
+    let info = reader.next_frame(&mut buf)?;
+    buf.truncate(info.buffer_size());
+
+    Ok((info, buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_tile, encode_tile, export_png, import_png};
+
+    fn test_tiles(bpp: u8, count: usize) -> Vec<u8> {
+        (0..count * usize::from(bpp) * 8)
+            .map(|i| (i * 37 + 11) as u8)
+            .collect()
+    }
+
+    fn test_palette(bpp: u8) -> Vec<[u8; 3]> {
+        (0..1usize << bpp)
+            .map(|i| [i as u8, (i * 3) as u8, 7])
+            .collect()
+    }
+
+    #[test]
+    fn decode_known_tile() {
+        // Row 0 uses color 1 in the left half and color 2 in the right half, row 7 color 3
+        let mut tile = [0; 16];
+        tile[0] = 0xF0;
+        tile[1] = 0x0F;
+        tile[14] = 0xFF;
+        tile[15] = 0xFF;
+
+        let pixels = decode_tile(&tile, 2);
+        assert_eq!(pixels[..8], [1, 1, 1, 1, 2, 2, 2, 2]);
+        assert_eq!(pixels[8..56], [0; 48]);
+        assert_eq!(pixels[56..], [3; 8]);
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        for bpp in [2, 4, 8] {
+            let tile = test_tiles(bpp, 1);
+            let mut encoded = vec![0; tile.len()];
+            encode_tile(&decode_tile(&tile, bpp), bpp, &mut encoded);
+            assert_eq!(encoded, tile, "{bpp}bpp");
+        }
+    }
+
+    #[test]
+    fn png_round_trip() {
+        for bpp in [2, 4, 8] {
+            // Not a multiple of the tiles per row, so the last row is partially filled
+            let tiles = test_tiles(bpp, 20);
+            let palette = test_palette(bpp);
+            let png = export_png(&tiles, bpp, &palette).unwrap();
+
+            let mut imported = vec![0; tiles.len()];
+            import_png(&png, bpp, &palette, &mut imported).unwrap();
+            assert_eq!(imported, tiles, "{bpp}bpp");
+        }
+    }
+
+    #[test]
+    fn import_rgb_png() {
+        let palette = test_palette(2);
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 128, 8);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels: Vec<u8> = (0..128 * 8).flat_map(|i| palette[i % 4]).collect();
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&pixels)
+            .unwrap();
+
+        let mut tiles = vec![0; 16 * 16];
+        import_png(&png, 2, &palette, &mut tiles).unwrap();
+        assert_eq!(decode_tile(&tiles[..16], 2)[..8], [0, 1, 2, 3, 0, 1, 2, 3]);
+    }
+}
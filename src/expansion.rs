@@ -0,0 +1,16 @@
+/// A device which is connected to the expansion I/O regions of the bus, i.e. `$2184-$21FF` on the
+/// B-Bus and `$2200-$3FFF` on the A-Bus (in banks `$00-$3F` and `$80-$BF`).
+///
+/// Addresses are passed as offsets within the bank, so devices can tell the regions apart.
+pub trait ExpansionDevice {
+    /// Returns `None` if the device doesn't drive the data bus for this address (open bus).
+    fn read(&mut self, addr: u16) -> Option<u8>;
+
+    /// Like [`ExpansionDevice::read`], but without side effects. Used by the debugger.
+    fn read_pure(&self, addr: u16) -> Option<u8> {
+        let _ = addr;
+        None
+    }
+
+    fn write(&mut self, addr: u16, value: u8);
+}
@@ -17,6 +17,17 @@ impl super::debugger::Tab for GameView {
             .show(ui, |ui| {
                 let (rect, sense) = ui.allocate_exact_size(ui.available_size(), egui::Sense::all());
 
+                // Light guns aim at the pixel below the mouse pointer
+                let aim = sense.hover_pos().and_then(|pos| {
+                    screen_position(
+                        rect,
+                        ui.pixels_per_point(),
+                        emulation_state.current_image_height,
+                        pos,
+                    )
+                });
+                emulation_state.current_input.write().unwrap().aim = aim;
+
                 if should_hide_pointer(ui) {
                     sense.on_hover_and_drag_cursor(egui::CursorIcon::None);
                 }
@@ -47,6 +58,43 @@ impl super::debugger::Tab for GameView {
     }
 }
 
+/// Maps a position inside the game view to the SNES pixel shown there, using the same layout as
+/// [`GameRenderCallback`].
+fn screen_position(
+    rect: egui::Rect,
+    pixels_per_point: f32,
+    image_height: u16,
+    pos: egui::Pos2,
+) -> Option<(u16, u16)> {
+    let image_size = egui::Vec2::new(OutputImage::WIDTH as f32, image_height as f32 * 2.0);
+    let viewport_size = rect.size() * pixels_per_point;
+
+    let mut scale = (viewport_size / image_size).min_elem();
+    if scale > 1.0 {
+        scale = scale.floor();
+    }
+
+    if scale < f32::EPSILON {
+        return None;
+    }
+
+    let target_size = image_size * scale;
+    let target_pos =
+        (rect.min.to_vec2() * pixels_per_point + (viewport_size - target_size) * 0.5).round();
+    let image_pos = (pos.to_vec2() * pixels_per_point - target_pos) / scale;
+
+    if image_pos.x < 0.0
+        || image_pos.y < 0.0
+        || image_pos.x >= image_size.x
+        || image_pos.y >= image_size.y
+    {
+        return None;
+    }
+
+    // Every SNES pixel is two pixels wide and two rows high in the output image
+    Some(((image_pos.x / 2.0) as u16, (image_pos.y / 2.0) as u16))
+}
+
 fn should_hide_pointer(ui: &mut egui::Ui) -> bool {
     const HIDE_AFTER_SECS: f64 = 3.0;
 
@@ -1,7 +1,6 @@
-use std::cmp::Ordering;
-
 use crate::cpu::memory::MappingMode;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Region {
     Japan,
     NorthAmerica,
@@ -58,6 +57,19 @@ impl Region {
     }
 }
 
+/// Enhancement chip on the cartridge, as declared by the upper nibble of the chipset byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coprocessor {
+    Dsp,
+    SuperFx,
+    Obc1,
+    Sa1,
+    Sdd1,
+    SRtc,
+    Other,
+    Custom,
+}
+
 pub struct RomHeader {
     pub title: Box<[u8]>,
     pub fast_rom: bool,
@@ -79,7 +91,7 @@ impl RomHeader {
         let title_len = title
             .iter()
             .rposition(|&c| c != b'\0' && c != b' ')
-            .unwrap_or(title.len());
+            .map_or(0, |i| i + 1);
         let title = title[..title_len].into();
 
         let speed_and_map_mode = header[21];
@@ -133,17 +145,91 @@ impl RomHeader {
         })
     }
 
+    /// Rates how likely it is that this is the actual header of the ROM.
+    fn score(&self, rom_checksum: u16) -> i32 {
+        let mut score = 0;
+        if self.checksum == rom_checksum {
+            score += 4;
+        }
+        if self.checksum ^ self.checksum_complement == 0xFFFF {
+            score += 2;
+        }
+        // The reset vector has to point into ROM, which is mapped at 0x8000..=0xFFFF in bank 0
+        if self.vector_table[14] >= 0x8000 {
+            score += 2;
+        } else {
+            score -= 4;
+        }
+        if self.region.is_some() {
+            score += 1;
+        }
+        score
+    }
+
+    /// Decodes the chipset byte. Values 0 to 2 of the lower nibble mean that there is no
+    /// coprocessor, just ROM and optionally (battery-backed) RAM.
+    pub fn coprocessor(&self) -> Option<Coprocessor> {
+        if self.chipset & 0x0F < 3 {
+            return None;
+        }
+        let coprocessor = match self.chipset >> 4 {
+            0x0 => Coprocessor::Dsp,
+            0x1 => Coprocessor::SuperFx,
+            0x2 => Coprocessor::Obc1,
+            0x3 => Coprocessor::Sa1,
+            0x4 => Coprocessor::Sdd1,
+            0x5 => Coprocessor::SRtc,
+            0xE => Coprocessor::Other,
+            0xF => Coprocessor::Custom,
+            _ => return None,
+        };
+        Some(coprocessor)
+    }
+
     pub fn hash(&self) -> u64 {
+        self.hash_with_title(&self.title)
+    }
+
+    /// The hash of older versions, which cut off the last character of the title. Allows finding
+    /// data that was stored under the old hash. `None` if the title is empty, because the old hash
+    /// included the padding of the title then.
+    pub fn legacy_hash(&self) -> Option<u64> {
+        let (_, title) = self.title.split_last()?;
+        Some(self.hash_with_title(title))
+    }
+
+    fn hash_with_title(&self, title: &[u8]) -> u64 {
         use std::hash::Hasher;
         let mut hasher = rustc_hash::FxHasher::default();
         // TODO: What should we put into the hash?
-        hasher.write(&self.title);
+        hasher.write(title);
         hasher.write_u8(self.developer_id);
-        if self.title.is_empty() {
+        if title.is_empty() {
             hasher.write_u16(self.checksum);
         }
         hasher.finish()
     }
+
+    pub fn cartridge_header(&self) -> CartridgeHeader {
+        CartridgeHeader {
+            title: String::from_utf8_lossy(&self.title).into_owned(),
+            rom_size: self.rom_size,
+            ram_size: self.ram_size,
+            region: self.region,
+        }
+    }
+}
+
+/// The parts of the [`RomHeader`] which describe the game, decoded for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    /// The title without padding. Characters outside of ASCII are replaced.
+    pub title: String,
+    /// Size of the ROM in bytes, as declared by the header.
+    pub rom_size: u32,
+    /// Size of the SRAM in bytes.
+    pub ram_size: u32,
+    pub region: Option<Region>,
 }
 
 fn extract_vector_table(rom: &[u8]) -> [u16; 16] {
@@ -164,6 +250,28 @@ fn checksum(rom: &[u8]) -> u16 {
     checksum
 }
 
+/// Length of the header which some copier devices put in front of the ROM, either 0 or 512.
+fn copier_header_len(rom: &[u8]) -> usize {
+    if rom.len() % 0x8000 == 512 { 512 } else { 0 }
+}
+
+/// Removes the 512 byte header which some copier devices put in front of the ROM.
+pub fn strip_copier_header(rom: Box<[u8]>) -> Box<[u8]> {
+    match copier_header_len(&rom) {
+        0 => rom,
+        len => rom[len..].into(),
+    }
+}
+
+/// Detects the mapping mode of a ROM image from its internal header. The candidates at the LoRom,
+/// HiRom and ExHiRom locations are rated by their checksum, reset vector and region. A copier
+/// header in front of the image is skipped.
+pub fn detect_mapping(rom: &[u8]) -> MappingMode {
+    extract(&rom[copier_header_len(rom)..]).mapping_mode
+}
+
+/// Finds the internal header of the ROM, which also determines its mapping mode. If there are
+/// multiple candidates, the most plausible one is chosen.
 pub fn extract(rom: &[u8]) -> RomHeader {
     assert!(!rom.is_empty() && rom.len() < u32::MAX as usize);
     let checksum = checksum(rom);
@@ -171,7 +279,11 @@ pub fn extract(rom: &[u8]) -> RomHeader {
 
     let mut headers = Vec::new();
 
-    let header_locations = [(MappingMode::LoRom, 0x7FC0), (MappingMode::HiRom, 0xFFC0)];
+    let header_locations = [
+        (MappingMode::LoRom, 0x7FC0),
+        (MappingMode::HiRom, 0xFFC0),
+        (MappingMode::ExHiRom, 0x40FFC0),
+    ];
 
     for (mapping_mode, header_pos) in header_locations {
         let Some(bytes) = rom.get(header_pos..header_pos + 64) else {
@@ -186,18 +298,12 @@ pub fn extract(rom: &[u8]) -> RomHeader {
         headers.push(header);
     }
 
-    headers.sort_by(|a, b| {
-        // TODO: Add more heuristics
-        if a.checksum == checksum {
-            Ordering::Less
-        } else if b.checksum == checksum {
-            Ordering::Greater
-        } else {
-            Ordering::Equal
-        }
-    });
-
-    if let Some(header) = headers.pop() {
+    // `max_by_key` returns the last of equally rated headers, but the first location should win
+    if let Some(header) = headers
+        .into_iter()
+        .rev()
+        .max_by_key(|header| header.score(checksum))
+    {
         return header;
     }
 
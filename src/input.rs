@@ -4,9 +4,20 @@ pub trait InputDevice {
     fn read_data2(&mut self) -> bool {
         false
     }
+    /// Light guns return the on-screen pixel they are aimed at, as of the last strobe. When the
+    /// PPU draws that pixel, the gun pulls pin 6 of the controller port low, which latches the H/V
+    /// counters if this is enabled through WRIO. This only works in port 2.
+    fn aim_position(&self) -> Option<(u16, u16)> {
+        None
+    }
+    /// Called when the latch line goes low again after [`InputDevice::strobe`].
+    fn end_strobe(&mut self) {}
+    /// Pin 6 is driven by the console through WRIO ($4201) and is high by default. Most devices
+    /// ignore it, the multitap uses it to select between its controllers.
+    fn set_pin6(&mut self, _high: bool) {}
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct JoypadState {
     pub button_b: bool,
     pub button_y: bool,
@@ -37,6 +48,8 @@ impl<F: FnMut() -> JoypadState> InputDevice for Joypad<F> {
     #[allow(clippy::identity_op)]
     fn strobe(&mut self) {
         let state = (self.updater)();
+        // Shifted out starting with bit 0, so the automatic read leaves B in bit 7 of JOYnH and R
+        // in bit 4 of JOYnL. Bits 12-15 are the signature of a standard controller, all zero.
         self.buffer = 0;
         self.buffer |= (state.button_b as u16) << 0;
         self.buffer |= (state.button_y as u16) << 1;
@@ -58,3 +71,164 @@ impl<F: FnMut() -> JoypadState> InputDevice for Joypad<F> {
         value
     }
 }
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SuperScopeState {
+    /// Horizontal aim position in pixels, values of 256 and above are off screen.
+    pub x: u16,
+    /// Vertical aim position in scanlines, values of 240 and above are off screen.
+    pub y: u16,
+    pub trigger: bool,
+    pub cursor: bool,
+    pub turbo: bool,
+    pub pause: bool,
+}
+
+impl SuperScopeState {
+    fn is_offscreen(&self) -> bool {
+        self.x >= 256 || self.y >= 240
+    }
+}
+
+pub struct SuperScope<F> {
+    updater: F,
+    state: SuperScopeState,
+    buffer: u16,
+}
+
+impl<F> SuperScope<F> {
+    pub fn new(updater: F) -> Self {
+        Self {
+            updater,
+            state: SuperScopeState::default(),
+            buffer: 0,
+        }
+    }
+}
+
+impl<F: FnMut() -> SuperScopeState> InputDevice for SuperScope<F> {
+    #[allow(clippy::identity_op)]
+    fn strobe(&mut self) {
+        self.state = (self.updater)();
+        // Bits 4, 5 and 7 (noise) are always clear, the upper 8 bits are the ID of the Super Scope
+        self.buffer = 0xFF00;
+        self.buffer |= (self.state.trigger as u16) << 0;
+        self.buffer |= (self.state.cursor as u16) << 1;
+        self.buffer |= (self.state.turbo as u16) << 2;
+        self.buffer |= (self.state.pause as u16) << 3;
+        self.buffer |= (self.state.is_offscreen() as u16) << 6;
+    }
+
+    fn read_data1(&mut self) -> bool {
+        let value = (self.buffer & 1) != 0;
+        self.buffer = (self.buffer >> 1) | 0x8000;
+        value
+    }
+
+    fn aim_position(&self) -> Option<(u16, u16)> {
+        (!self.state.is_offscreen()).then_some((self.state.x, self.state.y))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MouseState {
+    /// Horizontal movement since the last strobe, positive values are to the right.
+    pub dx: i32,
+    /// Vertical movement since the last strobe, positive values are downwards.
+    pub dy: i32,
+    pub button_left: bool,
+    pub button_right: bool,
+}
+
+/// The SNES Mouse. The movement of each axis is reported as a direction bit followed by a 7 bit
+/// magnitude, larger movements are clamped. Games can cycle through three sensitivities by reading
+/// while the latch is high, this is not emulated and the lowest sensitivity is always reported.
+pub struct Mouse<F> {
+    updater: F,
+    buffer: u32,
+}
+
+impl<F> Mouse<F> {
+    pub fn new(updater: F) -> Self {
+        Self { updater, buffer: 0 }
+    }
+}
+
+impl<F: FnMut() -> MouseState> InputDevice for Mouse<F> {
+    fn strobe(&mut self) {
+        let state = (self.updater)();
+        // Shifted out starting with bit 0, but the axes are sent MSB first, hence the reversal
+        let axis = |delta: i32| {
+            let magnitude = delta.unsigned_abs().min(0x7F) as u8;
+            let byte = ((delta < 0) as u8) << 7 | magnitude;
+            u32::from(byte.reverse_bits())
+        };
+        // The first byte is always zero, bits 12-15 are the signature of a mouse (0001)
+        self.buffer = 1 << 15;
+        self.buffer |= (state.button_right as u32) << 8;
+        self.buffer |= (state.button_left as u32) << 9;
+        self.buffer |= axis(state.dy) << 16;
+        self.buffer |= axis(state.dx) << 24;
+    }
+
+    fn read_data1(&mut self) -> bool {
+        let value = (self.buffer & 1) != 0;
+        self.buffer = (self.buffer >> 1) | 0x8000_0000;
+        value
+    }
+}
+
+/// Connects four devices to a single controller port. Pin 6 selects whether the first two or the
+/// last two are read, through data lines 1 and 2 respectively. While the latch is high, data line 2
+/// reads 1, which is how games detect the multitap.
+pub struct Multitap {
+    ports: [Option<Box<dyn InputDevice>>; 4],
+    latched: bool,
+    select: bool,
+}
+
+impl Multitap {
+    pub fn new(ports: [Option<Box<dyn InputDevice>>; 4]) -> Self {
+        Self {
+            ports,
+            latched: false,
+            select: true,
+        }
+    }
+
+    fn selected(&mut self, line: usize) -> Option<&mut Box<dyn InputDevice>> {
+        let index = if self.select { line } else { line + 2 };
+        self.ports[index].as_mut()
+    }
+}
+
+impl InputDevice for Multitap {
+    fn strobe(&mut self) {
+        self.latched = true;
+        for port in self.ports.iter_mut().flatten() {
+            port.strobe();
+        }
+    }
+
+    fn end_strobe(&mut self) {
+        self.latched = false;
+        for port in self.ports.iter_mut().flatten() {
+            port.end_strobe();
+        }
+    }
+
+    fn read_data1(&mut self) -> bool {
+        self.selected(0).is_some_and(|port| port.read_data1())
+    }
+
+    fn read_data2(&mut self) -> bool {
+        if self.latched {
+            return true;
+        }
+        self.selected(1).is_some_and(|port| port.read_data1())
+    }
+
+    fn set_pin6(&mut self, high: bool) {
+        self.select = high;
+    }
+}
@@ -1,9 +1,20 @@
 use super::input::InputDevice;
+use crate::Snes;
+
+/// Number of dots between two bits being shifted in by the automatic joypad read (4224 master
+/// cycles for all 16 bits)
+const AUTO_READ_DOTS_PER_BIT: u16 = 66;
 
 #[derive(Default)]
 pub struct JoypadIo {
     pub input1: Option<Box<dyn InputDevice>>,
     pub input2: Option<Box<dyn InputDevice>>,
+    auto_read_dots: u16,
+    auto_read_bits: u8,
+    latch: bool,
+    /// The H/V counter values at which the light gun in port 2 latches the counters, see
+    /// [`InputDevice::aim_position`].
+    pub(crate) light_gun_latch: Option<(u16, u16)>,
 }
 
 impl JoypadIo {
@@ -42,12 +53,108 @@ impl JoypadIo {
             return;
         }
         if value & 1 != 0 {
-            if let Some(input) = &mut self.input1 {
-                input.strobe();
-            }
-            if let Some(input) = &mut self.input2 {
-                input.strobe();
-            }
+            self.strobe();
+        } else if self.latch {
+            self.end_strobe();
+        }
+    }
+
+    fn strobe(&mut self) {
+        self.latch = true;
+        if let Some(input) = &mut self.input1 {
+            input.strobe();
+        }
+        if let Some(input) = &mut self.input2 {
+            input.strobe();
         }
+        self.update_light_gun_latch();
+    }
+
+    fn end_strobe(&mut self) {
+        self.latch = false;
+        if let Some(input) = &mut self.input1 {
+            input.end_strobe();
+        }
+        if let Some(input) = &mut self.input2 {
+            input.end_strobe();
+        }
+    }
+
+    fn update_light_gun_latch(&mut self) {
+        // The first visible pixel is drawn at H = 22 of scanline 1
+        self.light_gun_latch = self
+            .input2
+            .as_deref()
+            .and_then(InputDevice::aim_position)
+            .map(|(x, y)| (x + 22, y + 1));
+    }
+}
+
+/// Passes the pin 6 levels set through WRIO on to the connected devices.
+pub(crate) fn update_pin6(emu: &mut Snes) {
+    if let Some(input) = &mut emu.joypad.input1 {
+        input.set_pin6(emu.cpu.wrio_joypad1_pin6);
+    }
+    if let Some(input) = &mut emu.joypad.input2 {
+        input.set_pin6(emu.cpu.wrio_joypad2_pin6);
+    }
+}
+
+pub(crate) fn start_auto_read(emu: &mut Snes) {
+    // The automatic read pulses the latch before clocking in the data
+    emu.joypad.strobe();
+    emu.joypad.end_strobe();
+    emu.joypad.auto_read_dots = 0;
+    emu.joypad.auto_read_bits = 0;
+    emu.cpu.hvbjoy_auto_joypad_read_busy_flag = true;
+}
+
+/// Advances the automatic joypad read by one dot. The JOYn registers are shifted one bit at a
+/// time, so reading them before the read has finished yields partial values, like on hardware.
+pub(crate) fn run_auto_read(emu: &mut Snes) {
+    emu.joypad.auto_read_dots += 1;
+    if emu.joypad.auto_read_dots < AUTO_READ_DOTS_PER_BIT {
+        return;
+    }
+    emu.joypad.auto_read_dots = 0;
+
+    fn shift_in(
+        input: &mut Option<Box<dyn InputDevice>>,
+        joy1l: &mut u8,
+        joy1h: &mut u8,
+        joy2l: &mut u8,
+        joy2h: &mut u8,
+    ) {
+        let (data1, data2) = match input.as_deref_mut() {
+            Some(input) => (input.read_data1(), input.read_data2()),
+            None => (false, false),
+        };
+
+        let joy1 = u16::from_be_bytes([*joy1h, *joy1l]) << 1 | data1 as u16;
+        let joy2 = u16::from_be_bytes([*joy2h, *joy2l]) << 1 | data2 as u16;
+        [*joy1h, *joy1l] = joy1.to_be_bytes();
+        [*joy2h, *joy2l] = joy2.to_be_bytes();
+    }
+
+    // JOY1 and JOY2 are read through data line 1 of the two ports, JOY3 and JOY4 through data
+    // line 2
+    shift_in(
+        &mut emu.joypad.input1,
+        &mut emu.cpu.joy1l,
+        &mut emu.cpu.joy1h,
+        &mut emu.cpu.joy3l,
+        &mut emu.cpu.joy3h,
+    );
+    shift_in(
+        &mut emu.joypad.input2,
+        &mut emu.cpu.joy2l,
+        &mut emu.cpu.joy2h,
+        &mut emu.cpu.joy4l,
+        &mut emu.cpu.joy4h,
+    );
+
+    emu.joypad.auto_read_bits += 1;
+    if emu.joypad.auto_read_bits == 16 {
+        emu.cpu.hvbjoy_auto_joypad_read_busy_flag = false;
     }
 }
@@ -1,19 +1,26 @@
 use cpu::StepResult;
+use expansion::ExpansionDevice;
 use input::InputDevice;
 
 pub use apu::Apu;
 pub use cpu::{Cpu, memory::MappingMode};
-pub use header::RomHeader;
+pub use header::{CartridgeHeader, Coprocessor, Region, RomHeader, detect_mapping};
 pub use joypad::JoypadIo;
 pub use ppu::{OutputImage, Ppu};
+pub use registers::RegisterState;
 pub use wram::WRam;
 
 pub mod apu;
+pub mod audio;
 pub mod cpu;
+pub mod expansion;
 mod header;
 pub mod input;
 pub mod joypad;
 pub mod ppu;
+pub mod registers;
+pub mod screenshot;
+pub mod srtc;
 pub mod wram;
 
 pub struct Snes {
@@ -21,15 +28,34 @@ pub struct Snes {
     pub ppu: Ppu,
     pub apu: Apu,
     wram: WRam,
-    sram: Box<[u8; 0x080000]>,
+    /// Sized according to the ROM header, its size is always a power of two.
+    sram: Box<[u8]>,
     rom: Box<[u8]>,
     joypad: JoypadIo,
+    expansion: Option<Box<dyn ExpansionDevice>>,
+    srtc: Option<srtc::SRtc>,
     frame_finished: bool,
+    unimplemented_feature: Option<&'static str>,
+    reported_features: Vec<&'static str>,
+    /// Debug option: Stop emulation whenever the CPU accesses open bus. Otherwise the value of
+    /// the data bus is silently returned, just like on real hardware.
+    pub pause_on_open_bus: bool,
+    open_bus_access: Option<cpu::memory::OpenBusAccess>,
+    instruction_budget: Option<u64>,
+    resampler: audio::Resampler,
     pub header: RomHeader,
 }
 
 impl Snes {
+    /// Same as [`Snes::from_rom`].
     pub fn new(rom: Box<[u8]>) -> Self {
+        Self::from_rom(rom)
+    }
+
+    /// Loads a ROM image, which may start with a copier header. The mapping mode and the size of
+    /// the SRAM are taken from the internal header, see [`detect_mapping`].
+    pub fn from_rom(rom: Box<[u8]>) -> Self {
+        let rom = header::strip_copier_header(rom);
         let header = header::extract(&rom);
 
         let mut snes = Self {
@@ -37,10 +63,18 @@ impl Snes {
             ppu: Ppu::from_rom_header(&header),
             apu: Apu::default(),
             wram: WRam::default(),
-            sram: vec![0; 0x080000].try_into().unwrap(),
+            sram: vec![0; header.ram_size as usize].into_boxed_slice(),
             rom,
             joypad: JoypadIo::default(),
+            expansion: None,
+            srtc: (header.coprocessor() == Some(Coprocessor::SRtc)).then(srtc::SRtc::default),
             frame_finished: false,
+            unimplemented_feature: None,
+            reported_features: Vec::new(),
+            pause_on_open_bus: false,
+            open_bus_access: None,
+            instruction_budget: None,
+            resampler: audio::Resampler::new(Apu::SAMPLE_RATE, Apu::SAMPLE_RATE),
             header,
         };
         snes.cpu.raise_interrupt(cpu::Interrupt::Reset);
@@ -49,80 +83,202 @@ impl Snes {
 
     pub fn set_input1(&mut self, input: Option<Box<dyn InputDevice>>) {
         self.joypad.input1 = input;
+        joypad::update_pin6(self);
     }
 
     pub fn set_input2(&mut self, input: Option<Box<dyn InputDevice>>) {
         self.joypad.input2 = input;
+        joypad::update_pin6(self);
+    }
+
+    /// Connects a device to the expansion port. Without one, accesses to the expansion I/O regions
+    /// hit open bus.
+    pub fn set_expansion_device(&mut self, device: Option<Box<dyn ExpansionDevice>>) {
+        self.expansion = device;
+    }
+
+    /// The real-time clock on the cartridge, if it has an S-RTC. Its time can be set to keep it in
+    /// sync with the host, otherwise it starts at 2000-01-01.
+    pub fn srtc_mut(&mut self) -> Option<&mut srtc::SRtc> {
+        self.srtc.as_mut()
+    }
+
+    /// Enables or disables tracking of WRAM accesses for [`Snes::wram_heatmap`].
+    pub fn set_wram_heatmap_enabled(&mut self, enabled: bool) {
+        if enabled != self.wram.heatmap.is_some() {
+            self.wram.heatmap = enabled.then(Box::default);
+        }
+    }
+
+    pub fn wram_heatmap(&self) -> Option<&wram::AccessHeatmap> {
+        self.wram.heatmap.as_deref()
+    }
+
+    /// Limits the number of instructions the CPU may execute from now on. Once the budget is used
+    /// up, every step returns [`StepResult::BudgetExhausted`], so automated runs of a ROM that is
+    /// stuck can't hang. `None` removes the limit.
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
+    }
+
+    /// Sample rate in Hz of the samples returned by [`Snes::take_audio_samples`]. Defaults to the
+    /// native rate of the APU, [`Apu::SAMPLE_RATE`].
+    pub fn audio_sample_rate(&self) -> u32 {
+        self.resampler.output_rate()
+    }
+
+    /// Sets the sample rate of the samples returned by [`Snes::take_audio_samples`], usually the
+    /// rate of the output device. The audio output of the APU is resampled linearly. The rate can
+    /// be changed at any time, e.g. to keep the audio in sync when emulating faster or slower than
+    /// real time.
+    pub fn set_audio_sample_rate(&mut self, rate: u32) {
+        self.resampler.set_output_rate(rate);
+    }
+
+    /// Appends the audio output since the last call to `buf`, as interleaved stereo samples at
+    /// [`Snes::audio_sample_rate`].
+    pub fn take_audio_samples(&mut self, buf: &mut Vec<i16>) {
+        self.resampler.process(self.apu.drain_samples(), buf);
+    }
+
+    /// Runs the emulation until `count` stereo samples were output and returns them interleaved,
+    /// at [`Snes::audio_sample_rate`]. Samples which were not taken with
+    /// [`Snes::take_audio_samples`] yet come first, samples beyond `count` are discarded. Together
+    /// with [`audio::hash_samples`], this allows regression tests of the audio output. Fewer
+    /// samples are returned if the instruction budget runs out first.
+    pub fn run_audio(&mut self, count: usize) -> Vec<i16> {
+        // Roughly one scanline, which is about two samples at the native rate
+        const CHUNK_CYCLES: u64 = 1364;
+
+        let mut samples = Vec::with_capacity(count * 2);
+        self.take_audio_samples(&mut samples);
+        while samples.len() < count * 2 {
+            let result = self.run_cycles(CHUNK_CYCLES);
+            self.take_audio_samples(&mut samples);
+            if result == StepResult::BudgetExhausted {
+                break;
+            }
+        }
+        samples.truncate(count * 2);
+        samples
     }
 
     pub fn output_image(&self) -> &OutputImage {
         self.ppu.output()
     }
 
-    pub fn run(&mut self) -> bool {
+    /// Runs the emulation until the current frame is finished. Returns early if a breakpoint was
+    /// hit or an unimplemented feature was encountered.
+    pub fn run(&mut self) -> StepResult {
         let mut ignore_breakpoints = true;
 
-        if self.cpu.nmitimen_joypad_enable {
-            fn read_input(
-                input: &mut Option<Box<dyn InputDevice>>,
-                joy1l: &mut u8,
-                joy1h: &mut u8,
-                joy2l: &mut u8,
-                joy2h: &mut u8,
-            ) {
-                match input.as_deref_mut() {
-                    Some(input) => {
-                        input.strobe();
-                        for _ in 0..8 {
-                            *joy1h = (*joy1h << 1) | input.read_data1() as u8;
-                            *joy2h = (*joy2h << 1) | input.read_data2() as u8;
-                        }
-                        for _ in 0..8 {
-                            *joy1l = (*joy1l << 1) | input.read_data1() as u8;
-                            *joy2l = (*joy2l << 1) | input.read_data2() as u8;
-                        }
-                    }
-                    None => {
-                        *joy1h = 0;
-                        *joy2h = 0;
-                    }
-                }
-            }
+        while !self.frame_finished {
+            let result = cpu::step(self, ignore_breakpoints);
+            ignore_breakpoints = false;
 
-            read_input(
-                &mut self.joypad.input1,
-                &mut self.cpu.joy1l,
-                &mut self.cpu.joy1h,
-                &mut self.cpu.joy2l,
-                &mut self.cpu.joy2h,
-            );
-            read_input(
-                &mut self.joypad.input2,
-                &mut self.cpu.joy3l,
-                &mut self.cpu.joy3h,
-                &mut self.cpu.joy4l,
-                &mut self.cpu.joy4h,
-            );
-            self.cpu.hvbjoy_auto_joypad_read_busy_flag = false;
+            if result != StepResult::Stepped {
+                return result;
+            }
         }
 
+        self.frame_finished = false;
+        StepResult::Stepped
+    }
+
+    /// Runs the emulation until the current frame is finished, the rendered image is returned by
+    /// [`Snes::output_image`]. Unlike [`Snes::run`], breakpoints, watchpoints and open bus
+    /// accesses are ignored. Only an exhausted instruction budget stops it early, in which case
+    /// [`StepResult::BudgetExhausted`] is returned instead of [`StepResult::Stepped`].
+    pub fn run_frame(&mut self) -> StepResult {
         while !self.frame_finished {
-            let result = cpu::step(self, ignore_breakpoints);
-            ignore_breakpoints = false;
+            if cpu::step(self, true) == StepResult::BudgetExhausted {
+                return StepResult::BudgetExhausted;
+            }
+        }
+        self.frame_finished = false;
+        StepResult::Stepped
+    }
 
-            if result == StepResult::BreakpointHit {
-                return true;
+    /// Runs the emulation for at least `cycles` master cycles, ignoring breakpoints just like
+    /// [`Snes::run_frame`]. The last instruction may overshoot the target by a few cycles. Stops
+    /// early with [`StepResult::BudgetExhausted`] once the instruction budget is used up.
+    pub fn run_cycles(&mut self, cycles: u64) -> StepResult {
+        let mut result = StepResult::Stepped;
+        let mut remaining = cycles;
+        while remaining > 0 {
+            let start = self.cpu.cycles();
+            if cpu::step(self, true) == StepResult::BudgetExhausted {
+                result = StepResult::BudgetExhausted;
+                break;
             }
+            // A reset sets the cycle counter back to zero
+            let elapsed = self.cpu.cycles().saturating_sub(start);
+            remaining = remaining.saturating_sub(elapsed);
         }
+        ppu::catch_up(self);
+        apu::catch_up(self);
+        // Frames finished in between were not waited for, so the next call to `run` or
+        // `run_frame` continues until the end of the current frame.
+        self.frame_finished = false;
+        result
+    }
 
+    /// Resets the console, just like pressing the reset button. The CPU, PPU and APU are reset when
+    /// the reset interrupt is processed by the next step, while WRAM, SRAM and connected devices
+    /// are left untouched.
+    pub fn reset(&mut self) {
         self.frame_finished = false;
-        false
+        self.unimplemented_feature = None;
+        self.open_bus_access = None;
+        self.cpu.raise_interrupt(cpu::Interrupt::Reset);
+    }
+
+    /// Jumps to the 24 bit address `addr`, see [`Cpu::set_pc`]. Useful for running a routine in
+    /// isolation.
+    pub fn set_pc(&mut self, addr: u32) {
+        self.cpu.set_pc(addr);
     }
 
     pub fn step(&mut self) -> StepResult {
         let result = cpu::step(self, true);
         ppu::catch_up(self);
         apu::catch_up(self);
-        result
+        match self.unimplemented_feature.take() {
+            Some(feature) => StepResult::Unimplemented(feature),
+            None => result,
+        }
+    }
+
+    /// Executes `count` instructions of the APU while the main CPU stays paused, see
+    /// [`apu::step`].
+    pub fn step_apu(&mut self, count: u32) -> StepResult {
+        apu::step(self, count);
+        match self.unimplemented_feature.take() {
+            Some(feature) => StepResult::Unimplemented(feature),
+            None => StepResult::Stepped,
+        }
+    }
+
+    /// Records that emulation ran into an unimplemented feature. Every feature is only reported
+    /// once, so that emulation can be continued afterwards.
+    pub(crate) fn report_unimplemented(&mut self, feature: &'static str) {
+        if self.reported_features.contains(&feature) {
+            return;
+        }
+        tracing::error!("Feature not yet implemented: {feature}");
+        self.reported_features.push(feature);
+        self.unimplemented_feature.get_or_insert(feature);
+    }
+
+    /// Exports the CPU, PPU and APU registers as a pretty-printed JSON document.
+    pub fn export_registers(&self) -> String {
+        serde_json::to_string_pretty(&RegisterState::capture(self)).unwrap()
+    }
+
+    /// Loads register state that was previously produced by [`Snes::export_registers`].
+    pub fn import_registers(&mut self, document: &str) -> serde_json::Result<()> {
+        let state: RegisterState = serde_json::from_str(document)?;
+        state.apply(self);
+        Ok(())
     }
 }
@@ -4,12 +4,18 @@ use std::{
     time::Duration,
 };
 
+use controller::{ControllerType, Input, connect_controllers};
 use debugger::Debugger;
 use game_view::GameView;
 use render::Renderer;
-use snes_emu::{Snes, ppu::PpuVariant};
+use snes_emu::{
+    MappingMode, Snes,
+    cpu::{AccessKind, StepResult},
+    ppu::PpuVariant,
+    srtc::RtcTime,
+};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
-use web_time::Instant;
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
 use winit::{
     application::ApplicationHandler,
     event::{StartCause, WindowEvent},
@@ -17,9 +23,17 @@ use winit::{
     window::{Theme, Window, WindowId},
 };
 
+mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod audio_output;
+mod controller;
 mod debugger;
 mod game_view;
+mod profile;
 mod render;
+mod scripting;
+#[cfg(not(target_arch = "wasm32"))]
+mod wav;
 
 fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     #[cfg(target_arch = "wasm32")]
@@ -53,6 +67,8 @@ struct App {
 enum UserEvent {
     RomPicked(Option<Box<[u8]>>),
     ActiveStateReady(Box<ActiveState>),
+    #[cfg(not(target_arch = "wasm32"))]
+    AudioRecordingPathPicked(Option<std::path::PathBuf>),
 }
 
 fn create_window(event_loop: &ActiveEventLoop) -> Result<Window, Box<dyn std::error::Error>> {
@@ -123,16 +139,14 @@ impl ApplicationHandler<UserEvent> for App {
     }
 
     fn new_events(&mut self, _: &ActiveEventLoop, cause: StartCause) {
-        if !matches!(cause, StartCause::ResumeTimeReached { .. }) {
+        if !matches!(cause, StartCause::ResumeTimeReached { .. }) || self.state.sync_to_display {
             return;
         }
 
-        const PERIOD_50HZ: Duration = Duration::from_nanos(1_000_000_000 / 50);
-        const PERIOD_60HZ: Duration = Duration::from_nanos(1_000_000_000 / 60);
-
-        let Some(emu_state) = &mut self.state.emulation_state else {
+        let Some(emu_state) = &self.state.emulation_state else {
             return;
         };
+        let frame_period = emu_state.snes.ppu.variant.frame_period();
 
         let Some(next_frame_time) = &mut self.state.next_frame_time else {
             return;
@@ -143,29 +157,24 @@ impl ApplicationHandler<UserEvent> for App {
             return;
         };
 
-        {
-            let hit_breakpoint = emu_state.snes.run();
-
-            if hit_breakpoint {
-                emu_state.stopped = true;
-            }
-
-            emu_state.update_displayed_image();
-        }
-
-        *next_frame_time += match emu_state.snes.ppu.variant {
-            PpuVariant::Ntsc => PERIOD_60HZ,
-            PpuVariant::Pal => PERIOD_50HZ,
-        };
+        *next_frame_time += frame_period;
+        self.state.run_frame();
         active.window.request_redraw();
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         if let Some(active) = &mut self.active {
+            active.renderer.set_vsync(self.state.sync_to_display);
+            if self.state.sync_to_display && self.state.next_frame_time.is_some() {
+                self.state.run_display_synced_frames();
+                active.needs_redraw = true;
+            }
             active.draw(&mut self.state);
         };
 
         if self.state.should_exit {
+            #[cfg(not(target_arch = "wasm32"))]
+            self.state.stop_audio_recording();
             event_loop.exit();
         }
 
@@ -174,6 +183,7 @@ impl ApplicationHandler<UserEvent> for App {
                 if self.state.next_frame_time.is_some() {
                     tracing::info!("Pausing emulation");
                     self.state.next_frame_time = None;
+                    self.state.last_present_time = None;
                 }
             } else if self.state.next_frame_time.is_none() {
                 tracing::info!("Resuming emulation");
@@ -183,6 +193,8 @@ impl ApplicationHandler<UserEvent> for App {
 
         event_loop.set_control_flow(match self.state.next_frame_time {
             None => ControlFlow::Wait,
+            // Presenting blocks until the next vertical blank, which paces the event loop
+            Some(_) if self.state.sync_to_display => ControlFlow::Poll,
             Some(next_frame_time) => ControlFlow::WaitUntil(next_frame_time),
         });
     }
@@ -192,7 +204,7 @@ impl ApplicationHandler<UserEvent> for App {
             UserEvent::RomPicked(rom) => {
                 self.state.rom_picker_open = false;
                 if let Some(rom) = rom {
-                    self.state.load_rom(rom);
+                    self.state.open_file(rom);
                 }
             }
             UserEvent::ActiveStateReady(mut active_state) => {
@@ -202,6 +214,13 @@ impl ApplicationHandler<UserEvent> for App {
                 active_state.renderer.resize(size.width, size.height);
                 self.active = Some(*active_state);
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            UserEvent::AudioRecordingPathPicked(path) => {
+                self.state.audio_path_picker_open = false;
+                if let Some(path) = path {
+                    self.state.start_audio_recording(&path);
+                }
+            }
         }
     }
 }
@@ -296,16 +315,85 @@ struct EmulationState {
     current_image: Arc<Mutex<snes_emu::ppu::OutputImage>>,
     current_image_height: u16,
     current_input: Arc<RwLock<Input>>,
+    unimplemented_feature: Option<&'static str>,
+    script: Option<scripting::ScriptHost>,
+    profile: profile::GameProfile,
+    /// The controllers which are currently connected, `None` before the settings were applied
+    controller_types: Option<[ControllerType; 2]>,
 }
 
 impl EmulationState {
-    fn new(snes: snes_emu::Snes, current_input: Arc<RwLock<Input>>) -> Self {
+    fn new(
+        snes: snes_emu::Snes,
+        profile: profile::GameProfile,
+        current_input: Arc<RwLock<Input>>,
+    ) -> Self {
         Self {
             snes,
             stopped: false,
             current_image: Arc::new(Mutex::new(snes_emu::ppu::OutputImage::default())),
             current_image_height: snes_emu::ppu::OutputImage::MIN_HEIGHT,
             current_input,
+            unimplemented_feature: None,
+            script: None,
+            profile,
+            controller_types: None,
+        }
+    }
+
+    /// Applies the global settings, overridden by the profile of the current game.
+    fn apply_settings(&mut self, defaults: &profile::Settings) {
+        let settings = self.profile.resolve(defaults);
+        settings.apply(&mut self.snes);
+        self.profile.apply_overrides(&mut self.snes);
+
+        // Reconnecting would reset the state of the devices, so only do it when they changed
+        if self.controller_types != Some(settings.controller_types) {
+            connect_controllers(
+                &mut self.snes,
+                settings.controller_types,
+                &self.current_input,
+            );
+            self.controller_types = Some(settings.controller_types);
+        }
+    }
+
+    fn handle_step_result(&mut self, result: StepResult) {
+        match result {
+            StepResult::Stepped => (),
+            StepResult::BreakpointHit => {
+                self.stopped = true;
+                if let Some(script) = &mut self.script {
+                    let regs = &self.snes.cpu.regs;
+                    let addr = u32::from(regs.k) << 16 | u32::from(regs.pc.get());
+                    script.on_breakpoint(&mut self.snes, addr);
+                }
+            }
+            StepResult::Unimplemented(feature) => {
+                self.stopped = true;
+                self.unimplemented_feature = Some(feature);
+            }
+            StepResult::OpenBus(access) => {
+                self.stopped = true;
+                let kind = match access.write {
+                    true => "Write to",
+                    false => "Read from",
+                };
+                tracing::warn!("{kind} open bus at ${:06X}", access.addr);
+            }
+            StepResult::WatchpointHit(hit) => {
+                self.stopped = true;
+                let kind = match hit.kind {
+                    AccessKind::Read => "read from",
+                    AccessKind::Write => "written to",
+                };
+                tracing::info!(
+                    "Watchpoint hit: ${:02X} {kind} ${:06X}",
+                    hit.value,
+                    hit.addr
+                );
+            }
+            StepResult::BudgetExhausted => self.stopped = true,
         }
     }
 
@@ -317,22 +405,6 @@ impl EmulationState {
     }
 }
 
-#[derive(Default)]
-struct Input {
-    start: bool,
-    select: bool,
-    up: bool,
-    down: bool,
-    left: bool,
-    right: bool,
-    a: bool,
-    b: bool,
-    x: bool,
-    y: bool,
-    l: bool,
-    r: bool,
-}
-
 struct AppState {
     event_loop_proxy: EventLoopProxy<UserEvent>,
     emulation_state: Option<EmulationState>,
@@ -340,8 +412,29 @@ struct AppState {
     show_debugger: bool,
     should_exit: bool,
     next_frame_time: Option<Instant>,
+    /// Pace emulation by the refresh rate of the display instead of the exact frame rate of the
+    /// console, see [`AppState::run_display_synced_frames`].
+    sync_to_display: bool,
+    last_present_time: Option<Instant>,
+    frame_debt: Duration,
+    /// Average interval between presented frames while emulating one frame per displayed frame
+    display_period: Option<Duration>,
     current_input: Arc<RwLock<Input>>,
     rom_picker_open: bool,
+    pending_archive: Option<PendingArchive>,
+    settings: profile::Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    audio_output: Option<audio_output::AudioOutput>,
+    #[cfg(not(target_arch = "wasm32"))]
+    audio_recorder: Option<wav::WavWriter>,
+    #[cfg(not(target_arch = "wasm32"))]
+    audio_path_picker_open: bool,
+}
+
+/// An opened archive containing multiple ROMs, waiting for the user to pick one
+struct PendingArchive {
+    data: Box<[u8]>,
+    roms: Vec<String>,
 }
 
 impl AppState {
@@ -353,8 +446,111 @@ impl AppState {
             show_debugger: cfg!(debug_assertions),
             should_exit: false,
             next_frame_time: None,
+            sync_to_display: false,
+            last_present_time: None,
+            frame_debt: Duration::ZERO,
+            display_period: None,
             current_input: Arc::new(RwLock::new(Input::default())),
             rom_picker_open: false,
+            pending_archive: None,
+            settings: profile::Settings::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            audio_output: audio_output::AudioOutput::open(),
+            #[cfg(not(target_arch = "wasm32"))]
+            audio_recorder: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            audio_path_picker_open: false,
+        }
+    }
+
+    fn run_frame(&mut self) {
+        let Some(emu_state) = &mut self.emulation_state else {
+            return;
+        };
+
+        let result = emu_state.snes.run();
+        if result == StepResult::Stepped
+            && let Some(script) = &mut emu_state.script
+        {
+            script.on_frame(&mut emu_state.snes);
+        }
+        emu_state.handle_step_result(result);
+        emu_state.update_displayed_image();
+
+        let mut samples = Vec::new();
+        emu_state.snes.take_audio_samples(&mut samples);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(output) = &self.audio_output {
+                output.push_samples(&samples);
+            }
+            let frames = samples.chunks_exact(2).map(|frame| [frame[0], frame[1]]);
+            if let Some(recorder) = &mut self.audio_recorder
+                && let Err(err) = recorder.write_samples(frames)
+            {
+                tracing::error!("Failed to write audio recording: {err}");
+                self.audio_recorder = None;
+            }
+        }
+    }
+
+    /// Sample rate of the audio output device, or the native rate of the APU if there is none.
+    fn audio_sample_rate(&self) -> u32 {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(output) = &self.audio_output {
+            return output.sample_rate();
+        }
+        snes_emu::apu::Apu::SAMPLE_RATE
+    }
+
+    /// Runs the frames which are due until the next frame is presented.
+    ///
+    /// If the interval between two presented frames is within 5% of the emulated frame period,
+    /// exactly one frame is emulated per presented frame. This avoids the judder caused by
+    /// occasionally running zero or two frames, at the cost of running the game slightly too fast
+    /// or too slow (e.g. 60 Hz instead of 60.0988 Hz). The audio is resampled by the same ratio to
+    /// stay in sync. On displays with a very different refresh rate, frames are emulated according
+    /// to the elapsed real time, just like in exact-rate mode.
+    fn run_display_synced_frames(&mut self) {
+        let device_rate = self.audio_sample_rate();
+        let Some(emu_state) = &mut self.emulation_state else {
+            return;
+        };
+        let frame_period = emu_state.snes.ppu.variant.frame_period();
+
+        let now = Instant::now();
+        let elapsed = match self.last_present_time.replace(now) {
+            Some(last_present_time) => now - last_present_time,
+            None => frame_period,
+        };
+
+        if elapsed.abs_diff(frame_period) < frame_period / 20 {
+            self.frame_debt = frame_period;
+
+            // One frame of audio is played during one display period. The period is averaged so
+            // that jitter in the presentation times doesn't make the pitch wobble.
+            let display_period = match self.display_period {
+                Some(display_period) => display_period.mul_f64(0.95) + elapsed.mul_f64(0.05),
+                None => elapsed,
+            };
+            self.display_period = Some(display_period);
+            let ratio = display_period.as_secs_f64() / frame_period.as_secs_f64();
+            let rate = (f64::from(device_rate) * ratio).round() as u32;
+            emu_state.snes.set_audio_sample_rate(rate);
+        } else {
+            // Don't try to catch up after long hitches
+            self.frame_debt = (self.frame_debt + elapsed).min(frame_period * 4);
+            self.display_period = None;
+            emu_state.snes.set_audio_sample_rate(device_rate);
+        }
+
+        while self.frame_debt >= frame_period {
+            self.frame_debt -= frame_period;
+            self.run_frame();
+
+            if self.emulation_state.as_ref().is_none_or(|emu| emu.stopped) {
+                break;
+            }
         }
     }
 
@@ -367,6 +563,30 @@ impl AppState {
             });
         }
 
+        if let Some(pending_archive) = &self.pending_archive {
+            let mut selected = None;
+            let mut cancelled = false;
+            egui::Window::new("Select ROM")
+                .collapsible(false)
+                .resizable(false)
+                .show(ui.ctx(), |ui| {
+                    for name in &pending_archive.roms {
+                        if ui.button(name).clicked() {
+                            selected = Some(name.clone());
+                        }
+                    }
+                    ui.separator();
+                    cancelled = ui.button("Cancel").clicked();
+                });
+
+            if let Some(name) = selected {
+                let pending_archive = self.pending_archive.take().unwrap();
+                self.load_rom_from_archive(&pending_archive.data, &name);
+            } else if cancelled {
+                self.pending_archive = None;
+            }
+        }
+
         let Some(emu_state) = &mut self.emulation_state else {
             egui::CentralPanel::default().show(ui, |ui| {
                 ui.allocate_ui_with_layout(
@@ -396,6 +616,20 @@ impl AppState {
             }
         });
 
+        if let Some(feature) = emu_state.unimplemented_feature {
+            egui::Panel::bottom("unimplemented-feature").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::LIGHT_RED,
+                        format!("Feature not yet implemented: {feature}. Emulation was paused."),
+                    );
+                    if ui.button("Dismiss").clicked() {
+                        emu_state.unimplemented_feature = None;
+                    }
+                });
+            });
+        }
+
         if self.show_debugger {
             self.debugger.show(ui, emu_state);
         } else {
@@ -416,6 +650,13 @@ impl AppState {
             current_input.y = input.key_down(egui::Key::J);
             current_input.l = input.key_down(egui::Key::U);
             current_input.r = input.key_down(egui::Key::O);
+            current_input.pointer_primary = input.pointer.primary_down();
+            current_input.pointer_secondary = input.pointer.secondary_down();
+            let motion = input.pointer.delta();
+            current_input.mouse_motion.0 += motion.x;
+            current_input.mouse_motion.1 += motion.y;
+            current_input.turbo = input.key_down(egui::Key::T);
+            current_input.pause = input.key_down(egui::Key::P);
         })
     }
 
@@ -427,10 +668,169 @@ impl AppState {
                 }
             });
             #[cfg(not(target_arch = "wasm32"))]
+            if self.audio_recorder.is_some() {
+                if ui.button("Stop Audio Recording").clicked() {
+                    self.stop_audio_recording();
+                }
+            } else {
+                ui.add_enabled_ui(!self.audio_path_picker_open, |ui| {
+                    if ui.button("Record Audio").clicked() {
+                        self.open_audio_path_picker();
+                    }
+                });
+            }
+            #[cfg(not(target_arch = "wasm32"))]
             if ui.button("Exit").clicked() {
                 self.should_exit = true;
             }
         });
+        ui.menu_button("Options", |ui| {
+            let sync_changed = ui
+                .checkbox(&mut self.sync_to_display, "Sync to Display")
+                .on_hover_text(
+                    "Emulate one frame per displayed frame to reduce stutter. The game runs \
+                     slightly faster or slower than on real hardware.",
+                )
+                .changed();
+            if sync_changed && !self.sync_to_display {
+                // Go back to the exact rate, which was adjusted to the refresh rate of the display
+                self.display_period = None;
+                let rate = self.audio_sample_rate();
+                if let Some(emu_state) = &mut self.emulation_state {
+                    emu_state.snes.set_audio_sample_rate(rate);
+                }
+            }
+
+            let mut changed = false;
+            changed |= ui
+                .checkbox(
+                    &mut self.settings.disable_sprite_limits,
+                    "Disable Sprite Limits",
+                )
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.settings.disable_color_math, "Disable Color Math")
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.settings.disable_windows, "Disable Windows")
+                .changed();
+            changed |= ui
+                .checkbox(
+                    &mut self.settings.pause_on_open_bus,
+                    "Pause on Open Bus Access",
+                )
+                .changed();
+
+            if changed && let Some(emu_state) = &mut self.emulation_state {
+                emu_state.apply_settings(&self.settings);
+            }
+        });
+        ui.menu_button("Input", |ui| {
+            let mut changed = false;
+            for (i, controller_type) in self.settings.controller_types.iter_mut().enumerate() {
+                ui.menu_button(format!("Port {}", i + 1), |ui| {
+                    for option in ControllerType::ALL {
+                        changed |= ui
+                            .radio_value(controller_type, option, option.label())
+                            .changed();
+                    }
+                });
+            }
+
+            if changed && let Some(emu_state) = &mut self.emulation_state {
+                emu_state.apply_settings(&self.settings);
+            }
+        });
+        if let Some(emu_state) = &mut self.emulation_state {
+            ui.menu_button("Game", |ui| {
+                /// Lets the user pick one of `options` or `None`, which is labeled `none_label`.
+                fn override_menu<T: Copy + PartialEq>(
+                    ui: &mut egui::Ui,
+                    label: &str,
+                    value: &mut Option<T>,
+                    none_label: &str,
+                    options: &[(T, &str)],
+                ) -> bool {
+                    let mut changed = false;
+                    ui.menu_button(label, |ui| {
+                        changed |= ui.radio_value(value, None, none_label).changed();
+                        for &(option, option_label) in options {
+                            changed |= ui.radio_value(value, Some(option), option_label).changed();
+                        }
+                    });
+                    changed
+                }
+
+                const GLOBAL: &str = "Use Global Setting";
+                const HEADER: &str = "Use ROM Header";
+                const ON_OFF: &[(bool, &str)] = &[(true, "Enabled"), (false, "Disabled")];
+
+                let profile = &mut emu_state.profile;
+                let mut changed = false;
+                changed |= override_menu(
+                    ui,
+                    "Disable Sprite Limits",
+                    &mut profile.disable_sprite_limits,
+                    GLOBAL,
+                    ON_OFF,
+                );
+                changed |= override_menu(
+                    ui,
+                    "Disable Color Math",
+                    &mut profile.disable_color_math,
+                    GLOBAL,
+                    ON_OFF,
+                );
+                changed |= override_menu(
+                    ui,
+                    "Disable Windows",
+                    &mut profile.disable_windows,
+                    GLOBAL,
+                    ON_OFF,
+                );
+                changed |= override_menu(
+                    ui,
+                    "Pause on Open Bus Access",
+                    &mut profile.pause_on_open_bus,
+                    GLOBAL,
+                    ON_OFF,
+                );
+                ui.separator();
+                let controller_options = ControllerType::ALL.map(|option| (option, option.label()));
+                for (i, controller_type) in profile.controller_types.iter_mut().enumerate() {
+                    changed |= override_menu(
+                        ui,
+                        &format!("Port {}", i + 1),
+                        controller_type,
+                        GLOBAL,
+                        &controller_options,
+                    );
+                }
+                changed |= override_menu(
+                    ui,
+                    "Mapping Mode",
+                    &mut profile.mapping_mode,
+                    HEADER,
+                    &[
+                        (MappingMode::LoRom, "LoROM"),
+                        (MappingMode::HiRom, "HiROM"),
+                        (MappingMode::ExHiRom, "ExHiROM"),
+                    ],
+                );
+                changed |= override_menu(
+                    ui,
+                    "Region",
+                    &mut profile.ppu_variant,
+                    HEADER,
+                    &[(PpuVariant::Ntsc, "NTSC"), (PpuVariant::Pal, "PAL")],
+                );
+
+                if changed {
+                    emu_state.profile.save(&emu_state.snes.header);
+                    emu_state.apply_settings(&self.settings);
+                }
+            });
+        }
     }
 
     fn open_rom_picker(&mut self) {
@@ -441,7 +841,7 @@ impl AppState {
         let proxy = self.event_loop_proxy.clone();
         let pick_rom_future = async move {
             let handle = rfd::AsyncFileDialog::new()
-                .add_filter("SNES ROM", &["sfc", "smc", "SFC", "SMC"])
+                .add_filter("SNES ROM", &["sfc", "smc", "zip", "SFC", "SMC", "ZIP"])
                 .pick_file()
                 .await;
 
@@ -462,27 +862,91 @@ impl AppState {
         wasm_bindgen_futures::spawn_local(pick_rom_future);
     }
 
-    fn load_rom(&mut self, rom: Box<[u8]>) {
-        let mut snes = Snes::new(rom);
-
-        let current_input = Arc::clone(&self.current_input);
-        snes.set_input1(Some(Box::new(snes_emu::input::Joypad::new(move || {
-            let current_input = current_input.read().unwrap();
-            snes_emu::input::JoypadState {
-                button_b: current_input.b,
-                button_y: current_input.y,
-                button_select: current_input.select,
-                button_start: current_input.start,
-                dpad_up: current_input.up,
-                dpad_down: current_input.down,
-                dpad_left: current_input.left,
-                dpad_right: current_input.right,
-                button_a: current_input.a,
-                button_x: current_input.x,
-                button_l: current_input.l,
-                button_r: current_input.r,
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_audio_path_picker(&mut self) {
+        if self.audio_path_picker_open {
+            return;
+        }
+
+        let proxy = self.event_loop_proxy.clone();
+        let pick_path_future = async move {
+            let handle = rfd::AsyncFileDialog::new()
+                .add_filter("WAV", &["wav"])
+                .set_file_name("recording.wav")
+                .save_file()
+                .await;
+
+            let path = handle.map(|handle| handle.path().to_owned());
+            _ = proxy.send_event(UserEvent::AudioRecordingPathPicked(path));
+        };
+
+        self.audio_path_picker_open = true;
+        std::thread::spawn(|| pollster::block_on(pick_path_future));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_audio_recording(&mut self, path: &std::path::Path) {
+        self.stop_audio_recording();
+
+        match wav::WavWriter::create(path, self.audio_sample_rate()) {
+            Ok(recorder) => {
+                tracing::info!("Recording audio to {}", path.display());
+                self.audio_recorder = Some(recorder);
+            }
+            Err(err) => tracing::error!("Failed to create {}: {err}", path.display()),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn stop_audio_recording(&mut self) {
+        if let Some(recorder) = self.audio_recorder.take()
+            && let Err(err) = recorder.finish()
+        {
+            tracing::error!("Failed to finish audio recording: {err}");
+        }
+    }
+
+    fn open_file(&mut self, data: Box<[u8]>) {
+        if !archive::is_zip(&data) {
+            self.load_rom(data);
+            return;
+        }
+
+        let roms = match archive::list_roms(&data) {
+            Ok(roms) => roms,
+            Err(err) => {
+                tracing::error!("Failed to read archive: {err}");
+                return;
             }
-        }))));
-        self.emulation_state = Some(EmulationState::new(snes, Arc::clone(&self.current_input)));
+        };
+
+        match roms.as_slice() {
+            [] => tracing::error!("Archive does not contain any ROMs"),
+            [name] => self.load_rom_from_archive(&data, name),
+            _ => self.pending_archive = Some(PendingArchive { data, roms }),
+        }
+    }
+
+    fn load_rom_from_archive(&mut self, data: &[u8], name: &str) {
+        match archive::extract(data, name) {
+            Ok(rom) => self.load_rom(rom),
+            Err(err) => tracing::error!("Failed to extract {name} from archive: {err}"),
+        }
+    }
+
+    fn load_rom(&mut self, rom: Box<[u8]>) {
+        let mut snes = Snes::from_rom(rom);
+        snes.set_audio_sample_rate(self.audio_sample_rate());
+        if let Some(srtc) = snes.srtc_mut() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            srtc.set_time(RtcTime::from_unix_time(now.as_secs()));
+        }
+
+        let profile = profile::GameProfile::load(&snes.header);
+        let mut emu_state = EmulationState::new(snes, profile, Arc::clone(&self.current_input));
+        emu_state.apply_settings(&self.settings);
+        self.emulation_state = Some(emu_state);
     }
 }
@@ -1,4 +1,7 @@
+use std::time::Duration;
+
 use arbitrary_int::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{RomHeader, Snes, header::Region};
 
@@ -14,6 +17,22 @@ pub enum OBSELSizeSelection {
     Small16x32Large32x32,
 }
 
+impl OBSELSizeSelection {
+    /// Returns the width and height of small and large objects, in pixels.
+    pub fn sizes(self) -> [(u8, u8); 2] {
+        match self {
+            Self::Small8x8Large16x16 => [(8, 8), (16, 16)],
+            Self::Small8x8Large32x32 => [(8, 8), (32, 32)],
+            Self::Small8x8Large64x64 => [(8, 8), (64, 64)],
+            Self::Small16x16Large32x32 => [(16, 16), (32, 32)],
+            Self::Small16x16Large64x64 => [(16, 16), (64, 64)],
+            Self::Small32x32Large64x64 => [(32, 32), (64, 64)],
+            Self::Small16x32Large32x64 => [(16, 32), (32, 64)],
+            Self::Small16x32Large32x32 => [(16, 32), (32, 32)],
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum VMAINIncrementMode {
     Low,
@@ -68,7 +87,7 @@ pub enum MathOperation {
     Sub,
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub struct Background {
     pub size: BackgroundSize,
     /// 1k word-steps
@@ -175,6 +194,21 @@ impl Default for Windows {
     }
 }
 
+impl Windows {
+    /// Sets the window enable and invert flags of `layer` (the bit in the window masks) from a
+    /// nibble of W12SEL, W34SEL or WOBJSEL: bit 0 inverts window 1, bit 1 enables it, bits 2 and
+    /// 3 do the same for window 2.
+    fn set_layer_selection(&mut self, layer: u8, bits: u8) {
+        let set = |flags: &mut u8, bit: u8| {
+            *flags = *flags & !(1 << layer) | (bits >> bit & 0x01) << layer;
+        };
+        set(&mut self.w1inv, 0);
+        set(&mut self.w1en, 1);
+        set(&mut self.w2inv, 2);
+        set(&mut self.w2en, 3);
+    }
+}
+
 #[derive(Default, Clone, Copy)]
 pub struct Screens {
     pub tm: u8,
@@ -251,8 +285,8 @@ impl OutputImage {
     pub const MAX_PIXELS: usize = Self::WIDTH as usize * Self::MAX_HEIGHT as usize;
 
     fn set(&mut self, x: u16, y: u16, color: OutputColor) {
-        assert!(x < 256 * 2);
-        assert!(y < 239 * 2);
+        assert!(x < Self::WIDTH);
+        assert!(y < Self::MAX_HEIGHT);
         let idx = usize::from(x) | (usize::from(y) * 512);
         self.0[idx] = color;
     }
@@ -260,14 +294,63 @@ impl OutputImage {
     pub fn pixels_rgba(&self) -> &[u8] {
         bytemuck::cast_slice(&self.0[..])
     }
+
+    /// Converts the first `height` rows of the image to 8 bit RGB, applying the brightness the same
+    /// way as the display shader does.
+    pub fn to_rgb8(&self, height: u16) -> Vec<u8> {
+        let num_pixels = usize::from(Self::WIDTH) * usize::from(height.min(Self::MAX_HEIGHT));
+        self.0[..num_pixels]
+            .iter()
+            .flat_map(|color| {
+                let brightness = u32::from(color.brightness) + 1;
+                [color.red, color.green, color.blue]
+                    .map(|c| ((u32::from(c) * 255 * brightness + 31 * 8) / (31 * 16)) as u8)
+            })
+            .collect()
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PpuVariant {
     Ntsc,
     Pal,
 }
 
+impl PpuVariant {
+    /// The variant of the consoles sold in the given region. Unknown regions are assumed to be PAL.
+    pub fn from_region(region: Option<Region>) -> Self {
+        match region {
+            Some(Region::Japan | Region::NorthAmerica | Region::Canada) => Self::Ntsc,
+            _ => Self::Pal,
+        }
+    }
+
+    /// Number of master clock cycles per scanline, including the two long dots.
+    pub const MASTER_CYCLES_PER_SCANLINE: u32 = 1364;
+
+    /// Frequency of the master clock in Hz (6 times the NTSC/PAL color subcarrier).
+    pub fn master_clock_hz(self) -> f64 {
+        match self {
+            Self::Ntsc => 236_250_000.0 / 11.0,
+            Self::Pal => 21_281_370.0,
+        }
+    }
+
+    pub fn scanlines_per_frame(self) -> u16 {
+        match self {
+            Self::Ntsc => 262,
+            Self::Pal => 312,
+        }
+    }
+
+    /// Duration of a single (non-interlaced) frame on real hardware. This is roughly 60.0988 Hz on
+    /// NTSC and 50.007 Hz on PAL consoles.
+    pub fn frame_period(self) -> Duration {
+        let cycles = Self::MASTER_CYCLES_PER_SCANLINE * u32::from(self.scanlines_per_frame());
+        Duration::from_secs_f64(f64::from(cycles) / self.master_clock_hz())
+    }
+}
+
 pub struct Ppu {
     ////////////////////////////////////////////////////////////////////////////
     // write-only
@@ -330,16 +413,27 @@ pub struct Ppu {
     ////////////////////////////////////////////////////////////////////////////
     // internal
     pub variant: PpuVariant,
+    /// Enhancement: Ignore the per-scanline object and tile limits
+    pub disable_sprite_limits: bool,
+    /// Debugging: Show the main screen as is, without color math or clipping it to black
+    pub disable_color_math: bool,
+    /// Debugging: Ignore the windows, no layer is masked and the color window is empty
+    pub disable_windows: bool,
     pub oam: Box<[u8; 0x220]>,
+    /// 10-bit OAM byte address, OAMADD shifted left by one. Addresses >= 0x200 mirror the high table
     oam_addr: u16,
+    /// Holds the even byte written to the low table until the odd byte completes the word
+    oam_latch: u8,
     pub vram: Box<[u8; 0x10000]>,
     pub cgram: Box<[u8; 0x200]>,
     cgram_selector: u8,
+    /// Holds the low byte written to CGDATA until the high byte completes the color
+    cgram_latch: u8,
     bg_old: u8,
     m7_old: u8,
     ophct_selector: u8,
     opvct_selector: u8,
-    current_object_tiles: [ScanlineObjectTile; 34],
+    current_object_tiles: [ScanlineObjectTile; 128 * 8],
     current_object_tiles_len: usize,
 
     pub(super) cycles: u64,
@@ -350,10 +444,7 @@ pub struct Ppu {
 
 impl Ppu {
     pub fn from_rom_header(header: &RomHeader) -> Self {
-        let variant = match header.region {
-            Some(Region::Japan | Region::NorthAmerica | Region::Canada) => PpuVariant::Ntsc,
-            _ => PpuVariant::Pal,
-        };
+        let variant = PpuVariant::from_region(header.region);
 
         Self {
             backgrounds: Backgrounds::default(),
@@ -401,16 +492,21 @@ impl Ppu {
             stat78: 0x00,
 
             variant,
+            disable_sprite_limits: false,
+            disable_color_math: false,
+            disable_windows: false,
             oam: vec![0; 0x220].try_into().unwrap(),
             oam_addr: 0,
+            oam_latch: 0,
             vram: vec![0; 0x10000].try_into().unwrap(),
             cgram: vec![0; 0x200].try_into().unwrap(),
             cgram_selector: 0,
+            cgram_latch: 0,
             bg_old: 0,
             m7_old: 0,
             ophct_selector: 0,
             opvct_selector: 0,
-            current_object_tiles: [ScanlineObjectTile::default(); 34],
+            current_object_tiles: [ScanlineObjectTile::default(); 128 * 8],
             current_object_tiles_len: 0,
 
             cycles: 0,
@@ -425,13 +521,7 @@ impl Ppu {
             0x2134 => self.mpyl,
             0x2135 => self.mpym,
             0x2136 => self.mpyh,
-            0x2104 => {
-                let addr = usize::from(self.oam_addr);
-                if addr >= self.oam.len() {
-                    return None;
-                }
-                self.oam[addr]
-            }
+            0x2138 => self.oam[Self::oam_index(self.oam_addr)],
             0x2139 => self.vmdatal,
             0x213A => self.vmdatah,
             0x213B => {
@@ -454,18 +544,13 @@ impl Ppu {
             0x2135 => self.mpym,
             0x2136 => self.mpyh,
             0x2137 => {
-                self.ophct = self.hpos;
-                self.opvct = self.vpos;
-                self.stat78 |= 0x40;
+                self.latch_counters(self.hpos, self.vpos);
                 return None;
             }
             0x2138 => {
-                let addr = usize::from(self.oam_addr);
-                self.oam_addr = self.oam_addr.wrapping_add(1);
-                if addr >= self.oam.len() {
-                    return None;
-                }
-                self.oam[addr]
+                let value = self.oam[Self::oam_index(self.oam_addr)];
+                self.oam_addr = (self.oam_addr + 1) & 0x3FF;
+                value
             }
             0x2139 => {
                 let value = self.vmdatal;
@@ -536,18 +621,24 @@ impl Ppu {
             }
             0x2102 => {
                 self.oamaddl = value;
-                self.oam_addr = (self.oamaddh as u16) << 9 | (self.oamaddl as u16) << 1;
+                self.reload_oam_addr();
             }
             0x2103 => {
                 self.oamaddh = value & 1;
-                self.oam_addr = (self.oamaddh as u16) << 9 | (self.oamaddl as u16) << 1;
+                self.reload_oam_addr();
             }
             0x2104 => {
-                let addr = usize::from(self.oam_addr);
-                if addr < self.oam.len() {
+                let addr = self.oam_addr;
+                if addr >= 0x200 {
+                    self.oam[Self::oam_index(addr)] = value;
+                } else if addr & 1 == 0 {
+                    self.oam_latch = value;
+                } else {
+                    let addr = usize::from(addr);
+                    self.oam[addr - 1] = self.oam_latch;
                     self.oam[addr] = value;
                 }
-                self.oam_addr = self.oam_addr.wrapping_add(1);
+                self.oam_addr = (self.oam_addr + 1) & 0x3FF;
             }
             0x2105 => {
                 self.backgrounds.backgrounds[3].large_tiles = value & 0x80 != 0;
@@ -583,10 +674,13 @@ impl Ppu {
                 self.backgrounds.backgrounds[3].tile_base_address = u4::extract_u8(value, 4);
                 self.backgrounds.backgrounds[2].tile_base_address = u4::extract_u8(value, 0);
             }
+            // All eight BG scroll registers share one latch, which holds the last value written to
+            // any of them. The horizontal ones only take bits 3-7 from it and keep bits 0-2 of the
+            // old high byte. BG1HOFS and BG1VOFS double as M7HOFS and M7VOFS, which use the latch
+            // of the other Mode 7 registers instead and are updated independently.
             0x210D..=0x2114 => {
                 let background = &mut self.backgrounds.backgrounds[((addr - 0x210D) / 2) as usize];
                 if addr % 2 == 1 {
-                    // FIXME: Not sure if this is correct.
                     background.h_offset = (value as u16) << 8
                         | ((self.bg_old & !7) as u16)
                         | (background.h_offset >> 8 & 7);
@@ -635,13 +729,13 @@ impl Ppu {
                 self.prefetch_vmadd();
             }
             0x2118 => {
-                self.vram[usize::from(self.translated_vram_word_address() << 1)] = value;
+                self.vram[vram_byte_addr(self.translated_vram_word_address())] = value;
                 if self.vmain_increment_mode == VMAINIncrementMode::Low {
                     self.increment_vmadd();
                 }
             }
             0x2119 => {
-                self.vram[usize::from(self.translated_vram_word_address() << 1) | 1] = value;
+                self.vram[vram_byte_addr(self.translated_vram_word_address()) | 1] = value;
                 if self.vmain_increment_mode == VMAINIncrementMode::High {
                     self.increment_vmadd();
                 }
@@ -657,8 +751,8 @@ impl Ppu {
                 self.m7sel_screen_hflip = value & 0x1 != 0;
             }
             0x211B => {
-                // TODO: This port can also be used for general purpose math multiply.
-                // FIXME: I have no idea if this is the correct way.
+                // M7A and M7B double as the operands of a general purpose signed multiplication,
+                // see `update_mpy_regs`.
                 self.m7a = ((value as i16) << 8) | self.m7_old as i16;
                 self.m7_old = value;
                 self.update_mpy_regs();
@@ -684,68 +778,32 @@ impl Ppu {
                 self.m7y = i13::masked_new(((value as i16) << 8) | self.m7_old as i16);
                 self.m7_old = value;
             }
+            // CGADD selects one of the 256 colors, accesses through CGDATA wrap around from the last
+            // color to the first one
             0x2121 => {
                 self.cgadd = value;
                 self.cgram_selector = 0;
             }
             0x2122 => {
-                self.cgram[usize::from(self.cgadd) * 2 + usize::from(self.cgram_selector)] = value;
-                self.cgadd = self.cgadd.wrapping_add(self.cgram_selector);
+                if self.cgram_selector == 0 {
+                    self.cgram_latch = value;
+                } else {
+                    // While rendering, the PPU itself is accessing CGRAM. The write would end up
+                    // at whatever color is currently being looked up, so we just drop it.
+                    if !self.is_rendering() {
+                        let addr = usize::from(self.cgadd) * 2;
+                        self.cgram[addr] = self.cgram_latch;
+                        self.cgram[addr + 1] = value & 0x7F;
+                    }
+                    self.cgadd = self.cgadd.wrapping_add(1);
+                }
                 self.cgram_selector ^= 1;
             }
-            0x2123 => {
-                self.windows.w1en &= !0x03;
-                self.windows.w2en &= !0x03;
-                self.windows.w1inv &= !0x03;
-                self.windows.w2inv &= !0x03;
-
-                // bit 1
-                self.windows.w1inv |= value & 0x01;
-                self.windows.w1en |= (value & 0x02) >> 1;
-                self.windows.w2inv |= (value & 0x04) >> 2;
-                self.windows.w2en |= (value & 0x08) >> 3;
-
-                // bit 2
-                self.windows.w1inv |= (value & 0x10) >> 3;
-                self.windows.w1en |= (value & 0x20) >> 4;
-                self.windows.w2inv |= (value & 0x40) >> 5;
-                self.windows.w2en |= (value & 0x80) >> 6;
-            }
-            0x2124 => {
-                self.windows.w1en &= !0x0C;
-                self.windows.w2en &= !0x0C;
-                self.windows.w1inv &= !0x0C;
-                self.windows.w2inv &= !0x0C;
-
-                // bit 3
-                self.windows.w1inv |= (value & 0x01) << 2;
-                self.windows.w1en |= (value & 0x02) << 1;
-                self.windows.w2inv |= value & 0x04;
-                self.windows.w2en |= (value & 0x08) >> 1;
-
-                // bit 4
-                self.windows.w1inv |= value & 0x10 >> 1;
-                self.windows.w1en |= (value & 0x20) >> 2;
-                self.windows.w2inv |= (value & 0x40) >> 3;
-                self.windows.w2en |= (value & 0x80) >> 4;
-            }
-            0x2125 => {
-                self.windows.w1en &= !0x30;
-                self.windows.w2en &= !0x30;
-                self.windows.w1inv &= !0x30;
-                self.windows.w2inv &= !0x30;
-
-                // bit 5
-                self.windows.w1inv |= (value & 0x01) << 4;
-                self.windows.w1en |= (value & 0x02) << 3;
-                self.windows.w2inv |= (value & 0x04) << 2;
-                self.windows.w2en |= (value & 0x08) << 1;
-
-                // bit 6
-                self.windows.w1inv |= (value & 0x10) << 1;
-                self.windows.w1en |= value & 0x20;
-                self.windows.w2inv |= (value & 0x40) >> 1;
-                self.windows.w2en |= (value & 0x80) >> 2;
+            // Every register configures two layers, one per nibble
+            0x2123..=0x2125 => {
+                let layer = (addr - 0x2123) as u8 * 2;
+                self.windows.set_layer_selection(layer, value & 0x0F);
+                self.windows.set_layer_selection(layer + 1, value >> 4);
             }
             0x2126 => self.windows.window1_left = value,
             0x2127 => self.windows.window1_right = value,
@@ -820,13 +878,20 @@ impl Ppu {
         }
     }
 
+    /// Updates MPYL/MPYM/MPYH with the signed product of the full 16 bit M7A value and the last
+    /// byte written to M7B (which is the high byte of `m7b`), both interpreted as two's complement.
+    /// The result is a signed 24 bit value, which always fits since |-32768 * -128| < 2^23.
     fn update_mpy_regs(&mut self) {
-        let mul_result = self.m7a as i32 * (self.m7b as i32 >> 8);
-        self.mpyl = mul_result as u8;
-        self.mpym = (mul_result >> 8) as u8;
-        self.mpyh = (mul_result >> 16) as u8;
+        let factor_a = i32::from(self.m7a);
+        let factor_b = i32::from((self.m7b >> 8) as i8);
+        let [mpyl, mpym, mpyh, _] = (factor_a * factor_b).to_le_bytes();
+        self.mpyl = mpyl;
+        self.mpym = mpym;
+        self.mpyh = mpyh;
     }
 
+    /// Applies the address translation selected in VMAIN, which rotates the lowest `n` bits of
+    /// VMADD left by three, e.g. `aaaaaaaaYYYxxxxx` becomes `aaaaaaaaxxxxxYYY` for 8 bits.
     fn translated_vram_word_address(&self) -> u16 {
         let n = match self.vmain_address_translation {
             VMAINAddressTranslation::None => return self.vmadd,
@@ -835,8 +900,10 @@ impl Ppu {
             VMAINAddressTranslation::Bit10 => 10,
         };
 
+        // After rotating, the lowest three bits of `temp >> (16 - n)` contain the topmost bits of
+        // VMADD which must not end up in the translated address
         let temp = self.vmadd.rotate_right(n - 3);
-        let rotated = (temp & 0x7) | temp >> (16 - n);
+        let rotated = (temp & 0x7) | (temp >> (16 - n) & !0x7);
 
         (self.vmadd & (!0 << n)) | rotated
     }
@@ -850,10 +917,37 @@ impl Ppu {
         self.vmadd = self.vmadd.wrapping_add(step);
     }
 
+    /// Fills the VRAM read latch from the current (translated) address. This happens whenever
+    /// either half of VMADD is written, always using the full combined address, and on VMDATA
+    /// reads before the address is incremented. Reads of VMDATA return the latch from before the
+    /// prefetch, which is why the first read after setting VMADD already returns the word at that
+    /// address.
     fn prefetch_vmadd(&mut self) {
         let word_addr = self.translated_vram_word_address();
-        self.vmdatal = self.vram[usize::from(word_addr << 1)];
-        self.vmdatah = self.vram[usize::from(word_addr << 1) + 1];
+        self.vmdatal = self.vram[vram_byte_addr(word_addr)];
+        self.vmdatah = self.vram[vram_byte_addr(word_addr) | 1];
+    }
+
+    /// Sets the internal OAM address to the one written to OAMADD. Besides on writes to OAMADD, this
+    /// also happens at the start of VBlank, which undoes the increments of 0x2104/0x2138 accesses
+    /// during the frame.
+    pub(crate) fn reload_oam_addr(&mut self) {
+        self.oam_addr = (self.oamaddh as u16) << 9 | (self.oamaddl as u16) << 1;
+    }
+
+    /// Latches the H/V counters into OPHCT/OPVCT, either by reading SLHV or through pin 6 of
+    /// controller port 2 (used by light guns).
+    pub(crate) fn latch_counters(&mut self, h: u16, v: u16) {
+        self.ophct = h;
+        self.opvct = v;
+        self.stat78 |= 0x40;
+    }
+
+    fn oam_index(addr: u16) -> usize {
+        match addr {
+            0x000..0x200 => usize::from(addr),
+            _ => 0x200 | usize::from(addr & 0x1F),
+        }
     }
 
     pub fn reset(&mut self) {
@@ -864,18 +958,51 @@ impl Ppu {
         self.setini_hpseudo512 = false;
         self.setini_extbg = false;
         self.setini_external_sync = false;
+
+        // Write-twice latches
+        self.bg_old = 0;
+        self.m7_old = 0;
+        self.oam_latch = 0;
+        self.cgram_selector = 0;
+        self.cgram_latch = 0;
+        self.ophct_selector = 0;
+        self.opvct_selector = 0;
+
+        for background in &mut self.backgrounds.backgrounds {
+            background.h_offset = 0;
+            background.v_offset = 0;
+        }
+        self.m7hofs = i13::ZERO;
+        self.m7vofs = i13::ZERO;
+        self.m7a = 0;
+        self.m7b = 0;
+        self.m7c = 0;
+        self.m7d = 0;
+        self.m7x = i13::ZERO;
+        self.m7y = i13::ZERO;
+
         self.cycles = 0;
         self.hpos = 0;
         self.vpos = 0;
     }
 
     pub fn max_vpos(&self) -> u16 {
-        match self.variant {
-            PpuVariant::Ntsc => 261,
-            PpuVariant::Pal => 311,
-        }
+        self.variant.scanlines_per_frame() - 1
+    }
+
+    fn is_rendering(&self) -> bool {
+        !self.inidisp_forced_blanking
+            && (1..=self.output_height()).contains(&self.vpos)
+            && (22..278).contains(&self.hpos)
     }
 
+    /// Toggles at the start of every frame and can be read through STAT78. In interlace mode, the
+    /// second field is drawn to the odd rows of the output.
+    pub fn interlace_field(&self) -> bool {
+        self.stat78 & 0x80 != 0
+    }
+
+    /// Number of visible scanlines per field.
     pub fn output_height(&self) -> u16 {
         match self.setini_overscan {
             false => 224,
@@ -883,23 +1010,45 @@ impl Ppu {
         }
     }
 
+    /// Whether every pixel is split into two halves with different colors, either through
+    /// pseudo-hires or by using BG mode 5 or 6.
+    pub fn hires(&self) -> bool {
+        self.setini_hpseudo512 || matches!(self.backgrounds.mode.value(), 5 | 6)
+    }
+
+    /// Resolution of the picture as selected by SETINI and BGMODE, interlacing doubles the
+    /// vertical resolution.
+    pub fn video_dimensions(&self) -> (u16, u16) {
+        let width = match self.hires() {
+            false => 256,
+            true => 512,
+        };
+
+        let height = match (self.setini_overscan, self.setini_interlace) {
+            (false, false) => 224,
+            (false, true) => 448,
+            (true, false) => 239,
+            (true, true) => 478,
+        };
+
+        (width, height)
+    }
+
     pub fn output(&self) -> &OutputImage {
         &self.output
     }
 
     fn prepare_objects(&mut self, y: u8) {
-        let sizes: [(u8, u8); 2] = match self.obsel_size_selection {
-            OBSELSizeSelection::Small8x8Large16x16 => [(8, 8), (16, 16)],
-            OBSELSizeSelection::Small8x8Large32x32 => [(8, 8), (32, 32)],
-            OBSELSizeSelection::Small8x8Large64x64 => [(8, 8), (64, 64)],
-            OBSELSizeSelection::Small16x16Large32x32 => [(16, 16), (32, 32)],
-            OBSELSizeSelection::Small16x16Large64x64 => [(16, 16), (32, 32)],
-            OBSELSizeSelection::Small32x32Large64x64 => [(32, 32), (64, 64)],
-            OBSELSizeSelection::Small16x32Large32x64 => [(16, 32), (32, 64)],
-            OBSELSizeSelection::Small16x32Large32x32 => [(16, 32), (32, 32)],
+        let sizes = self.obsel_size_selection.sizes();
+
+        // The range and time over flags are computed against the hardware limits even when the
+        // limits are disabled, since games may check them.
+        const MAX_OBJECTS: usize = 32;
+        const MAX_TILES: usize = 34;
+        let max_tiles = match self.disable_sprite_limits {
+            false => MAX_TILES,
+            true => self.current_object_tiles.len(),
         };
-
-        const MAX_OBJECTS: u32 = 32;
         let mut num_objects = 0;
         let mut num_tiles = 0;
 
@@ -929,14 +1078,20 @@ impl Ppu {
             num_objects += 1;
             if num_objects > MAX_OBJECTS {
                 self.stat77 |= 1 << 6;
-                break;
+                if !self.disable_sprite_limits {
+                    break;
+                }
             }
+            // Only the tiles of the first 32 objects on the line count towards the time over flag
+            let counts_tiles = num_objects <= MAX_OBJECTS;
 
-            if num_tiles >= self.current_object_tiles.len() {
+            if num_tiles >= MAX_TILES && counts_tiles {
                 // If the previous object filled to tiles array exactly, the time overflow flag
                 // would not have been set yet, and since we would be adding at least one tile now,
                 // we need to set the flag here too.
                 self.stat77 |= 1 << 7;
+            }
+            if num_tiles >= max_tiles {
                 continue;
             }
 
@@ -957,20 +1112,29 @@ impl Ppu {
             tile_row = tile_row.wrapping_add(y_off / 8 * 0x10);
 
             for mut x_off in (0..width).step_by(8) {
-                if num_tiles >= self.current_object_tiles.len() {
-                    self.stat77 |= 1 << 7;
-                    continue 'iterate_objects;
-                }
-
                 let tile_offset = ((tile_row | tile_col) as u16) << 4;
                 let tile_addr = tilemap_addr.wrapping_add(tile_offset) & 0x7FFF;
+                tile_col = (tile_col + 1) & 0x0F;
 
                 if x_flip {
                     x_off = width - 8 - x_off;
                 }
 
+                // Only tiles which are at least partially on screen count towards the tile limit
+                let tile_x = (obj_x + x_off as u16) & 0x1FF;
+                if tile_x > 255 && tile_x < 512 - 7 {
+                    continue;
+                }
+
+                if num_tiles >= MAX_TILES && counts_tiles {
+                    self.stat77 |= 1 << 7;
+                }
+                if num_tiles >= max_tiles {
+                    continue 'iterate_objects;
+                }
+
                 self.current_object_tiles[num_tiles] = ScanlineObjectTile {
-                    x: (obj_x + x_off as u16) & 0x1FF,
+                    x: tile_x,
                     tile_y_off: y.wrapping_sub(obj_y) & 0x07,
                     tile_addr,
                     x_flip,
@@ -979,24 +1143,33 @@ impl Ppu {
                     priority,
                 };
                 num_tiles += 1;
-
-                tile_col = (tile_col + 1) & 0x0F;
             }
         }
 
         self.current_object_tiles_len = num_tiles;
     }
 
-    fn render_pixel(&self, x: u16, y: u16) -> OutputColor {
+    /// Renders the two halves of the pixel at `x`. Without hires, both show the main screen. In
+    /// hires, the left half shows the sub screen instead. In BG modes 5 and 6 the backgrounds have
+    /// twice the horizontal resolution, so the sub screen shows the left and the main screen the
+    /// right half of every pixel.
+    fn render_pixel(&self, x: u16, y: u16, hires: bool) -> [OutputColor; 2] {
         let master_brightness = self.inidisp_master_brightness;
         if master_brightness == u4::ZERO {
-            return OutputColor::BLACK;
+            return [OutputColor::BLACK; 2];
         }
 
         let mode = self.backgrounds.mode.value();
-        let mut colors = self.get_layer_colors(x, y, mode);
+        let colors = self.get_layer_colors(x, y, mode, 1);
+        let sub_colors = match mode {
+            5 | 6 => self.get_layer_colors(x, y, mode, 0),
+            _ => colors,
+        };
 
-        let window = self.compute_window_mask(x);
+        let window = match self.disable_windows {
+            true => 0,
+            false => self.compute_window_mask(x),
+        };
 
         let main_layers = self.screens.tm & !(window & self.windows.tmw);
         let sub_layers = self.screens.ts & !(window & self.windows.tsw);
@@ -1006,9 +1179,10 @@ impl Ppu {
             mut layers: u8,
             bg3_high_priority: bool,
         ) -> (Color, u8) {
+            // Only BG3 tiles with their priority bit set are moved in front of everything else
             if bg3_high_priority
                 && (layers & (1 << LAYER_BG3) != 0)
-                && colors[LAYER_BG3 as usize].priority > 0
+                && colors[LAYER_BG3 as usize].priority == ModeDefinition::MODE1.bg_priorities[2][1]
             {
                 return (colors[LAYER_BG3 as usize].color, LAYER_BG3);
             }
@@ -1026,20 +1200,33 @@ impl Ppu {
             (colors[layer as usize].color, layer)
         }
 
+        fn blend(a: Color, b: Color, subtract: bool, halve: bool) -> Color {
+            let [r, g, b] = [(a.r, b.r), (a.g, b.g), (a.b, b.b)].map(|(a, b)| {
+                let (a, b) = (a.value() as i8, b.value() as i8);
+                let mut value = if subtract { a - b } else { a + b };
+                if halve {
+                    value /= 2;
+                }
+                u5::extract_u8(value.clamp(0x00, 0x1F) as u8, 0)
+            });
+            Color::new(r, g, b)
+        }
+
+        // The BG3 priority bit of BGMODE has no effect outside of mode 1
         let bg3_high_priority = mode == 1 && self.backgrounds.bg3_high_priority;
         let (mut main_color, main_layer) = select_color(&colors, main_layers, bg3_high_priority);
 
         let window_math_enabled = (window & WINDOW_MATH) == 0;
         let enable_screen_lut = [false, window_math_enabled, !window_math_enabled, true];
 
-        let enable_main_screen =
-            enable_screen_lut[usize::from(self.windows.main_screen_black as u8)];
+        let enable_main_screen = self.disable_color_math
+            || enable_screen_lut[usize::from(self.windows.main_screen_black as u8)];
 
         if !enable_main_screen {
             main_color = Color::BLACK;
         }
 
-        let math_enabled = match main_layer {
+        let math_enabled = |layer: u8| match layer {
             LAYER_BG1 => self.screens.math_on_backgrounds[0],
             LAYER_BG2 => self.screens.math_on_backgrounds[1],
             LAYER_BG3 => self.screens.math_on_backgrounds[2],
@@ -1048,68 +1235,68 @@ impl Ppu {
             _ => self.screens.math_on_backdrop,
         };
 
-        if !math_enabled {
-            return OutputColor::new(main_color.r, main_color.g, main_color.b, master_brightness);
-        }
-
-        let enable_sub_screen = enable_screen_lut[usize::from(self.windows.sub_screen_black as u8)];
+        let enable_sub_screen = !self.disable_color_math
+            && enable_screen_lut[usize::from(self.windows.sub_screen_black as u8)];
 
-        let mut sub_color = Color::BLACK;
-        let mut sub_layer = LAYER_BACKDROP;
-        let backdrop = Color::new(
+        let fixed_color = Color::new(
             self.screens.backdrop_red,
             self.screens.backdrop_green,
             self.screens.backdrop_blue,
         );
-        colors[LAYER_BACKDROP as usize] = LayerColor::new(backdrop, 0, 0);
-        if enable_sub_screen {
-            (sub_color, sub_layer) = match self.screens.sub_screen_bg_obj_enable {
-                true => select_color(&colors, sub_layers, bg3_high_priority),
-                false => (backdrop, 0xFF),
-            };
-        }
+        let subtract = self.screens.math_operation == MathOperation::Sub;
 
-        let mut output = [
-            sub_color.r.value() as i8,
-            sub_color.g.value() as i8,
-            sub_color.b.value() as i8,
-        ];
+        let main_math = math_enabled(main_layer) && enable_sub_screen;
+        let (sub_color, sub_layer) = match main_math || hires {
+            true => select_color(&sub_colors, sub_layers, bg3_high_priority),
+            false => (Color::BLACK, LAYER_BACKDROP),
+        };
 
-        if self.screens.math_operation == MathOperation::Sub {
-            output = output.map(std::ops::Neg::neg);
+        let mut right = main_color;
+        if main_math {
+            // Where the sub screen is transparent, the fixed color is used without halving
+            let (other, halve) = match self.screens.sub_screen_bg_obj_enable {
+                true if sub_layer == LAYER_BACKDROP => (fixed_color, false),
+                true => (sub_color, self.screens.half),
+                false => (fixed_color, self.screens.half),
+            };
+            right = blend(main_color, other, subtract, halve && enable_main_screen);
         }
 
-        output[0] += main_color.r.value() as i8;
-        output[1] += main_color.g.value() as i8;
-        output[2] += main_color.b.value() as i8;
-
-        if self.screens.half && enable_main_screen && sub_layer != LAYER_BACKDROP {
-            output = output.map(|v| v / 2);
+        let right = OutputColor::new(right.r, right.g, right.b, master_brightness);
+        if !hires {
+            return [right; 2];
         }
 
-        output = output.map(|v| v.clamp(0x00, 0x1F));
+        // Color math on the sub screen half works the same way, except that the sub screen is
+        // blended with the main screen
+        let mut left = match enable_main_screen {
+            true => sub_color,
+            false => Color::BLACK,
+        };
+        if math_enabled(sub_layer) && enable_sub_screen {
+            let other = match self.screens.sub_screen_bg_obj_enable {
+                true => main_color,
+                false => fixed_color,
+            };
+            left = blend(
+                left,
+                other,
+                subtract,
+                self.screens.half && enable_main_screen,
+            );
+        }
 
-        OutputColor::new(
-            u5::extract_u8(output[0] as u8, 0),
-            u5::extract_u8(output[1] as u8, 0),
-            u5::extract_u8(output[2] as u8, 0),
-            master_brightness,
-        )
+        let left = OutputColor::new(left.r, left.g, left.b, master_brightness);
+        [left, right]
     }
 
     fn compute_window_mask(&self, x: u16) -> u8 {
-        let pos = (x >> self.setini_hpseudo512 as u8) as u8;
+        let pos = x as u8;
         let outside_w1 = pos < self.windows.window1_left || pos > self.windows.window1_right;
         let outside_w2 = pos < self.windows.window2_left || pos > self.windows.window2_right;
 
-        let mut w1 = (outside_w1 as u8).wrapping_sub(1);
-        let mut w2 = (outside_w2 as u8).wrapping_sub(1);
-
-        w1 &= self.windows.w1en;
-        w2 &= self.windows.w2en;
-
-        w1 ^= self.windows.w1inv;
-        w2 ^= self.windows.w2inv;
+        let w1 = (outside_w1 as u8).wrapping_sub(1) ^ self.windows.w1inv;
+        let w2 = (outside_w2 as u8).wrapping_sub(1) ^ self.windows.w2inv;
 
         let or = w1 | w2;
         let and = w1 & w2;
@@ -1125,10 +1312,18 @@ impl Ppu {
         masks[self.windows.objects as usize] |= WINDOW_OBJ;
         masks[self.windows.math as usize] |= WINDOW_MATH;
 
-        (or & masks[0]) | (and & masks[1]) | (xor & masks[2]) | (xnor & masks[3])
+        let combined = (or & masks[0]) | (and & masks[1]) | (xor & masks[2]) | (xnor & masks[3]);
+
+        // The logic only applies if both windows are enabled for a layer, otherwise the enabled
+        // window is used as is. Layers without any enabled windows are never masked.
+        let w1en = self.windows.w1en;
+        let w2en = self.windows.w2en;
+        (combined & w1en & w2en) | (w1 & w1en & !w2en) | (w2 & w2en & !w1en)
     }
 
-    fn get_layer_colors(&self, x: u16, y: u16, mode: u8) -> [LayerColor; NUM_LAYERS] {
+    /// Returns the colors of all layers at `x`. In BG modes 5 and 6, `half` selects the left (0)
+    /// or right (1) half of the pixel for the backgrounds.
+    fn get_layer_colors(&self, x: u16, y: u16, mode: u8, half: u16) -> [LayerColor; NUM_LAYERS] {
         let mut colors = [LayerColor::TRANSPARENT; NUM_LAYERS];
         colors[LAYER_BACKDROP as usize] = LayerColor::new(self.get_color(0), 0, 0);
 
@@ -1164,7 +1359,7 @@ impl Ppu {
             let mode_def = &ModeDefinition::MODES[usize::from(mode)];
             let num_bgs = usize::from(mode_def.num_backgrounds);
             for (i, color) in colors.iter_mut().enumerate().take(num_bgs) {
-                *color = self.get_bg_color(x, y, i, mode_def);
+                *color = self.get_bg_color(x, y, half, i, mode_def);
             }
             obj_priorities = &mode_def.obj_priorities;
         }
@@ -1184,35 +1379,38 @@ impl Ppu {
         &self,
         mut x: u16,
         mut y: u16,
+        mut half: u16,
         bg_num: usize,
         mode_def: &ModeDefinition,
     ) -> LayerColor {
         let bg = &self.backgrounds.backgrounds[bg_num];
 
-        // screens in the order: top left, top right, bottom left, bottom right
-        let screens: [u8; 4] =
-            [[0, 0, 0, 0], [0, 1, 0, 1], [0, 0, 1, 1], [0, 1, 2, 3]][bg.size as usize];
-
-        let tile_size = 8 << (bg.large_tiles as u8);
+        let tile_height = 8 << (bg.large_tiles as u8);
+        // In hires modes, tiles are always 16 pixels wide
+        let tile_width = match mode_def.hires {
+            true => 16,
+            false => tile_height,
+        };
 
         if bg.mosaic {
             let size = self.backgrounds.mosaic_size.as_u16() + 1;
             x = x / size * size;
-            y = y / size * size;
+            // The blocks start at the first visible scanline
+            y = (y - 1) / size * size + 1;
+            half = 0;
         }
 
-        let translated_x = x.wrapping_add(bg.h_offset & 0x3FF);
-        let translated_y = y.wrapping_add(bg.v_offset & 0x3FF);
-
-        let tile_x = (translated_x / tile_size) & 0x3F;
-        let tile_y = (translated_y / tile_size) & 0x3F;
-        let tile_off_x = translated_x % tile_size;
-        let tile_off_y = translated_y % tile_size;
-
-        let quadrant = (tile_x >> 5) | (tile_y >> 4 & 0x02);
-        let screen = screens[usize::from(quadrant)];
+        let (h_offset, v_offset) = self.bg_offsets(x, bg_num, mode_def.offset_per_tile);
+        let translated_x = match mode_def.hires {
+            // The scroll offset still counts in pixels of the regular resolution
+            true => (x * 2 + half).wrapping_add((h_offset & 0x3FF) * 2),
+            false => x.wrapping_add(h_offset & 0x3FF),
+        };
+        let translated_y = y.wrapping_add(v_offset & 0x3FF);
 
-        let tile_idx = (tile_y & 0x1F) * 32 + (tile_x & 0x1F);
+        let tile_off_x = translated_x % tile_width;
+        let tile_off_y = translated_y % tile_height;
+        let (screen, tile_idx) = Self::tilemap_position(bg, translated_x, translated_y, tile_width);
 
         let bpp = mode_def.bpp[bg_num] as u16;
         let palette_offset = mode_def.palette_offset[bg_num];
@@ -1220,6 +1418,7 @@ impl Ppu {
             bg,
             screen,
             tile_idx,
+            tile_width,
             tile_off_x,
             tile_off_y,
             bpp,
@@ -1228,25 +1427,145 @@ impl Ppu {
         )
     }
 
+    /// Renders the complete tile map of a background as 8 bit RGB, decoding it the same way as
+    /// the current mode does. Transparent pixels show the backdrop color. Returns the width and
+    /// height of the image, or `None` if the mode doesn't use the background. Mode 7 has its own
+    /// tile map format and isn't supported.
+    pub fn render_tilemap(&self, bg_num: usize) -> Option<(u16, u16, Vec<u8>)> {
+        let mode_def = ModeDefinition::MODES.get(usize::from(self.backgrounds.mode.value()))?;
+        if bg_num >= usize::from(mode_def.num_backgrounds) {
+            return None;
+        }
+
+        let bg = &self.backgrounds.backgrounds[bg_num];
+        let tile_height = 8 << (bg.large_tiles as u8);
+        let tile_width = match mode_def.hires {
+            true => 16,
+            false => tile_height,
+        };
+        let (screens_x, screens_y) = match bg.size {
+            BackgroundSize::OneScreen => (1, 1),
+            BackgroundSize::VMirror => (2, 1),
+            BackgroundSize::HMirror => (1, 2),
+            BackgroundSize::FourScreen => (2, 2),
+        };
+        let width = screens_x * 32 * tile_width;
+        let height = screens_y * 32 * tile_height;
+
+        let bpp = mode_def.bpp[bg_num] as u16;
+        let backdrop = self.get_color(0);
+        let mut rgb = Vec::with_capacity(usize::from(width) * usize::from(height) * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let (screen, tile_idx) = Self::tilemap_position(bg, x, y, tile_width);
+                let color = self.get_screen_color(
+                    bg,
+                    screen,
+                    tile_idx,
+                    tile_width,
+                    x % tile_width,
+                    y % tile_height,
+                    bpp,
+                    mode_def.palette_offset[bg_num],
+                    &mode_def.bg_priorities[bg_num],
+                );
+                let color = match color.priority {
+                    0 => backdrop,
+                    _ => color.color,
+                };
+                rgb.extend(
+                    [color.r, color.g, color.b]
+                        .map(|c| ((u32::from(c.value()) * 255 + 15) / 31) as u8),
+                );
+            }
+        }
+
+        Some((width, height, rgb))
+    }
+
+    /// Returns the screen and the index of the tile map entry within it at the given position.
+    fn tilemap_position(bg: &Background, x: u16, y: u16, tile_width: u16) -> (u8, u16) {
+        // screens in the order: top left, top right, bottom left, bottom right
+        let screens: [u8; 4] =
+            [[0, 0, 0, 0], [0, 1, 0, 1], [0, 0, 1, 1], [0, 1, 2, 3]][bg.size as usize];
+
+        let tile_height = 8 << (bg.large_tiles as u8);
+        let tile_x = (x / tile_width) & 0x3F;
+        let tile_y = (y / tile_height) & 0x3F;
+
+        let quadrant = (tile_x >> 5) | (tile_y >> 4 & 0x02);
+        let screen = screens[usize::from(quadrant)];
+
+        let tile_idx = (tile_y & 0x1F) * 32 + (tile_x & 0x1F);
+        (screen, tile_idx)
+    }
+
+    fn tilemap_entry(&self, bg: &Background, screen: u8, tile_idx: u16) -> u16 {
+        let tilemap_addr = ((bg.base_address.value() + screen) as u16) << 10; // * 1024
+        let map_entry_addr = tilemap_addr.wrapping_add(tile_idx) << 1;
+        let map_entry_lo = self.vram[usize::from(map_entry_addr)];
+        let map_entry_hi = self.vram[usize::from(map_entry_addr + 1)];
+        (map_entry_lo as u16) | (map_entry_hi as u16) << 8
+    }
+
+    /// Returns the scroll offsets of BG1 or BG2 for the column containing `x`. With offset-per-tile,
+    /// every column but the leftmost can take its offsets from the tile map of BG3 instead of the
+    /// BGnHOFS and BGnVOFS registers.
+    fn bg_offsets(&self, x: u16, bg_num: usize, offset_per_tile: OffsetPerTile) -> (u16, u16) {
+        let bg = &self.backgrounds.backgrounds[bg_num];
+        let (mut h_offset, mut v_offset) = (bg.h_offset, bg.v_offset);
+
+        let column_x = x + (h_offset & 0x07);
+        if offset_per_tile == OffsetPerTile::Disabled || column_x < 8 {
+            return (h_offset, v_offset);
+        }
+
+        let bg3 = &self.backgrounds.backgrounds[2];
+        let lookup = |row: u16| {
+            let lookup_x = (column_x - 8).wrapping_add(bg3.h_offset & !0x07);
+            let lookup_y = bg3.v_offset.wrapping_add(row * 8);
+            let tile_width = 8 << (bg3.large_tiles as u8);
+            let (screen, tile_idx) = Self::tilemap_position(bg3, lookup_x, lookup_y, tile_width);
+            self.tilemap_entry(bg3, screen, tile_idx)
+        };
+
+        let (h_entry, v_entry) = match offset_per_tile {
+            OffsetPerTile::Disabled => unreachable!(),
+            OffsetPerTile::Separate => (lookup(0), lookup(1)),
+            OffsetPerTile::Shared => match lookup(0) {
+                entry if entry & 0x8000 == 0 => (entry, 0),
+                entry => (0, entry),
+            },
+        };
+
+        // Bit 13 enables the offset for BG1, bit 14 for BG2
+        let enable_bit = 0x2000 << bg_num;
+        if h_entry & enable_bit != 0 {
+            // The fine scroll still comes from the register
+            h_offset = h_offset & 0x07 | h_entry & 0x3F8;
+        }
+        if v_entry & enable_bit != 0 {
+            v_offset = v_entry & 0x3FF;
+        }
+        (h_offset, v_offset)
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn get_screen_color(
         &self,
         bg: &Background,
         screen: u8,
         tile_idx: u16,
+        tile_width: u16,
         mut tile_off_x: u16,
         mut tile_off_y: u16,
         bpp: u16,
         mut palette_offset: u8,
         priorities: &[u8; 2],
     ) -> LayerColor {
-        let tilemap_addr = ((bg.base_address.value() + screen) as u16) << 10; // * 1024
-        let map_entry_addr = tilemap_addr.wrapping_add(tile_idx) << 1;
-        let map_entry_lo = self.vram[usize::from(map_entry_addr)];
-        let map_entry_hi = self.vram[usize::from(map_entry_addr + 1)];
-        let map_entry = (map_entry_lo as u16) | (map_entry_hi as u16) << 8;
+        let map_entry = self.tilemap_entry(bg, screen, tile_idx);
 
-        let tile_size = 8 << (bg.large_tiles as u8);
+        let tile_height = 8 << (bg.large_tiles as u8);
 
         let mut tile_number = map_entry & 0x03FF;
         let palette_number = ((map_entry >> 10) & 0x7) as u8;
@@ -1255,10 +1574,10 @@ impl Ppu {
         let y_flip = (map_entry >> 15) & 1 != 0;
 
         if x_flip {
-            tile_off_x = tile_size - 1 - tile_off_x;
+            tile_off_x = tile_width - 1 - tile_off_x;
         }
         if y_flip {
-            tile_off_y = tile_size - 1 - tile_off_y;
+            tile_off_y = tile_height - 1 - tile_off_y;
         }
 
         tile_number = tile_number.wrapping_add(tile_off_x >> 3);
@@ -1347,6 +1666,8 @@ impl Ppu {
         palette_idx
     }
 
+    /// CGRAM stores colors as little endian `0bbbbbgggggrrrrr` words, so red occupies the low bits
+    /// of the first byte written through CGDATA and blue the high bits of the second one.
     fn get_color(&self, palette_idx: u8) -> Color {
         let cgram_addr = usize::from(palette_idx) * 2;
         let color_lo = self.cgram[cgram_addr];
@@ -1360,7 +1681,28 @@ impl Ppu {
         Color::new(r, g, b)
     }
 
-    fn get_mode7_bg_color_data(&self, x: u16, y: u16) -> u8 {
+    /// Returns the 8 bit color of the Mode 7 background at the given screen position.
+    ///
+    /// The transformation is computed the same way as the hardware does it: The screen flip is
+    /// applied to the screen coordinates before transforming them, the scroll offsets relative to
+    /// the center are clipped to 10 bits (plus sign) and the lowest 6 bits of the products of the
+    /// constant terms are discarded.
+    fn get_mode7_bg_color_data(&self, mut x: u16, mut y: u16) -> u8 {
+        /// Sign extends a 14 bit value if it is negative, otherwise only keeps the lower 10 bits.
+        fn clip(value: i32) -> i32 {
+            match value & 0x2000 != 0 {
+                true => value | !0x3FF,
+                false => value & 0x3FF,
+            }
+        }
+
+        if self.m7sel_screen_hflip {
+            x = 255 - x;
+        }
+        if self.m7sel_screen_vflip {
+            y = 255 - y;
+        }
+
         let offset_x = self.m7hofs.as_i32();
         let offset_y = self.m7vofs.as_i32();
         let origin_x = self.m7x.as_i32();
@@ -1371,38 +1713,33 @@ impl Ppu {
         let c = self.m7c as i32;
         let d = self.m7d as i32;
 
-        let tmp_x = x as i32 + offset_x - origin_x;
-        let tmp_y = y as i32 + offset_y - origin_y;
-
-        let mut x = (((a * tmp_x + b * tmp_y) >> 8) + origin_x) as u16;
-        let mut y = (((c * tmp_x + d * tmp_y) >> 8) + origin_y) as u16;
-
-        if x > 1023 || y > 1023 {
-            let mask = match self.m7sel_screen_over {
-                M7SELScreenOver::Wrap => 0x03FF,
-                M7SELScreenOver::Tile0 => 0x0007,
-                M7SELScreenOver::Transparent => return 0,
-            };
-            x &= mask;
-            y &= mask;
-        }
-
-        if self.m7sel_screen_hflip {
-            x = 1023 - x;
-        }
-        if self.m7sel_screen_vflip {
-            y = 1023 - y;
-        }
-
-        let tilemap_x = x >> 3;
-        let tilemap_y = y >> 3;
-
-        let char_x = x & 0x7;
-        let char_y = y & 0x7;
-
-        let tile_number = (tilemap_y << 7) + tilemap_x;
-
-        let char_number = self.vram[usize::from(tile_number * 2)] as u16;
+        let rel_x = clip(offset_x - origin_x);
+        let rel_y = clip(offset_y - origin_y);
+        let y = i32::from(y);
+        let x = i32::from(x);
+
+        let start_x = ((a * rel_x) & !63) + ((b * rel_y) & !63) + ((b * y) & !63) + (origin_x << 8);
+        let start_y = ((c * rel_x) & !63) + ((d * rel_y) & !63) + ((d * y) & !63) + (origin_y << 8);
+
+        let pos_x = (start_x + a * x) >> 8;
+        let pos_y = (start_y + c * x) >> 8;
+
+        let char_x = (pos_x & 0x7) as u16;
+        let char_y = (pos_y & 0x7) as u16;
+
+        let outside = !(0..1024).contains(&pos_x) || !(0..1024).contains(&pos_y);
+        let char_number = match (outside, self.m7sel_screen_over) {
+            (true, M7SELScreenOver::Transparent) => return 0,
+            (true, M7SELScreenOver::Tile0) => 0,
+            (false, _) | (true, M7SELScreenOver::Wrap) => {
+                let tilemap_x = ((pos_x >> 3) & 0x7F) as u16;
+                let tilemap_y = ((pos_y >> 3) & 0x7F) as u16;
+                let tile_number = tilemap_y << 7 | tilemap_x;
+                // The tilemap is stored in the low bytes of VRAM, the character data in the high
+                // bytes
+                self.vram[usize::from(tile_number * 2)] as u16
+            }
+        };
 
         let pixel_addr = char_number << 6 | char_y << 3 | char_x;
 
@@ -1410,6 +1747,23 @@ impl Ppu {
     }
 }
 
+/// Converts a VRAM word address into the index of its low byte in [`Ppu::vram`]. VRAM only has
+/// 32K words, so the topmost bit of the address is ignored. The renderer uses the same layout, with
+/// the low byte of every word at an even index.
+fn vram_byte_addr(word_addr: u16) -> usize {
+    usize::from(word_addr & 0x7FFF) << 1
+}
+
+/// Where BG1 and BG2 take per-column scroll offsets from, in the tile map of BG3.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OffsetPerTile {
+    Disabled,
+    /// The first row holds H offsets, the second row V offsets.
+    Separate,
+    /// A single row, bit 15 of every entry selects whether it is an H or V offset (mode 4).
+    Shared,
+}
+
 #[derive(PartialEq, Eq)]
 struct ModeDefinition {
     num_backgrounds: u8,
@@ -1417,6 +1771,9 @@ struct ModeDefinition {
     palette_offset: [u8; 4],
     bg_priorities: [[u8; 2]; 4],
     obj_priorities: [u8; 4],
+    offset_per_tile: OffsetPerTile,
+    /// The backgrounds have twice the horizontal resolution.
+    hires: bool,
 }
 
 impl ModeDefinition {
@@ -1426,6 +1783,8 @@ impl ModeDefinition {
         palette_offset: [0, 32, 64, 96],
         bg_priorities: [[8, 11], [7, 10], [2, 5], [1, 4]],
         obj_priorities: [3, 6, 9, 12],
+        offset_per_tile: OffsetPerTile::Disabled,
+        hires: false,
     };
     const MODE1: Self = Self {
         num_backgrounds: 3,
@@ -1433,6 +1792,8 @@ impl ModeDefinition {
         palette_offset: [0, 0, 0, 0],
         bg_priorities: [[8, 11], [7, 10], [2, 5], [0, 0]],
         obj_priorities: [3, 6, 9, 12],
+        offset_per_tile: OffsetPerTile::Disabled,
+        hires: false,
     };
     const MODE2: Self = Self {
         num_backgrounds: 2,
@@ -1440,6 +1801,8 @@ impl ModeDefinition {
         palette_offset: [0, 0, 0, 0],
         bg_priorities: [[3, 7], [1, 5], [0, 0], [0, 0]],
         obj_priorities: [2, 4, 6, 8],
+        offset_per_tile: OffsetPerTile::Separate,
+        hires: false,
     };
     const MODE3: Self = Self {
         num_backgrounds: 2,
@@ -1447,6 +1810,8 @@ impl ModeDefinition {
         palette_offset: [0, 0, 0, 0],
         bg_priorities: [[3, 7], [1, 5], [0, 0], [0, 0]],
         obj_priorities: [2, 4, 6, 8],
+        offset_per_tile: OffsetPerTile::Disabled,
+        hires: false,
     };
     const MODE4: Self = Self {
         num_backgrounds: 2,
@@ -1454,6 +1819,8 @@ impl ModeDefinition {
         palette_offset: [0, 0, 0, 0],
         bg_priorities: [[3, 7], [1, 5], [0, 0], [0, 0]],
         obj_priorities: [2, 4, 6, 8],
+        offset_per_tile: OffsetPerTile::Shared,
+        hires: false,
     };
     const MODE5: Self = Self {
         num_backgrounds: 2,
@@ -1461,6 +1828,8 @@ impl ModeDefinition {
         palette_offset: [0, 0, 0, 0],
         bg_priorities: [[3, 7], [1, 5], [0, 0], [0, 0]],
         obj_priorities: [2, 4, 6, 8],
+        offset_per_tile: OffsetPerTile::Disabled,
+        hires: true,
     };
     const MODE6: Self = Self {
         num_backgrounds: 1,
@@ -1468,6 +1837,8 @@ impl ModeDefinition {
         palette_offset: [0, 0, 0, 0],
         bg_priorities: [[2, 5], [0, 0], [0, 0], [0, 0]],
         obj_priorities: [1, 3, 4, 6],
+        offset_per_tile: OffsetPerTile::Separate,
+        hires: true,
     };
 
     const MODES: [Self; 7] = [
@@ -1516,30 +1887,9 @@ impl Color {
 }
 
 pub fn catch_up(emu: &mut Snes) {
-    /*
-    let width = match ppu.setini_hpseudo512 {
-        false => 256,
-        true => 512,
-    };
-
-    let height = match (ppu.setini_overscan, ppu.setini_interlace) {
-        (false, false) => 224,
-        (false, true) => 448,
-        (true, false) => 239,
-        (true, true) => 478,
-    };
-    */
-
     let max_vpos = emu.ppu.max_vpos();
     let output_height = emu.ppu.output_height();
 
-    if emu.ppu.setini_interlace {
-        todo!()
-    }
-    if emu.ppu.setini_hpseudo512 {
-        todo!()
-    }
-
     while emu.ppu.cycles < emu.cpu.cycles() {
         emu.ppu.cycles += 4;
 
@@ -1549,30 +1899,55 @@ pub fn catch_up(emu: &mut Snes) {
             emu.ppu.vpos += 1;
             if emu.ppu.vpos > max_vpos {
                 emu.ppu.vpos = 0;
+                emu.ppu.stat78 ^= 0x80;
+
+                // The range and time over flags are reset at the end of VBlank
+                if !emu.ppu.inidisp_forced_blanking {
+                    emu.ppu.stat77 &= !0xC0;
+                }
+            }
+
+            if emu.ppu.vpos == output_height + 1 && !emu.ppu.inidisp_forced_blanking {
+                emu.ppu.reload_oam_addr();
             }
         }
 
         let hblank = emu.ppu.hpos < 22 || emu.ppu.hpos > 277;
         let vblank = emu.ppu.vpos < 1 || emu.ppu.vpos > output_height;
 
+        // Objects are evaluated during the previous scanline, so we prepare them for the next
+        // scanline at the start of HBlank. No evaluation takes place during forced blank, which
+        // means that objects only show up one scanline after forced blank was disabled.
+        if emu.ppu.hpos == 278 && emu.ppu.vpos < output_height {
+            match emu.ppu.inidisp_forced_blanking {
+                false => emu.ppu.prepare_objects(emu.ppu.vpos as u8),
+                true => emu.ppu.current_object_tiles_len = 0,
+            }
+        }
+
         #[allow(clippy::identity_op)]
         if !hblank && !vblank {
             let x = emu.ppu.hpos - 22;
             let y = emu.ppu.vpos;
 
-            if x == 0 {
-                emu.ppu.prepare_objects(y as u8 - 1);
-            }
-
-            let color = match emu.ppu.inidisp_forced_blanking {
-                false => emu.ppu.render_pixel(x, y),
-                true => OutputColor::BLACK,
+            let [left, right] = match emu.ppu.inidisp_forced_blanking {
+                false => emu.ppu.render_pixel(x, y, emu.ppu.hires()),
+                true => [OutputColor::BLACK; 2],
             };
 
-            emu.ppu.output.set(x * 2 + 0, y * 2 + 0, color);
-            emu.ppu.output.set(x * 2 + 1, y * 2 + 0, color);
-            emu.ppu.output.set(x * 2 + 0, y * 2 + 1, color);
-            emu.ppu.output.set(x * 2 + 1, y * 2 + 1, color);
+            // The first visible scanline is 1, so it ends up in the topmost row of the output
+            let row = (y - 1) * 2;
+            if emu.ppu.setini_interlace {
+                // Every field only covers every other row, the rows of the previous field stay
+                let row = row + emu.ppu.interlace_field() as u16;
+                emu.ppu.output.set(x * 2 + 0, row, left);
+                emu.ppu.output.set(x * 2 + 1, row, right);
+            } else {
+                emu.ppu.output.set(x * 2 + 0, row + 0, left);
+                emu.ppu.output.set(x * 2 + 1, row + 0, right);
+                emu.ppu.output.set(x * 2 + 0, row + 1, left);
+                emu.ppu.output.set(x * 2 + 1, row + 1, right);
+            }
         }
     }
 }
@@ -0,0 +1,226 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use snes_emu::{MappingMode, RomHeader, Snes, ppu::PpuVariant};
+
+use crate::controller::ControllerType;
+
+/// Options which can be set globally and overridden for individual games.
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    pub disable_sprite_limits: bool,
+    pub disable_color_math: bool,
+    pub disable_windows: bool,
+    pub pause_on_open_bus: bool,
+    pub controller_types: [ControllerType; 2],
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            disable_sprite_limits: false,
+            disable_color_math: false,
+            disable_windows: false,
+            pause_on_open_bus: false,
+            controller_types: [ControllerType::Joypad, ControllerType::None],
+        }
+    }
+}
+
+impl Settings {
+    /// Applies the options to the emulator. The controllers are connected by the caller, since
+    /// the devices need access to the input of the frontend.
+    pub fn apply(&self, snes: &mut Snes) {
+        snes.ppu.disable_sprite_limits = self.disable_sprite_limits;
+        snes.ppu.disable_color_math = self.disable_color_math;
+        snes.ppu.disable_windows = self.disable_windows;
+        snes.pause_on_open_bus = self.pause_on_open_bus;
+    }
+}
+
+/// Per-game overrides of the global [`Settings`]. Options which are `None` use the global value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameProfile {
+    pub disable_sprite_limits: Option<bool>,
+    pub disable_color_math: Option<bool>,
+    pub disable_windows: Option<bool>,
+    pub pause_on_open_bus: Option<bool>,
+    pub controller_types: [Option<ControllerType>; 2],
+    /// Overrides the mapping mode detected from the header, for ROMs with a broken header
+    pub mapping_mode: Option<MappingMode>,
+    /// Overrides the console variant derived from the region in the header
+    pub ppu_variant: Option<PpuVariant>,
+}
+
+impl GameProfile {
+    pub fn resolve(&self, defaults: &Settings) -> Settings {
+        Settings {
+            disable_sprite_limits: self
+                .disable_sprite_limits
+                .unwrap_or(defaults.disable_sprite_limits),
+            disable_color_math: self
+                .disable_color_math
+                .unwrap_or(defaults.disable_color_math),
+            disable_windows: self.disable_windows.unwrap_or(defaults.disable_windows),
+            pause_on_open_bus: self.pause_on_open_bus.unwrap_or(defaults.pause_on_open_bus),
+            controller_types: std::array::from_fn(|i| {
+                self.controller_types[i].unwrap_or(defaults.controller_types[i])
+            }),
+        }
+    }
+
+    /// Applies the options which have no global value. Without an override, the values from the
+    /// header of the game are used.
+    pub fn apply_overrides(&self, snes: &mut Snes) {
+        snes.cpu.mapping_mode = self.mapping_mode.unwrap_or(snes.header.mapping_mode);
+        snes.ppu.variant = self
+            .ppu_variant
+            .unwrap_or_else(|| PpuVariant::from_region(snes.header.region));
+    }
+
+    /// Loads the profile of the game with the given header. Returns an empty profile if there is
+    /// none yet.
+    pub fn load(header: &RomHeader) -> Self {
+        let Some(dir) = profile_dir() else {
+            return Self::default();
+        };
+        migrate_legacy_profile(&dir, header);
+        let path = profile_path(&dir, header);
+
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                tracing::error!("Failed to read {}: {err}", path.display());
+                return Self::default();
+            }
+        };
+
+        serde_json::from_slice(&data).unwrap_or_else(|err| {
+            tracing::error!("Failed to parse {}: {err}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, header: &RomHeader) {
+        let Some(dir) = profile_dir() else {
+            return;
+        };
+        let path = profile_path(&dir, header);
+
+        let result = fs::create_dir_all(path.parent().unwrap()).and_then(|()| {
+            let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+            fs::write(&path, data)
+        });
+
+        if let Err(err) = result {
+            tracing::error!("Failed to write {}: {err}", path.display());
+        }
+    }
+}
+
+fn profile_dir() -> Option<PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", "snes-emu")?;
+    Some(dirs.config_dir().join("profiles"))
+}
+
+fn profile_path(dir: &Path, header: &RomHeader) -> PathBuf {
+    dir.join(profile_file_name(header, header.hash()))
+}
+
+fn profile_file_name(header: &RomHeader, hash: u64) -> String {
+    format!("{:04x}-{hash:016x}.json", header.checksum)
+}
+
+/// Profiles used to be named after a hash which left out the last character of the title. Such a
+/// profile is renamed, unless there already is one under the current name.
+fn migrate_legacy_profile(dir: &Path, header: &RomHeader) {
+    let Some(legacy_hash) = header.legacy_hash() else {
+        return;
+    };
+    let legacy_path = dir.join(profile_file_name(header, legacy_hash));
+    let path = profile_path(dir, header);
+    if !legacy_path.exists() || path.exists() {
+        return;
+    }
+    if let Err(err) = fs::rename(&legacy_path, &path) {
+        tracing::error!(
+            "Failed to rename {} to {}: {err}",
+            legacy_path.display(),
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use snes_emu::{MappingMode, Snes, ppu::PpuVariant};
+
+    use super::{GameProfile, Settings, migrate_legacy_profile, profile_file_name, profile_path};
+    use crate::controller::ControllerType;
+
+    fn load_smoke() -> Snes {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/smoke.sfc");
+        Snes::new(fs::read(path).unwrap().into_boxed_slice())
+    }
+
+    #[test]
+    fn overrides_survive_saving() {
+        let profile = GameProfile {
+            controller_types: [None, Some(ControllerType::SuperScope)],
+            mapping_mode: Some(MappingMode::HiRom),
+            ppu_variant: Some(PpuVariant::Pal),
+            ..GameProfile::default()
+        };
+        let json = serde_json::to_string(&profile).unwrap();
+        let profile: GameProfile = serde_json::from_str(&json).unwrap();
+
+        let settings = profile.resolve(&Settings::default());
+        assert_eq!(
+            settings.controller_types,
+            [ControllerType::Joypad, ControllerType::SuperScope]
+        );
+
+        let mut snes = load_smoke();
+        profile.apply_overrides(&mut snes);
+        assert_eq!(snes.cpu.mapping_mode, MappingMode::HiRom);
+        assert_eq!(snes.ppu.variant, PpuVariant::Pal);
+
+        GameProfile::default().apply_overrides(&mut snes);
+        assert_eq!(snes.cpu.mapping_mode, MappingMode::LoRom);
+        assert_eq!(snes.ppu.variant, PpuVariant::Ntsc);
+    }
+
+    #[test]
+    fn missing_options_use_defaults() {
+        let profile: GameProfile = serde_json::from_str(r#"{ "disable_windows": true }"#).unwrap();
+        assert_eq!(profile.disable_windows, Some(true));
+        assert_eq!(profile.controller_types, [None, None]);
+        assert_eq!(profile.mapping_mode, None);
+        assert_eq!(profile.ppu_variant, None);
+    }
+
+    #[test]
+    fn legacy_profile_renamed() {
+        let dir = env::temp_dir().join(format!("snes-emu-profiles-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let header = &load_smoke().header;
+        let legacy_hash = header.legacy_hash().unwrap();
+        let legacy_path = dir.join(profile_file_name(header, legacy_hash));
+        fs::write(&legacy_path, r#"{ "disable_windows": true }"#).unwrap();
+
+        migrate_legacy_profile(&dir, header);
+        assert!(!legacy_path.exists());
+        let data = fs::read(profile_path(&dir, header)).unwrap();
+        let profile: GameProfile = serde_json::from_slice(&data).unwrap();
+        assert_eq!(profile.disable_windows, Some(true));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
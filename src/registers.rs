@@ -0,0 +1,169 @@
+//! Human-readable snapshot of the CPU, PPU and APU registers.
+//!
+//! Unlike a full save state this only covers the registers (no memory), which makes it easy to
+//! inspect and tweak by hand in order to set up a specific machine state.
+
+use arbitrary_int::{i13, u3, u4};
+use serde::{Deserialize, Serialize};
+
+use crate::Snes;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterState {
+    pub cpu: CpuRegisters,
+    pub ppu: PpuRegisters,
+    pub apu: ApuRegisters,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuRegisters {
+    pub a: u16,
+    pub x: u16,
+    pub y: u16,
+    pub s: u16,
+    pub d: u16,
+    pub dbr: u8,
+    pub k: u8,
+    pub pc: u16,
+    pub p: u8,
+    pub e: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PpuRegisters {
+    pub forced_blanking: bool,
+    pub master_brightness: u8,
+    pub bg_mode: u8,
+    pub bg3_high_priority: bool,
+    pub bg_h_offsets: [u16; 4],
+    pub bg_v_offsets: [u16; 4],
+    pub m7a: i16,
+    pub m7b: i16,
+    pub m7c: i16,
+    pub m7d: i16,
+    pub m7x: i16,
+    pub m7y: i16,
+    pub m7hofs: i16,
+    pub m7vofs: i16,
+    pub vmadd: u16,
+    pub cgadd: u8,
+    pub oamaddl: u8,
+    pub oamaddh: u8,
+    pub interlace: bool,
+    pub overscan: bool,
+    pub hpseudo512: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApuRegisters {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub sp: u8,
+    pub psw: u8,
+    pub pc: u16,
+}
+
+impl RegisterState {
+    pub fn capture(emu: &Snes) -> Self {
+        let regs = &emu.cpu.regs;
+        let cpu = CpuRegisters {
+            a: regs.a.get(),
+            x: regs.x.get(),
+            y: regs.y.get(),
+            s: regs.s.get(),
+            d: regs.d.get(),
+            dbr: regs.dbr,
+            k: regs.k,
+            pc: regs.pc.get(),
+            p: regs.p.to_bits(),
+            e: regs.p.e,
+        };
+
+        let ppu = &emu.ppu;
+        let bgs = &ppu.backgrounds.backgrounds;
+        let ppu = PpuRegisters {
+            forced_blanking: ppu.inidisp_forced_blanking,
+            master_brightness: ppu.inidisp_master_brightness.value(),
+            bg_mode: ppu.backgrounds.mode.value(),
+            bg3_high_priority: ppu.backgrounds.bg3_high_priority,
+            bg_h_offsets: bgs.map(|bg| bg.h_offset),
+            bg_v_offsets: bgs.map(|bg| bg.v_offset),
+            m7a: ppu.m7a,
+            m7b: ppu.m7b,
+            m7c: ppu.m7c,
+            m7d: ppu.m7d,
+            m7x: ppu.m7x.as_i32() as i16,
+            m7y: ppu.m7y.as_i32() as i16,
+            m7hofs: ppu.m7hofs.as_i32() as i16,
+            m7vofs: ppu.m7vofs.as_i32() as i16,
+            vmadd: ppu.vmadd,
+            cgadd: ppu.cgadd,
+            oamaddl: ppu.oamaddl,
+            oamaddh: ppu.oamaddh,
+            interlace: ppu.setini_interlace,
+            overscan: ppu.setini_overscan,
+            hpseudo512: ppu.setini_hpseudo512,
+        };
+
+        let apu = &emu.apu;
+        let apu = ApuRegisters {
+            a: apu.a,
+            x: apu.x,
+            y: apu.y,
+            sp: apu.sp,
+            psw: apu.psw.to_bits(),
+            pc: apu.pc,
+        };
+
+        Self { cpu, ppu, apu }
+    }
+
+    pub fn apply(&self, emu: &mut Snes) {
+        let regs = &mut emu.cpu.regs;
+        regs.a.set(self.cpu.a);
+        regs.x.set(self.cpu.x);
+        regs.y.set(self.cpu.y);
+        regs.s.set(self.cpu.s);
+        regs.d.set(self.cpu.d);
+        regs.dbr = self.cpu.dbr;
+        regs.k = self.cpu.k;
+        regs.pc.set(self.cpu.pc);
+        regs.p.e = self.cpu.e;
+        regs.set_p(self.cpu.p);
+
+        let ppu = &mut emu.ppu;
+        ppu.inidisp_forced_blanking = self.ppu.forced_blanking;
+        ppu.inidisp_master_brightness = u4::masked_new(self.ppu.master_brightness);
+        ppu.backgrounds.mode = u3::masked_new(self.ppu.bg_mode);
+        ppu.backgrounds.bg3_high_priority = self.ppu.bg3_high_priority;
+        for (i, bg) in ppu.backgrounds.backgrounds.iter_mut().enumerate() {
+            bg.h_offset = self.ppu.bg_h_offsets[i] & 0x3FF;
+            bg.v_offset = self.ppu.bg_v_offsets[i] & 0x3FF;
+        }
+        ppu.m7a = self.ppu.m7a;
+        ppu.m7b = self.ppu.m7b;
+        ppu.m7c = self.ppu.m7c;
+        ppu.m7d = self.ppu.m7d;
+        ppu.m7x = i13::masked_new(self.ppu.m7x);
+        ppu.m7y = i13::masked_new(self.ppu.m7y);
+        ppu.m7hofs = i13::masked_new(self.ppu.m7hofs);
+        ppu.m7vofs = i13::masked_new(self.ppu.m7vofs);
+        ppu.vmadd = self.ppu.vmadd;
+        ppu.cgadd = self.ppu.cgadd;
+        ppu.oamaddl = self.ppu.oamaddl;
+        ppu.oamaddh = self.ppu.oamaddh;
+        ppu.reload_oam_addr();
+        ppu.setini_interlace = self.ppu.interlace;
+        ppu.setini_overscan = self.ppu.overscan;
+        ppu.setini_hpseudo512 = self.ppu.hpseudo512;
+
+        let apu = &mut emu.apu;
+        apu.a = self.apu.a;
+        apu.x = self.apu.x;
+        apu.y = self.apu.y;
+        apu.sp = self.apu.sp;
+        apu.psw.set_from_bits(self.apu.psw);
+        apu.pc = self.apu.pc;
+    }
+}
@@ -94,6 +94,17 @@ impl Renderer {
         })
     }
 
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let present_mode = match vsync {
+            true => wgpu::PresentMode::AutoVsync,
+            false => wgpu::PresentMode::AutoNoVsync,
+        };
+        if self.surface_config.present_mode != present_mode {
+            self.surface_config.present_mode = present_mode;
+            self.reconfigure_surface = true;
+        }
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         let limits = self.device.limits();
         self.surface_config.width = width.clamp(1, limits.max_texture_dimension_2d);
@@ -0,0 +1,206 @@
+//! Comparison of the emulator output against reference screenshots, for PPU regression tests.
+
+use std::{
+    fmt, fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use crate::Snes;
+
+/// An RGB screenshot of the current frame, as it would be shown on screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    /// 8 bit RGB pixels, row by row.
+    pub pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    pub fn capture(snes: &Snes) -> Self {
+        // Every scanline is output as two rows
+        let height = snes.ppu.output_height() * 2;
+        Self {
+            width: u32::from(crate::OutputImage::WIDTH),
+            height: u32::from(height),
+            pixels: snes.output_image().to_rgb8(height),
+        }
+    }
+
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(data)
+    }
+
+    pub fn decode_png(data: &[u8]) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(Cursor::new(data));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+
+        let Some(buffer_size) = reader.output_buffer_size() else {
+            return Err(png::DecodingError::LimitsExceeded);
+        };
+        let mut buf = vec![0; buffer_size];
+        let info = reader.next_frame(&mut buf)?;
+        buf.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgb => buf,
+            png::ColorType::Rgba => buf
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                .collect(),
+            png::ColorType::Grayscale => buf.iter().flat_map(|&v| [v; 3]).collect(),
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0]; 3])
+                .collect(),
+            png::ColorType::Indexed => unreachable!("indexed images are expanded"),
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Hashes the resolution and pixels with 64 bit FNV-1a. Unlike [`std::hash::Hash`], the result
+    /// is stable across platforms and Rust versions, so it can be stored in regression tests.
+    pub fn hash(&self) -> u64 {
+        let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+        let size = self
+            .width
+            .to_le_bytes()
+            .into_iter()
+            .chain(self.height.to_le_bytes());
+        for byte in size.chain(self.pixels.iter().copied()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+        hash
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let data = self.encode_png().map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        Self::decode_png(&data).map_err(io::Error::other)
+    }
+}
+
+#[derive(Debug)]
+pub enum FrameMismatch {
+    /// The reference screenshot could not be loaded.
+    Reference(io::Error),
+    /// The reference screenshot has a different resolution than the current frame.
+    Size {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// Some pixels differ by more than the tolerance. A diff image was written to `diff_path`.
+    Pixels {
+        num_pixels: usize,
+        diff_path: PathBuf,
+    },
+}
+
+impl fmt::Display for FrameMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reference(err) => write!(f, "failed to load reference screenshot: {err}"),
+            Self::Size { expected, actual } => write!(
+                f,
+                "expected a {}x{} frame, got {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            Self::Pixels {
+                num_pixels,
+                diff_path,
+            } => write!(
+                f,
+                "{num_pixels} pixels differ from the reference, see {}",
+                diff_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrameMismatch {}
+
+/// Compares the current frame against the reference PNG at `reference_path`. Color channels may
+/// differ by up to `tolerance`.
+///
+/// On mismatch, a diff image is written next to the reference (`<name>.diff.png`). It shows
+/// matching pixels dimmed and mismatching pixels in red.
+pub fn compare_frame(
+    snes: &Snes,
+    reference_path: &Path,
+    tolerance: u8,
+) -> Result<(), FrameMismatch> {
+    let reference = Screenshot::load(reference_path).map_err(FrameMismatch::Reference)?;
+    let actual = Screenshot::capture(snes);
+
+    if (reference.width, reference.height) != (actual.width, actual.height) {
+        return Err(FrameMismatch::Size {
+            expected: (reference.width, reference.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+
+    let mut diff = actual.clone();
+    let mut num_pixels = 0;
+    for ((expected, actual), diff) in reference
+        .pixels
+        .chunks_exact(3)
+        .zip(actual.pixels.chunks_exact(3))
+        .zip(diff.pixels.chunks_exact_mut(3))
+    {
+        let matches = expected
+            .iter()
+            .zip(actual)
+            .all(|(&e, &a)| e.abs_diff(a) <= tolerance);
+
+        if matches {
+            diff.iter_mut().for_each(|c| *c /= 4);
+        } else {
+            diff.copy_from_slice(&[0xFF, 0x00, 0x00]);
+            num_pixels += 1;
+        }
+    }
+
+    if num_pixels == 0 {
+        return Ok(());
+    }
+
+    let diff_path = reference_path.with_extension("diff.png");
+    if let Err(err) = diff.save(&diff_path) {
+        tracing::error!("Failed to write {}: {err}", diff_path.display());
+    }
+
+    Err(FrameMismatch::Pixels {
+        num_pixels,
+        diff_path,
+    })
+}
+
+/// Panics if the current frame doesn't exactly match the reference PNG at `reference_path`.
+///
+/// See [`compare_frame`] for comparing with a tolerance.
+#[track_caller]
+pub fn assert_frame_matches(snes: &Snes, reference_path: impl AsRef<Path>) {
+    let reference_path = reference_path.as_ref();
+    if let Err(err) = compare_frame(snes, reference_path, 0) {
+        panic!("frame does not match {}: {err}", reference_path.display());
+    }
+}
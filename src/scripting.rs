@@ -0,0 +1,215 @@
+use std::{
+    cell::{Cell, RefCell},
+    ptr,
+    rc::Rc,
+};
+
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Scope};
+use snes_emu::{Snes, cpu::memory};
+
+type SnesPtr = Rc<Cell<*mut Snes>>;
+
+/// Runs a user-provided [rhai](https://rhai.rs) script which can inspect and modify the emulator.
+///
+/// Scripts have access to the following functions:
+/// - `read(addr)` / `write(addr, value)`: Access the CPU bus, including side effects
+/// - `read_pure(addr)`: Access the CPU bus without side effects, returns `()` for open bus
+/// - `reg(name)` / `set_reg(name, value)`: Access the CPU registers (`A`, `X`, `Y`, `S`, `D`,
+///   `DBR`, `K`, `PC` and `P`)
+///
+/// The top level statements of the script are executed once when it is loaded. Afterwards the
+/// script can react to events by defining the following functions:
+/// - `on_frame()`: Called after each emulated frame
+/// - `on_breakpoint(addr)`: Called when a breakpoint was hit
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    snes: SnesPtr,
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+fn with_snes<T>(snes: &SnesPtr, f: impl FnOnce(&mut Snes) -> T) -> T {
+    let snes = snes.get();
+    assert!(
+        !snes.is_null(),
+        "emulator accessed outside of a script call"
+    );
+    // SAFETY: The pointer is only set for the duration of `ScriptHost::call`, which holds a
+    // mutable borrow of the emulator for that time. The registered functions never call back
+    // into the script, so there is never more than one reference at a time.
+    f(unsafe { &mut *snes })
+}
+
+fn unknown_register(name: &str) -> Box<EvalAltResult> {
+    format!("unknown register: {name}").into()
+}
+
+impl ScriptHost {
+    pub fn new(source: &str, snes: &mut Snes) -> Result<Self, Box<EvalAltResult>> {
+        let snes_ptr: SnesPtr = Rc::new(Cell::new(ptr::null_mut()));
+        let log: Rc<RefCell<Vec<String>>> = Rc::default();
+
+        let mut engine = Engine::new();
+
+        let print_log = Rc::clone(&log);
+        engine.on_print(move |text| print_log.borrow_mut().push(text.to_owned()));
+        let debug_log = Rc::clone(&log);
+        engine.on_debug(move |text, _, pos| debug_log.borrow_mut().push(format!("{pos}: {text}")));
+
+        let ptr = Rc::clone(&snes_ptr);
+        engine.register_fn("read", move |addr: i64| {
+            with_snes(&ptr, |snes| {
+                i64::from(memory::read_with_cycle_counting(snes, addr as u32, false))
+            })
+        });
+
+        let ptr = Rc::clone(&snes_ptr);
+        engine.register_fn("read_pure", move |addr: i64| {
+            with_snes(&ptr, |snes| match memory::read_pure(snes, addr as u32) {
+                Some(value) => Dynamic::from_int(i64::from(value)),
+                None => Dynamic::UNIT,
+            })
+        });
+
+        let ptr = Rc::clone(&snes_ptr);
+        engine.register_fn("write", move |addr: i64, value: i64| {
+            with_snes(&ptr, |snes| {
+                memory::write_with_cycle_counting(snes, addr as u32, value as u8, false)
+            })
+        });
+
+        let ptr = Rc::clone(&snes_ptr);
+        engine.register_fn("reg", move |name: &str| {
+            with_snes(&ptr, |snes| {
+                let regs = &snes.cpu.regs;
+                let value = match name {
+                    "A" => regs.a.get(),
+                    "X" => regs.x.get(),
+                    "Y" => regs.y.get(),
+                    "S" => regs.s.get(),
+                    "D" => regs.d.get(),
+                    "DBR" => u16::from(regs.dbr),
+                    "K" => u16::from(regs.k),
+                    "PC" => regs.pc.get(),
+                    "P" => u16::from(regs.p.to_bits()),
+                    _ => return Err(unknown_register(name)),
+                };
+                Ok(i64::from(value))
+            })
+        });
+
+        let ptr = Rc::clone(&snes_ptr);
+        engine.register_fn("set_reg", move |name: &str, value: i64| {
+            with_snes(&ptr, |snes| {
+                let regs = &mut snes.cpu.regs;
+                match name {
+                    "A" => regs.a.set(value as u16),
+                    "X" => regs.x.set(value as u16),
+                    "Y" => regs.y.set(value as u16),
+                    "S" => regs.s.set(value as u16),
+                    "D" => regs.d.set(value as u16),
+                    "DBR" => regs.dbr = value as u8,
+                    "K" => regs.k = value as u8,
+                    "PC" => regs.pc.set(value as u16),
+                    "P" => regs.set_p(value as u8),
+                    _ => return Err(unknown_register(name)),
+                }
+                Ok(())
+            })
+        });
+
+        let ast = engine.compile(source)?;
+
+        let mut host = Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            snes: snes_ptr,
+            log,
+        };
+
+        host.call(snes, |engine, scope, ast| {
+            engine.run_ast_with_scope(scope, ast)
+        })?;
+
+        Ok(host)
+    }
+
+    pub fn log(&self) -> std::cell::Ref<'_, Vec<String>> {
+        self.log.borrow()
+    }
+
+    pub fn clear_log(&self) {
+        self.log.borrow_mut().clear();
+    }
+
+    pub fn on_frame(&mut self, snes: &mut Snes) {
+        self.call_hook(snes, "on_frame", ());
+    }
+
+    pub fn on_breakpoint(&mut self, snes: &mut Snes, addr: u32) {
+        self.call_hook(snes, "on_breakpoint", (i64::from(addr),));
+    }
+
+    fn call_hook(&mut self, snes: &mut Snes, name: &str, args: impl FuncArgs) {
+        if !self.ast.iter_functions().any(|func| func.name == name) {
+            return;
+        }
+
+        let result = self.call(snes, |engine, scope, ast| {
+            let options = CallFnOptions::new().eval_ast(false);
+            engine
+                .call_fn_with_options::<Dynamic>(options, scope, ast, name, args)
+                .map(|_| ())
+        });
+
+        if let Err(err) = result {
+            self.log
+                .borrow_mut()
+                .push(format!("Error in {name}: {err}"));
+        }
+    }
+
+    fn call<T>(
+        &mut self,
+        snes: &mut Snes,
+        f: impl FnOnce(&Engine, &mut Scope<'static>, &AST) -> T,
+    ) -> T {
+        self.snes.set(snes);
+        let result = f(&self.engine, &mut self.scope, &self.ast);
+        self.snes.set(ptr::null_mut());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use snes_emu::Snes;
+
+    use super::ScriptHost;
+
+    fn load_smoke() -> Snes {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/smoke.sfc");
+        Snes::new(fs::read(path).unwrap().into_boxed_slice())
+    }
+
+    #[test]
+    fn set_p_truncates_index_registers() {
+        let mut snes = load_smoke();
+        snes.cpu.regs.p.e = false;
+        ScriptHost::new(r#"set_reg("X", 0x1234); set_reg("P", 0x10);"#, &mut snes).unwrap();
+        assert_eq!(snes.cpu.regs.x.get(), 0x0034);
+    }
+
+    #[test]
+    fn set_p_in_emulation_mode_forces_flags() {
+        let mut snes = load_smoke();
+        snes.cpu.regs.p.e = true;
+        ScriptHost::new(r#"set_reg("S", 0x0ABC); set_reg("P", 0x00);"#, &mut snes).unwrap();
+        assert_eq!(snes.cpu.regs.p.to_bits(), 0x30);
+        assert_eq!(snes.cpu.regs.s.get(), 0x01BC);
+    }
+}
@@ -0,0 +1,287 @@
+//! The Sharp S-RTC real-time clock, used by Daikaijuu Monogatari II. It is accessed through two
+//! registers on the A-Bus: `$2800` returns the next digit of the time and `$2801` receives
+//! commands and the digits of a new time, only the low nibble is used for both.
+
+/// Addresses of the registers within banks `$00-$3F` and `$80-$BF`.
+pub(crate) const REGISTERS: std::ops::RangeInclusive<u32> = 0x2800..=0x2801;
+
+/// A point in time as kept by the S-RTC. The chip only counts years 1000 to 2999.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtcTime {
+    pub year: u16,
+    /// 1 to 12
+    pub month: u8,
+    /// 1 to 31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl RtcTime {
+    /// Converts seconds since the Unix epoch (1970-01-01 00:00:00) into a date and time.
+    pub fn from_unix_time(seconds: u64) -> Self {
+        let days = (seconds / 86400) as i64;
+        let seconds = seconds % 86400;
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    /// Day of the week, 0 is Sunday.
+    pub fn weekday(&self) -> u8 {
+        let days = days_from_civil(i64::from(self.year), self.month, self.day);
+        // 1970-01-01 was a Thursday
+        (days + 4).rem_euclid(7) as u8
+    }
+}
+
+impl Default for RtcTime {
+    fn default() -> Self {
+        Self {
+            year: 2000,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        }
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ready,
+    Command,
+    Read,
+    Write,
+}
+
+pub struct SRtc {
+    time: RtcTime,
+    weekday: u8,
+    state: State,
+    /// Index of the next digit to read or write. -1 is the marker before the first digit.
+    index: i8,
+    /// Master cycle counter at the last synchronization
+    last_cycles: u64,
+    /// Master cycles which have not yet added up to a full second
+    pending_cycles: u64,
+}
+
+impl Default for SRtc {
+    fn default() -> Self {
+        let time = RtcTime::default();
+        Self {
+            time,
+            weekday: time.weekday(),
+            state: State::Ready,
+            index: -1,
+            last_cycles: 0,
+            pending_cycles: 0,
+        }
+    }
+}
+
+impl SRtc {
+    pub fn time(&self) -> RtcTime {
+        self.time
+    }
+
+    /// Sets the clock, it keeps running from there along with the emulated time.
+    pub fn set_time(&mut self, time: RtcTime) {
+        self.time = time;
+        self.weekday = time.weekday();
+        self.pending_cycles = 0;
+    }
+
+    /// Advances the clock to the master cycle counter `cycles`.
+    pub(crate) fn sync(&mut self, cycles: u64, master_clock_hz: f64) {
+        // The cycle counter starts over when the console is reset
+        self.pending_cycles += cycles.saturating_sub(self.last_cycles);
+        self.last_cycles = cycles;
+
+        let cycles_per_second = master_clock_hz.round() as u64;
+        while self.pending_cycles >= cycles_per_second {
+            self.pending_cycles -= cycles_per_second;
+            self.tick_second();
+        }
+    }
+
+    fn tick_second(&mut self) {
+        let time = &mut self.time;
+        time.second += 1;
+        if time.second < 60 {
+            return;
+        }
+        time.second = 0;
+        time.minute += 1;
+        if time.minute < 60 {
+            return;
+        }
+        time.minute = 0;
+        time.hour += 1;
+        if time.hour < 24 {
+            return;
+        }
+        time.hour = 0;
+        self.weekday = (self.weekday + 1) % 7;
+        time.day += 1;
+        if time.day <= days_in_month(time.year, time.month) {
+            return;
+        }
+        time.day = 1;
+        time.month += 1;
+        if time.month <= 12 {
+            return;
+        }
+        time.month = 1;
+        time.year += 1;
+    }
+
+    fn digit(&self, index: i8) -> u8 {
+        let time = &self.time;
+        let year = time.year.saturating_sub(1000);
+        match index {
+            0 => time.second % 10,
+            1 => time.second / 10,
+            2 => time.minute % 10,
+            3 => time.minute / 10,
+            4 => time.hour % 10,
+            5 => time.hour / 10,
+            6 => time.day % 10,
+            7 => time.day / 10,
+            8 => time.month,
+            9 => (year % 10) as u8,
+            10 => (year / 10 % 10) as u8,
+            11 => (year / 100) as u8,
+            _ => self.weekday,
+        }
+    }
+
+    fn set_digit(&mut self, index: i8, value: u8) {
+        let time = &mut self.time;
+        let year = time.year.saturating_sub(1000);
+        let value16 = u16::from(value);
+        match index {
+            0 => time.second = time.second / 10 * 10 + value,
+            1 => time.second = value * 10 + time.second % 10,
+            2 => time.minute = time.minute / 10 * 10 + value,
+            3 => time.minute = value * 10 + time.minute % 10,
+            4 => time.hour = time.hour / 10 * 10 + value,
+            5 => time.hour = value * 10 + time.hour % 10,
+            6 => time.day = time.day / 10 * 10 + value,
+            7 => time.day = value * 10 + time.day % 10,
+            8 => time.month = value,
+            9 => time.year = 1000 + year / 10 * 10 + value16,
+            10 => time.year = 1000 + year / 100 * 100 + value16 * 10 + year % 10,
+            _ => time.year = 1000 + value16 * 100 + year % 100,
+        }
+    }
+
+    pub(crate) fn read_pure(&self, addr: u16) -> Option<u8> {
+        if addr != 0x2800 {
+            return None;
+        }
+        Some(match (self.state, self.index) {
+            (State::Read, 0..=12) => self.digit(self.index),
+            (State::Read, _) => 0x0F,
+            _ => 0x00,
+        })
+    }
+
+    pub(crate) fn read(&mut self, addr: u16) -> Option<u8> {
+        let value = self.read_pure(addr)?;
+        if self.state == State::Read {
+            // After the weekday, another marker is returned and the time starts over
+            self.index = if self.index > 12 { -1 } else { self.index + 1 };
+        }
+        Some(value)
+    }
+
+    pub(crate) fn write(&mut self, addr: u16, value: u8) {
+        if addr != 0x2801 {
+            return;
+        }
+        match (value & 0x0F, self.state) {
+            (0x0D, _) => {
+                self.state = State::Read;
+                self.index = -1;
+            }
+            (0x0E, _) => self.state = State::Command,
+            (0x0F, _) => (),
+            (0x00, State::Command) => {
+                self.state = State::Write;
+                self.index = 0;
+            }
+            (0x04, State::Command) => {
+                // Clears all digits, which isn't a valid date
+                self.state = State::Ready;
+                self.time = RtcTime {
+                    year: 1000,
+                    month: 0,
+                    day: 0,
+                    hour: 0,
+                    minute: 0,
+                    second: 0,
+                };
+                self.weekday = 0;
+            }
+            (_, State::Command) => self.state = State::Ready,
+            (digit, State::Write) if self.index < 12 => {
+                self.set_digit(self.index, digit);
+                self.index += 1;
+                // The day of the week is calculated by the chip once the year has been written
+                if self.index == 12 {
+                    self.weekday = self.time.weekday();
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+const HEADER_SIZE: u32 = 44;
+const NUM_CHANNELS: u16 = 2;
+const BYTES_PER_SAMPLE: u16 = 2;
+
+/// Writes 16 bit stereo PCM samples to a WAV file.
+///
+/// The sizes in the header are only known once recording stops, so they are filled in by
+/// [`WavWriter::finish`]. Until then the file is not a valid WAV file.
+pub struct WavWriter {
+    writer: BufWriter<File>,
+    num_frames: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let block_align = NUM_CHANNELS * BYTES_PER_SAMPLE;
+        let byte_rate = sample_rate * u32::from(block_align);
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(HEADER_SIZE - 8).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&NUM_CHANNELS.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&byte_rate.to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&(BYTES_PER_SAMPLE * 8).to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            num_frames: 0,
+        })
+    }
+
+    pub fn write_samples(&mut self, samples: impl IntoIterator<Item = [i16; 2]>) -> io::Result<()> {
+        for [left, right] in samples {
+            self.writer.write_all(&left.to_le_bytes())?;
+            self.writer.write_all(&right.to_le_bytes())?;
+            self.num_frames += 1;
+        }
+        Ok(())
+    }
+
+    /// Fills in the sizes in the header and flushes the file.
+    pub fn finish(mut self) -> io::Result<()> {
+        let data_size = self.num_frames * u32::from(NUM_CHANNELS * BYTES_PER_SAMPLE);
+
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&data_size.to_le_bytes())?;
+
+        self.writer.flush()
+    }
+}
@@ -1,6 +1,33 @@
+/// Per-address access counters of the WRAM, used by the debugger to visualize which memory is in
+/// active use. The counters saturate and are halved at the end of every frame, so that they decay
+/// once an address is no longer accessed.
+pub struct AccessHeatmap {
+    pub reads: Box<[u8; 0x020000]>,
+    pub writes: Box<[u8; 0x020000]>,
+}
+
+impl Default for AccessHeatmap {
+    fn default() -> Self {
+        Self {
+            reads: vec![0; 0x020000].try_into().unwrap(),
+            writes: vec![0; 0x020000].try_into().unwrap(),
+        }
+    }
+}
+
+impl AccessHeatmap {
+    pub(crate) fn decay(&mut self) {
+        self.reads.iter_mut().for_each(|count| *count >>= 1);
+        self.writes.iter_mut().for_each(|count| *count >>= 1);
+    }
+}
+
 pub struct WRam {
+    /// 17 bit address of the next access through WMDATA, which wraps from 0x1FFFF to 0.
     wmadd: u32,
     pub data: Box<[u8; 0x020000]>,
+    /// Only tracked while enabled, since it slows down every WRAM access.
+    pub heatmap: Option<Box<AccessHeatmap>>,
 }
 
 impl Default for WRam {
@@ -8,11 +35,26 @@ impl Default for WRam {
         Self {
             data: vec![0; 0x020000].try_into().unwrap(),
             wmadd: 0,
+            heatmap: None,
         }
     }
 }
 
 impl WRam {
+    pub(crate) fn record_read(&mut self, addr: u32) {
+        if let Some(heatmap) = &mut self.heatmap {
+            let count = &mut heatmap.reads[addr as usize];
+            *count = count.saturating_add(1);
+        }
+    }
+
+    pub(crate) fn record_write(&mut self, addr: u32) {
+        if let Some(heatmap) = &mut self.heatmap {
+            let count = &mut heatmap.writes[addr as usize];
+            *count = count.saturating_add(1);
+        }
+    }
+
     pub fn read_pure(&self, addr: u32) -> Option<u8> {
         match addr {
             0x2180 => Some(self.data[self.wmadd as usize]),
@@ -25,6 +67,7 @@ impl WRam {
             0x2180 => {
                 let addr = self.wmadd;
                 self.wmadd = (self.wmadd + 1) & 0x01FFFF;
+                self.record_read(addr);
                 Some(self.data[addr as usize])
             }
             _ => None,
@@ -34,6 +77,7 @@ impl WRam {
     pub fn write(&mut self, addr: u32, value: u8) {
         match addr {
             0x2180 => {
+                self.record_write(self.wmadd);
                 self.data[self.wmadd as usize] = value;
                 self.wmadd = (self.wmadd + 1) & 0x01FFFF;
             }
@@ -0,0 +1,42 @@
+; LoRom test ROM for the ABORT interrupt, which the test runner raises at the start of frames 1
+; and 2 while the CPU loops in native mode. The handler copies the stack frame to WRAM. Since the
+; aborted instruction is executed again after the handler returns, the second ABORT interrupts
+; the same loop with the same stack.
+;
+; $7E0000: pushed K, $00
+; $7E0001: pushed PC, high byte, $80
+; $7E0002: pushed PC, low byte, $0B (the address of loop)
+; $7E0003: pushed P, $35
+; $7E0004: S in the handler, $FB
+; $7E0005: number of times the handler ran, $02
+
+.title "SNES-EMU ABORT"
+.vectors reset, vector
+.vector abort, abort
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    ldx #$1FFF
+    txs
+    sep #$30
+loop:
+    bra loop
+
+abort:
+    lda $04,s
+    sta $00
+    lda $03,s
+    sta $01
+    lda $02,s
+    sta $02
+    lda $01,s
+    sta $03
+    tsx
+    stx $04
+    inc $05
+vector:
+    rti
@@ -0,0 +1,80 @@
+; LoRom test ROM for the number of cycles taken by SPC700 instructions. Uploads an SPC700 program
+; through the IPL boot ROM, which sets timer 2 to count every 16 cycles and runs a sequence of
+; instructions between two reads of its output. According to the documented cycle counts, the
+; sequence including the second read takes exactly 240 cycles, so the timer has to be read as 15
+; regardless of when the sequence started.
+;
+; $7E0000: T2OUT, $0F
+; $7E0001: $55 when done
+
+.title "SNES-EMU APU CYCLES"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    lda $2141
+    sta $00
+    lda #$55
+    sta $01
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200. The cycle count of every instruction after the first read
+; of T2OUT is noted next to it.
+program:
+.spc700 SPC_BASE
+spc_entry:
+    mov $FC, #$01   ; T2DIV: count every tick of the 64 kHz clock
+    mov $F1, #$04   ; CONTROL: enable timer 2, turn it off and on again to reset its counter
+    mov $F1, #$00
+    mov $F1, #$04
+    mov a, $FF      ; Clear T2OUT
+    mov x, #$08     ; 2
+loop1:
+    mul ya          ; 9
+    dec x           ; 2
+    bne loop1       ; 4 when taken, 2 otherwise
+    mov $10, #$05   ; 5
+loop2:
+    dbnz $10, loop2 ; 7 when taken, 5 otherwise
+    call sub        ; 8, 5 for the RET
+    xcn a           ; 5
+    push a          ; 4
+    pop a           ; 4
+    movw ya, $10    ; 5
+    incw $10        ; 6
+    bbs $10.0, skip ; 7, taken
+skip:
+    mov $20, $21    ; 5
+    adc a, #$01     ; 2
+    cmp x, #$00     ; 2
+    set1 $10.1      ; 4
+    tset1 !$0010    ; 6
+    mov a, !$0010   ; 4
+    mov !$0020, a   ; 5
+    mov a, $10      ; 3
+    clrc            ; 2
+    setc            ; 2
+    mov a, $FF      ; 3, read T2OUT
+    mov $F5, a
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+
+sub:
+    ret
+spc_end:
@@ -0,0 +1,77 @@
+; LoRom test ROM for the flags of the SPC700 MOV instructions. Uploads an SPC700 program through
+; the IPL boot ROM, which loads a value that sets Z and then stores $80 in several ways. Stores to
+; memory don't update the flags, so Z has to stay set and N clear. A load of $80 afterwards sets N.
+;
+; MOVW dp,YA reads the low byte of the destination before writing it. With T0OUT as the
+; destination, the read clears the counter and the write is ignored, so it has to read 0 right
+; afterwards.
+;
+; $7E0000: N and Z after the stores, $02
+; $7E0001: N and Z after the load, $80
+; $7E0002: T0OUT after MOVW, $00
+; $7E0003: $55 when done
+
+.title "SNES-EMU MOV FLAGS"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    lda $2140
+    sta $00
+    lda $2141
+    sta $01
+    lda $2142
+    sta $02
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200
+program:
+.spc700 SPC_BASE
+spc_entry:
+    mov x, #$80
+    mov a, #$00     ; Set Z and clear N
+    mov $10, x      ; MOV dp,X
+    mov $11, #$80   ; MOV dp,#imm
+    mov $12, $10    ; MOV dp,dp
+    mov !$0013, x   ; MOV !abs,X
+    push psw
+    pop a
+    and a, #$82
+    mov $F4, a
+
+    mov a, $10      ; Set N and clear Z
+    push psw
+    pop a
+    and a, #$82
+    mov $F5, a
+
+    mov $FA, #$04   ; T0DIV: timer 0 counts every 512 cycles
+    mov $F1, #$01   ; CONTROL: start timer 0
+    mov x, #$00
+delay:
+    dec x
+    bne delay       ; 1536 cycles, T0OUT counts to about 3
+    movw $FD, ya
+    mov a, $FD
+    mov $F6, a
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+spc_end:
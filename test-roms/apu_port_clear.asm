@@ -0,0 +1,66 @@
+; LoRom test ROM for clearing the CPU to APU ports through the CONTROL register. Uploads an SPC700
+; program through the IPL boot ROM and writes $11, $22 and $33 to ports 1 to 3, then $44 to port 0
+; as a signal. The program writes $30 to CONTROL, which clears both pairs of input ports, and
+; reports the ORed values of all four ports afterwards.
+;
+; $7E0000: Port 3 before clearing, $33
+; $7E0001: All ports ORed after clearing, $00
+; $7E0002: $55 when done
+
+.title "SNES-EMU PORT CLEAR"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+    lda #$11
+    sta $2141
+    lda #$22
+    sta $2142
+    lda #$33
+    sta $2143
+    lda #$44        ; Signal that all ports have been written
+    sta $2140
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    lda $2141
+    sta $00
+    lda $2142
+    sta $01
+    lda #$55
+    sta $02
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200
+program:
+.spc700 SPC_BASE
+spc_entry:
+wait_cpu:
+    mov a, $F4
+    cmp a, #$44
+    bne wait_cpu
+    mov $10, $F7    ; Port 3 before clearing
+    mov $F1, #$30   ; CONTROL: clear ports 0/1 and 2/3
+    mov a, $F4
+    or a, $F5
+    or a, $F6
+    or a, $F7
+    mov $F6, a
+    mov $F5, $10
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+spc_end:
@@ -0,0 +1,42 @@
+; LoRom test ROM for reads of the APU ports. The four ports are mirrored across $2140-$217F and
+; always driven, so unused ports read as $00 instead of open bus. The IPL ROM signals that it is
+; ready by writing $AA and $BB to the first two ports.
+;
+; $7E0000: $2144, a mirror of $2140, $AA
+; $7E0001: $217D, a mirror of $2141, $BB
+; $7E0002: $2142, $00
+; $7E0003: $217F, a mirror of $2143, $00
+; $7E0004: $55 when done
+
+.title "SNES-EMU APU MIRROR"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+wait:
+    lda $2140
+    cmp #$AA
+    bne wait
+    lda $2141
+    cmp #$BB
+    bne wait
+
+    lda $2144
+    sta $00
+    lda $217D
+    sta $01
+    lda $2142
+    sta $02
+    lda $217F
+    sta $03
+
+    lda #$55
+    sta $04
+done:
+    bra done
+
+vector:
+    rti
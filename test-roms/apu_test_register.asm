@@ -0,0 +1,35 @@
+; LoRom test ROM for the unimplemented TEST register of the SPC700. Uploads an SPC700 program
+; through the IPL boot ROM, which writes the power-on value $0A to TEST, which is ignored, and then
+; $00, which has to be reported as an unimplemented feature instead of panicking.
+;
+; $7E0000: $55 once the program was started, the test stops at the second write to TEST though
+
+.title "SNES-EMU APU TEST REG"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+    lda #$55
+    sta $00
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200
+program:
+.spc700 SPC_BASE
+spc_entry:
+    mov $F0, #$0A   ; TEST: power-on value
+    mov $F0, #$00   ; TEST: timers and RAM writes disabled
+spc_done:
+    bra spc_done
+spc_end:
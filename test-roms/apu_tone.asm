@@ -0,0 +1,78 @@
+; LoRom test ROM which plays a known tone. Uploads an SPC700 program through the IPL boot ROM,
+; which plays a BRR sample on voice 0 at full volume. The sample is a single looped block of
+; 8 high and 8 low samples, played back at 32 kHz it is a 2 kHz square wave.
+;
+; $7E0000: $55 once the voice has been keyed on
+
+.title "SNES-EMU APU TONE"
+.vectors reset, vector
+
+SPC_BASE = $0300
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    sta $00
+done:
+    bra done
+
+vector:
+    rti
+
+; Uploaded to $0300, the sample directory has to start at a page boundary.
+program:
+.spc700 SPC_BASE
+    ; Sample directory, entry 0 starts and loops at $0304
+    .dw $0304, $0304
+    ; BRR block: shift 11, filter 0, loop and end flags set
+    .db $B3, $77, $77, $77, $77, $88, $88, $88, $88
+
+spc_entry:          ; $030D
+    mov $F2, #$5D   ; DIR: $0300
+    mov $F3, #$03
+    mov $F2, #$00   ; V0VOLL
+    mov $F3, #$7F
+    mov $F2, #$01   ; V0VOLR
+    mov $F3, #$7F
+    mov $F2, #$02   ; V0PITCHL: $1000, the native rate of 32 kHz
+    mov $F3, #$00
+    mov $F2, #$03   ; V0PITCHH
+    mov $F3, #$10
+    mov $F2, #$04   ; V0SRCN: sample 0
+    mov $F3, #$00
+    mov $F2, #$05   ; V0ADSR1: use GAIN
+    mov $F3, #$00
+    mov $F2, #$07   ; V0GAIN: direct, full volume
+    mov $F3, #$7F
+    mov $F2, #$0C   ; MVOLL
+    mov $F3, #$7F
+    mov $F2, #$1C   ; MVOLR
+    mov $F3, #$7F
+    mov $F2, #$2C   ; EVOLL
+    mov $F3, #$00
+    mov $F2, #$3C   ; EVOLR
+    mov $F3, #$00
+    mov $F2, #$2D   ; PMON
+    mov $F3, #$00
+    mov $F2, #$3D   ; NON
+    mov $F3, #$00
+    mov $F2, #$4D   ; EON
+    mov $F3, #$00
+    mov $F2, #$5C   ; KOFF
+    mov $F3, #$00
+    mov $F2, #$6C   ; FLG: unmute, echo writes disabled
+    mov $F3, #$20
+    mov $F2, #$4C   ; KON: voice 0
+    mov $F3, #$01
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+spc_end:
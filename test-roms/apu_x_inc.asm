@@ -0,0 +1,64 @@
+; LoRom test ROM for the SPC700 MOV instructions with (X)+ operands. Uploads an SPC700 program
+; through the IPL boot ROM which stores A with MOV (X)+,A and loads it back with MOV A,(X)+. Both
+; instructions use X before incrementing it, so the load reads the byte after the stored one.
+; Only the load sets N and Z.
+;
+; $7E0000: A after MOV A,(X)+, $C3 (the byte at $41)
+; $7E0001: N and Z after the store in bits 7 and 1, after the load in bits 6 and 0, $42
+; $7E0002: X after both instructions, $42
+; $7E0003: $55 when done
+
+.title "SNES-EMU APU X INC"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143       ; X is written last
+    beq wait_done
+    sta $02
+    lda $2141
+    sta $00
+    lda $2142
+    sta $01
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200
+program:
+.spc700 SPC_BASE
+spc_entry:
+    mov $41, #$C3
+    mov x, #$40
+    mov a, #$80
+    mov y, #$00     ; Set Z and clear N
+    mov (x)+, a
+    push psw
+    mov y, #$00
+    mov a, (x)+
+    push psw
+    mov $F5, a
+    pop a           ; Flags after the load
+    and a, #$82
+    lsr a
+    mov $10, a
+    pop a           ; Flags after the store
+    and a, #$82
+    or a, $10
+    mov $F6, a
+    mov $F7, x
+spc_done:
+    bra spc_done
+spc_end:
@@ -0,0 +1,59 @@
+; LoRom test ROM for MOV (X)+ when X is $FF. Uploads an SPC700 program through the IPL boot ROM,
+; which sets the direct page to $01 and stores A to $FE and $FF with MOV (X)+,A. X wraps around to
+; 0, so the following MOV A,(X)+ loads the byte at $0100 and leaves X at 1.
+;
+; $7E0000: A after MOV A,(X)+, $A5 (the byte at $0100)
+; $7E0001: X afterwards, $01
+; $7E0002: Byte at $01FF, $5A
+; $7E0003: $55 when done
+
+.title "SNES-EMU APU X WRAP"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    lda $2141
+    sta $00
+    lda $2142
+    sta $01
+    lda $2140
+    sta $02
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200
+program:
+.spc700 SPC_BASE
+spc_entry:
+    setp            ; Direct page at $0100, away from the I/O registers
+    mov $00, #$A5
+    mov x, #$FE
+    mov a, #$5A
+    mov (x)+, a
+    mov (x)+, a     ; Stores to $01FF, X wraps around to 0
+    mov a, (x)+
+    clrp
+    mov $F5, a
+    mov $F6, x
+    mov a, !$01FF
+    mov $F4, a
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+spc_end:
@@ -0,0 +1,43 @@
+; LoRom test ROM for color math on the backdrop. The main screen only shows the backdrop ($0010,
+; red 16), which is added to the fixed color (green 8, blue 4) and halved, but only inside window 1
+; covering the left half of the screen. The expected output is (8, 4, 2) on the left and (16, 0, 0)
+; on the right.
+
+.title "SNES-EMU BACKDROP"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    stz $2121       ; CGADD
+    lda #$10
+    sta $2122       ; CGDATA: backdrop color $0010
+    stz $2122
+    stz $212C       ; TM: only the backdrop on the main screen
+    stz $212D       ; TS
+
+    stz $2126       ; WH0: window 1 covers x = 0..=127
+    lda #$7F
+    sta $2127       ; WH1
+    lda #$20
+    sta $2125       ; WOBJSEL: enable window 1 for color math
+    stz $212B       ; WOBJLOG
+
+    lda #$10
+    sta $2130       ; CGWSEL: color math only inside the window, subscreen is the fixed color
+    lda #$60
+    sta $2131       ; CGADSUB: add, half, enable on the backdrop
+    lda #$48
+    sta $2132       ; COLDATA: green 8
+    lda #$84
+    sta $2132       ; COLDATA: blue 4
+
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+    bra *
+
+vector:
+    rti
@@ -0,0 +1,109 @@
+; LoRom test ROM for the BG3 priority bit of BGMODE outside of mode 1. In mode 0 with the bit set,
+; BG1 covers the left half of the screen with low priority tiles and BG3 covers the whole screen
+; with high priority tiles. The bit has no effect in mode 0, so BG1 stays in front of BG3: The left
+; half is red (BG1) and the right half green (BG3).
+
+.title "SNES-EMU BG3 PRIORITY"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    ; Clear both tile maps and tile 0
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+    lda #$09
+    sta $4300       ; DMAP0: A to B, two registers, fixed source
+    lda #$18        ; VMDATAL
+    sta $4301
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$2010
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; The BG1 tile map at $0000 uses tile 1 with low priority in the left 16 columns
+    ldx #$0000
+    stx $2116
+bg1_map:
+    txa
+    and #$10
+    bne bg1_right
+    lda #$01
+bg1_right:
+    and #$01
+    sta $2118
+    stz $2119
+    inx
+    cpx #$0400
+    bne bg1_map
+
+    ; The BG3 tile map at $0400 uses tile 1 with high priority everywhere
+    ldx #$0000
+bg3_map:
+    lda #$01
+    sta $2118
+    lda #$20
+    sta $2119
+    inx
+    cpx #$0400
+    bne bg3_map
+
+    ; Tile 1 at $1008 uses color 1 everywhere
+    ldx #$1008
+    stx $2116
+    ldx #$0000
+tile:
+    lda #$FF
+    sta $2118
+    stz $2119
+    inx
+    cpx #$0008
+    bne tile
+
+    ; Black backdrop, BG1 color 1 red and BG3 color 1 green
+    stz $2121
+    stz $2122
+    stz $2122
+    lda #$1F
+    sta $2122
+    stz $2122
+    lda #$41
+    sta $2121
+    lda #$E0
+    sta $2122
+    lda #$03
+    sta $2122
+
+    lda #$08
+    sta $2105       ; BGMODE: mode 0, BG3 priority
+    stz $2107       ; BG1SC: tile map at $0000
+    lda #$04
+    sta $2109       ; BG3SC: tile map at $0400
+    lda #$01
+    sta $210B       ; BG12NBA: BG1 tiles at $1000
+    sta $210C       ; BG34NBA: BG3 tiles at $1000
+    lda #$05
+    sta $212C       ; TM: BG1 and BG3
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+zero:
+    .db $00
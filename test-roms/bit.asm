@@ -0,0 +1,46 @@
+; LoRom test ROM for the flags of BIT with a 16-bit accumulator. A is $0100 and N and V are set
+; beforehand. BIT #imm only updates Z, which is computed over the whole accumulator, while BIT dp
+; copies bits 15 and 14 of the operand into N and V.
+;
+; $7E0000: N, V and Z after BIT #$0100, $C0
+; $7E0001: N, V and Z after BIT #$0001, $C2
+; $7E0002: N, V and Z after BIT dp with $4000, $42
+; $7E0003: $55 when done
+
+.title "SNES-EMU BIT"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$20
+    lda #$4000
+    sta $10         ; Operand of BIT dp
+    lda #$0100
+    sep #$C0        ; Set N and V
+    bit #$0100      ; Only the high byte has a bit in common
+    php
+    bit #$0001
+    php
+    bit $10
+    php
+
+    sep #$20
+    pla
+    and #$C2
+    sta $02
+    pla
+    and #$C2
+    sta $01
+    pla
+    and #$C2
+    sta $00
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
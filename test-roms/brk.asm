@@ -0,0 +1,93 @@
+; LoRom test ROM for the return address of BRK and COP. Both are two bytes long, the opcode is
+; followed by a signature byte, and they push the address after the signature byte. Each one is
+; followed by an INC A as its signature, which would increment A if RTI returned to it. The handler
+; copies the pushed PC to WRAM and clears A before returning, in native and emulation mode. The
+; pushed PC is at the same stack offset in both modes, since K is pushed before it. In emulation
+; mode, the handler is shared with IRQs, which push P with the B bit cleared, while BRK sets it.
+;
+; $7E0000: A after BRK in native mode, $00
+; $7E0001: A after COP in native mode, $00
+; $7E0002: A after BRK in emulation mode, $00
+; $7E0003: A after COP in emulation mode, $00
+; $7E0004: PC pushed by BRK in native mode, $8009
+; $7E0006: PC pushed by COP in native mode, $800F
+; $7E0008: PC pushed by BRK in emulation mode, $8017
+; $7E000A: PC pushed by COP in emulation mode, $8021
+; $7E000C: PC pushed by the IRQ in emulation mode, $8034 (the address of ret)
+; $7E000E: P pushed by the last interrupt
+; $7E000F: P pushed by BRK in emulation mode, $34 (B set)
+; $7E0010: P pushed by the IRQ in emulation mode, $20 (B clear)
+; $7E0011: $55 when done
+
+.title "SNES-EMU BRK"
+.vectors reset, vector
+.vector cop, handler
+.vector brk, handler
+.vector emu_cop, handler
+.vector emu_irq, handler
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+
+    ldx #$00
+brk_native:
+    brk
+    inc a           ; Signature byte
+    sta $00
+    ldx #$02
+cop_native:
+    cop
+    inc a
+    sta $01
+
+    sec
+    xce
+    ldx #$04
+brk_emulation:
+    brk
+    inc a
+    sta $02
+    lda $0E
+    sta $0F
+    ldx #$06
+cop_emulation:
+    cop
+    inc a
+    sta $03
+
+    ldx #$08
+    lda #100
+    sta $4209       ; VTIMEL
+    stz $420A       ; VTIMEH
+    lda #$20
+    sta $4200       ; NMITIMEN: V IRQ
+    cli
+    wai
+ret:
+    sei
+    stz $4200
+    lda $0E
+    sta $10
+
+    lda #$55
+    sta $11
+done:
+    bra done
+
+handler:
+    lda $4211       ; TIMEUP: acknowledge
+    lda $01,s       ; Pushed P
+    sta $0E
+    lda $02,s       ; Pushed PC, low byte
+    sta $04,x
+    lda $03,s
+    sta $05,x
+    lda #$00
+    rti
+
+vector:
+    rti
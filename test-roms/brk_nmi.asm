@@ -0,0 +1,64 @@
+; LoRom test ROM for an NMI which is raised around a BRK. The test raises the NMI itself, either
+; right before the BRK at $8100 is executed or right after it. Each handler appends its ID to a log
+; in WRAM, and the NMI handler also stores the PC it pushed.
+;
+; $7E0000: ID of the first handler, $01 for NMI, $02 for BRK
+; $7E0001: ID of the second handler
+; $7E0002: PC pushed by the NMI
+; $7E0004: $55 when done
+
+.title "SNES-EMU BRK NMI"
+.vectors reset, vector
+.vector nmi, nmi
+.vector brk, brk_handler
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    ldx #$0000
+    stx $10
+    jmp brk_site
+
+nmi:
+    pha
+    phx
+    ldx $10
+    lda #$01
+    sta $00,x
+    inx
+    stx $10
+    lda $05,s       ; Pushed PC, low byte
+    sta $02
+    lda $06,s
+    sta $03
+    plx
+    pla
+    rti
+
+brk_handler:
+    pha
+    phx
+    ldx $10
+    lda #$02
+    sta $00,x
+    inx
+    stx $10
+    plx
+    pla
+    rti
+
+vector:
+    rti
+
+.org $8100
+brk_site:
+    brk
+    .db $00         ; Signature byte
+    lda #$55
+    sta $04
+done:
+    bra done
@@ -0,0 +1,53 @@
+; LoRom test ROM for CGDATA. Writing starts at the last color, blue ($7C00), and wraps around to
+; color 0, the backdrop, which becomes red 16 ($0010). Both colors are read back through
+; CGDATAREAD starting at the last color again, low byte first. The screen only shows the backdrop,
+; so it has to be red 16 everywhere, the same as backdrop_math.sfc without color math.
+;
+; $7E0000: color 255 low byte, $00
+; $7E0001: color 255 high byte, $7C
+; $7E0002: color 0 low byte, $10
+; $7E0003: color 0 high byte, $00
+; $7E0004: $55 when done
+
+.title "SNES-EMU CGDATA"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    lda #$FF
+    sta $2121       ; CGADD: last color
+    stz $2122       ; CGDATA: blue
+    lda #$7C
+    sta $2122
+    lda #$10
+    sta $2122       ; CGDATA: red 16, wrapped around to color 0
+    stz $2122
+
+    lda #$FF
+    sta $2121       ; CGADD
+    ldx #$00
+read:
+    lda $213B       ; CGDATAREAD
+    sta $00,x
+    inx
+    cpx #$04
+    bne read
+
+    stz $212C       ; TM: only the backdrop on the main screen
+    stz $212D       ; TS
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+    lda #$55
+    sta $04
+done:
+    bra done
+
+vector:
+    rti
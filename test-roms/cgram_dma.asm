@@ -0,0 +1,82 @@
+; LoRom test ROM for CGDATA writes through DMA and during the active display. During forced blank,
+; a DMA writes all 512 bytes of CGRAM from WRAM, where byte n is n for the first 256 and n + $55
+; for the last 256 bytes. Bit 7 of the high bytes is dropped by CGRAM.
+;
+; Then the display is enabled and color 0 is written during scanline 50, at H = 100. The PPU is
+; reading CGRAM itself then, so the write is dropped, but CGADD still advances. Another write
+; during forced blank afterwards lands at color 1, which becomes $001F.
+;
+; $7E0000: $55 when done
+
+.title "SNES-EMU CGRAM DMA"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    stz $0000
+
+    ldx #$0000
+fill:
+    txa
+    sta $7E2000,x
+    clc
+    adc #$55
+    sta $7E2100,x
+    inx
+    cpx #$0100
+    bne fill
+
+    stz $2121       ; CGADD
+    stz $4300       ; DMAP0: A to B, one register
+    lda #$22
+    sta $4301       ; BBAD0: CGDATA
+    ldx #$2000
+    stx $4302
+    lda #$7E
+    sta $4304       ; A1T0: $7E2000
+    ldx #$0200
+    stx $4305       ; DAS0: 512 bytes
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; Wait for H = 100 on scanline 50 with the display enabled
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+    stz $2121
+    lda #$64
+    sta $4207       ; HTIMEL
+    stz $4208
+    lda #$32
+    sta $4209       ; VTIMEL
+    stz $420A
+    lda #$30
+    sta $4200       ; NMITIMEN: H/V IRQ, polled through TIMEUP
+    lda #$FF
+    sta $2122       ; The low byte only goes to the latch
+poll:
+    lda $4211       ; TIMEUP
+    bpl poll
+    lda #$7F
+    sta $2122       ; Dropped
+    stz $4200
+
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    lda #$1F
+    sta $2122       ; Color 1
+    stz $2122
+
+    lda #$55
+    sta $0000
+done:
+    bra done
+
+vector:
+    rti
@@ -0,0 +1,40 @@
+; LoRom test ROM for the number of master cycles taken by CPU instructions. The ROM is slow, so
+; every access takes 8 cycles and every internal operation 6. The expected totals of each
+; instruction are listed in `tests/cycles.rs`. WAI halts the CPU for good, since no interrupts are
+; enabled. STP at $8100 and JSR (a,x) at $8200 are jumped to separately.
+
+.title "SNES-EMU CYCLES"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    lda #$1234
+    sta $10
+    lda $10
+    pha
+    pla
+    nop
+    wai
+
+.org $8100
+stop:
+    stp
+
+; Calls jsr_target through the second entry of the table
+.org $8200
+jsr_indirect:
+    ldx #$02
+    jsr (jsr_table,x)
+
+jsr_table:
+    .dw $0000, jsr_target
+
+jsr_target:
+    stp
+
+vector:
+    rti
@@ -0,0 +1,39 @@
+; LoRom test ROM for the DMAP registers. Every value written to DMAP0 and DMAP7 has to be read
+; back unchanged, so that all bits survive being decoded into the channel settings and encoded
+; again. Writes $55 to $7E0000 on success. On failure, $FF is written there and the value that
+; was not read back to $7E0001.
+
+.title "SNES-EMU DMAP TEST"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    ldx #$00
+loop:
+    stx $4300       ; DMAP0
+    cpx $4300
+    bne fail
+    stx $4370       ; DMAP7
+    cpx $4370
+    bne fail
+    inx
+    bne loop
+
+    lda #$55
+    sta $00
+done:
+    bra done
+
+fail:
+    stx $01
+    lda #$FF
+    sta $00
+hang:
+    bra hang
+
+vector:
+    rti
@@ -0,0 +1,27 @@
+; LoRom test ROM for the extra cycle direct page accesses take when the low byte of D is not zero.
+; The same direct page loads run with D = $0000 and D = $0080. With D = $0080, every load takes
+; 6 master cycles longer, which shows in the H positions of the trace.
+;
+; $7E0000: $55 when done
+
+.title "SNES-EMU DP PENALTY"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    lda $10         ; D = $0000
+    lda $10,x
+    pea $0080
+    pld
+    lda $10         ; D = $0080
+    lda $10,x
+    lda #$55
+    sta $0000
+done:
+    bra done
+
+vector:
+    rti
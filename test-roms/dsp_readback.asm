@@ -0,0 +1,85 @@
+; LoRom test ROM for reading back DSP registers. Uploads an SPC700 program through the IPL boot
+; ROM, which keys on voice 0 with a fixed GAIN of $7F, writes $55 to its ENVX register and reads
+; it back a few samples later. Since the DSP overwrites ENVX with the current envelope level on
+; every sample, it has to read $7F. The voice plays a single looping BRR block, so the ENDX bit of
+; voice 0 is set again after clearing ENDX.
+;
+; $7E0000: ENVX of voice 0, $7F
+; $7E0001: ENDX, $01
+; $7E0002: $55 when done
+
+.title "SNES-EMU DSP READBACK"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    lda $2141
+    sta $00
+    lda $2142
+    sta $01
+    lda #$55
+    sta $02
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200
+program:
+.spc700 SPC_BASE
+spc_entry:
+    mov $F2, #$5D   ; DIR: sample directory at $0300
+    mov $F3, #$03
+    mov $F2, #$04   ; SRCN0: sample 0
+    mov $F3, #$00
+    mov $F2, #$05   ; ADSR1: use GAIN
+    mov $F3, #$00
+    mov $F2, #$07   ; GAIN: direct, $7F
+    mov $F3, #$7F
+    mov $F2, #$03   ; PITCHH0: one sample per output sample
+    mov $F3, #$10
+    mov $F2, #$6C   ; FLG: no reset, not muted, no echo writes
+    mov $F3, #$20
+    mov $F2, #$7C   ; ENDX: writing clears all bits
+    mov $F3, #$00
+    mov $F2, #$4C   ; KON: voice 0
+    mov $F3, #$01
+    mov x, #$00
+delay1:
+    dec x
+    bne delay1
+    mov $F2, #$08   ; ENVX0
+    mov $F3, #$55
+    mov x, #$00
+delay2:
+    dec x
+    bne delay2
+    mov a, $F3
+    mov $F5, a
+    mov $F2, #$7C   ; ENDX
+    mov a, $F3
+    mov $F6, a
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+
+.org $0300
+directory:          ; $0300: start and loop address of sample 0
+    .dw $0310, $0310
+
+.org $0310
+brr_block:          ; $0310: silent block with the end and loop flags set
+    .db $03, $00, $00, $00, $00, $00, $00, $00, $00
+spc_end:
@@ -0,0 +1,27 @@
+; LoRom test ROM for starting execution at a routine other than the reset handler. The reset
+; handler writes $FF to $7E0000. The routine at $80:8100 writes the program bank to $7E0001 and
+; $55 to $7E0000 instead, which only happens if the test runner jumped there.
+
+.title "SNES-EMU ENTRY TEST"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    lda #$FF
+    sta $0000
+hang:
+    bra hang
+
+vector:
+    rti
+
+.org $8100
+routine:
+    phk
+    pla
+    sta $0001
+    lda #$55
+    sta $0000
+done:
+    bra done
@@ -0,0 +1,82 @@
+; LoRom test ROM for HDMA transfers with and without overscan. An HDMA channel writes one byte per
+; scanline to WMDATA, so the number of bytes written to WRAM is the number of transfers in a frame.
+; The first frame runs without overscan and fills $7E1000, the second one with overscan fills
+; $7E1100. Lines 0-224 transfer without overscan, lines 0-239 with it.
+;
+; $7E10E0: last transfer without overscan, $01
+; $7E10E1: $00
+; $7E11EF: last transfer with overscan, $01
+; $7E11F0: $00
+; $7E0000: $55 when done
+
+.title "SNES-EMU HDMA LINES"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+
+    ldx #$0000
+clear:
+    stz $1000,x
+    inx
+    cpx #$0200
+    bne clear
+
+    stz $2181       ; WMADD: $7E1000
+    lda #$10
+    sta $2182
+    stz $2183
+
+    stz $4300       ; DMAP0: one register, write once, absolute table
+    lda #$80
+    sta $4301       ; BBAD0: WMDATA
+    ldx #table
+    stx $4302       ; A1T0
+    stz $4304       ; A1B0
+
+    ; Enable HDMA during vblank, so the first frame starts with a reload
+    jsr wait_vblank
+    lda #$01
+    sta $420C       ; HDMAEN
+
+    jsr wait_vblank
+    stz $2181       ; WMADD: $7E1100
+    lda #$11
+    sta $2182
+    ; Only enable overscan during the active display, so the frame doesn't end up without vblank
+wait_active:
+    lda $4212       ; HVBJOY
+    bmi wait_active
+    lda #$04
+    sta $2133       ; SETINI: overscan
+
+    jsr wait_vblank
+    stz $420C
+    lda #$55
+    sta $0000
+done:
+    bra done
+
+wait_vblank:
+    lda $4212       ; HVBJOY
+    bmi wait_vblank
+wait_vblank_start:
+    lda $4212
+    bpl wait_vblank_start
+    rts
+
+; Two runs of 127 lines with the repeat flag, which is more than an overscan frame has
+table:
+    .db $FF
+    .dsb 127, $01
+    .db $FF
+    .dsb 127, $01
+    .db $00
+
+vector:
+    rti
@@ -0,0 +1,26 @@
+; LoRom test ROM for the instruction statistics. It runs a fixed sequence of instructions and then
+; halts in WAI, which is never woken up because no interrupts are enabled.
+;
+; SEI, CLC, XCE, REP and LDX are executed once, DEX and BNE ten times each, WAI once.
+
+.title "SNES-EMU HISTOGRAM"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    ldx #$000A
+
+loop:
+    dex
+    bne loop
+
+halt:
+    wai
+    bra halt
+
+vector:
+    rti
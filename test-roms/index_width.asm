@@ -0,0 +1,47 @@
+; LoRom test ROM for the side effects of the index register width. Setting x truncates X and Y
+; right away, so clearing it again exposes zeroed high bytes. Entering emulation mode forces x and
+; the high byte of S to $01, and REP can't clear x again until native mode is restored.
+;
+; $7E0000: X after SEP #$10 and REP #$10, $0034
+; $7E0002: Y after SEP #$10 and REP #$10, $0078
+; $7E0004: X after a round trip through emulation mode with REP #$30, $00CD
+; $7E0006: S after a round trip through emulation mode, $01F0
+; $7E0008: $55 when done
+
+.title "SNES-EMU INDEX WIDTH"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    ldx #$1234
+    ldy #$5678
+    sep #$10        ; Truncates X and Y
+    rep #$10
+    stx $00
+    sty $02
+
+    ldx #$ABCD
+    lda #$1FF0
+    tcs
+    sec
+    xce             ; Forces m, x and the high byte of S
+    rep #$30        ; Has no effect on m and x in emulation mode
+    clc
+    xce
+    rep #$30
+    stx $04
+    tsc
+    sta $06
+
+    sep #$20
+    lda #$55
+    sta $08
+done:
+    bra done
+
+vector:
+    rti
@@ -0,0 +1,42 @@
+; LoRom test ROM for interlaced output. The NMI handler sets the backdrop color for the next field
+; based on the field flag in STAT78, red for the first field and blue for the second. Since every
+; field is only drawn to every other row, the even rows end up red and the odd rows blue.
+
+.title "SNES-EMU INTERLACE"
+.vectors reset, vector
+.vector nmi, nmi
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    lda #$01
+    sta $2133       ; SETINI: interlace
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+    lda #$80
+    sta $4200       ; NMITIMEN: NMI
+
+loop:
+    wai
+    bra loop
+
+nmi:
+    lda $4210       ; RDNMI: acknowledge the NMI
+    stz $2121
+    lda $213F       ; STAT78: bit 7 is the current field, the next one is the other
+    bmi second
+    stz $2122       ; The second field is next, blue
+    lda #$7C
+    sta $2122
+    rti
+second:
+    lda #$1F        ; The first field is next, red
+    sta $2122
+    stz $2122
+vector:
+    rti
@@ -0,0 +1,43 @@
+; LoRom test ROM for the timing of the APU relative to the CPU. Waits until the IPL boot ROM has
+; cleared the zero page and signals that it is ready by writing $AA and $BB to the first two
+; ports, then latches the counters to record on which scanline that happened. The APU runs from
+; its own 24.576 MHz oscillator, so the scanline depends on the ratio between the two clocks.
+;
+; $7E0000: OPVCT low byte
+; $7E0001: OPVCT high bit
+; $7E0002: APUIO0, $AA
+; $7E0003: APUIO1, $BB
+; $7E0004: $55 when done
+
+.title "SNES-EMU IPL SYNC"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+wait_ipl:
+    lda $2140
+    cmp #$BBAA
+    bne wait_ipl
+    sep #$20
+
+    lda $2137       ; SLHV: latch the counters
+    lda $213D       ; OPVCT
+    sta $00
+    lda $213D
+    and #$01
+    sta $01
+    lda $2140
+    sta $02
+    lda $2141
+    sta $03
+    lda #$55
+    sta $04
+done:
+    bra done
+
+vector:
+    rti
@@ -0,0 +1,53 @@
+; LoRom test ROM for interrupts with the stack pointer at $0100 in native mode. The stack doesn't
+; wrap within page 1 like in emulation mode, so the IRQ pushes K to $0100 and the return address
+; to $00FF and $00FE. RTI pulls them back from the same addresses.
+;
+; $7E0000: K at $0100, $00
+; $7E0001: return address high byte at $00FF, $80
+; $7E0002: return address low byte at $00FE, $1A (the address of ret)
+; $7E0003: S in the handler, low byte, $FC
+; $7E0004: S after RTI, low byte, $00
+; $7E0005: $55 when done
+
+.title "SNES-EMU IRQ STACK"
+.vectors reset, vector
+.vector irq, irq
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    ldx #$0100
+    txs
+    sep #$30
+    lda #100
+    sta $4209       ; VTIMEL
+    stz $420A       ; VTIMEH
+    lda #$20
+    sta $4200       ; NMITIMEN: V IRQ
+    cli
+    wai
+ret:
+    sei
+    stz $4200
+    tsx
+    stx $04
+    lda #$55
+    sta $05
+loop:
+    bra loop
+
+irq:
+    lda $4211       ; TIMEUP: acknowledge
+    lda $0100
+    sta $00
+    lda $00FF
+    sta $01
+    lda $00FE
+    sta $02
+    tsx
+    stx $03
+vector:
+    rti
@@ -0,0 +1,47 @@
+; LoRom test ROM for the pointer fetches of JMP ($FFFF) and JMP [$FFFE], which wrap around from
+; $00:FFFF to $00:0000 instead of continuing in bank 1. The emulation mode IRQ vector at $FFFE is
+; $8FF0, and $00:0000 in WRAM supplies the last byte of each pointer:
+;
+; - JMP ($FFFF) reads $8F from $00:FFFF and $90 from $00:0000, jumping to $908F.
+; - JMP [$FFFE] reads $F0, $8F and then $01 from $00:0000, jumping to $01:8FF0.
+;
+; Without the wrap, the last byte would be read from $01:0000, which mirrors $00:0000. The test
+; catches that with a watchpoint.
+;
+; $7E0010: $11 if JMP ($FFFF) arrived
+; $7E0011: $22 if JMP [$FFFE] arrived
+; $7E0012: $55 when done
+
+.title "SNES-EMU JMP WRAP"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda #$90
+    sta $0000
+    jmp ($FFFF)
+
+.org $8FF0
+vector:
+    rti
+
+.org $908F
+wrapped_jmp:
+    lda #$11
+    sta $10
+    lda #$01
+    sta $0000
+    jmp [$FFFE]
+
+.org $018FF0
+wrapped_jml:
+    lda #$22
+    sta $11
+    lda #$55
+    sta $12
+done:
+    bra done
@@ -0,0 +1,61 @@
+; LoRom test ROM for the bit order of a standard controller in port 1. The automatic read stores
+; the report in JOY1 as B, Y, Select, Start, Up, Down, Left, Right in the high byte and A, X, L, R
+; followed by the four signature bits (all zero) in the low byte. The same report is then read
+; manually through $4016, which must agree with it. The states are stored as high byte, low byte:
+;
+; $7E0000: JOY1 ($4218)
+; $7E0002: 16 bits read from data line 1 of $4016
+; $7E0004: The 17th bit, the controller returns 1 after the report
+; $7E0005: $55 when done
+
+.title "SNES-EMU JOYPAD"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+
+    ; Let the automatic read run once
+    lda #$01
+    sta $4200       ; NMITIMEN: automatic joypad read
+wait_start:
+    lda $4212       ; HVBJOY: the first frame starts in the vertical blanking period
+    bmi wait_start
+wait_vblank:
+    lda $4212
+    bpl wait_vblank
+wait_active:
+    lda $4212
+    bmi wait_active
+    stz $4200
+    lda $4219
+    sta $00
+    lda $4218
+    sta $01
+
+    lda #$01
+    sta $4016
+    stz $4016
+    ldx #$10
+read:
+    lda $4016
+    lsr
+    rol $03
+    rol $02
+    dex
+    bne read
+
+    lda $4016
+    and #$01
+    sta $04
+
+    lda #$55
+    sta $05
+done:
+    bra done
+
+vector:
+    rti
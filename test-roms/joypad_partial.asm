@@ -0,0 +1,58 @@
+; LoRom test ROM for reading JOY1 during the automatic joypad read, with B and R held. The read
+; shifts in one bit at a time, so JOY1 doesn't have its final value until HVBJOY bit 0 clears. B
+; is the first bit, so after eight bits it is in bit 7 of JOY1L, while JOY1H is still empty. The
+; states are stored as high byte, low byte:
+;
+; $7E0000: JOY1 right after the read started, $00 $00
+; $7E0002: JOY1H once B reached bit 7 of JOY1L, $00
+; $7E0003: HVBJOY bit 0 after that, $01 (still reading)
+; $7E0004: JOY1 after the read, $80 $10
+; $7E0006: $55 when done
+
+.title "SNES-EMU JOYPAD BUSY"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+
+    lda #$01
+    sta $4200       ; NMITIMEN: automatic joypad read
+wait_busy:
+    lda $4212       ; HVBJOY
+    and #$01
+    beq wait_busy
+    lda $4219       ; JOY1H
+    sta $00
+    lda $4218       ; JOY1L
+    sta $01
+
+wait_b:
+    lda $4218
+    bpl wait_b
+    lda $4219
+    sta $02
+    lda $4212
+    and #$01
+    sta $03
+
+wait_done:
+    lda $4212
+    and #$01
+    bne wait_done
+    stz $4200
+    lda $4219
+    sta $04
+    lda $4218
+    sta $05
+
+    lda #$55
+    sta $06
+done:
+    bra done
+
+vector:
+    rti
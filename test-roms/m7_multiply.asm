@@ -0,0 +1,54 @@
+; LoRom test ROM for the signed multiplication of M7A and the last byte written to M7B, whose 24 bit
+; product is read from MPYL/MPYM/MPYH.
+;
+; $7E0000: M7A=$8000 * M7B=$FF, $008000
+; $7E0003: M7A=$8000 * M7B=$80, $400000
+; $7E0006: M7A=$1234 * M7B=$80, $F6E600
+; $7E0009: $55 when done
+
+.title "SNES-EMU M7 MULTIPLY"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+
+    stz $211B       ; M7A
+    lda #$80
+    sta $211B
+    lda #$FF
+    sta $211C       ; M7B
+    ldx #$00
+    jsr store_product
+
+    lda #$80
+    sta $211C
+    ldx #$03
+    jsr store_product
+
+    lda #$34
+    sta $211B
+    lda #$12
+    sta $211B
+    ldx #$06
+    jsr store_product
+
+    lda #$55
+    sta $09
+done:
+    bra done
+
+store_product:
+    lda $2134       ; MPYL
+    sta $00,x
+    lda $2135       ; MPYM
+    sta $01,x
+    lda $2136       ; MPYH
+    sta $02,x
+    rts
+
+vector:
+    rti
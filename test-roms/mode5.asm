@@ -0,0 +1,109 @@
+; LoRom test ROM for BG mode 5, which has twice the horizontal resolution. BG1 is enabled on both
+; screens and filled with a 16 pixel wide tile: tile 1 alternates between red and green on every
+; pixel, tile 2 next to it is blue. BG1HOFS is 1, which scrolls by two pixels of the doubled
+; resolution.
+
+.title "SNES-EMU MODE 5"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    lda #$08
+    sta $4300       ; DMAP0: A to B, one register, fixed source
+    stz $4304
+
+    ; Clear the high bytes of the tile map
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+    lda #$19        ; VMDATAH
+    sta $4301
+    ldx #zero
+    stx $4302
+    ldx #$0400
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; The tile map at $0000 uses tile 1 everywhere, tile 2 is used for the right half
+    stz $2115       ; VMAIN: increment after writing the low byte
+    ldx #$0000
+    stx $2116
+    lda #$18        ; VMDATAL
+    sta $4301
+    ldx #map_entry
+    stx $4302
+    ldx #$0400
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Tiles 1 and 2 at $1010
+    lda #$80
+    sta $2115
+    ldx #$1010
+    stx $2116
+    lda #$01
+    sta $4300       ; DMAP0: A to B, two registers
+    ldx #tiles
+    stx $4302
+    ldx #$0040
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Black backdrop, colors 1 to 3 red, green and blue
+    stz $2121
+    stz $2122
+    stz $2122
+    lda #$1F
+    sta $2122
+    stz $2122
+    lda #$E0
+    sta $2122
+    lda #$03
+    sta $2122
+    stz $2122
+    lda #$7C
+    sta $2122
+
+    lda #$05
+    sta $2105       ; BGMODE: mode 5
+    stz $2107       ; BG1SC: tile map at $0000
+    lda #$01
+    sta $210B       ; BG12NBA: BG1 tiles at $1000
+    lda #$01
+    sta $210D       ; BG1HOFS = 1
+    stz $210D
+    lda #$01
+    sta $212C       ; TM: BG1
+    lda #$01
+    sta $212D       ; TS: BG1
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+zero:
+    .db $00
+map_entry:
+    .db $01
+tiles:
+    ; Tile 1: colors 1 and 2 alternating
+    .db $AA, $55, $AA, $55, $AA, $55, $AA, $55, $AA, $55, $AA, $55, $AA, $55, $AA, $55
+    .db $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00
+    ; Tile 2: color 3
+    .db $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF, $FF
+    .db $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00
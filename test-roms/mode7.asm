@@ -0,0 +1,131 @@
+; LoRom test ROM for Mode 7, rotating the tile map by 90 degrees. The tile map uses tile 1 (red) in
+; even and tile 2 (blue) in odd rows, so the rows show up as vertical stripes 8 pixels wide. The
+; matrix A = 0, B = 1, C = -1, D = 0 with the center at (0, 128) and the offsets (0, 128) maps
+; pixel (x, y) to (y, 128 - x) in the tile map, so only x <= 128 is inside the tile map. HDMA sets
+; the screen over bits of M7SEL to transparent for the first 112 lines and to tile 0 (green) below.
+;
+; Expected picture: vertical red and blue stripes on the left, starting with red from x = 121 to
+; x = 128. To the right of it black in the upper and green in the lower part of the screen.
+
+.title "SNES-EMU MODE 7"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    ; The tile map in the low bytes of VRAM, 128 rows of 128 tiles, one DMA per row
+    stz $2115       ; VMAIN: increment after writing the low byte
+    ldx #$0000
+    stx $2116
+    lda #$08
+    sta $4300       ; DMAP0: A to B, one register, fixed source
+    lda #$18
+    sta $4301       ; BBAD0: VMDATAL
+    stz $4304
+    ldy #$0000      ; Row
+map_row:
+    ldx #tile_numbers
+    tya
+    and #$01
+    beq even_row
+    inx
+even_row:
+    stx $4302       ; A1T0
+    ldx #$0080
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+    iny
+    cpy #$0080
+    bne map_row
+
+    ; Tiles 0 to 2 in the high bytes, every one filled with a single color
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+    ldy #$0000
+tile:
+    lda tile_colors,y
+    ldx #$0040
+tile_pixel:
+    sta $2119       ; VMDATAH
+    dex
+    bne tile_pixel
+    iny
+    cpy #$0003
+    bne tile
+
+    ; Black backdrop, colors 1 to 3 red, blue and green
+    stz $2121
+    ldx #$0000
+palette:
+    lda colors,x
+    sta $2122
+    inx
+    cpx #$0008
+    bne palette
+
+    lda #$07
+    sta $2105       ; BGMODE: mode 7
+    ; Matrix A = 0, B = 1.0, C = -1.0, D = 0
+    stz $211B       ; M7A
+    stz $211B
+    stz $211C       ; M7B
+    lda #$01
+    sta $211C
+    stz $211D       ; M7C
+    lda #$FF
+    sta $211D
+    stz $211E       ; M7D
+    stz $211E
+    stz $211F       ; M7X = 0
+    stz $211F
+    lda #$80
+    sta $2120       ; M7Y = 128
+    stz $2120
+    stz $210D       ; M7HOFS = 0
+    stz $210D
+    sta $210E       ; M7VOFS = 128
+    stz $210E
+    lda #$80
+    sta $211A       ; M7SEL: transparent outside of the tile map
+
+    ; HDMA channel 0 switches M7SEL to tile 0 below line 112
+    stz $4300       ; DMAP0: one register, write once
+    lda #$1A
+    sta $4301       ; BBAD0: M7SEL
+    ldx #m7sel_table
+    stx $4302
+    stz $4304
+    lda #$01
+    sta $420C       ; HDMAEN
+
+    lda #$01
+    sta $212C       ; TM: BG1
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+tile_numbers:
+    .db $01, $02
+tile_colors:
+    .db $03, $01, $02
+colors:
+    .dw $0000, $001F, $7C00, $03E0
+m7sel_table:
+    .db 112, $80
+    .db 1, $C0
+    .db 0
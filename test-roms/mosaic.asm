@@ -0,0 +1,103 @@
+; LoRom test ROM for mosaic. BG1 is filled with a tile which has a different color in every 4x4
+; quarter, offset by one pixel through BG1HOFS. With a mosaic size of 4, every 4x4 block of the
+; screen, starting at the first visible scanline, shows the color of its top left pixel.
+
+.title "SNES-EMU MOSAIC"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    lda #$08
+    sta $4300       ; DMAP0: A to B, one register, fixed source
+    stz $4304
+
+    ; Clear the high bytes of the tile map
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+    lda #$19        ; VMDATAH
+    sta $4301
+    ldx #zero
+    stx $4302
+    ldx #$0400
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; The tile map at $0000 uses tile 1 everywhere
+    stz $2115       ; VMAIN: increment after writing the low byte
+    ldx #$0000
+    stx $2116
+    lda #$18        ; VMDATAL
+    sta $4301
+    ldx #map_entry
+    stx $4302
+    ldx #$0400
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Tile 1 at $1008
+    lda #$80
+    sta $2115
+    ldx #$1008
+    stx $2116
+    lda #$01
+    sta $4300       ; DMAP0: A to B, two registers
+    ldx #tile
+    stx $4302
+    ldx #$0010
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Black backdrop, colors 1 to 3 red, green and blue
+    stz $2121
+    stz $2122
+    stz $2122
+    lda #$1F
+    sta $2122
+    stz $2122
+    lda #$E0
+    sta $2122
+    lda #$03
+    sta $2122
+    stz $2122
+    lda #$7C
+    sta $2122
+
+    stz $2105       ; BGMODE: mode 0
+    stz $2107       ; BG1SC: tile map at $0000
+    lda #$01
+    sta $210B       ; BG12NBA: BG1 tiles at $1000
+    lda #$01
+    sta $210D       ; BG1HOFS = 1
+    stz $210D
+    lda #$31
+    sta $2106       ; MOSAIC: size 4, BG1
+    lda #$01
+    sta $212C       ; TM: BG1
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+zero:
+    .db $00
+map_entry:
+    .db $01
+tile:
+    .db $5A, $3C, $5A, $3C, $5A, $3C, $5A, $3C
+    .db $5A, $C3, $5A, $C3, $5A, $C3, $5A, $C3
@@ -0,0 +1,80 @@
+; LoRom test ROM for a multitap in port 2, with one button held on each of its controllers. The
+; first two controllers are read automatically while pin 6 is high, the last two manually after
+; setting pin 6 low through WRIO. The states are stored as high byte, low byte:
+;
+; $7E0000: Controller 1 from JOY2 ($421A)
+; $7E0002: Controller 2 from JOY4 ($421E)
+; $7E0004: Controller 3 from data line 1 of $4017
+; $7E0006: Controller 4 from data line 2 of $4017
+; $7E0008: Data line 2 of $4017 while the latch is high, $02 if a multitap is connected
+; $7E0009: $55 when done
+
+.title "SNES-EMU MULTITAP"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    ldx #$09
+clear:
+    stz $00,x
+    dex
+    bpl clear
+
+    ; Let the automatic read run once
+    lda #$01
+    sta $4200       ; NMITIMEN: automatic joypad read
+wait_start:
+    lda $4212       ; HVBJOY: the first frame starts in the vertical blanking period
+    bmi wait_start
+wait_vblank:
+    lda $4212
+    bpl wait_vblank
+wait_active:
+    lda $4212
+    bmi wait_active
+    stz $4200
+    lda $421B
+    sta $00
+    lda $421A
+    sta $01
+    lda $421F
+    sta $02
+    lda $421E
+    sta $03
+
+    ; Select controllers 3 and 4 and read them manually
+    lda #$7F
+    sta $4201       ; WRIO: pin 6 of port 2 low
+    lda #$01
+    sta $4016
+    stz $4016
+    ldx #$10
+read:
+    lda $4017
+    lsr
+    rol $05
+    rol $04
+    lsr
+    rol $07
+    rol $06
+    dex
+    bne read
+
+    lda #$01
+    sta $4016
+    lda $4017
+    and #$02
+    sta $08
+    stz $4016
+
+    lda #$55
+    sta $09
+done:
+    bra done
+
+vector:
+    rti
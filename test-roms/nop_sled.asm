@@ -0,0 +1,25 @@
+; LoRom test ROM which executes NOPs through three 32 KiB banks, so more than 64 Ki distinct
+; instruction addresses are executed before it stops. Used to test the limit of the instruction
+; statistics.
+
+.title "SNES-EMU NOP SLED"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    .dsb $7F00, $EA
+    jml $018000
+
+vector:
+    rti
+
+.org $018000
+    .dsb $7F00, $EA
+    jml $028000
+
+.org $028000
+    .dsb $7F00, $EA
+    stp
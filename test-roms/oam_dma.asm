@@ -0,0 +1,74 @@
+; LoRom test ROM for OAM accesses by DMA. Nine bytes are written to OAMDATA starting at OAMADD
+; $0000: the low table only commits a word with its odd byte, so the ninth byte stays in the latch.
+; Two more bytes go to the high table at OAMADD $0100, where every byte is written directly. The
+; low table is read back through OAMDATAREAD by a B-bus to A-bus DMA, the high table by the CPU.
+;
+; $7E0000: The first ten bytes of OAM, $11 $22 $33 $44 $55 $66 $77 $88 $00 $00
+; $7E000A: The first two bytes of the high table, $AA $BB
+; $7E000C: $55 when done
+
+.title "SNES-EMU OAM DMA"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$20
+    rep #$10
+    lda #$80
+    sta $2100       ; Forced blanking
+    stz $2102
+    stz $2103
+
+    stz $4300       ; A-bus to B-bus, one register
+    lda #$04
+    sta $4301       ; OAMDATA
+    ldx #low_table
+    stx $4302
+    stz $4304
+    ldx #9
+    stx $4305
+    lda #$01
+    sta $420B
+
+    sta $2103       ; OAMADD = $0100
+    ldx #high_table
+    stx $4302
+    ldx #2
+    stx $4305
+    sta $420B
+
+    stz $2103       ; OAMADD = $0000
+    lda #$80
+    sta $4300       ; B-bus to A-bus
+    lda #$38
+    sta $4301       ; OAMDATAREAD
+    ldx #$0000
+    stx $4302
+    lda #$7E
+    sta $4304
+    ldx #10
+    stx $4305
+    lda #$01
+    sta $420B
+
+    sta $2103       ; OAMADD = $0100
+    lda $2138
+    sta $0A
+    lda $2138
+    sta $0B
+
+    lda #$55
+    sta $0C
+done:
+    bra done
+
+vector:
+    rti
+
+low_table:
+    .db $11, $22, $33, $44, $55, $66, $77, $88, $99
+high_table:
+    .db $AA, $BB
@@ -0,0 +1,64 @@
+; LoRom test ROM for the reload of the internal OAM address at the start of VBlank. Two reads of
+; OAMDATAREAD advance the address from OAMADD $0000 to the third byte. With the display enabled,
+; the start of the next VBlank resets it to OAMADD, during forced blank it stays where it is.
+;
+; $7E0000: OAMDATAREAD after VBlank started with the display enabled, $11
+; $7E0001: OAMDATAREAD after VBlank started during forced blank, $33
+; $7E0002: $55 when done
+
+.title "SNES-EMU OAM RELOAD"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    lda #$80
+    sta $2100       ; Forced blanking
+    stz $2102
+    stz $2103
+    lda #$11
+    sta $2104
+    lda #$22
+    sta $2104
+    lda #$33
+    sta $2104
+    lda #$44
+    sta $2104
+
+    lda #$0F
+    sta $2100
+    jsr wait_vblank
+    stz $2102
+    lda $2138
+    lda $2138
+    jsr wait_vblank
+    lda $2138
+    sta $00
+
+    lda #$80
+    sta $2100
+    stz $2102
+    lda $2138
+    lda $2138
+    jsr wait_vblank
+    lda $2138
+    sta $01
+
+    lda #$55
+    sta $02
+done:
+    bra done
+
+; Waits until the next VBlank has started
+wait_vblank:
+    lda $4212
+    bmi wait_vblank
+wait_vblank_start:
+    lda $4212
+    bpl wait_vblank_start
+    rts
+
+vector:
+    rti
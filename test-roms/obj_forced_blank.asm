@@ -0,0 +1,126 @@
+; LoRom test ROM for objects when forced blank is disabled mid-frame. Two rows of white 8x8
+; objects cover lines 96-111 in front of a blue backdrop. Every frame, forced blank is enabled
+; during VBlank and disabled by an H/V IRQ in the HBlank of line 99, after objects for line 100
+; would have been evaluated. Lines before 100 are black, line 100 only shows the backdrop since no
+; objects were evaluated for it, and the objects show up from line 101 on.
+
+.title "SNES-EMU OBJ BLANK"
+.vectors reset, vector
+.vector irq, irq
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$20
+    rep #$10
+    lda #$80
+    sta $2100       ; Forced blanking
+    stz $2101       ; 8x8 objects, name base $0000
+
+    ; Tile 0: Color 1 everywhere
+    lda #$80
+    sta $2115       ; VMAIN: increment after $2119
+    stz $2116
+    stz $2117
+    ldx #0
+tile:
+    lda #$FF
+    sta $2118
+    stz $2119
+    inx
+    cpx #8
+    bne tile
+    ldx #0
+tile_high_planes:
+    stz $2118
+    stz $2119
+    inx
+    cpx #8
+    bne tile_high_planes
+
+    stz $2121
+    stz $2122
+    lda #$50
+    sta $2122       ; Backdrop: blue 20
+    lda #$81
+    sta $2121
+    lda #$FF
+    sta $2122
+    lda #$7F
+    sta $2122       ; Object palette 0, color 1: white
+
+    stz $2102
+    stz $2103
+    ldx #0
+hide:
+    stz $2104       ; X
+    lda #$F0
+    sta $2104       ; Y, below the screen
+    stz $2104       ; Tile
+    stz $2104       ; Attributes
+    inx
+    cpx #128
+    bne hide
+    ldx #0
+high_table:
+    stz $2104
+    inx
+    cpx #32
+    bne high_table
+
+    stz $2102
+    ldx #0
+show:
+    txa
+    and #$03
+    asl a
+    asl a
+    asl a
+    sta $2104       ; X: 0, 8, 16 or 24
+    lda #95
+    cpx #4
+    bcc first_row
+    lda #103
+first_row:
+    sta $2104
+    stz $2104
+    stz $2104
+    inx
+    cpx #8
+    bne show
+
+    lda #$10
+    sta $212C       ; OBJ on the main screen
+
+    lda #$2C
+    sta $4207       ; HTIMEL: 300
+    lda #$01
+    sta $4208
+    lda #99
+    sta $4209       ; VTIMEL
+    stz $420A
+    lda #$30
+    sta $4200       ; NMITIMEN: H/V IRQ
+    cli
+loop:
+    lda $4212
+    bpl loop        ; Wait for VBlank
+    lda #$80
+    sta $2100
+wait_vblank_end:
+    lda $4212
+    bmi wait_vblank_end
+    bra loop
+
+irq:
+    pha
+    lda $4211       ; TIMEUP: acknowledge
+    lda #$0F
+    sta $2100
+    pla
+    rti
+
+vector:
+    rti
@@ -0,0 +1,128 @@
+; LoRom test ROM for offset-per-tile in mode 2. The tile map of BG1 has tile 1 in the even columns
+; of the top 8 rows. BG3 is not displayed, its tile map holds the offset table: H offsets in the
+; first row and V offsets in the second row, each entry applying to the screen column after it.
+; BG1HOFS is 3, the fine scroll is kept for columns with an H offset.
+;
+; - Columns 2 to 8 get increasing V offsets, so they are shorter the further to the right.
+; - Columns 11 to 16 are scrolled to the right by one tile, swapping filled and empty columns.
+; - Column 22 has a V offset that is only enabled for BG2, so BG1 is unaffected.
+
+.title "SNES-EMU OFFSET/TILE"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    lda #$01
+    sta $4300       ; DMAP0: A to B, two registers
+    lda #$18        ; VMDATAL/VMDATAH
+    sta $4301
+    stz $4304
+
+    ; BG1 tile map at $0000
+    ldx #$0000
+    stx $2116
+    ldx #bg1_map
+    stx $4302
+    ldx #$0800
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; BG3 tile map at $0800, only the first two rows are used
+    ldx #$0800
+    stx $2116
+    ldx #bg3_map
+    stx $4302
+    ldx #$0080
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Tile 1 at $1010 has color 1 in every pixel
+    ldx #$1010
+    stx $2116
+    ldx #tile
+    stx $4302
+    ldx #$0020
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Dark blue backdrop, color 1 red
+    stz $2121
+    stz $2122
+    lda #$28
+    sta $2122
+    lda #$1F
+    sta $2122
+    stz $2122
+
+    lda #$02
+    sta $2105       ; BGMODE: mode 2
+    stz $2107       ; BG1SC: tile map at $0000
+    lda #$08
+    sta $2109       ; BG3SC: tile map at $0800
+    lda #$01
+    sta $210B       ; BG12NBA: BG1 tiles at $1000
+    lda #$03
+    sta $210D       ; BG1HOFS = 3
+    stz $210D
+    lda #$01
+    sta $212C       ; TM: BG1
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+bg1_map:
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000, $0001, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+bg3_map:
+    .dw $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $2008, $2008, $2008, $2008, $2008, $2008, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+    .dw $0000, $2004, $2008, $200C, $2010, $2014, $2018, $201C, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $4040, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000, $0000
+tile:
+    .db $FF, $00, $FF, $00, $FF, $00, $FF, $00, $FF, $00, $FF, $00, $FF, $00, $FF, $00
+    .db $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00, $00
@@ -0,0 +1,19 @@
+; LoRom test ROM for open bus reads. $2200 is not mapped to anything, so reading it returns the
+; last value on the data bus, which is the high byte of the operand ($22). The value read is
+; written to $7E0000.
+
+.title "SNES-EMU OPEN BUS"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda $2200
+    sta $0000
+    bra *
+
+vector:
+    rti
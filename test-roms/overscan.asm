@@ -0,0 +1,87 @@
+; LoRom test ROM for the start of the vertical blanking period with and without overscan. The
+; scanline is latched through SLHV and read from OPVCT right after the vblank flag in HVBJOY is set
+; and in the NMI handler.
+;
+; $7E0000: scanline when the vblank flag is set without overscan, low byte, $E1 (225)
+; $7E0001: high bit, $00
+; $7E0002: scanline when the vblank flag is set with overscan, low byte, $F0 (240)
+; $7E0003: high bit, $00
+; $7E0004: scanline of the NMI with overscan, low byte, $F0
+; $7E0005: scanline of the NMI without overscan, low byte, $E1
+; $7E0006: $55 when done
+
+.title "SNES-EMU OVERSCAN"
+.vectors reset, vector
+.vector nmi, nmi
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    stz $04
+    stz $05
+
+    ldx #$00
+    jsr measure
+
+    ; Only enable overscan during the active display, so the frame doesn't end up without vblank
+wait_active1:
+    lda $4212       ; HVBJOY
+    bmi wait_active1
+    lda #$04
+    sta $2133       ; SETINI: overscan
+    ldx #$02
+    jsr measure
+
+    ldx #$04
+    lda $4210       ; RDNMI: clear the flag so enabling NMIs doesn't trigger one right away
+    lda #$80
+    sta $4200       ; NMITIMEN: NMI
+wait_nmi1:
+    lda $04
+    beq wait_nmi1
+
+wait_active2:
+    lda $4212
+    bmi wait_active2
+    stz $2133       ; SETINI: no overscan
+    inx
+wait_nmi2:
+    lda $05
+    beq wait_nmi2
+
+    stz $4200
+    lda #$55
+    sta $06
+loop:
+    bra loop
+
+; Waits for the start of the next vblank period and stores the scanline at $00,x and $01,x
+measure:
+    lda $4212       ; HVBJOY: wait for the active display
+    bmi measure
+wait_vblank:
+    lda $4212
+    bpl wait_vblank
+    lda $2137       ; SLHV: latch the counters
+    lda $213F       ; STAT78: reset the OPVCT byte selector
+    lda $213D       ; OPVCT
+    sta $00,x
+    lda $213D
+    and #$01
+    sta $01,x
+    rts
+
+nmi:
+    pha
+    lda $4210       ; RDNMI: acknowledge
+    lda $2137
+    lda $213F
+    lda $213D
+    sta $00,x
+    lda $213D
+    pla
+vector:
+    rti
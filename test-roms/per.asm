@@ -0,0 +1,39 @@
+; LoRom test ROM for the effective address of PER, which is relative to the following
+; instruction. The address is 16 bits wide and wraps around within the bank.
+;
+; $7E0000: Pushed by PER to the forward label at $9000, $9000
+; $7E0002: Pushed by PER to the backward label at $8000, $8000
+; $7E0004: Pushed by PER $7FFF at $9000, $1002
+; $7E0006: $55 when done
+
+.title "SNES-EMU PER"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    per forward
+    pla
+    sta $00
+    per reset
+    pla
+    sta $02
+    jmp forward
+
+.org $9000
+forward:
+    .db $62, $FF, $7F ; PER $7FFF, which wraps past the end of the bank
+    pla
+    sta $04
+
+    sep #$20
+    lda #$55
+    sta $06
+done:
+    bra done
+
+vector:
+    rti
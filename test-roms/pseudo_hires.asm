@@ -0,0 +1,99 @@
+; LoRom test ROM for pseudo-hires. BG1 is filled with a red tile and only enabled on the main
+; screen, BG2 is filled with a blue tile and only enabled on the sub screen. With pseudo-hires,
+; the even columns of the output show the sub screen and the odd columns the main screen.
+
+.title "SNES-EMU PSEUDO HIRES"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    lda #$08
+    sta $4300       ; DMAP0: A to B, one register, fixed source
+    stz $4304
+
+    ; Clear the high bytes of the tile maps and tiles
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+    lda #$19        ; VMDATAH
+    sta $4301
+    ldx #zero
+    stx $4302
+    ldx #$1010
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; Both tile maps ($0000 and $0400) use tile 1 everywhere
+    stz $2115       ; VMAIN: increment after writing the low byte
+    ldx #$0000
+    stx $2116
+    lda #$18        ; VMDATAL
+    sta $4301
+    ldx #map_entry
+    stx $4302
+    ldx #$0800
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Tile 1 at $1008 has color 1 in every pixel
+    ldx #$1008
+    stx $2116
+    ldx #tile_row
+    stx $4302
+    ldx #$0008
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Green backdrop, color 1 of BG1 red, color 1 of BG2 blue
+    stz $2121
+    lda #$E0
+    sta $2122
+    lda #$03
+    sta $2122
+    lda #$1F
+    sta $2122
+    stz $2122
+    lda #33
+    sta $2121
+    stz $2122
+    lda #$7C
+    sta $2122
+
+    stz $2105       ; BGMODE: mode 0
+    stz $2107       ; BG1SC: tile map at $0000
+    lda #$04
+    sta $2108       ; BG2SC: tile map at $0400
+    lda #$11
+    sta $210B       ; BG12NBA: tiles at $1000
+    lda #$01
+    sta $212C       ; TM: BG1
+    lda #$02
+    sta $212D       ; TS: BG2
+    lda #$08
+    sta $2133       ; SETINI: pseudo-hires
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+zero:
+    .db $00
+map_entry:
+    .db $01
+tile_row:
+    .db $FF
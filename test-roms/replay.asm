@@ -0,0 +1,63 @@
+; LoRom test ROM for deterministic input replay. Counts the frames in which B is held on
+; controller 1 in $7E0000 and shows the count as the red component of the backdrop color.
+
+.title "SNES-EMU REPLAY TEST"
+.vectors reset, vector
+.vector nmi, nmi
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    ldx #$1FFF
+    txs
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+    stz $0000
+
+    ; Copy the initial backdrop color into CGRAM using DMA channel 0
+    stz $2121       ; CGADD
+    stz $4300       ; DMAP0: A to B, one register, increment
+    lda #$22        ; CGDATA
+    sta $4301
+    ldx #palette
+    stx $4302
+    stz $4304
+    ldx #$0002
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+    lda #$81
+    sta $4200       ; NMITIMEN: NMI and automatic joypad read
+
+loop:
+    wai
+    bra loop
+
+nmi:
+    lda $4210       ; RDNMI: acknowledge the NMI
+wait:
+    lda $4212       ; HVBJOY: wait for the automatic joypad read to finish
+    and #$01
+    bne wait
+    lda $4219       ; JOY1H: bit 7 is B
+    bpl skip
+    inc $0000
+skip:
+    stz $2121
+    lda $0000
+    asl
+    asl
+    sta $2122
+    stz $2122
+vector:
+    rti
+
+palette:
+    .dw $7C00
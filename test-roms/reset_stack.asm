@@ -0,0 +1,56 @@
+; LoRom test ROM for the stack accesses during reset. The first boot switches to native mode, sets
+; S to $01F0, fills $01E8-$01F0 with $AA and waits for the test to reset the console. Reset
+; decrements S by three like an interrupt, but it reads from the stack instead of pushing PC and P,
+; so the second boot finds the fill intact.
+;
+; $7E0000: $A5 after the first boot
+; $7E0001: S after the reset (low byte), should be $ED
+; $7E0002: $01EE, should be $AA
+; $7E0003: $01EF, should be $AA
+; $7E0004: $01F0, should be $AA
+; $7E0005: $55 when done
+
+.title "SNES-EMU RESET STACK"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    lda $0000
+    cmp #$A5
+    beq after_reset
+
+    clc
+    xce
+    rep #$30
+    ldx #$01F0
+    txs
+    lda #$AAAA
+    sta $01E8
+    sta $01EA
+    sta $01EC
+    sta $01EE
+    sta $01F0       ; Also writes $01F1, which is above the stack
+    sep #$20
+    lda #$A5
+    sta $0000
+wait_reset:
+    bra wait_reset
+
+after_reset:
+    ; Still in emulation mode with 8 bit registers, nothing may be pushed here
+    tsx
+    stx $0001
+    lda $01EE
+    sta $0002
+    lda $01EF
+    sta $0003
+    lda $01F0
+    sta $0004
+    lda #$55
+    sta $0005
+done:
+    bra done
+
+vector:
+    rti
@@ -0,0 +1,46 @@
+; LoRom test ROM for the mirroring of ROMs whose size isn't a power of two. The image is 96 KiB,
+; three 32 KiB banks, i.e. a scaled down 24 Mbit ROM. It has to appear as banks 0-2 followed by
+; bank 2 again, and all four of them are mirrored in banks 4-7. The byte at $F000 of each bank is
+; $A0 plus the number of the bank in the image.
+;
+; $7E0000: $03F000, should be $A2
+; $7E0001: $04F000, should be $A0
+; $7E0002: $05F000, should be $A1
+; $7E0003: $06F000, should be $A2
+; $7E0004: $07F000, should be $A2
+; $7E0005: $55 when done
+
+.title "SNES-EMU ROM MIRROR"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda $03F000
+    sta $00
+    lda $04F000
+    sta $01
+    lda $05F000
+    sta $02
+    lda $06F000
+    sta $03
+    lda $07F000
+    sta $04
+    lda #$55
+    sta $05
+done:
+    bra done
+
+vector:
+    rti
+
+; The rest of each bank is filled with zeros
+.org $00F000
+    .db $A0
+.org $01F000
+    .db $A1
+.org $02F000
+    .db $A2
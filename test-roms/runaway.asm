@@ -0,0 +1,16 @@
+; LoRom test ROM which never does anything but loop, for checking that the instruction budget of
+; the test runner stops it.
+
+.title "SNES-EMU RUNAWAY"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+loop:
+    bra loop
+
+vector:
+    rti
@@ -0,0 +1,167 @@
+; LoRom test ROM for the write latches of the scroll registers. Writes $2D to BG1HOFS, $13 to
+; BG2HOFS and $01 to BG1HOFS again, after setting up the Mode 7 matrix, which leaves $00 in the
+; Mode 7 latch:
+;
+; - BG1HOFS = $01 << 8 | ($13 & ~7) | ($2D & 7) = $0115, the BG latch is shared by all BGs
+; - M7HOFS = $01 << 8 | $2D = $012D, the Mode 7 latch only sees the writes to $210D
+;
+; BG1 is shown in Mode 0, or in Mode 7 if B is held on the controller in port 1. In both modes
+; tile n of the tilemap row is followed by tile n + 1 (wrapping after 8 tiles) and has a white
+; line in column n, so the lines repeat every 64 pixels and show the scroll offset.
+;
+; $7E0000: $55 when done
+
+.title "SNES-EMU SCROLL LATCH"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    ; Mode 7 tilemap in the low bytes, every entry is its column & 7
+    stz $2115       ; VMAIN: increment after writing the low byte
+    stz $2116
+    stz $2117
+    ldx #$0000
+m7_map:
+    txa
+    and #$07
+    sta $2118
+    inx
+    cpx #$4000
+    bne m7_map
+
+    ; Mode 7 tiles 0-7 in the high bytes
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    stz $2116
+    stz $2117
+    ldx #$0000
+m7_tiles:
+    rep #$20
+    txa
+    lsr
+    lsr
+    lsr
+    lsr
+    lsr
+    lsr
+    sep #$20
+    sta $10         ; Tile number
+    txa
+    and #$07        ; Column
+    cmp $10
+    beq m7_set
+    lda #$00
+    bra m7_store
+m7_set:
+    lda #$01
+m7_store:
+    sta $2119
+    inx
+    cpx #$0200
+    bne m7_tiles
+
+    ; BG1 tilemap at $4000
+    stz $2116
+    lda #$40
+    sta $2117
+    ldx #$0000
+bg_map:
+    txa
+    and #$07
+    sta $2118
+    stz $2119
+    inx
+    cpx #$0400
+    bne bg_map
+
+    ; BG1 tiles (2 bpp) at $5000
+    stz $2116
+    lda #$50
+    sta $2117
+    ldx #$0000
+bg_tiles:
+    rep #$20
+    txa
+    lsr
+    lsr
+    lsr
+    tay
+    sep #$20
+    lda columns,y
+    sta $2118
+    stz $2119
+    inx
+    cpx #$0040
+    bne bg_tiles
+
+    ; Black and white
+    stz $2121
+    stz $2122
+    stz $2122
+    lda #$FF
+    sta $2122
+    lda #$7F
+    sta $2122
+
+    lda #$40
+    sta $2107       ; BG1SC: tilemap at $4000
+    lda #$05
+    sta $210B       ; BG12NBA: BG1 tiles at $5000
+    lda #$01
+    sta $212C       ; TM: BG1
+    stz $211A       ; M7SEL
+
+    ; Identity matrix, centered at 0
+    stz $211B
+    lda #$01
+    sta $211B
+    stz $211C
+    stz $211C
+    stz $211D
+    stz $211D
+    stz $211E
+    lda #$01
+    sta $211E
+    stz $211F
+    stz $211F
+    stz $2120
+    stz $2120
+
+    lda #$2D
+    sta $210D       ; BG1HOFS
+    lda #$13
+    sta $210F       ; BG2HOFS
+    lda #$01
+    sta $210D       ; BG1HOFS
+
+    ; Mode 7 if B is held, otherwise Mode 0
+    lda #$01
+    sta $4016
+    stz $4016
+    lda $4016
+    and #$01
+    beq set_mode
+    lda #$07
+set_mode:
+    sta $2105       ; BGMODE
+    lda #$0F
+    sta $2100
+
+    lda #$55
+    sta $00
+done:
+    bra done
+
+vector:
+    rti
+
+columns:
+    .db $80, $40, $20, $10, $08, $04, $02, $01
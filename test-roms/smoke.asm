@@ -0,0 +1,43 @@
+; Minimal LoRom test ROM checking basic CPU arithmetic and the hardware multiplier.
+; Writes $55 to $7E0000 on success and $FF on failure.
+
+.title "SNES-EMU SMOKE TEST"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    lda #$1234
+    clc
+    adc #$4321
+    cmp #$5555
+    bne fail
+
+    sep #$30
+    lda #$12
+    sta $4202       ; WRMPYA
+    lda #$34
+    sta $4203       ; WRMPYB
+    nop             ; the multiplication takes 8 cycles
+    nop
+    nop
+    nop
+    lda $4216       ; RDMPYL
+    cmp #$A8
+    bne fail
+
+    lda #$55
+    sta $7E0000
+    bra *
+
+fail:
+    sep #$30
+    lda #$FF
+    sta $7E0000
+    bra *
+
+vector:
+    rti
@@ -0,0 +1,83 @@
+; LoRom test ROM for the SPC700 timers. Uploads an SPC700 program through the IPL boot ROM, which
+; sets the divider of timer 0 to 2, so that its output counts every 256 cycles:
+;
+; 1. The timer is stopped for a while, its output has to stay 0.
+; 2. The timer is started and read exactly 768 cycles after clearing its output, which has to be
+;    3 regardless of the phase of the 8 kHz clock.
+; 3. The timer is stopped exactly 512 cycles after the last read and read a while later. Stopping
+;    a timer keeps its output, so it has to be 2.
+;
+; $7E0000: T0OUT while stopped, $00
+; $7E0001: T0OUT after 768 cycles, $03
+; $7E0002: T0OUT after 512 cycles and stopping, $02
+; $7E0003: $55 when done
+
+.title "SNES-EMU SPC TIMERS"
+.vectors reset, vector
+
+SPC_BASE = $0200
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+.include "ipl_upload.inc"
+
+wait_done:
+    lda $2143
+    cmp #$55
+    bne wait_done
+    lda $2140
+    sta $00
+    lda $2141
+    sta $01
+    lda $2142
+    sta $02
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
+
+; SPC700 program, uploaded to $0200. Cycle counts are noted for the measured sections.
+program:
+.spc700 SPC_BASE
+spc_entry:
+    mov $FA, #$02   ; T0DIV
+    mov $F1, #$00   ; CONTROL: all timers stopped
+    mov x, #$00
+delay1:
+    dec x
+    bne delay1
+    mov a, $FD      ; T0OUT
+    mov $F4, a
+
+    mov $F1, #$01   ; CONTROL: start timer 0
+    mov a, $FD      ; Clear T0OUT
+    mov x, #$7F     ; 2
+delay2:
+    dec x           ; 2
+    bne delay2      ; 4 when taken, 2 otherwise
+    mov a, $10      ; 3
+    mov a, $FD      ; 3, read T0OUT
+    mov $F5, a      ; 4
+
+    mov x, #$53     ; 2
+delay3:
+    dec x           ; 2
+    bne delay3      ; 4 when taken, 2 otherwise
+    mov $10, #$00   ; 5
+    mov $F1, #$00   ; 5, stop timer 0
+    mov x, #$00
+delay4:
+    dec x
+    bne delay4
+    mov a, $FD      ; T0OUT
+    mov $F6, a
+    mov $F7, #$55
+spc_done:
+    bra spc_done
+spc_end:
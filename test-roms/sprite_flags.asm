@@ -0,0 +1,113 @@
+; LoRom test ROM for the range over and time over flags in STAT77. Objects 0-31 are placed on
+; line $20 first, which is exactly at the limit. Object 32 then exceeds the 32 objects per line and
+; sets the range over flag. Making objects 0-19 16x16 finally needs 52 tiles for the first 32
+; objects, more than the 34 which can be fetched per line, so the time over flag is set as well.
+; The flags are the same whether the sprite limits are disabled or not.
+;
+; $7E0000: STAT77 & $C0 with 32 8x8 objects on the line, $00
+; $7E0001: STAT77 & $C0 with 33 8x8 objects on the line, $40
+; $7E0002: STAT77 & $C0 with 20 16x16 and 13 8x8 objects on the line, $C0
+; $7E0003: $55 when done
+
+.title "SNES-EMU SPRITE FLAGS"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$20
+    rep #$10
+    lda #$80
+    sta $2100       ; Forced blanking
+    stz $2101       ; 8x8 and 16x16 objects
+    lda #$10
+    sta $212C       ; OBJ on the main screen
+
+    stz $2102
+    stz $2103
+    ldx #0
+hide:
+    stz $2104       ; X
+    lda #$F0
+    sta $2104       ; Y, below the screen
+    stz $2104       ; Tile
+    stz $2104       ; Attributes
+    inx
+    cpx #128
+    bne hide
+    ldx #0
+high_table:
+    stz $2104
+    inx
+    cpx #32
+    bne high_table
+
+    stz $2102
+    ldx #0
+show:
+    stz $2104
+    lda #$20
+    sta $2104
+    stz $2104
+    stz $2104
+    inx
+    cpx #32
+    bne show
+    jsr render_frame
+    sta $00
+
+    lda #$80
+    sta $2100
+    lda #$40
+    sta $2102       ; Object 32
+    stz $2103
+    stz $2104
+    lda #$20
+    sta $2104
+    stz $2104
+    stz $2104
+    jsr render_frame
+    sta $01
+
+    lda #$80
+    sta $2100
+    stz $2102
+    lda #$01
+    sta $2103       ; High table
+    lda #$AA        ; Four large objects
+    ldx #0
+large:
+    sta $2104
+    inx
+    cpx #5
+    bne large
+    jsr render_frame
+    sta $02
+
+    lda #$55
+    sta $03
+done:
+    bra done
+
+; Renders two frames with the display enabled and returns the flags of the last one in A
+render_frame:
+    lda #$0F
+    sta $2100
+    jsr wait_vblank
+    jsr wait_vblank
+    lda $213E
+    and #$C0
+    rts
+
+wait_vblank:
+    lda $4212
+    bmi wait_vblank
+wait_vblank_start:
+    lda $4212
+    bpl wait_vblank_start
+    rts
+
+vector:
+    rti
@@ -0,0 +1,39 @@
+; LoRom test ROM for the mirroring of a 2 KiB SRAM across its address window in banks $70-$7D.
+; Bytes written to the start and end of the SRAM are read back through mirrors at $700800 and
+; $717FFF, and a write through the mirror at $700801 is read back at $700001.
+;
+; $7E0000: $700800, should be $11
+; $7E0001: $717FFF, should be $22
+; $7E0002: $700001, should be $33
+; $7E0003: $55 when done
+
+.title "SNES-EMU SRAM MIRROR"
+.sram_size $01
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda #$11
+    sta $700000
+    lda #$22
+    sta $7007FF
+    lda #$33
+    sta $700801
+
+    lda $700800
+    sta $00
+    lda $717FFF
+    sta $01
+    lda $700001
+    sta $02
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
@@ -0,0 +1,70 @@
+; LoRom test ROM for the S-RTC real-time clock, declared by the chipset byte $55 in the header.
+; Sets the clock to 1999-12-31 23:59:59, waits 75 frames and reads the time back, which has to
+; have rolled over to 2000-01-01 00:00:00, a Saturday. The digits are read from $2800, starting
+; and ending with the marker $0F:
+;
+; $7E0000: $0F
+; $7E0001-$7E0002: second, $00 $00
+; $7E0003-$7E0004: minute, $00 $00
+; $7E0005-$7E0006: hour, $00 $00
+; $7E0007-$7E0008: day, $01 $00
+; $7E0009: month, $01
+; $7E000A-$7E000C: year - 1000, $00 $00 $0A
+; $7E000D: day of the week, $06
+; $7E000E: $0F
+; $7E000F: $55 when done
+
+.title "SNES-EMU S-RTC"
+.vectors reset, vector
+.chipset $55
+.sram_size $01
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+
+    lda #$0E        ; Command
+    sta $2801
+    lda #$00        ; Write the time
+    sta $2801
+    ldx #$0000
+write_digit:
+    lda time,x
+    sta $2801
+    inx
+    cpx #$000C
+    bne write_digit
+
+    ldy #$004B      ; Wait for 75 frames
+wait_vblank:
+    lda $4212       ; HVBJOY
+    bpl wait_vblank
+wait_end:
+    lda $4212
+    bmi wait_end
+    dey
+    bne wait_vblank
+
+    lda #$0D        ; Read the time
+    sta $2801
+    ldx #$0000
+read_digit:
+    lda $2800
+    sta $00,x
+    inx
+    cpx #$000F
+    bne read_digit
+    lda #$55
+    sta $0F
+done:
+    bra done
+
+vector:
+    rti
+
+time:               ; Least significant digit first, the month is a single digit
+    .db $09, $05, $09, $05, $03, $02, $01, $03, $0C, $09, $09, $09
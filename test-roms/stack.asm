@@ -0,0 +1,61 @@
+; LoRom test ROM for pushing and pulling the accumulator. In native mode, a 16 bit PHA/PLA may
+; cross from page 1 into page 0, in emulation mode the stack pointer wraps around within page 1.
+; Writes $55 to $7E0000 on success and $FF on failure.
+
+.title "SNES-EMU STACK TEST"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    ldx #$0100
+    txs
+
+    ; Native mode: the high byte goes to $0100, the low byte to $00FF
+    lda #$1234
+    pha
+    lda $00FF
+    cmp #$1234
+    bne fail
+    lda #$0000
+    pla
+    cmp #$1234
+    bne fail
+    tsc
+    cmp #$0100
+    bne fail
+
+    ; Emulation mode: pushing at $0100 wraps around to $01FF
+    sec
+    xce
+    lda #$AB
+    pha
+    tsx
+    cpx #$FF
+    bne fail
+    lda $0100
+    cmp #$AB
+    bne fail
+    lda #$00
+    pla
+    cmp #$AB
+    bne fail
+    tsx
+    bne fail
+
+    lda #$55
+    sta $0000
+    bra *
+
+fail:
+    sec
+    xce
+    lda #$FF
+    sta $0000
+    bra *
+
+vector:
+    rti
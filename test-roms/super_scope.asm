@@ -0,0 +1,72 @@
+; LoRom test ROM for a Super Scope in port 2, aimed at pixel (100, 50) with the trigger pulled. The
+; buttons are read manually during vblank, which also tells the Super Scope where it is aimed. In
+; the next frame, the beam passing that pixel latches the H/V counters through pin 6 of port 2.
+;
+; $7E0000: Low byte of the serial data, $01 (trigger)
+; $7E0001: High byte of the serial data, $FF (Super Scope signature)
+; $7E0002: Counter latch flag of STAT78, $40
+; $7E0003: OPHCT, $7A $00 (H = 22 + 100)
+; $7E0005: OPVCT, $33 $00 (V = 1 + 50)
+; $7E0007: $55 when done
+
+.title "SNES-EMU SUPER SCOPE"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    ldx #$07
+clear:
+    stz $00,x
+    dex
+    bpl clear
+
+    ; The Super Scope only sees the beam if the screen isn't force blanked
+    lda #$0F
+    sta $2100       ; INIDISP
+
+    jsr wait_vblank
+    lda #$01
+    sta $4016
+    stz $4016
+    ldx #$10
+read:
+    lda $4017
+    lsr
+    ror $01
+    ror $00
+    dex
+    bne read
+    lda $213F       ; STAT78: clear the latch flag
+
+    jsr wait_vblank
+    lda $213F
+    and #$40
+    sta $02
+    lda $213C       ; OPHCT
+    sta $03
+    lda $213C
+    sta $04
+    lda $213D       ; OPVCT
+    sta $05
+    lda $213D
+    sta $06
+
+    lda #$55
+    sta $07
+done:
+    bra done
+
+wait_vblank:
+    lda $4212       ; HVBJOY
+    bmi wait_vblank
+wait_vblank_start:
+    lda $4212
+    bpl wait_vblank_start
+    rts
+
+vector:
+    rti
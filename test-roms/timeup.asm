@@ -0,0 +1,65 @@
+; LoRom test ROM for the TIMEUP flag. Waits for a V-IRQ by polling $4211, then lets the IRQ fire
+; once more. The IRQ handler disables H/V IRQs, which must also clear the flag, so neither the
+; handler nor the main program read it as set afterwards and the IRQ doesn't fire again.
+;
+; $7E0000: $4211 when the polled IRQ was seen, $80
+; $7E0001: Number of IRQs taken, 1
+; $7E0002: $4211 read in the handler after disabling IRQs, $00
+; $7E0003: $4211 read after returning from the handler, $00
+; $7E0004: $55 after waiting two more frames
+
+.title "SNES-EMU TIMEUP TEST"
+.vectors reset, vector
+.vector irq, irq
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    stz $0000
+    stz $0001
+    stz $0002
+    stz $0003
+    stz $0004
+
+    lda #$10
+    sta $4209       ; VTIMEL
+    stz $420A       ; VTIMEH
+    lda #$20
+    sta $4200       ; NMITIMEN: V-IRQ
+
+poll:
+    lda $4211       ; TIMEUP: acknowledges the IRQ
+    bpl poll
+    sta $0000
+
+    cli
+    wai
+    lda $4211
+    sta $0003
+
+    ldx #$02
+frame:
+wait_vblank:
+    lda $4212       ; HVBJOY
+    bpl wait_vblank
+wait_active:
+    lda $4212
+    bmi wait_active
+    dex
+    bne frame
+
+    lda #$55
+    sta $0004
+done:
+    bra done
+
+irq:
+    inc $0001
+    stz $4200       ; NMITIMEN: disable IRQs, this also clears TIMEUP
+    lda $4211
+    sta $0002
+vector:
+    rti
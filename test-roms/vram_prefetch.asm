@@ -0,0 +1,67 @@
+; LoRom test ROM for writes to VMADD and the VRAM read prefetch. Every write to either half of
+; VMADD fills the read latch from the combined address, so the first read of VMDATA afterwards
+; already returns the word at that address. Reads return the latch and fill it again before the
+; address is incremented, so the second read returns the same word and the third one the next.
+;
+; $7E0000: VMDATA at VMADD $1200 after writing the low half $34, $EF $BE
+; $7E0002: The second VMDATA read, $EF $BE
+; $7E0004: The third VMDATA read, $FE $CA
+; $7E0006: VMDATA at VMADD $0034 after writing the high half $12, $EF $BE
+; $7E0008: Three reads of VMDATAL with the increment after the low byte, $EF $EF $FE
+; $7E000B: $55 when done
+
+.title "SNES-EMU VMADD"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$20
+    rep #$10
+    lda #$80
+    sta $2100       ; Forced blanking
+    sta $2115       ; Increment after VMDATAH
+    ldx #$1234
+    stx $2116
+    ldx #$BEEF
+    stx $2118
+    ldx #$CAFE
+    stx $2118
+
+    ldx #$1200
+    stx $2116
+    lda #$34
+    sta $2116       ; VMADD = $1234
+    ldx $2139
+    stx $00
+    ldx $2139
+    stx $02
+    ldx $2139
+    stx $04
+
+    ldx #$0034
+    stx $2116
+    lda #$12
+    sta $2117       ; VMADD = $1234
+    ldx $2139
+    stx $06
+
+    stz $2115       ; Increment after VMDATAL
+    ldx #$1234
+    stx $2116
+    lda $2139
+    sta $08
+    lda $2139
+    sta $09
+    lda $2139
+    sta $0A
+
+    lda #$55
+    sta $0B
+done:
+    bra done
+
+vector:
+    rti
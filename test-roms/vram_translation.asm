@@ -0,0 +1,90 @@
+; LoRom test ROM for the address translation of VMAIN. The first 32 2bpp tiles are written through
+; the 8 bit translation at VMADD $E000, which mirrors the BG1 tiles at $6000. The translation turns
+; the address of the n-th write `YYYxxxxx` into `xxxxxYYY`, so consecutive writes fill the same
+; row of consecutive tiles. Every row of tile 1 is written, with fewer pixels set in each row.
+; BG1 uses tile 1 everywhere, so every 8x8 block of the screen shows a white staircase, which is
+; missing or scrambled if the writes end up at the wrong addresses.
+
+.title "SNES-EMU VMAIN TRANS"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    ; The tile map at $0000 uses tile 1 everywhere
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+bg1_map:
+    lda #$01
+    sta $2118
+    stz $2119
+    inx
+    cpx #$0400
+    bne bg1_map
+
+    ; Rows of the tiles at $6000 through the 8 bit translation
+    lda #$84
+    sta $2115       ; VMAIN: increment after writing the high byte, 8 bit translation
+    ldx #$E000
+    stx $2116
+    ldx #$0000
+tiles:
+    txa
+    and #$1F
+    cmp #$01
+    bne empty_row
+    rep #$20
+    txa
+    lsr a
+    lsr a
+    lsr a
+    lsr a
+    lsr a
+    tay
+    sep #$20
+    lda staircase,y
+    bra write_row
+empty_row:
+    lda #$00
+write_row:
+    sta $2118
+    stz $2119
+    inx
+    cpx #$0100
+    bne tiles
+
+    ; Black backdrop, color 1 white
+    stz $2121
+    stz $2122
+    stz $2122
+    lda #$FF
+    sta $2122
+    lda #$7F
+    sta $2122
+
+    stz $2105       ; BGMODE: mode 0
+    stz $2107       ; BG1SC: tile map at $0000
+    lda #$06
+    sta $210B       ; BG12NBA: BG1 tiles at $6000
+    lda #$01
+    sta $212C       ; TM: BG1
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+staircase:
+    .db $FF, $7F, $3F, $1F, $0F, $07, $03, $01
@@ -0,0 +1,43 @@
+; LoRom test ROM for WAI with interrupts disabled in native mode. An IRQ ends WAI even though I is
+; set, but it isn't serviced: execution continues after WAI and the IRQ stays pending in TIMEUP.
+;
+; $7E0000: $01 once WAI returned
+; $7E0001: $FF if the IRQ handler ran, $00
+; $7E0002: TIMEUP & $80 after WAI, $80
+; $7E0003: $55 when done
+
+.title "SNES-EMU WAI MASKED"
+.vectors reset, vector
+.vector irq, irq
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+    lda #$10
+    sta $4209       ; VTIME = 16
+    stz $420A
+    lda #$20
+    sta $4200       ; V-IRQ
+    wai
+    lda #$01
+    sta $00
+    lda $4211
+    and #$80
+    sta $02
+
+    lda #$55
+    sta $03
+done:
+    bra done
+
+irq:
+    lda #$FF
+    sta $01
+    lda $4211
+    rti
+
+vector:
+    rti
@@ -0,0 +1,49 @@
+; LoRom test ROM for watchpoints. Copies 16 bytes ($A5 to $B4) to $7E0020 by DMA through WMDATA,
+; reads the first one back with the CPU and writes $42 to $0010. The tests put watchpoints on these
+; accesses and expect emulation to stop at the right one:
+;
+; - A write watchpoint on $0010 stops at the STA with $42. WMDATA is watched as well, which is only
+;   written by DMA and must not stop emulation.
+; - A read watchpoint on $0020-$002F stops at the LDA with $A5.
+; - With DMA included, a write watchpoint on WMDATA stops at the first byte of the DMA, $A5.
+;
+; $7E0000: $55 when done, only reached if no watchpoint was hit
+
+.title "SNES-EMU WATCHPOINTS"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+
+    ldx #$0020
+    stx $2181       ; WMADD: $7E0020
+    stz $2183
+    stz $4300       ; DMAP0: A to B, one register
+    lda #$80
+    sta $4301       ; WMDATA
+    ldx #data
+    stx $4302
+    stz $4304
+    ldx #$0010
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    lda $0020
+    lda #$42
+    sta $0010
+    lda #$55
+    sta $0000
+done:
+    bra done
+
+vector:
+    rti
+
+data:
+    .db $A5, $A6, $A7, $A8, $A9, $AA, $AB, $AC, $AD, $AE, $AF, $B0, $B1, $B2, $B3, $B4
@@ -0,0 +1,276 @@
+; LoRom test ROM for the window selection registers W12SEL, W34SEL and WOBJSEL. Mode 0 with every
+; layer on the main screen, each one only visible in its own horizontal band:
+;
+;   y =   0..=31   BG1 red      only W1 enabled: masked at x = 64..=127
+;   y =  32..=63   BG2 green    only W1 enabled and inverted, the AND logic is ignored: masked at
+;                               x = 0..=63 and 128..=255
+;   y =  64..=95   BG3 blue     both windows inverted but disabled: never masked
+;   y =  96..=127  BG4 yellow   W1 inverted AND W2: masked at x = 128..=191
+;   y = 128..=159  OBJ white    W1 XOR inverted W2: masked at x = 0..=63, 96..=127 and 192..=255
+;
+; Window 1 covers x = 64..=127 and window 2 x = 96..=191. The backdrop is grey and shows through
+; wherever a layer is masked. Color math adds the fixed color (blue 31) to the backdrop inside the
+; color window, W1 OR W2, so the backdrop at x = 64..=191 is light blue.
+
+.title "SNES-EMU WINDOWS"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$10
+    sep #$20
+    lda #$80
+    sta $2100       ; INIDISP: forced blank
+
+    ; BG tiles at $0000: tile 0 and everything after it is solid color 3
+    lda #$80
+    sta $2115       ; VMAIN: increment after writing the high byte
+    ldx #$0000
+    stx $2116
+    lda #$09
+    sta $4300       ; DMAP0: A to B, two registers, fixed source
+    lda #$18
+    sta $4301       ; VMDATAL
+    ldx #ones
+    stx $4302
+    stz $4304
+    ldx #$2000
+    stx $4305
+    lda #$01
+    sta $420B       ; MDMAEN
+
+    ; Tile 1 is transparent
+    ldx #$0008
+    stx $2116
+    lda #$09
+    sta $4300
+    lda #$18
+    sta $4301
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$0010
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Tile maps at $1000, $1400, $1800 and $1C00 use tile 1 everywhere
+    ldx #$1000
+    stx $2116
+    lda #$08
+    sta $4300       ; DMAP0: A to B, one register, fixed source
+    lda #$19
+    sta $4301       ; VMDATAH
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$1000
+    stx $4305
+    lda #$01
+    sta $420B
+    stz $2115       ; VMAIN: increment after writing the low byte
+    ldx #$1000
+    stx $2116
+    lda #$08
+    sta $4300
+    lda #$18
+    sta $4301       ; VMDATAL
+    ldx #one
+    stx $4302
+    stz $4304
+    ldx #$1000
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Four rows of tile 0 in each tile map, BG1 in rows 0-3, BG2 in rows 4-7 and so on
+    ldx #$1000
+    stx $2116
+    lda #$08
+    sta $4300
+    lda #$18
+    sta $4301
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$0080
+    stx $4305
+    lda #$01
+    sta $420B
+    ldx #$1480
+    stx $2116
+    lda #$08
+    sta $4300
+    lda #$18
+    sta $4301
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$0080
+    stx $4305
+    lda #$01
+    sta $420B
+    ldx #$1900
+    stx $2116
+    lda #$08
+    sta $4300
+    lda #$18
+    sta $4301
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$0080
+    stx $4305
+    lda #$01
+    sta $420B
+    ldx #$1D80
+    stx $2116
+    lda #$08
+    sta $4300
+    lda #$18
+    sta $4301
+    ldx #zero
+    stx $4302
+    stz $4304
+    ldx #$0080
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Solid object tiles at $6000
+    lda #$80
+    sta $2115
+    ldx #$6000
+    stx $2116
+    lda #$09
+    sta $4300
+    lda #$18
+    sta $4301
+    ldx #ones
+    stx $4302
+    stz $4304
+    ldx #$4000
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; OAM
+    ldx #$0000
+    stx $2102       ; OAMADD
+    stz $4300       ; DMAP0: A to B, one register
+    lda #$04
+    sta $4301       ; OAMDATA
+    ldx #oam
+    stx $4302
+    stz $4304
+    ldx #$0220
+    stx $4305
+    lda #$01
+    sta $420B
+
+    ; Grey backdrop, color 3 of the first palette of every BG and color 15 of the first object
+    ; palette
+    stz $2121
+    lda #$08
+    sta $2122
+    lda #$21
+    sta $2122
+    lda #$03
+    sta $2121
+    lda #$1F
+    sta $2122       ; red
+    stz $2122
+    lda #$23
+    sta $2121
+    lda #$E0
+    sta $2122       ; green
+    lda #$03
+    sta $2122
+    lda #$43
+    sta $2121
+    stz $2122       ; blue
+    lda #$7C
+    sta $2122
+    lda #$63
+    sta $2121
+    lda #$FF
+    sta $2122       ; yellow
+    lda #$03
+    sta $2122
+    lda #$8F
+    sta $2121
+    lda #$FF
+    sta $2122       ; white
+    lda #$7F
+    sta $2122
+
+    stz $2105       ; BGMODE: mode 0
+    lda #$10
+    sta $2107       ; BG1SC: tile map at $1000
+    lda #$14
+    sta $2108       ; BG2SC: tile map at $1400
+    lda #$18
+    sta $2109       ; BG3SC: tile map at $1800
+    lda #$1C
+    sta $210A       ; BG4SC: tile map at $1C00
+    stz $210B       ; BG12NBA: tiles at $0000
+    stz $210C       ; BG34NBA
+    lda #$A3
+    sta $2101       ; OBSEL: 32x32 objects at $6000
+
+    lda #$32
+    sta $2123       ; W12SEL: BG1 W1, BG2 W1 inverted
+    lda #$B5
+    sta $2124       ; W34SEL: BG3 W1 and W2 inverted but disabled, BG4 W1 inverted and W2
+    lda #$AE
+    sta $2125       ; WOBJSEL: OBJ W1 and W2 inverted, color window W1 and W2
+    lda #$40
+    sta $2126       ; WH0: window 1 covers x = 64..=127
+    lda #$7F
+    sta $2127       ; WH1
+    lda #$60
+    sta $2128       ; WH2: window 2 covers x = 96..=191
+    lda #$BF
+    sta $2129       ; WH3
+    lda #$44
+    sta $212A       ; WBGLOG: BG2 and BG4 AND
+    lda #$02
+    sta $212B       ; WOBJLOG: OBJ XOR, color window OR
+    lda #$1F
+    sta $212C       ; TM: all layers
+    lda #$1F
+    sta $212E       ; TMW: window masking for all layers
+    lda #$10
+    sta $2130       ; CGWSEL: color math only inside the color window
+    lda #$20
+    sta $2131       ; CGADSUB: add, enable on the backdrop
+    lda #$9F
+    sta $2132       ; COLDATA: blue 31
+    lda #$0F
+    sta $2100       ; INIDISP: full brightness
+
+loop:
+    bra loop
+
+vector:
+    rti
+
+zero:
+    .db $00
+one:
+    .db $01
+ones:
+    .db $FF
+oam:
+    ; Eight objects in a row at y = 127, the first line they show up on is 128
+    .db $00, $7F, $00, $30, $20, $7F, $00, $30, $40, $7F, $00, $30, $60, $7F, $00, $30
+    .db $80, $7F, $00, $30, $A0, $7F, $00, $30, $C0, $7F, $00, $30, $E0, $7F, $00, $30
+    ; The remaining 120 objects are off screen
+    .rept 120
+    .db $00, $E1, $00, $00
+    .endr
+    ; High table: small objects, X below 256
+    .dsb 32, $00
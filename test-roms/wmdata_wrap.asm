@@ -0,0 +1,52 @@
+; LoRom test ROM for the wrapping of the WRAM port address. Sets WMADD to $1FFFE, with the high
+; bit through $2183, and writes $11, $22 and $33 through WMDATA. The address wraps at $20000, so
+; the third byte ends up at the start of WRAM. Reading from $1FFFF wraps around the same way.
+;
+; $7FFFFE: $11
+; $7FFFFF: $22
+; $7E0000: $33, written through WMDATA
+; $7E0001: Read from $1FFFF, should be $22
+; $7E0002: Read after wrapping, should be $33
+; $7E0003: $55 when done
+
+.title "SNES-EMU WMDATA WRAP"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    sep #$30
+
+    lda #$FE
+    sta $2181       ; WMADDL
+    lda #$FF
+    sta $2182       ; WMADDM
+    lda #$01
+    sta $2183       ; WMADDH
+    lda #$11
+    sta $2180       ; WMDATA
+    lda #$22
+    sta $2180
+    lda #$33
+    sta $2180
+
+    ; WMADD is $00001 now, all three bytes have to be set again
+    lda #$FF
+    sta $2181
+    sta $2182
+    lda #$01
+    sta $2183       ; WMADD: $1FFFF
+    lda $2180
+    sta $01
+    lda $2180
+    sta $02
+
+    lda #$55
+    sta $03
+done:
+    bra done
+
+vector:
+    rti
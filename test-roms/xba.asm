@@ -0,0 +1,57 @@
+; LoRom test ROM for XBA with an 8 bit accumulator. XBA always swaps both bytes of A and sets N
+; and Z from the new low byte, regardless of the m flag.
+;
+; $7E0000: A after swapping $1234, $12
+; $7E0001: A after swapping back, $34
+; $7E0002: N and Z after swapping $1234, both clear
+; $7E0003: N and Z after swapping $8000, N set
+; $7E0004: N and Z after swapping $00FF, Z set
+; $7E0005: $55 when done
+
+.title "SNES-EMU XBA TEST"
+.vectors reset, vector
+
+.org $8000
+reset:
+    sei
+    clc
+    xce
+    rep #$30
+    ldx #$1FFF
+    txs
+    lda #$1234
+    sep #$20
+    xba
+    php
+    sta $00
+    xba
+    sta $01
+    pla
+    and #$82        ; N and Z
+    sta $02
+
+    rep #$20
+    lda #$8000
+    sep #$20
+    xba
+    php
+    pla
+    and #$82
+    sta $03
+
+    rep #$20
+    lda #$00FF
+    sep #$20
+    xba
+    php
+    pla
+    and #$82
+    sta $04
+
+    lda #$55
+    sta $05
+done:
+    bra done
+
+vector:
+    rti
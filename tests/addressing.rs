@@ -0,0 +1,105 @@
+//! Effective addresses of the addressing modes with unusual wrapping behavior. Each test runs a
+//! single instruction from WRAM and uses a read watchpoint to find out where a byte was fetched.
+
+use std::{fs, path::Path};
+
+use snes_emu::{
+    Snes,
+    cpu::{AccessKind, StepResult, Watchpoint, WatchpointHit, memory::write},
+};
+
+const CODE: u32 = 0x7E1000;
+
+/// Processes the reset of smoke.sfc and jumps to `code`, which is copied to WRAM. The CPU is still
+/// in emulation mode after the reset.
+fn load(code: &[u8]) -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/smoke.sfc");
+    let mut snes = Snes::new(fs::read(path).unwrap().into_boxed_slice());
+    snes.step();
+
+    for (addr, &byte) in (CODE..).zip(code) {
+        write(&mut snes, addr, byte);
+    }
+    snes.set_pc(CODE);
+    snes
+}
+
+/// Executes the instruction and returns the address the byte `value` was read from.
+fn read_address(snes: &mut Snes, value: u8) -> u32 {
+    snes.cpu.debug.watchpoints.push(Watchpoint {
+        start: 0x000000,
+        end: 0xFFFFFF,
+        read: true,
+        write: false,
+        value: Some(value),
+    });
+    match snes.step() {
+        StepResult::WatchpointHit(WatchpointHit {
+            addr,
+            kind: AccessKind::Read,
+            ..
+        }) => addr,
+        result => panic!("expected a read of {value:#04X}, got {result:?}"),
+    }
+}
+
+#[test]
+fn stack_relative_indirect_indexed() {
+    // LDA ($28,S),Y
+    let mut snes = load(&[0xB3, 0x28]);
+    // Native mode with an 8 bit accumulator and 16 bit index registers
+    snes.cpu.regs.p.e = false;
+    snes.cpu.regs.set_p(0x24);
+    snes.cpu.regs.s.set(0xFFF8);
+    snes.cpu.regs.y.set(0x0020);
+    snes.cpu.regs.dbr = 0x7E;
+    write(&mut snes, 0x7E0020, 0xF0);
+    write(&mut snes, 0x7E0021, 0xFF);
+    write(&mut snes, 0x7E0010, 0x5A);
+    write(&mut snes, 0x7F0010, 0xA5);
+
+    // S + $28 wraps within bank 0
+    assert_eq!(read_address(&mut snes, 0xF0), 0x000020);
+    // The pointer $FFF0 + Y crosses from bank $7E into bank $7F
+    assert_eq!(snes.cpu.regs.a.getl(), 0xA5);
+}
+
+/// Executes `code` in emulation mode with the pointer's low byte at $00FF and returns the address
+/// its high byte was read from. Both $0000 and $0100 hold the same high byte.
+fn pointer_high_byte_address(code: &[u8], d: u16, index: u8) -> u32 {
+    let mut snes = load(code);
+    snes.cpu.regs.d.set(d);
+    snes.cpu.regs.x.setl(index);
+    snes.cpu.regs.y.setl(index);
+    write(&mut snes, 0x7E00FF, 0x34);
+    write(&mut snes, 0x7E0000, 0x12);
+    write(&mut snes, 0x7E0100, 0x12);
+    read_address(&mut snes, 0x12)
+}
+
+#[test]
+fn direct_x_indirect_emulation_page_wrap() {
+    // LDA ($FE,X) with DL = 0 wraps around within the direct page
+    assert_eq!(
+        pointer_high_byte_address(&[0xA1, 0xFE], 0x0000, 0x01),
+        0x000000
+    );
+}
+
+#[test]
+fn direct_x_indirect_emulation_no_page_wrap() {
+    // LDA ($FD,X) with DL != 0 crosses into the next page
+    assert_eq!(
+        pointer_high_byte_address(&[0xA1, 0xFD], 0x0001, 0x01),
+        0x000100
+    );
+}
+
+#[test]
+fn direct_indirect_y_emulation_page_wrap() {
+    // LDA ($FF),Y with DL = 0 wraps around within the direct page
+    assert_eq!(
+        pointer_high_byte_address(&[0xB1, 0xFF], 0x0000, 0x00),
+        0x000000
+    );
+}
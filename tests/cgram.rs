@@ -0,0 +1,38 @@
+use std::{fs, path::Path};
+
+use snes_emu::{Snes, cpu::memory::read_pure};
+
+/// Runs `cgram_dma.sfc` until it is done.
+fn run_cgram_dma() -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/cgram_dma.sfc");
+    let mut snes = Snes::new(fs::read(path).unwrap().into_boxed_slice());
+    for _ in 0..3 {
+        snes.run();
+    }
+    assert_eq!(read_pure(&snes, 0x7E0000), Some(0x55));
+    snes
+}
+
+#[test]
+fn dma_writes_every_color() {
+    let snes = run_cgram_dma();
+    // Colors 0 and 1 are written again later
+    for (i, &byte) in snes.ppu.cgram.iter().enumerate().skip(4) {
+        let source = match i < 0x100 {
+            true => i as u8,
+            false => (i as u8).wrapping_add(0x55),
+        };
+        let expected = match i % 2 {
+            0 => source,
+            _ => source & 0x7F,
+        };
+        assert_eq!(byte, expected, "CGRAM byte {i:#05X}");
+    }
+}
+
+#[test]
+fn write_during_active_display_dropped() {
+    let snes = run_cgram_dma();
+    // Color 0 keeps the value from the DMA, but CGADD still advanced to color 1
+    assert_eq!(snes.ppu.cgram[..4], [0x00, 0x01, 0x1F, 0x00]);
+}
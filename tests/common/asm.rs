@@ -0,0 +1,991 @@
+//! A small two-pass assembler for the sources of the test ROMs in `test-roms/`. It covers the
+//! 65816 and SPC700 instructions and the handful of directives those sources use, see
+//! `test-roms/README.md` for the syntax.
+
+use std::{collections::HashMap, fs, path::Path, rc::Rc};
+
+const BANK_SIZE: usize = 0x8000;
+const HEADER: usize = 0x7FC0;
+const TITLE_LEN: usize = 21;
+
+/// Names of the entries of the vector table at $FFE0, the unused ones are empty.
+const VECTORS: [&str; 16] = [
+    "",
+    "",
+    "cop",
+    "brk",
+    "abort",
+    "nmi",
+    "",
+    "irq",
+    "",
+    "",
+    "emu_cop",
+    "",
+    "emu_abort",
+    "emu_nmi",
+    "reset",
+    "emu_irq",
+];
+
+/// Assembles the source at `path` into a LoRom image, padded to a multiple of 32 KiB, with a
+/// valid header checksum.
+pub fn assemble(path: &Path) -> Result<Vec<u8>, String> {
+    let mut lines = Vec::new();
+    load(path, &mut lines)?;
+
+    // Forward references evaluate to 0 in the first pass, which only collects the labels. The
+    // size of an instruction never depends on the value of a label.
+    let mut asm = Assembler::new(HashMap::new(), false);
+    asm.run(&lines)?;
+    let mut asm = Assembler::new(asm.labels, true);
+    asm.run(&lines)?;
+    asm.finish()
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    file: Rc<str>,
+    number: usize,
+    text: String,
+}
+
+/// Appends the lines of the source at `path` to `lines`, with included files inserted and the
+/// lines between `.rept` and `.endr` repeated. Included files are relative to the source.
+fn load(path: &Path, lines: &mut Vec<Line>) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let file: Rc<str> = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into();
+    let mut repetition: Option<(usize, usize)> = None;
+    for (index, text) in source.lines().enumerate() {
+        let number = index + 1;
+        let error = |message: &str| format!("{file}:{number}: {message}");
+        let statement = strip_comment(text).trim();
+        if let Some(name) = statement.strip_prefix(".include") {
+            let name = name
+                .trim()
+                .strip_prefix('"')
+                .and_then(|name| name.strip_suffix('"'))
+                .ok_or_else(|| error("the file name has to be quoted"))?;
+            load(&path.with_file_name(name), lines)?;
+        } else if let Some(count) = statement.strip_prefix(".rept") {
+            if repetition.is_some() {
+                return Err(error("nested .rept"));
+            }
+            let count = parse_number(count.trim()).ok_or_else(|| error("invalid count"))?;
+            repetition = Some((lines.len(), count as usize));
+        } else if statement == ".endr" {
+            let (start, count) = repetition
+                .take()
+                .ok_or_else(|| error(".endr without .rept"))?;
+            let body = lines.split_off(start);
+            for _ in 0..count {
+                lines.extend_from_slice(&body);
+            }
+        } else {
+            lines.push(Line {
+                file: Rc::clone(&file),
+                number,
+                text: text.to_owned(),
+            });
+        }
+    }
+    match repetition {
+        Some(_) => Err(format!("{file}: missing .endr")),
+        None => Ok(()),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    match s.strip_prefix('$') {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// The number of hex digits if `expr` is a single hex literal, which selects the size of an
+/// operand.
+fn literal_digits(expr: &str) -> Option<usize> {
+    let hex = expr.trim().strip_prefix('$')?;
+    hex.bytes()
+        .all(|b| b.is_ascii_hexdigit())
+        .then_some(hex.len())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cpu {
+    W65816,
+    /// Code uploaded to the APU, `base` is the SPC700 address of the byte at `start` in the
+    /// image.
+    Spc700 {
+        start: usize,
+        base: u32,
+    },
+}
+
+/// A part of the operand of an instruction, in the order it is emitted.
+enum Arg<'a> {
+    Byte(&'a str),
+    Word(&'a str),
+    Long(&'a str),
+    /// Relative to the end of the instruction.
+    Rel8(&'a str),
+    Rel16(&'a str),
+}
+
+struct Assembler {
+    labels: HashMap<String, u32>,
+    final_pass: bool,
+    rom: Vec<u8>,
+    /// Offset in the image.
+    pos: usize,
+    /// Address of the next byte, as seen by the CPU which executes it.
+    pc: u32,
+    cpu: Cpu,
+    /// Size of the accumulator and index registers, tracked through REP and SEP.
+    m16: bool,
+    x16: bool,
+    title: Option<String>,
+    chipset: u8,
+    sram_size: u8,
+    vectors: [Option<String>; 16],
+}
+
+impl Assembler {
+    fn new(labels: HashMap<String, u32>, final_pass: bool) -> Self {
+        Self {
+            labels,
+            final_pass,
+            rom: Vec::new(),
+            pos: 0,
+            pc: 0x8000,
+            cpu: Cpu::W65816,
+            m16: false,
+            x16: false,
+            title: None,
+            chipset: 0,
+            sram_size: 0,
+            vectors: Default::default(),
+        }
+    }
+
+    fn run(&mut self, lines: &[Line]) -> Result<(), String> {
+        for line in lines {
+            self.line(&line.text)
+                .map_err(|err| format!("{}:{}: {err}", line.file, line.number))?;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let mut statement = strip_comment(line).trim();
+        if let Some((name, rest)) = statement.split_once(':')
+            && is_identifier(name)
+        {
+            self.define(name, self.pc)?;
+            statement = rest.trim();
+        }
+        if statement.is_empty() {
+            return Ok(());
+        }
+        if let Some((name, expr)) = statement.split_once('=')
+            && is_identifier(name.trim())
+        {
+            let value = self.eval(expr)?;
+            return self.define(name.trim(), value);
+        }
+
+        let (mnemonic, operand) = match statement.split_once(char::is_whitespace) {
+            Some((mnemonic, operand)) => (mnemonic, operand.trim()),
+            None => (statement, ""),
+        };
+        let mnemonic = mnemonic.to_ascii_lowercase();
+        if mnemonic.starts_with('.') {
+            return self.directive(&mnemonic, operand);
+        }
+        match self.cpu {
+            Cpu::W65816 => self.w65816(&mnemonic, operand),
+            Cpu::Spc700 { .. } => self.spc700(&mnemonic, operand),
+        }
+    }
+
+    fn define(&mut self, name: &str, value: u32) -> Result<(), String> {
+        match self.labels.insert(name.to_owned(), value) {
+            Some(_) if !self.final_pass => Err(format!("{name} is defined twice")),
+            Some(old) if old != value => {
+                Err(format!("{name} moved from ${old:04X} to ${value:04X}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn eval(&self, expr: &str) -> Result<u32, String> {
+        let expr = expr.trim();
+        let mut value = 0u32;
+        let mut rest = expr;
+        let mut negate = false;
+        loop {
+            let end = rest[1.min(rest.len())..]
+                .find(['+', '-'])
+                .map_or(rest.len(), |i| i + 1);
+            let term = rest[..end].trim();
+            let term = match term {
+                "*" => self.pc,
+                _ if is_identifier(term) => match self.labels.get(term) {
+                    Some(&value) => value,
+                    None if !self.final_pass => 0,
+                    None => return Err(format!("undefined label {term}")),
+                },
+                _ => parse_number(term).ok_or_else(|| format!("invalid expression {expr:?}"))?,
+            };
+            value = match negate {
+                true => value.wrapping_sub(term),
+                false => value.wrapping_add(term),
+            };
+            if end == rest.len() {
+                return Ok(value);
+            }
+            negate = rest.as_bytes()[end] == b'-';
+            rest = &rest[end + 1..];
+        }
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        let end = self.pos + bytes.len();
+        if self.rom.len() < end {
+            self.rom.resize(end, 0);
+        }
+        self.rom[self.pos..end].copy_from_slice(bytes);
+        self.pos = end;
+        self.pc += bytes.len() as u32;
+    }
+
+    fn directive(&mut self, directive: &str, operand: &str) -> Result<(), String> {
+        match directive {
+            ".title" => {
+                let title = operand
+                    .strip_prefix('"')
+                    .and_then(|title| title.strip_suffix('"'))
+                    .ok_or("the title has to be quoted")?;
+                if title.len() > TITLE_LEN {
+                    return Err(format!("the title is longer than {TITLE_LEN} bytes"));
+                }
+                self.title = Some(title.to_owned());
+            }
+            ".chipset" => self.chipset = self.eval(operand)? as u8,
+            ".sram_size" => self.sram_size = self.eval(operand)? as u8,
+            ".vectors" => {
+                let (reset, default) = operand
+                    .split_once(',')
+                    .ok_or("expected the reset vector and the default vector")?;
+                for (vector, name) in self.vectors.iter_mut().zip(VECTORS) {
+                    *vector = Some(default.trim().to_owned());
+                    if name == "reset" {
+                        *vector = Some(reset.trim().to_owned());
+                    }
+                }
+            }
+            ".vector" => {
+                let (name, target) = operand
+                    .split_once(',')
+                    .ok_or("expected the name and the target of the vector")?;
+                let index = VECTORS
+                    .iter()
+                    .position(|&vector| !vector.is_empty() && vector == name.trim())
+                    .ok_or_else(|| format!("unknown vector {name}"))?;
+                self.vectors[index] = Some(target.trim().to_owned());
+            }
+            ".org" => {
+                let addr = self.eval(operand)?;
+                let pos = match self.cpu {
+                    Cpu::W65816 => {
+                        if addr & 0x8000 == 0 {
+                            return Err(format!("${addr:06X} is not mapped to the ROM"));
+                        }
+                        (addr >> 16) as usize * BANK_SIZE + (addr & 0x7FFF) as usize
+                    }
+                    Cpu::Spc700 { start, base } => {
+                        let offset = addr.checked_sub(base).ok_or("address below the base")?;
+                        start + offset as usize
+                    }
+                };
+                if pos < self.pos {
+                    return Err(format!("${addr:04X} overlaps the previous code"));
+                }
+                self.rom.resize(self.rom.len().max(pos), 0);
+                self.pos = pos;
+                self.pc = addr;
+            }
+            ".spc700" => {
+                let base = self.eval(operand)?;
+                self.cpu = Cpu::Spc700 {
+                    start: self.pos,
+                    base,
+                };
+                self.pc = base;
+            }
+            ".db" => {
+                for value in operand.split(',') {
+                    let value = self.eval(value)?;
+                    self.emit(&[value as u8]);
+                }
+            }
+            ".dw" => {
+                for value in operand.split(',') {
+                    let value = self.eval(value)?;
+                    self.emit(&(value as u16).to_le_bytes());
+                }
+            }
+            ".dsb" => {
+                let (count, value) = operand.split_once(',').unwrap_or((operand, "0"));
+                let count = self.eval(count)? as usize;
+                let value = self.eval(value)? as u8;
+                self.emit(&vec![value; count]);
+            }
+            _ => return Err(format!("unknown directive {directive}")),
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, opcode: u8, args: &[Arg]) -> Result<(), String> {
+        let len = 1 + args
+            .iter()
+            .map(|arg| match arg {
+                Arg::Byte(_) | Arg::Rel8(_) => 1,
+                Arg::Word(_) | Arg::Rel16(_) => 2,
+                Arg::Long(_) => 3,
+            })
+            .sum::<u32>();
+        let end = self.pc + len;
+        let mut bytes = vec![opcode];
+        for arg in args {
+            match *arg {
+                Arg::Byte(expr) => bytes.push(self.eval(expr)? as u8),
+                Arg::Word(expr) => {
+                    bytes.extend_from_slice(&(self.eval(expr)? as u16).to_le_bytes())
+                }
+                Arg::Long(expr) => bytes.extend_from_slice(&self.eval(expr)?.to_le_bytes()[..3]),
+                Arg::Rel8(expr) => {
+                    let offset = self.eval(expr)?.wrapping_sub(end) as i32;
+                    if self.final_pass && !(-128..128).contains(&offset) {
+                        return Err(format!("branch to {expr} is out of range"));
+                    }
+                    bytes.push(offset as u8);
+                }
+                Arg::Rel16(expr) => {
+                    let offset = self.eval(expr)?.wrapping_sub(end);
+                    bytes.extend_from_slice(&(offset as u16).to_le_bytes());
+                }
+            }
+        }
+        self.emit(&bytes);
+        Ok(())
+    }
+
+    fn w65816(&mut self, mnemonic: &str, operand: &str) -> Result<(), String> {
+        use W65816Mode::*;
+
+        let (mode, expr) = parse_w65816_operand(mnemonic, operand)?;
+        let Some(opcode) = w65816_opcode(mnemonic, mode) else {
+            return Err(format!("invalid instruction {mnemonic} {operand}"));
+        };
+        let arg = match mode {
+            Implied | Accumulator => None,
+            Immediate => {
+                let wide = match literal_digits(expr) {
+                    Some(digits) => digits > 2,
+                    None => match mnemonic {
+                        "ldx" | "ldy" | "cpx" | "cpy" => self.x16,
+                        "rep" | "sep" => false,
+                        _ => self.m16,
+                    },
+                };
+                Some(if wide {
+                    Arg::Word(expr)
+                } else {
+                    Arg::Byte(expr)
+                })
+            }
+            Direct | DirectX | DirectY | Stack | DirectIndirect | DirectIndirectX
+            | DirectIndirectY | DirectIndirectLong | DirectIndirectLongY | StackIndirectY => {
+                Some(Arg::Byte(expr))
+            }
+            Absolute | AbsoluteX | AbsoluteY | AbsoluteIndirect | AbsoluteIndirectX
+            | AbsoluteIndirectLong => Some(Arg::Word(expr)),
+            Long | LongX => Some(Arg::Long(expr)),
+            Relative => Some(Arg::Rel8(expr)),
+            RelativeLong => Some(Arg::Rel16(expr)),
+        };
+
+        if let Immediate = mode {
+            let flags = self.eval(expr)?;
+            match mnemonic {
+                "rep" => {
+                    self.m16 |= flags & 0x20 != 0;
+                    self.x16 |= flags & 0x10 != 0;
+                }
+                "sep" => {
+                    self.m16 &= flags & 0x20 == 0;
+                    self.x16 &= flags & 0x10 == 0;
+                }
+                _ => (),
+            }
+        }
+        self.instruction(opcode, arg.as_slice())
+    }
+
+    fn spc700(&mut self, mnemonic: &str, operand: &str) -> Result<(), String> {
+        use SpcOperand::*;
+
+        let operands = match operand {
+            "" => Vec::new(),
+            _ => operand
+                .split(',')
+                .map(parse_spc_operand)
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let alu = match mnemonic {
+            "or" => Some(0x00),
+            "and" => Some(0x20),
+            "eor" => Some(0x40),
+            "cmp" => Some(0x60),
+            "adc" => Some(0x80),
+            "sbc" => Some(0xA0),
+            _ => None,
+        };
+        let shift = match mnemonic {
+            "asl" => Some(0x00),
+            "rol" => Some(0x20),
+            "lsr" => Some(0x40),
+            "ror" => Some(0x60),
+            _ => None,
+        };
+        let branch = match mnemonic {
+            "bpl" => Some(0x10),
+            "bra" => Some(0x2F),
+            "bmi" => Some(0x30),
+            "bvc" => Some(0x50),
+            "bvs" => Some(0x70),
+            "bcc" => Some(0x90),
+            "bcs" => Some(0xB0),
+            "bne" => Some(0xD0),
+            "beq" => Some(0xF0),
+            _ => None,
+        };
+        let alu_base = alu.unwrap_or_default();
+        let shift_base = shift.unwrap_or_default();
+
+        let (opcode, args): (u8, Vec<Arg>) = match (mnemonic, operands.as_slice()) {
+            ("mov", [A, Immediate(e)]) => (0xE8, vec![Arg::Byte(e)]),
+            ("mov", [A, IndirectX]) => (0xE6, vec![]),
+            ("mov", [A, IndirectXInc]) => (0xBF, vec![]),
+            ("mov", [A, Direct(e)]) => (0xE4, vec![Arg::Byte(e)]),
+            ("mov", [A, DirectX(e)]) => (0xF4, vec![Arg::Byte(e)]),
+            ("mov", [A, Absolute(e)]) => (0xE5, vec![Arg::Word(e)]),
+            ("mov", [A, AbsoluteX(e)]) => (0xF5, vec![Arg::Word(e)]),
+            ("mov", [A, AbsoluteY(e)]) => (0xF6, vec![Arg::Word(e)]),
+            ("mov", [A, X]) => (0x7D, vec![]),
+            ("mov", [A, Y]) => (0xDD, vec![]),
+            ("mov", [X, A]) => (0x5D, vec![]),
+            ("mov", [Y, A]) => (0xFD, vec![]),
+            ("mov", [X, Sp]) => (0x9D, vec![]),
+            ("mov", [Sp, X]) => (0xBD, vec![]),
+            ("mov", [X, Immediate(e)]) => (0xCD, vec![Arg::Byte(e)]),
+            ("mov", [X, Direct(e)]) => (0xF8, vec![Arg::Byte(e)]),
+            ("mov", [X, Absolute(e)]) => (0xE9, vec![Arg::Word(e)]),
+            ("mov", [Y, Immediate(e)]) => (0x8D, vec![Arg::Byte(e)]),
+            ("mov", [Y, Direct(e)]) => (0xEB, vec![Arg::Byte(e)]),
+            ("mov", [Y, Absolute(e)]) => (0xEC, vec![Arg::Word(e)]),
+            ("mov", [IndirectX, A]) => (0xC6, vec![]),
+            ("mov", [IndirectXInc, A]) => (0xAF, vec![]),
+            ("mov", [Direct(e), A]) => (0xC4, vec![Arg::Byte(e)]),
+            ("mov", [DirectX(e), A]) => (0xD4, vec![Arg::Byte(e)]),
+            ("mov", [Absolute(e), A]) => (0xC5, vec![Arg::Word(e)]),
+            ("mov", [AbsoluteX(e), A]) => (0xD5, vec![Arg::Word(e)]),
+            ("mov", [AbsoluteY(e), A]) => (0xD6, vec![Arg::Word(e)]),
+            ("mov", [Direct(e), X]) => (0xD8, vec![Arg::Byte(e)]),
+            ("mov", [Direct(e), Y]) => (0xCB, vec![Arg::Byte(e)]),
+            ("mov", [Absolute(e), X]) => (0xC9, vec![Arg::Word(e)]),
+            ("mov", [Absolute(e), Y]) => (0xCC, vec![Arg::Word(e)]),
+            ("mov", [Direct(d), Immediate(i)]) => (0x8F, vec![Arg::Byte(i), Arg::Byte(d)]),
+            ("mov", [Direct(d), Direct(s)]) => (0xFA, vec![Arg::Byte(s), Arg::Byte(d)]),
+            ("cmp", [X, Immediate(e)]) => (0xC8, vec![Arg::Byte(e)]),
+            ("cmp", [X, Direct(e)]) => (0x3E, vec![Arg::Byte(e)]),
+            ("cmp", [X, Absolute(e)]) => (0x1E, vec![Arg::Word(e)]),
+            ("cmp", [Y, Immediate(e)]) => (0xAD, vec![Arg::Byte(e)]),
+            ("cmp", [Y, Direct(e)]) => (0x7E, vec![Arg::Byte(e)]),
+            ("cmp", [Y, Absolute(e)]) => (0x5E, vec![Arg::Word(e)]),
+            (_, [A, Immediate(e)]) if alu.is_some() => (alu_base | 0x08, vec![Arg::Byte(e)]),
+            (_, [A, IndirectX]) if alu.is_some() => (alu_base | 0x06, vec![]),
+            (_, [A, Direct(e)]) if alu.is_some() => (alu_base | 0x04, vec![Arg::Byte(e)]),
+            (_, [A, DirectX(e)]) if alu.is_some() => (alu_base | 0x14, vec![Arg::Byte(e)]),
+            (_, [A, Absolute(e)]) if alu.is_some() => (alu_base | 0x05, vec![Arg::Word(e)]),
+            (_, [A, AbsoluteX(e)]) if alu.is_some() => (alu_base | 0x15, vec![Arg::Word(e)]),
+            (_, [A, AbsoluteY(e)]) if alu.is_some() => (alu_base | 0x16, vec![Arg::Word(e)]),
+            (_, [Direct(d), Immediate(i)]) if alu.is_some() => {
+                (alu_base | 0x18, vec![Arg::Byte(i), Arg::Byte(d)])
+            }
+            (_, [Direct(d), Direct(s)]) if alu.is_some() => {
+                (alu_base | 0x09, vec![Arg::Byte(s), Arg::Byte(d)])
+            }
+            (_, [A]) if shift.is_some() => (shift_base | 0x1C, vec![]),
+            (_, [Direct(e)]) if shift.is_some() => (shift_base | 0x0B, vec![Arg::Byte(e)]),
+            (_, [Absolute(e)]) if shift.is_some() => (shift_base | 0x0C, vec![Arg::Word(e)]),
+            (_, [Direct(e)]) if branch.is_some() => {
+                (branch.unwrap_or_default(), vec![Arg::Rel8(e)])
+            }
+            ("inc", [A]) => (0xBC, vec![]),
+            ("inc", [X]) => (0x3D, vec![]),
+            ("inc", [Y]) => (0xFC, vec![]),
+            ("inc", [Direct(e)]) => (0xAB, vec![Arg::Byte(e)]),
+            ("inc", [Absolute(e)]) => (0xAC, vec![Arg::Word(e)]),
+            ("dec", [A]) => (0x9C, vec![]),
+            ("dec", [X]) => (0x1D, vec![]),
+            ("dec", [Y]) => (0xDC, vec![]),
+            ("dec", [Direct(e)]) => (0x8B, vec![Arg::Byte(e)]),
+            ("dec", [Absolute(e)]) => (0x8C, vec![Arg::Word(e)]),
+            ("push", [Psw]) => (0x0D, vec![]),
+            ("push", [A]) => (0x2D, vec![]),
+            ("push", [X]) => (0x4D, vec![]),
+            ("push", [Y]) => (0x6D, vec![]),
+            ("pop", [Psw]) => (0x8E, vec![]),
+            ("pop", [A]) => (0xAE, vec![]),
+            ("pop", [X]) => (0xCE, vec![]),
+            ("pop", [Y]) => (0xEE, vec![]),
+            ("movw", [Ya, Direct(e)]) => (0xBA, vec![Arg::Byte(e)]),
+            ("movw", [Direct(e), Ya]) => (0xDA, vec![Arg::Byte(e)]),
+            ("incw", [Direct(e)]) => (0x3A, vec![Arg::Byte(e)]),
+            ("decw", [Direct(e)]) => (0x1A, vec![Arg::Byte(e)]),
+            ("addw", [Ya, Direct(e)]) => (0x7A, vec![Arg::Byte(e)]),
+            ("subw", [Ya, Direct(e)]) => (0x9A, vec![Arg::Byte(e)]),
+            ("cmpw", [Ya, Direct(e)]) => (0x5A, vec![Arg::Byte(e)]),
+            ("mul", [Ya]) => (0xCF, vec![]),
+            ("div", [Ya, X]) => (0x9E, vec![]),
+            ("xcn", [A]) => (0x9F, vec![]),
+            ("dbnz", [Direct(d), Direct(t)]) => (0x6E, vec![Arg::Byte(d), Arg::Rel8(t)]),
+            ("dbnz", [Y, Direct(t)]) => (0xFE, vec![Arg::Rel8(t)]),
+            ("cbne", [Direct(d), Direct(t)]) => (0x2E, vec![Arg::Byte(d), Arg::Rel8(t)]),
+            ("bbs", [DirectBit(d, bit), Direct(t)]) => {
+                (0x03 | bit << 5, vec![Arg::Byte(d), Arg::Rel8(t)])
+            }
+            ("bbc", [DirectBit(d, bit), Direct(t)]) => {
+                (0x13 | bit << 5, vec![Arg::Byte(d), Arg::Rel8(t)])
+            }
+            ("set1", [DirectBit(d, bit)]) => (0x02 | bit << 5, vec![Arg::Byte(d)]),
+            ("clr1", [DirectBit(d, bit)]) => (0x12 | bit << 5, vec![Arg::Byte(d)]),
+            ("tset1", [Absolute(e)]) => (0x0E, vec![Arg::Word(e)]),
+            ("tclr1", [Absolute(e)]) => (0x4E, vec![Arg::Word(e)]),
+            ("call", [Direct(e) | Absolute(e)]) => (0x3F, vec![Arg::Word(e)]),
+            ("jmp", [Direct(e) | Absolute(e)]) => (0x5F, vec![Arg::Word(e)]),
+            ("nop", []) => (0x00, vec![]),
+            ("clrp", []) => (0x20, vec![]),
+            ("setp", []) => (0x40, vec![]),
+            ("clrc", []) => (0x60, vec![]),
+            ("ret", []) => (0x6F, vec![]),
+            ("reti", []) => (0x7F, vec![]),
+            ("setc", []) => (0x80, vec![]),
+            ("ei", []) => (0xA0, vec![]),
+            ("di", []) => (0xC0, vec![]),
+            ("notc", []) => (0xED, vec![]),
+            ("sleep", []) => (0xEF, vec![]),
+            ("stop", []) => (0xFF, vec![]),
+            _ => return Err(format!("invalid instruction {mnemonic} {operand}")),
+        };
+        self.instruction(opcode, &args)
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, String> {
+        let size = self.rom.len().max(BANK_SIZE).next_multiple_of(BANK_SIZE);
+        let mut rom = std::mem::take(&mut self.rom);
+        rom.resize(size, 0);
+
+        let title = self.title.as_deref().ok_or("missing .title")?;
+        let header = &mut rom[HEADER..HEADER + 0x20];
+        header[..TITLE_LEN].copy_from_slice(format!("{title:TITLE_LEN$}").as_bytes());
+        header[0x15] = 0x20; // LoRom
+        header[0x16] = self.chipset;
+        header[0x17] = (size.next_power_of_two() / 1024).ilog2() as u8;
+        header[0x18] = self.sram_size;
+        header[0x19] = 0x01; // North America
+        for (i, vector) in self.vectors.iter().enumerate() {
+            let vector = vector.as_deref().ok_or("missing .vectors")?;
+            let addr = self.eval(vector)? as u16;
+            rom[HEADER + 0x20 + 2 * i..][..2].copy_from_slice(&addr.to_le_bytes());
+        }
+
+        // The checksum and its complement add up to $1FE in the sum of all bytes
+        rom[HEADER + 0x1C..HEADER + 0x20].copy_from_slice(&[0xFF, 0xFF, 0x00, 0x00]);
+        let checksum = rom.iter().fold(0u16, |sum, &b| sum.wrapping_add(b.into()));
+        rom[HEADER + 0x1C..HEADER + 0x1E].copy_from_slice(&(!checksum).to_le_bytes());
+        rom[HEADER + 0x1E..HEADER + 0x20].copy_from_slice(&checksum.to_le_bytes());
+        Ok(rom)
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum W65816Mode {
+    Implied,
+    Accumulator,
+    Immediate,
+    Direct,
+    DirectX,
+    DirectY,
+    Stack,
+    DirectIndirect,
+    DirectIndirectX,
+    DirectIndirectY,
+    DirectIndirectLong,
+    DirectIndirectLongY,
+    StackIndirectY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    AbsoluteIndirect,
+    AbsoluteIndirectX,
+    AbsoluteIndirectLong,
+    Long,
+    LongX,
+    Relative,
+    RelativeLong,
+}
+
+/// Direct page, absolute and long addresses are told apart by the number of hex digits. Other
+/// expressions are absolute addresses, except for the targets of long jumps.
+fn parse_w65816_operand<'a>(
+    mnemonic: &str,
+    operand: &'a str,
+) -> Result<(W65816Mode, &'a str), String> {
+    use W65816Mode::*;
+
+    let lower = operand.to_ascii_lowercase();
+    if operand.is_empty() {
+        return Ok((Implied, operand));
+    }
+    if lower == "a" {
+        return Ok((Accumulator, operand));
+    }
+    if let Some(expr) = operand.strip_prefix('#') {
+        return Ok((Immediate, expr.trim()));
+    }
+    match mnemonic {
+        "bpl" | "bmi" | "bvc" | "bvs" | "bcc" | "bcs" | "bne" | "beq" | "bra" => {
+            return Ok((Relative, operand));
+        }
+        "brl" | "per" => return Ok((RelativeLong, operand)),
+        _ => (),
+    }
+
+    let size = |expr: &str| match literal_digits(expr) {
+        Some(0..=2) => 1,
+        Some(3..=4) => 2,
+        Some(_) => 3,
+        None if matches!(mnemonic, "jml" | "jsl") => 3,
+        None => 2,
+    };
+    let indirect = |inner: &'a str, short, long| {
+        let inner = inner.trim();
+        Ok((if size(inner) == 1 { short } else { long }, inner))
+    };
+    if let Some(inner) = lower.strip_prefix('(') {
+        let inner = &operand[operand.len() - inner.len()..];
+        if let Some(expr) = inner.strip_suffix(",s),y").or(inner.strip_suffix(",S),Y")) {
+            return Ok((StackIndirectY, expr.trim()));
+        }
+        if let Some(expr) = inner.strip_suffix("),y").or(inner.strip_suffix("),Y")) {
+            return Ok((DirectIndirectY, expr.trim()));
+        }
+        if let Some(expr) = inner.strip_suffix(",x)").or(inner.strip_suffix(",X)")) {
+            return indirect(expr, DirectIndirectX, AbsoluteIndirectX);
+        }
+        if let Some(expr) = inner.strip_suffix(')') {
+            return indirect(expr, DirectIndirect, AbsoluteIndirect);
+        }
+    }
+    if let Some(inner) = operand.strip_prefix('[') {
+        if let Some(expr) = inner.strip_suffix("],y").or(inner.strip_suffix("],Y")) {
+            return Ok((DirectIndirectLongY, expr.trim()));
+        }
+        if let Some(expr) = inner.strip_suffix(']') {
+            return indirect(expr, DirectIndirectLong, AbsoluteIndirectLong);
+        }
+    }
+
+    let (expr, index) = match lower.rsplit_once(',') {
+        Some((_, index)) => (&operand[..lower.len() - index.len() - 1], index.trim()),
+        None => (operand, ""),
+    };
+    let mode = match (size(expr), index) {
+        (1, "") => Direct,
+        (1, "x") => DirectX,
+        (1, "y") => DirectY,
+        (1, "s") => Stack,
+        (2, "") => Absolute,
+        (2, "x") => AbsoluteX,
+        (2, "y") => AbsoluteY,
+        (3, "") => Long,
+        (3, "x") => LongX,
+        _ => return Err(format!("invalid operand {operand}")),
+    };
+    Ok((mode, expr.trim()))
+}
+
+fn w65816_opcode(mnemonic: &str, mode: W65816Mode) -> Option<u8> {
+    use W65816Mode::*;
+
+    // ORA, AND, EOR, ADC, STA, LDA, CMP and SBC share their addressing modes
+    let alu = match mnemonic {
+        "ora" => Some(0x00),
+        "and" => Some(0x20),
+        "eor" => Some(0x40),
+        "adc" => Some(0x60),
+        "sta" => Some(0x80),
+        "lda" => Some(0xA0),
+        "cmp" => Some(0xC0),
+        "sbc" => Some(0xE0),
+        _ => None,
+    };
+    if let Some(base) = alu {
+        let offset = match mode {
+            DirectIndirectX => 0x01,
+            Stack => 0x03,
+            Direct => 0x05,
+            DirectIndirectLong => 0x07,
+            Immediate if mnemonic != "sta" => 0x09,
+            Absolute => 0x0D,
+            Long => 0x0F,
+            DirectIndirectY => 0x11,
+            DirectIndirect => 0x12,
+            StackIndirectY => 0x13,
+            DirectX => 0x15,
+            DirectIndirectLongY => 0x17,
+            AbsoluteY => 0x19,
+            AbsoluteX => 0x1D,
+            LongX => 0x1F,
+            _ => return None,
+        };
+        return Some(base | offset);
+    }
+
+    // ASL, ROL, LSR and ROR, as well as INC and DEC apart from the accumulator
+    let shift = match mnemonic {
+        "asl" => Some(0x00),
+        "rol" => Some(0x20),
+        "lsr" => Some(0x40),
+        "ror" => Some(0x60),
+        "dec" => Some(0xC0),
+        "inc" => Some(0xE0),
+        _ => None,
+    };
+    if let Some(base) = shift {
+        return match (mnemonic, mode) {
+            ("inc", Implied | Accumulator) => Some(0x1A),
+            ("dec", Implied | Accumulator) => Some(0x3A),
+            (_, Implied | Accumulator) => Some(base | 0x0A),
+            (_, Direct) => Some(base | 0x06),
+            (_, Absolute) => Some(base | 0x0E),
+            (_, DirectX) => Some(base | 0x16),
+            (_, AbsoluteX) => Some(base | 0x1E),
+            _ => None,
+        };
+    }
+
+    Some(match (mnemonic, mode) {
+        ("ldx", Immediate) => 0xA2,
+        ("ldx", Direct) => 0xA6,
+        ("ldx", Absolute) => 0xAE,
+        ("ldx", DirectY) => 0xB6,
+        ("ldx", AbsoluteY) => 0xBE,
+        ("ldy", Immediate) => 0xA0,
+        ("ldy", Direct) => 0xA4,
+        ("ldy", Absolute) => 0xAC,
+        ("ldy", DirectX) => 0xB4,
+        ("ldy", AbsoluteX) => 0xBC,
+        ("stx", Direct) => 0x86,
+        ("stx", Absolute) => 0x8E,
+        ("stx", DirectY) => 0x96,
+        ("sty", Direct) => 0x84,
+        ("sty", Absolute) => 0x8C,
+        ("sty", DirectX) => 0x94,
+        ("stz", Direct) => 0x64,
+        ("stz", DirectX) => 0x74,
+        ("stz", Absolute) => 0x9C,
+        ("stz", AbsoluteX) => 0x9E,
+        ("cpx", Immediate) => 0xE0,
+        ("cpx", Direct) => 0xE4,
+        ("cpx", Absolute) => 0xEC,
+        ("cpy", Immediate) => 0xC0,
+        ("cpy", Direct) => 0xC4,
+        ("cpy", Absolute) => 0xCC,
+        ("bit", Immediate) => 0x89,
+        ("bit", Direct) => 0x24,
+        ("bit", Absolute) => 0x2C,
+        ("bit", DirectX) => 0x34,
+        ("bit", AbsoluteX) => 0x3C,
+        ("tsb", Direct) => 0x04,
+        ("tsb", Absolute) => 0x0C,
+        ("trb", Direct) => 0x14,
+        ("trb", Absolute) => 0x1C,
+        ("jmp", Absolute) => 0x4C,
+        ("jmp", AbsoluteIndirect) => 0x6C,
+        ("jmp", AbsoluteIndirectX) => 0x7C,
+        ("jmp" | "jml", Long) => 0x5C,
+        ("jmp" | "jml", AbsoluteIndirectLong) => 0xDC,
+        ("jsr", Absolute) => 0x20,
+        ("jsr", AbsoluteIndirectX) => 0xFC,
+        ("jsr" | "jsl", Long) => 0x22,
+        ("pea", Absolute) => 0xF4,
+        ("pei", DirectIndirect) => 0xD4,
+        ("per", RelativeLong) => 0x62,
+        ("rep", Immediate) => 0xC2,
+        ("sep", Immediate) => 0xE2,
+        ("bpl", Relative) => 0x10,
+        ("bmi", Relative) => 0x30,
+        ("bvc", Relative) => 0x50,
+        ("bvs", Relative) => 0x70,
+        ("bra", Relative) => 0x80,
+        ("bcc", Relative) => 0x90,
+        ("bcs", Relative) => 0xB0,
+        ("bne", Relative) => 0xD0,
+        ("beq", Relative) => 0xF0,
+        ("brl", RelativeLong) => 0x82,
+        (_, Implied) => match mnemonic {
+            // BRK and COP are followed by their signature byte like any other data
+            "brk" => 0x00,
+            "cop" => 0x02,
+            "php" => 0x08,
+            "phd" => 0x0B,
+            "clc" => 0x18,
+            "tcs" => 0x1B,
+            "plp" => 0x28,
+            "pld" => 0x2B,
+            "sec" => 0x38,
+            "tsc" => 0x3B,
+            "rti" => 0x40,
+            "pha" => 0x48,
+            "phk" => 0x4B,
+            "cli" => 0x58,
+            "phy" => 0x5A,
+            "tcd" => 0x5B,
+            "rts" => 0x60,
+            "pla" => 0x68,
+            "rtl" => 0x6B,
+            "sei" => 0x78,
+            "ply" => 0x7A,
+            "tdc" => 0x7B,
+            "dey" => 0x88,
+            "txa" => 0x8A,
+            "phb" => 0x8B,
+            "tya" => 0x98,
+            "txs" => 0x9A,
+            "txy" => 0x9B,
+            "tay" => 0xA8,
+            "tax" => 0xAA,
+            "plb" => 0xAB,
+            "clv" => 0xB8,
+            "tsx" => 0xBA,
+            "tyx" => 0xBB,
+            "iny" => 0xC8,
+            "dex" => 0xCA,
+            "wai" => 0xCB,
+            "cld" => 0xD8,
+            "phx" => 0xDA,
+            "stp" => 0xDB,
+            "inx" => 0xE8,
+            "nop" => 0xEA,
+            "xba" => 0xEB,
+            "sed" => 0xF8,
+            "plx" => 0xFA,
+            "xce" => 0xFB,
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpcOperand<'a> {
+    A,
+    X,
+    Y,
+    Sp,
+    Ya,
+    Psw,
+    Immediate(&'a str),
+    IndirectX,
+    IndirectXInc,
+    Direct(&'a str),
+    DirectX(&'a str),
+    DirectBit(&'a str, u8),
+    Absolute(&'a str),
+    AbsoluteX(&'a str),
+    AbsoluteY(&'a str),
+}
+
+/// Absolute addresses are prefixed with `!`, everything else is a direct page address or the
+/// target of a branch.
+fn parse_spc_operand(operand: &str) -> Result<SpcOperand<'_>, String> {
+    use SpcOperand::*;
+
+    let operand = operand.trim();
+    let lower = operand.to_ascii_lowercase();
+    Ok(match lower.as_str() {
+        "a" => A,
+        "x" => X,
+        "y" => Y,
+        "sp" => Sp,
+        "ya" => Ya,
+        "psw" => Psw,
+        "(x)" => IndirectX,
+        "(x)+" => IndirectXInc,
+        _ => {
+            if let Some(expr) = operand.strip_prefix('#') {
+                return Ok(Immediate(expr.trim()));
+            }
+            let (expr, index) = match lower.strip_suffix("+x").or(lower.strip_suffix("+y")) {
+                Some(expr) => (&operand[..expr.len()], &lower[expr.len() + 1..]),
+                None => (operand, ""),
+            };
+            match (expr.strip_prefix('!'), index) {
+                (Some(expr), "") => Absolute(expr),
+                (Some(expr), "x") => AbsoluteX(expr),
+                (Some(expr), _) => AbsoluteY(expr),
+                (None, "x") => DirectX(expr),
+                (None, "y") => return Err(format!("invalid operand {operand}")),
+                (None, _) => match expr.rsplit_once('.') {
+                    Some((expr, bit)) => {
+                        let bit = bit
+                            .parse()
+                            .ok()
+                            .filter(|&bit| bit < 8)
+                            .ok_or_else(|| format!("invalid bit {bit}"))?;
+                        DirectBit(expr, bit)
+                    }
+                    None => Direct(expr),
+                },
+            }
+        }
+    })
+}
@@ -0,0 +1,424 @@
+//! Headless runner for the test ROMs in `test-roms/`, which report their result in memory or on
+//! screen.
+
+pub mod asm;
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use snes_emu::{
+    Snes, audio,
+    cpu::{Interrupt, StepResult},
+    input::{InputDevice, Joypad, JoypadState, Multitap},
+    screenshot::{self, Screenshot},
+};
+
+/// More instructions than the CPU can execute within a frame, so the default budget of a test is
+/// only used up if frames stop finishing.
+const INSTRUCTIONS_PER_FRAME: u64 = 100_000;
+
+/// How a test ROM signals that it passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassCondition<'a> {
+    /// The byte at `addr` has the given value. This is checked after every frame, so the test
+    /// passes as soon as the value was written.
+    Memory { addr: u32, value: u8 },
+    /// The bytes starting at `addr` have the given values, like a [`PassCondition::Memory`] for
+    /// each of them.
+    MemoryRange { addr: u32, bytes: &'a [u8] },
+    /// The last frame matches the reference PNG.
+    Screenshot { reference: &'a str },
+    /// The last frame has the given [`Screenshot::hash`].
+    FrameHash { hash: u64 },
+    /// The opcode was executed exactly `count` times, counted with
+    /// [`InstructionStats`](snes_emu::cpu::InstructionStats).
+    Executed { opcode: u8, count: u64 },
+    /// Emulation stops with the given result before the frame limit is reached. The other
+    /// conditions are checked at that point. Any other result than [`StepResult::Stepped`] fails
+    /// the test, including [`StepResult::Unimplemented`].
+    Stopped { result: StepResult },
+    /// The number of stereo samples output over the whole test, at the rate set with
+    /// [`Snes::set_audio_sample_rate`].
+    AudioSamples { count: usize },
+    /// The audio output over the whole test has the given [`audio::hash_samples`], at the rate
+    /// set with [`Snes::set_audio_sample_rate`].
+    AudioHash { hash: u64 },
+    /// The trace log of the CPU consists of exactly the given lines, see
+    /// [`write_trace_line`](snes_emu::cpu::write_trace_line).
+    Trace { lines: &'a [&'a str] },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TestRom<'a> {
+    pub name: &'a str,
+    pub rom: &'a str,
+    /// Number of frames to run. For memory conditions, the test fails if the value wasn't written
+    /// by then.
+    pub frames: u32,
+    /// Recorded input of a joypad in port 1, as `(frame, state)` pairs sorted by frame. Each state
+    /// is held from the start of its frame until the next one. If empty, no joypad is connected.
+    pub inputs: &'a [(u32, JoypadState)],
+    /// Buttons held on the controllers of a multitap in port 2 for the whole test. If empty, no
+    /// multitap is connected.
+    pub multitap: &'a [JoypadState],
+    /// If set, execution jumps to this address using [`Snes::set_pc`] after the first instruction
+    /// of the reset handler, to test a routine in isolation.
+    pub entry: Option<u32>,
+    /// Interrupts raised at the start of the given frames, as `(frame, interrupt)` pairs.
+    pub interrupts: &'a [(u32, Interrupt)],
+    /// Number of instructions after which the test is stopped with
+    /// [`StepResult::BudgetExhausted`]. Defaults to [`INSTRUCTIONS_PER_FRAME`] per frame.
+    pub instruction_budget: Option<u64>,
+    /// Called before the first frame, to change options of the emulator which the ROM itself
+    /// can't, like the debugging toggles of the PPU.
+    pub setup: Option<fn(&mut Snes)>,
+    /// All conditions have to be met. If there are only memory conditions, the test passes as soon
+    /// as all of them are met, otherwise it always runs for the full number of frames.
+    pub pass: &'a [PassCondition<'a>],
+}
+
+#[derive(Debug)]
+pub enum TestFailure {
+    /// The ROM could not be loaded.
+    Rom(io::Error),
+    /// Emulation stopped before the frame limit was reached.
+    Stopped {
+        frame: u32,
+        result: StepResult,
+    },
+    /// The memory condition was not met within the frame limit.
+    Memory {
+        addr: u32,
+        expected: u8,
+        actual: Option<u8>,
+    },
+    Frame(screenshot::FrameMismatch),
+    FrameHash {
+        expected: u64,
+        actual: u64,
+    },
+    Executed {
+        opcode: u8,
+        expected: u64,
+        actual: u64,
+    },
+    /// Emulation was expected to stop, but ran until the frame limit.
+    NotStopped {
+        expected: StepResult,
+    },
+    AudioSamples {
+        expected: usize,
+        actual: usize,
+    },
+    AudioHash {
+        expected: u64,
+        actual: u64,
+    },
+    /// Line `line` (counted from 1) of the trace log differs, `None` stands for the end of the
+    /// log.
+    Trace {
+        line: usize,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+}
+
+impl fmt::Display for TestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rom(err) => write!(f, "failed to load ROM: {err}"),
+            Self::Stopped { frame, result } => {
+                write!(f, "emulation stopped in frame {frame}: {result:?}")
+            }
+            Self::Memory {
+                addr,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "expected {expected:02X} at {addr:06X}, found {actual:02X}"
+            ),
+            Self::Memory {
+                addr,
+                expected,
+                actual: None,
+            } => write!(f, "expected {expected:02X} at {addr:06X}, found open bus"),
+            Self::Frame(err) => err.fmt(f),
+            Self::FrameHash { expected, actual } => {
+                write!(f, "expected frame hash {expected:016X}, got {actual:016X}")
+            }
+            Self::Executed {
+                opcode,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "expected opcode {opcode:02X} to be executed {expected} times, got {actual}"
+            ),
+            Self::NotStopped { expected } => {
+                write!(f, "expected emulation to stop with {expected:?}")
+            }
+            Self::AudioSamples { expected, actual } => {
+                write!(f, "expected {expected} audio samples, got {actual}")
+            }
+            Self::AudioHash { expected, actual } => {
+                write!(f, "expected audio hash {expected:016X}, got {actual:016X}")
+            }
+            Self::Trace {
+                line,
+                expected,
+                actual,
+            } => {
+                match expected {
+                    Some(expected) => {
+                        writeln!(f, "expected trace line {line} to be\n  {expected}")?
+                    }
+                    None => writeln!(f, "expected the trace to end before line {line}")?,
+                }
+                match actual {
+                    Some(actual) => write!(f, "got\n  {actual}"),
+                    None => write!(f, "but the trace ended"),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for TestFailure {}
+
+impl TestRom<'_> {
+    /// Every option at its default, for the fields a test doesn't set. `name`, `rom`, `frames`
+    /// and `pass` are always set.
+    pub const DEFAULT: Self = Self {
+        name: "",
+        rom: "",
+        frames: 0,
+        inputs: &[],
+        multitap: &[],
+        entry: None,
+        interrupts: &[],
+        instruction_budget: None,
+        setup: None,
+        pass: &[],
+    };
+
+    /// Runs the test with the files in [`test_rom_dir`] and panics if it fails.
+    pub fn assert_passes(&self) {
+        if let Err(err) = self.run(&test_rom_dir()) {
+            panic!("{}: {err}", self.name);
+        }
+    }
+
+    /// Runs the test headlessly. Paths are resolved relative to `dir`.
+    pub fn run(&self, dir: &Path) -> Result<(), TestFailure> {
+        let rom = fs::read(dir.join(self.rom)).map_err(TestFailure::Rom)?;
+        let mut snes = Snes::new(rom.into_boxed_slice());
+        if let Some(setup) = self.setup {
+            setup(&mut snes);
+        }
+
+        let joypad_state = Rc::new(Cell::new(JoypadState::default()));
+        if !self.inputs.is_empty() {
+            let joypad_state = Rc::clone(&joypad_state);
+            snes.set_input1(Some(Box::new(Joypad::new(move || joypad_state.get()))));
+        }
+        if !self.multitap.is_empty() {
+            let mut ports: [Option<Box<dyn InputDevice>>; 4] = Default::default();
+            for (port, &state) in ports.iter_mut().zip(self.multitap) {
+                *port = Some(Box::new(Joypad::new(move || state)));
+            }
+            snes.set_input2(Some(Box::new(Multitap::new(ports))));
+        }
+        if let Some(entry) = self.entry {
+            snes.step();
+            snes.set_pc(entry);
+        }
+        if self
+            .pass
+            .iter()
+            .any(|pass| matches!(pass, PassCondition::Executed { .. }))
+        {
+            snes.cpu.debug.instruction_stats = Some(Default::default());
+        }
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        if self
+            .pass
+            .iter()
+            .any(|pass| matches!(pass, PassCondition::Trace { .. }))
+        {
+            snes.cpu.debug.trace = Some(Box::new(SharedBuffer(Rc::clone(&trace))));
+        }
+        let budget = u64::from(self.frames) * INSTRUCTIONS_PER_FRAME;
+        snes.set_instruction_budget(Some(self.instruction_budget.unwrap_or(budget)));
+        let mut inputs = self.inputs.iter().peekable();
+        let mut audio = Vec::new();
+
+        for frame in 0..self.frames {
+            while let Some(&(_, state)) = inputs.next_if(|&&(start, _)| start <= frame) {
+                joypad_state.set(state);
+            }
+            for &(_, interrupt) in self.interrupts.iter().filter(|&&(at, _)| at == frame) {
+                snes.cpu.raise_interrupt(interrupt);
+            }
+
+            let result = snes.run();
+            snes.take_audio_samples(&mut audio);
+            match result {
+                StepResult::Stepped => (),
+                // Includes StepResult::Unimplemented, tests which expect to hit an unimplemented
+                // feature have to say so with a Stopped condition
+                result => {
+                    let stopped = PassCondition::Stopped { result };
+                    if !self.pass.contains(&stopped) {
+                        return Err(TestFailure::Stopped { frame, result });
+                    }
+                    for pass in self.pass.iter().filter(|&&pass| pass != stopped) {
+                        check_condition(&snes, &audio, &trace.borrow(), dir, pass)?;
+                    }
+                    return Ok(());
+                }
+            }
+
+            let passed_early = self.pass.iter().all(|pass| match *pass {
+                PassCondition::Memory { .. } | PassCondition::MemoryRange { .. } => {
+                    check_condition(&snes, &audio, &[], dir, pass).is_ok()
+                }
+                _ => false,
+            });
+            if passed_early {
+                return Ok(());
+            }
+        }
+
+        for pass in self.pass {
+            check_condition(&snes, &audio, &trace.borrow(), dir, pass)?;
+        }
+        Ok(())
+    }
+}
+
+/// `audio` holds the interleaved stereo samples output over the whole test, `trace` the trace log
+/// if the conditions include [`PassCondition::Trace`].
+fn check_condition(
+    snes: &Snes,
+    audio: &[i16],
+    trace: &[u8],
+    dir: &Path,
+    pass: &PassCondition,
+) -> Result<(), TestFailure> {
+    match *pass {
+        PassCondition::Memory { addr, value } => {
+            let actual = snes_emu::cpu::memory::read_pure(snes, addr);
+            if actual == Some(value) {
+                return Ok(());
+            }
+            Err(TestFailure::Memory {
+                addr,
+                expected: value,
+                actual,
+            })
+        }
+        PassCondition::MemoryRange { addr, bytes } => {
+            for (&value, addr) in bytes.iter().zip(addr..) {
+                check_condition(
+                    snes,
+                    audio,
+                    trace,
+                    dir,
+                    &PassCondition::Memory { addr, value },
+                )?;
+            }
+            Ok(())
+        }
+        PassCondition::Screenshot { reference } => {
+            screenshot::compare_frame(snes, &dir.join(reference), 0).map_err(TestFailure::Frame)
+        }
+        PassCondition::FrameHash { hash } => {
+            let actual = Screenshot::capture(snes).hash();
+            if actual == hash {
+                return Ok(());
+            }
+            Err(TestFailure::FrameHash {
+                expected: hash,
+                actual,
+            })
+        }
+        PassCondition::Stopped { result } => Err(TestFailure::NotStopped { expected: result }),
+        PassCondition::Executed { opcode, count } => {
+            let actual =
+                snes.cpu.debug.instruction_stats.as_ref().unwrap().opcodes[usize::from(opcode)];
+            if actual == count {
+                return Ok(());
+            }
+            Err(TestFailure::Executed {
+                opcode,
+                expected: count,
+                actual,
+            })
+        }
+        PassCondition::AudioSamples { count } => {
+            let actual = audio.len() / 2;
+            if actual == count {
+                return Ok(());
+            }
+            Err(TestFailure::AudioSamples {
+                expected: count,
+                actual,
+            })
+        }
+        PassCondition::AudioHash { hash } => {
+            let actual = audio::hash_samples(audio);
+            if actual == hash {
+                return Ok(());
+            }
+            Err(TestFailure::AudioHash {
+                expected: hash,
+                actual,
+            })
+        }
+        PassCondition::Trace { lines } => {
+            let trace = String::from_utf8_lossy(trace);
+            let mut actual_lines = trace.lines();
+            let mut expected_lines = lines.iter();
+            let mut line = 0;
+            loop {
+                line += 1;
+                match (expected_lines.next(), actual_lines.next()) {
+                    (None, None) => return Ok(()),
+                    (Some(&expected), Some(actual)) if expected == actual => (),
+                    (expected, actual) => {
+                        return Err(TestFailure::Trace {
+                            line,
+                            expected: expected.map(|&line| line.to_owned()),
+                            actual: actual.map(str::to_owned),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Collects the trace log of a test, while the [`Snes`] owns the writer.
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The `test-roms` directory of the repository, which contains the ROMs and reference screenshots
+/// of the regression tests.
+pub fn test_rom_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms")
+}
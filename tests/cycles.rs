@@ -0,0 +1,68 @@
+use std::{fs, path::Path};
+
+use snes_emu::{Snes, cpu::memory::read_pure};
+
+fn load_cycles_rom() -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/cycles.sfc");
+    Snes::new(fs::read(path).unwrap().into_boxed_slice())
+}
+
+/// Runs one instruction and returns the number of master cycles it took.
+fn step_cycles(snes: &mut Snes) -> u64 {
+    let start = snes.cpu.cycles();
+    snes.step();
+    snes.cpu.cycles() - start
+}
+
+#[test]
+fn instruction_cycles() {
+    // Cycles of the instructions in cycles.asm, following the cycle counts of the 65816 datasheet
+    // with 8 master cycles per access and 6 per internal operation
+    const INSTRUCTIONS: [(&str, u64); 10] = [
+        ("CLC", 8 + 6),
+        ("XCE", 8 + 6),
+        ("REP #$30", 8 + 8 + 6),
+        ("LDA #$1234", 3 * 8),
+        ("STA $10", 4 * 8),
+        ("LDA $10", 4 * 8),
+        ("PHA", 8 + 6 + 2 * 8),
+        ("PLA", 8 + 2 * 6 + 2 * 8),
+        ("NOP", 8 + 6),
+        ("WAI", 8 + 2 * 6),
+    ];
+
+    let mut snes = load_cycles_rom();
+    // Processes the reset and executes SEI
+    snes.step();
+    for (instruction, cycles) in INSTRUCTIONS {
+        assert_eq!(step_cycles(&mut snes), cycles, "{instruction}");
+    }
+    // The halted CPU keeps waiting one internal operation at a time
+    assert_eq!(step_cycles(&mut snes), 6);
+}
+
+#[test]
+fn stp_cycles() {
+    let mut snes = load_cycles_rom();
+    snes.step();
+    snes.set_pc(0x008100);
+    assert_eq!(step_cycles(&mut snes), 8 + 2 * 6);
+    assert_eq!(step_cycles(&mut snes), 6);
+}
+
+#[test]
+fn jsr_absolute_x_indirect_cycles() {
+    let mut snes = load_cycles_rom();
+    snes.step();
+    snes.set_pc(0x008200);
+    assert_eq!(step_cycles(&mut snes), 2 * 8, "LDX #$02");
+    // Opcode, two pointer bytes, two pushes and two reads of the target, with one internal
+    // operation in between
+    assert_eq!(step_cycles(&mut snes), 7 * 8 + 6, "JSR ($8205,X)");
+
+    let s = snes.cpu.regs.s.get();
+    assert_eq!(snes.cpu.regs.pc.get(), 0x8209);
+    // The return address is the last byte of the JSR instruction
+    let pushed = [u32::from(s) + 1, u32::from(s) + 2].map(|addr| read_pure(&snes, addr).unwrap());
+    assert_eq!(pushed, [0x04, 0x82]);
+}
@@ -0,0 +1,100 @@
+//! The B-Bus registers written by each transfer unit mode of DMAP. All bytes transferred by a
+//! channel are distinct and a write watchpoint is set for one byte per unit, so stepping through
+//! the transfer one unit at a time reveals where each byte of a unit is written.
+
+use std::{fs, path::Path};
+
+use snes_emu::{
+    Snes,
+    cpu::{AccessKind, StepResult, Watchpoint, WatchpointHit, memory::write},
+};
+
+/// Transfers one unit per byte of a unit in `mode` to BBAD $26, and returns the B-Bus address
+/// the n-th byte of the n-th unit was written to.
+fn b_bus_addresses(mode: u8, unit_len: u8) -> Vec<u32> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/smoke.sfc");
+    let mut snes = Snes::new(fs::read(path).unwrap().into_boxed_slice());
+    // Processes the reset, the CPU doesn't run again until the DMA is finished
+    snes.step();
+
+    // Byte n of unit u is $10 * (u + 1) + n
+    let byte = |unit: u8, n: u8| 0x10 * (unit + 1) + n;
+    for unit in 0..unit_len {
+        for n in 0..unit_len {
+            let addr = 0x7E2000 + u32::from(unit * unit_len + n);
+            write(&mut snes, addr, byte(unit, n));
+        }
+        snes.cpu.debug.watchpoints.push(Watchpoint {
+            start: 0x002100,
+            end: 0x0021FF,
+            read: false,
+            write: true,
+            value: Some(byte(unit, unit)),
+        });
+    }
+    snes.cpu.debug.watch_dma = true;
+
+    write(&mut snes, 0x004300, mode); // DMAP0: A to B, increment
+    write(&mut snes, 0x004301, 0x26); // BBAD0: WH0, WH1, WH2 and WH3 have no side effects
+    write(&mut snes, 0x004302, 0x00);
+    write(&mut snes, 0x004303, 0x20);
+    write(&mut snes, 0x004304, 0x7E); // A1T0: $7E2000
+    write(&mut snes, 0x004305, unit_len * unit_len);
+    write(&mut snes, 0x004306, 0x00);
+    write(&mut snes, 0x00420B, 0x01); // MDMAEN
+
+    (0..unit_len)
+        .map(|unit| match snes.step() {
+            StepResult::WatchpointHit(WatchpointHit {
+                addr,
+                value,
+                kind: AccessKind::Write,
+            }) => {
+                assert_eq!(value, byte(unit, unit));
+                addr
+            }
+            result => panic!("unit {unit}: expected a watchpoint hit, got {result:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn mode0() {
+    assert_eq!(b_bus_addresses(0, 1), [0x2126]);
+}
+
+#[test]
+fn mode1() {
+    assert_eq!(b_bus_addresses(1, 2), [0x2126, 0x2127]);
+}
+
+#[test]
+fn mode2() {
+    assert_eq!(b_bus_addresses(2, 2), [0x2126, 0x2126]);
+}
+
+#[test]
+fn mode3() {
+    assert_eq!(b_bus_addresses(3, 4), [0x2126, 0x2126, 0x2127, 0x2127]);
+}
+
+#[test]
+fn mode4() {
+    assert_eq!(b_bus_addresses(4, 4), [0x2126, 0x2127, 0x2128, 0x2129]);
+}
+
+#[test]
+fn mode5() {
+    assert_eq!(b_bus_addresses(5, 4), [0x2126, 0x2127, 0x2126, 0x2127]);
+}
+
+// Modes 6 and 7 repeat modes 2 and 3
+#[test]
+fn mode6() {
+    assert_eq!(b_bus_addresses(6, 2), [0x2126, 0x2126]);
+}
+
+#[test]
+fn mode7() {
+    assert_eq!(b_bus_addresses(7, 4), [0x2126, 0x2126, 0x2127, 0x2127]);
+}
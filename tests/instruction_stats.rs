@@ -0,0 +1,26 @@
+use std::{fs, path::Path};
+
+use snes_emu::{Snes, cpu::InstructionStats};
+
+#[test]
+fn addresses_limited() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/nop_sled.sfc");
+    let mut snes = Snes::new(fs::read(path).unwrap().into_boxed_slice());
+    snes.cpu.debug.instruction_stats = Some(InstructionStats::default());
+    // A few more steps than there are instructions, STP keeps the CPU where it is
+    for _ in 0..3 * 0x7F00 + 10 {
+        snes.step();
+    }
+
+    let stats = snes.cpu.debug.instruction_stats.as_ref().unwrap();
+    // 3 * 0x7F00 NOPs, the two JMLs, SEI, CLC, XCE and STP
+    assert_eq!(stats.total(), 3 * 0x7F00 + 6);
+    let pcs = stats.hottest_pcs(usize::MAX);
+    assert_eq!(pcs.len(), InstructionStats::MAX_PCS);
+    // Every address was executed once, the ones past the limit are only counted in total
+    assert!(pcs.iter().all(|&(_, count, _)| count == 1));
+    assert_eq!(
+        stats.untracked,
+        stats.total() - InstructionStats::MAX_PCS as u64
+    );
+}
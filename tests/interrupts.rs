@@ -0,0 +1,60 @@
+use std::{fs, path::Path};
+
+use snes_emu::{
+    Snes,
+    cpu::{Interrupt, memory::read_pure},
+};
+
+const BRK_SITE: u32 = 0x8100;
+
+fn load(rom: &str) -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("test-roms/{rom}"));
+    Snes::new(fs::read(path).unwrap().into_boxed_slice())
+}
+
+fn run_to_brk() -> Snes {
+    let mut snes = load("brk_nmi.sfc");
+    while snes.cpu.regs.pc.get() as u32 != BRK_SITE {
+        snes.step();
+    }
+    snes
+}
+
+fn finish(snes: &mut Snes) -> [u8; 5] {
+    for _ in 0..100 {
+        snes.step();
+    }
+    let mut log = [0; 5];
+    for (i, byte) in log.iter_mut().enumerate() {
+        *byte = read_pure(snes, 0x7E0000 + i as u32).unwrap();
+    }
+    log
+}
+
+fn brk_handler(snes: &Snes) -> [u8; 2] {
+    [
+        read_pure(snes, 0xFFE6).unwrap(),
+        read_pure(snes, 0xFFE7).unwrap(),
+    ]
+}
+
+#[test]
+fn nmi_pending_at_brk_runs_first() {
+    let mut snes = run_to_brk();
+    snes.cpu.raise_interrupt(Interrupt::Nmi);
+    // The NMI is taken before the BRK executes, so it returns to the BRK
+    let log = finish(&mut snes);
+    assert_eq!(log, [0x01, 0x02, 0x00, 0x81, 0x55]);
+}
+
+#[test]
+fn nmi_during_brk_runs_before_brk_handler() {
+    let mut snes = run_to_brk();
+    snes.step();
+    snes.cpu.raise_interrupt(Interrupt::Nmi);
+    // The BRK has already pushed its frame, the NMI is taken before the first instruction of the
+    // BRK handler and returns to it
+    let log = finish(&mut snes);
+    let [lo, hi] = brk_handler(&snes);
+    assert_eq!(log, [0x01, 0x02, lo, hi, 0x55]);
+}
@@ -0,0 +1,98 @@
+//! Header detection and memory maps, tested with ROM images which are built in memory instead of
+//! assembled from `test-roms/`. The ExHiRom image would be far too large to check in.
+
+use snes_emu::{
+    CartridgeHeader, MappingMode, Region, Snes, cpu::memory::read_pure, detect_mapping,
+};
+
+/// Writes a header with a valid checksum to `rom[pos..]`. The checksum covers the whole image, so
+/// this has to be the last change.
+fn write_header(rom: &mut [u8], pos: usize, title: &[u8; 21], map_mode: u8, size_code: u8) {
+    let header = &mut rom[pos..pos + 64];
+    header[..21].copy_from_slice(title);
+    header[21] = map_mode;
+    header[23] = size_code;
+    header[24] = 0x03; // 8 KiB SRAM
+    header[25] = 0x01; // North America
+    header[0x3C..0x3E].copy_from_slice(&0x8000u16.to_le_bytes()); // reset vector
+    // The checksum and its complement always add up to 0x1FE, so they can be filled in last
+    header[28..32].copy_from_slice(&[0xFF, 0xFF, 0x00, 0x00]);
+    let checksum = rom
+        .iter()
+        .fold(0u16, |sum, &byte| sum.wrapping_add(byte.into()));
+    rom[pos + 28..pos + 30].copy_from_slice(&(!checksum).to_le_bytes());
+    rom[pos + 30..pos + 32].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Builds an 8 MiB ExHiRom image with a valid header at 0x40FFC0. The byte at the start of each
+/// 32 KiB half bank is 0x80 plus the number of the 64 KiB bank it is in.
+fn exhirom_image() -> Box<[u8]> {
+    let mut rom = vec![0; 0x800000];
+    for bank in 0..0x80 {
+        rom[bank << 16] = 0x80 | bank as u8;
+        rom[bank << 16 | 0x8000] = 0x80 | bank as u8;
+    }
+    write_header(&mut rom, 0x40FFC0, b"SNES-EMU EXHIROM     ", 0x35, 0x0D);
+    rom.into_boxed_slice()
+}
+
+#[test]
+fn lorom_detected() {
+    let mut rom = vec![0; 0x20000];
+    write_header(&mut rom, 0x7FC0, b"SNES-EMU LOROM       ", 0x20, 0x07);
+    assert_eq!(detect_mapping(&rom), MappingMode::LoRom);
+}
+
+#[test]
+fn hirom_detected_next_to_invalid_lorom_header() {
+    let mut rom = vec![0; 0x20000];
+    // Plausible at first glance, but the checksum doesn't match
+    rom[0x7FD5] = 0x20;
+    rom[0x7FFC..0x7FFE].copy_from_slice(&0x8000u16.to_le_bytes());
+    write_header(&mut rom, 0xFFC0, b"SNES-EMU HIROM       ", 0x21, 0x07);
+    assert_eq!(detect_mapping(&rom), MappingMode::HiRom);
+}
+
+#[test]
+fn copier_header_skipped() {
+    let mut rom = vec![0; 0x20000];
+    write_header(&mut rom, 0xFFC0, b"SNES-EMU HIROM       ", 0x21, 0x07);
+    let mut image = vec![0xFF; 512];
+    image.extend_from_slice(&rom);
+    assert_eq!(detect_mapping(&image), MappingMode::HiRom);
+
+    let snes = Snes::from_rom(image.into_boxed_slice());
+    assert_eq!(snes.cpu.mapping_mode, MappingMode::HiRom);
+    assert_eq!(
+        snes.header.cartridge_header(),
+        CartridgeHeader {
+            title: "SNES-EMU HIROM".into(),
+            rom_size: 0x20000,
+            ram_size: 0x2000,
+            region: Some(Region::NorthAmerica),
+        }
+    );
+}
+
+#[test]
+fn exhirom_detected() {
+    let snes = Snes::from_rom(exhirom_image());
+    assert_eq!(snes.header.mapping_mode, MappingMode::ExHiRom);
+    assert_eq!(snes.cpu.mapping_mode, MappingMode::ExHiRom);
+}
+
+#[test]
+fn exhirom_banks() {
+    let snes = Snes::new(exhirom_image());
+    // Banks 0xC0-0xFF map the first 4 MiB
+    assert_eq!(read_pure(&snes, 0xC00000), Some(0x80));
+    assert_eq!(read_pure(&snes, 0xFF8000), Some(0xBF));
+    // Banks 0x40-0x7D map the upper 4 MiB
+    assert_eq!(read_pure(&snes, 0x400000), Some(0xC0));
+    // Banks 0x00-0x3F map the upper half of each bank of the upper 4 MiB at 0x8000-0xFFFF
+    assert_eq!(read_pure(&snes, 0x208000), Some(0xE0));
+    assert_eq!(read_pure(&snes, 0x00FFFC), Some(0x00));
+    assert_eq!(read_pure(&snes, 0x00FFFD), Some(0x80));
+    // While banks 0x80-0xBF do the same for the first 4 MiB
+    assert_eq!(read_pure(&snes, 0xA08000), Some(0xA0));
+}
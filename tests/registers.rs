@@ -0,0 +1,91 @@
+use std::{fs, path::Path};
+
+use snes_emu::{Snes, cpu::memory::write, registers::RegisterState};
+
+fn load_smoke() -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/smoke.sfc");
+    Snes::new(fs::read(path).unwrap().into_boxed_slice())
+}
+
+#[test]
+fn export_import_round_trip() {
+    let mut snes = load_smoke();
+    for _ in 0..3 {
+        snes.run();
+    }
+    let state = RegisterState::capture(&snes);
+
+    let mut other = load_smoke();
+    other.import_registers(&snes.export_registers()).unwrap();
+    assert_eq!(RegisterState::capture(&other), state);
+}
+
+#[test]
+fn apply_emulation_mode_forces_flags() {
+    let mut snes = load_smoke();
+    let mut state = RegisterState::capture(&snes);
+    state.cpu.e = true;
+    state.cpu.p = 0x00;
+    state.cpu.s = 0x0ABC;
+    state.cpu.x = 0x1234;
+    state.cpu.y = 0x5678;
+    state.apply(&mut snes);
+
+    let applied = RegisterState::capture(&snes);
+    assert_eq!(applied.cpu.p, 0x30);
+    assert_eq!(applied.cpu.s, 0x01BC);
+    assert_eq!(applied.cpu.x, 0x0034);
+    assert_eq!(applied.cpu.y, 0x0078);
+}
+
+#[test]
+fn apply_native_mode_truncates_index_registers() {
+    let mut snes = load_smoke();
+    let mut state = RegisterState::capture(&snes);
+    state.cpu.e = false;
+    state.cpu.p = 0x10;
+    state.cpu.x = 0x1234;
+    state.apply(&mut snes);
+    assert_eq!(RegisterState::capture(&snes).cpu.x, 0x0034);
+
+    state.cpu.p = 0x00;
+    state.apply(&mut snes);
+    assert_eq!(RegisterState::capture(&snes).cpu.x, 0x1234);
+}
+
+#[test]
+fn apply_reloads_oam_address() {
+    let mut snes = load_smoke();
+    for (i, byte) in snes.ppu.oam.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    let mut state = RegisterState::capture(&snes);
+    state.ppu.oamaddl = 0x10;
+    state.ppu.oamaddh = 0x00;
+    state.apply(&mut snes);
+    assert_eq!(snes.ppu.read_pure(0x2138), Some(0x20));
+
+    state.ppu.oamaddh = 0x01;
+    state.ppu.oamaddl = 0x03;
+    state.apply(&mut snes);
+    assert_eq!(snes.ppu.read_pure(0x2138), Some(0x06));
+}
+
+#[test]
+fn reset_clears_scroll_latch() {
+    let mut snes = load_smoke();
+    snes.step();
+    write(&mut snes, 0x00210D, 0xFF);
+
+    snes.reset();
+    // Processes the reset interrupt
+    snes.step();
+    assert_eq!(snes.ppu.backgrounds.backgrounds[0].h_offset, 0);
+
+    // The first write after the reset combines with an empty latch instead of 0xFF
+    write(&mut snes, 0x00210D, 0x34);
+    assert_eq!(snes.ppu.backgrounds.backgrounds[0].h_offset, 0x3400);
+    write(&mut snes, 0x00210D, 0x01);
+    assert_eq!(snes.ppu.backgrounds.backgrounds[0].h_offset, 0x0134);
+}
@@ -0,0 +1,55 @@
+//! Example of a PPU regression test which compares the output against a reference screenshot
+//! directly, without going through the test ROM runner.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use snes_emu::{
+    Snes,
+    screenshot::{self, FrameMismatch},
+};
+
+fn test_rom_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms")
+}
+
+fn run_frames(rom: &str, frames: usize) -> Snes {
+    let rom = fs::read(test_rom_dir().join(rom)).unwrap();
+    let mut snes = Snes::new(rom.into_boxed_slice());
+    for _ in 0..frames {
+        snes.run();
+    }
+    snes
+}
+
+#[test]
+fn frame_matches_reference() {
+    let snes = run_frames("window_select.sfc", 3);
+    screenshot::assert_frame_matches(&snes, test_rom_dir().join("window_select.png"));
+}
+
+#[test]
+fn mismatch_writes_diff_image() {
+    // Work on a copy, so the diff image doesn't end up next to the real references
+    let dir = env::temp_dir().join(format!("snes-emu-screenshot-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let reference = dir.join("reference.png");
+    fs::copy(test_rom_dir().join("window_select.png"), &reference).unwrap();
+
+    let snes = run_frames("backdrop_math.sfc", 3);
+    let result = screenshot::compare_frame(&snes, &reference, 0);
+    let Err(FrameMismatch::Pixels {
+        num_pixels,
+        diff_path,
+    }) = result
+    else {
+        panic!("expected differing pixels, got {result:?}");
+    };
+    assert!(num_pixels > 0);
+    assert_eq!(diff_path, dir.join("reference.diff.png"));
+    assert!(diff_path.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
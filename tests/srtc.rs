@@ -0,0 +1,73 @@
+use std::{fs, path::Path};
+
+use snes_emu::{
+    Snes,
+    cpu::memory::{read, write},
+    srtc::RtcTime,
+};
+
+fn load_srtc_rom() -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-roms/srtc.sfc");
+    Snes::new(fs::read(path).unwrap().into_boxed_slice())
+}
+
+#[test]
+fn read_time_digits() {
+    let mut snes = load_srtc_rom();
+    snes.srtc_mut().unwrap().set_time(RtcTime {
+        year: 2024,
+        month: 3,
+        day: 15,
+        hour: 13,
+        minute: 47,
+        second: 29,
+    });
+
+    write(&mut snes, 0x002801, 0x0D);
+    let digits: Vec<u8> = (0..15).map(|_| read(&mut snes, 0x002800)).collect();
+    assert_eq!(
+        digits,
+        [
+            0x0F, // marker
+            0x09, 0x02, // second
+            0x07, 0x04, // minute
+            0x03, 0x01, // hour
+            0x05, 0x01, // day
+            0x03, // month
+            0x04, 0x02, 0x0A, // year - 1000
+            0x05, // Friday
+            0x0F, // marker
+        ]
+    );
+}
+
+#[test]
+fn write_time_digits() {
+    let mut snes = load_srtc_rom();
+    write(&mut snes, 0x002801, 0x0E);
+    write(&mut snes, 0x002801, 0x00);
+    for digit in [
+        0x08, 0x05, 0x03, 0x02, 0x02, 0x00, 0x09, 0x02, 0x02, 0x06, 0x09, 0x09,
+    ] {
+        write(&mut snes, 0x002801, digit);
+    }
+    write(&mut snes, 0x002801, 0x0D);
+
+    assert_eq!(
+        snes.srtc_mut().unwrap().time(),
+        RtcTime {
+            year: 1996,
+            month: 2,
+            day: 29,
+            hour: 2,
+            minute: 23,
+            second: 58,
+        }
+    );
+    // The day of the week is calculated from the date, 1996-02-29 was a Thursday
+    assert_eq!(read(&mut snes, 0x002800), 0x0F);
+    for _ in 0..12 {
+        read(&mut snes, 0x002800);
+    }
+    assert_eq!(read(&mut snes, 0x002800), 0x04);
+}
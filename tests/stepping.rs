@@ -0,0 +1,84 @@
+use std::{fs, path::Path};
+
+use snes_emu::{Snes, apu::master_to_apu_cycles, cpu::StepResult};
+
+fn load(rom: &str) -> Snes {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("test-roms/{rom}"));
+    Snes::new(fs::read(path).unwrap().into_boxed_slice())
+}
+
+#[test]
+fn step_apu_keeps_cpu_paused() {
+    let mut snes = load("smoke.sfc");
+    // Processes the reset of both CPUs
+    snes.step();
+    let cpu_pc = snes.cpu.regs.pc.get();
+    let cpu_cycles = snes.cpu.cycles();
+    let apu_pc = snes.apu.pc;
+    let apu_cycles = snes.apu.cycles();
+
+    assert_eq!(snes.step_apu(1), StepResult::Stepped);
+    assert_ne!(snes.apu.pc, apu_pc);
+    assert!(snes.apu.cycles() > apu_cycles);
+    assert_eq!(snes.cpu.regs.pc.get(), cpu_pc);
+    assert_eq!(snes.cpu.cycles(), cpu_cycles);
+}
+
+#[test]
+fn paused_apu_keeps_still() {
+    let mut snes = load("smoke.sfc");
+    snes.step();
+    snes.apu.paused = true;
+    let cpu_pc = snes.cpu.regs.pc.get();
+    let apu_pc = snes.apu.pc;
+    let apu_cycles = snes.apu.cycles();
+
+    for _ in 0..10 {
+        snes.step();
+    }
+    assert_ne!(snes.cpu.regs.pc.get(), cpu_pc);
+    assert_eq!(snes.apu.pc, apu_pc);
+    assert_eq!(snes.apu.cycles(), apu_cycles);
+
+    // Once resumed, the APU catches up on the time it missed
+    let target = |snes: &Snes| master_to_apu_cycles(snes.cpu.cycles(), snes.ppu.variant);
+    assert!(snes.apu.cycles() < target(&snes));
+    snes.apu.paused = false;
+    snes.step();
+    assert!(snes.apu.cycles() >= target(&snes));
+}
+
+#[test]
+fn run_frame_follows_overscan() {
+    // overscan.sfc enables overscan during the second frame and disables it during the fourth
+    let mut snes = load("overscan.sfc");
+    let heights: Vec<u16> = (0..4)
+        .map(|_| {
+            snes.run_frame();
+            // Frames end on the last visible line
+            assert_eq!(snes.cpu.v_counter(), snes.ppu.output_height());
+            snes.ppu.output_height()
+        })
+        .collect();
+    assert_eq!(heights, [224, 239, 239, 224]);
+}
+
+#[test]
+fn run_frame_stops_at_budget() {
+    let mut snes = load("runaway.sfc");
+    snes.set_instruction_budget(Some(1000));
+    assert_eq!(snes.run_frame(), StepResult::BudgetExhausted);
+
+    // Nothing is executed anymore, however often it is called
+    let cycles = snes.cpu.cycles();
+    let pc = snes.cpu.regs.pc.get();
+    assert_eq!(snes.run_frame(), StepResult::BudgetExhausted);
+    assert_eq!(snes.run_cycles(10_000), StepResult::BudgetExhausted);
+    assert_eq!(snes.step(), StepResult::BudgetExhausted);
+    assert_eq!(snes.cpu.cycles(), cycles);
+    assert_eq!(snes.cpu.regs.pc.get(), pc);
+
+    // The frame continues once the limit is lifted
+    snes.set_instruction_budget(None);
+    assert_eq!(snes.run_frame(), StepResult::Stepped);
+}
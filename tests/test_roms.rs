@@ -0,0 +1,1320 @@
+//! Runs the test ROMs in `test-roms/` headlessly and checks their results.
+
+mod common;
+
+use std::{env, fs};
+
+use common::{PassCondition, TestRom, asm, test_rom_dir};
+use snes_emu::{
+    cpu::{AccessKind, Interrupt, StepResult, Watchpoint, WatchpointHit},
+    input::{JoypadState, SuperScope, SuperScopeState},
+};
+
+const PRESS_B: JoypadState = JoypadState {
+    button_b: true,
+    ..RELEASED
+};
+
+const PRESS_Y: JoypadState = JoypadState {
+    button_y: true,
+    ..RELEASED
+};
+
+const PRESS_A: JoypadState = JoypadState {
+    button_a: true,
+    ..RELEASED
+};
+
+const PRESS_X: JoypadState = JoypadState {
+    button_x: true,
+    ..RELEASED
+};
+
+const PRESS_R: JoypadState = JoypadState {
+    button_r: true,
+    ..RELEASED
+};
+
+const PRESS_B_R: JoypadState = JoypadState {
+    button_b: true,
+    button_r: true,
+    ..RELEASED
+};
+
+const RELEASED: JoypadState = JoypadState {
+    button_b: false,
+    button_y: false,
+    button_select: false,
+    button_start: false,
+    dpad_up: false,
+    dpad_down: false,
+    dpad_left: false,
+    dpad_right: false,
+    button_a: false,
+    button_x: false,
+    button_l: false,
+    button_r: false,
+};
+
+/// Every ROM has to be the assembled source next to it. Run with `UPDATE_TEST_ROMS=1` to write
+/// the assembled ROMs instead.
+#[test]
+fn roms_match_sources() {
+    let update = env::var_os("UPDATE_TEST_ROMS").is_some();
+    let mut mismatches = Vec::new();
+    for entry in fs::read_dir(test_rom_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "asm") {
+            continue;
+        }
+        let rom = asm::assemble(&path).unwrap_or_else(|err| panic!("{err}"));
+        let rom_path = path.with_extension("sfc");
+        if update {
+            fs::write(&rom_path, rom).unwrap();
+        } else if fs::read(&rom_path).ok().as_ref() != Some(&rom) {
+            mismatches.push(rom_path);
+        }
+    }
+    assert!(mismatches.is_empty(), "out of date: {mismatches:?}");
+}
+
+#[test]
+fn smoke() {
+    TestRom {
+        name: "smoke",
+        rom: "smoke.sfc",
+        frames: 10,
+        pass: &[PassCondition::Memory {
+            addr: 0x7E0000,
+            value: 0x55,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The trace log of the first instructions, in the format of bsnes and Mesen
+#[test]
+fn trace() {
+    TestRom {
+        name: "trace",
+        rom: "smoke.sfc",
+        frames: 1,
+        setup: Some(|snes| snes.cpu.debug.trace_range = Some(0x008000..=0x008008)),
+        pass: &[PassCondition::Trace {
+            lines: &[
+                "008000 SEI             A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzc V:  0 H:   0",
+                "008001 CLC             A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzc V:  0 H:  16",
+                "008002 XCE             A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzc V:  0 H:  28",
+                "008003 REP #$30        A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzC V:  0 H:  44",
+                "008005 LDA #$1234      A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvmxdIzC V:  0 H:  64",
+                "008008 CLC             A:1234 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvmxdIzC V:  0 H:  88",
+            ],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Only the three instructions within the range are traced
+#[test]
+fn trace_range() {
+    TestRom {
+        name: "trace_range",
+        rom: "smoke.sfc",
+        frames: 1,
+        setup: Some(|snes| snes.cpu.debug.trace_range = Some(0x008011..=0x008015)),
+        pass: &[PassCondition::Trace {
+            lines: &[
+                "008011 SEP #$30        A:5555 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvmxdIZC V:  0 H: 168",
+                "008013 LDA #$12        A:5555 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIZC V:  0 H: 188",
+                "008015 STA $4202       A:5512 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzC V:  0 H: 204",
+            ],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Direct page accesses take one extra cycle when DL isn't zero, visible in the H positions
+#[test]
+fn dp_penalty() {
+    TestRom {
+        name: "dp_penalty",
+        rom: "dp_penalty.sfc",
+        frames: 1,
+        setup: Some(|snes| snes.cpu.debug.trace_range = Some(0x008003..=0x00800F)),
+        pass: &[PassCondition::Trace {
+            lines: &[
+                "008003 LDA $10         A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIzC V:  0 H:  44",
+                "008005 LDA $10,X       A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIZC V:  0 H:  68",
+                "008007 PEA $0080       A:0000 X:0000 Y:0000 S:01FD D:0000 DB:00 P:nvMXdIZC V:  0 H:  96",
+                "00800A PLD             A:0000 X:0000 Y:0000 S:01FB D:0000 DB:00 P:nvMXdIZC V:  0 H: 136",
+                "00800B LDA $10         A:0000 X:0000 Y:0000 S:01FD D:0080 DB:00 P:nvMXdIzC V:  0 H: 172",
+                "00800D LDA $10,X       A:0000 X:0000 Y:0000 S:01FD D:0080 DB:00 P:nvMXdIZC V:  0 H: 204",
+                "00800F LDA #$55        A:0000 X:0000 Y:0000 S:01FD D:0080 DB:00 P:nvMXdIZC V:  0 H: 240",
+            ],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Unmapped addresses return the last value on the data bus instead of stopping emulation
+#[test]
+fn open_bus() {
+    TestRom {
+        name: "open_bus",
+        rom: "open_bus.sfc",
+        frames: 2,
+        pass: &[PassCondition::Memory {
+            addr: 0x7E0000,
+            value: 0x22,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// A 96 KiB ROM is mapped as banks 0-2, bank 2 again and then all of that mirrored
+#[test]
+fn rom_mirror() {
+    TestRom {
+        name: "rom_mirror",
+        rom: "rom_mirror.sfc",
+        frames: 2,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0xA2, 0xA0, 0xA1, 0xA2, 0xA2, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn sram_mirror() {
+    TestRom {
+        name: "sram_mirror",
+        rom: "sram_mirror.sfc",
+        frames: 2,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x11, 0x22, 0x33, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// $01:0000 mirrors the WRAM at $00:0000, so reading the pointer from there would go unnoticed
+// without the watchpoint
+#[test]
+fn jmp_wrap() {
+    TestRom {
+        name: "jmp_wrap",
+        rom: "jmp_wrap.sfc",
+        frames: 2,
+        setup: Some(|snes| {
+            snes.cpu.debug.watchpoints.push(Watchpoint {
+                start: 0x010000,
+                end: 0x010001,
+                read: true,
+                write: false,
+                value: None,
+            })
+        }),
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0010,
+            bytes: &[0x11, 0x22, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn stack() {
+    TestRom {
+        name: "stack",
+        rom: "stack.sfc",
+        frames: 2,
+        pass: &[PassCondition::Memory {
+            addr: 0x7E0000,
+            value: 0x55,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Starts at the routine in the FastROM mirror instead of the reset handler
+#[test]
+fn entry() {
+    TestRom {
+        name: "entry",
+        rom: "entry.sfc",
+        frames: 2,
+        entry: Some(0x808100),
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x55, 0x80],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn histogram() {
+    TestRom {
+        name: "histogram",
+        rom: "histogram.sfc",
+        frames: 2,
+        pass: &[
+            PassCondition::Executed {
+                opcode: 0x78,
+                count: 1,
+            },
+            PassCondition::Executed {
+                opcode: 0xCA,
+                count: 10,
+            },
+            PassCondition::Executed {
+                opcode: 0xD0,
+                count: 10,
+            },
+            PassCondition::Executed {
+                opcode: 0xCB,
+                count: 1,
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn irq_stack() {
+    TestRom {
+        name: "irq_stack",
+        rom: "irq_stack.sfc",
+        frames: 2,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x80, 0x1A, 0xFC, 0x00, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn wai_masked() {
+    TestRom {
+        name: "wai_masked",
+        rom: "wai_masked.sfc",
+        frames: 1,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x01, 0x00, 0x80, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn abort() {
+    TestRom {
+        name: "abort",
+        rom: "abort.sfc",
+        frames: 3,
+        interrupts: &[(1, Interrupt::Abort), (2, Interrupt::Abort)],
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x80, 0x0B, 0x35, 0xFB, 0x02],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn brk() {
+    TestRom {
+        name: "brk",
+        rom: "brk.sfc",
+        frames: 2,
+        pass: &[
+            PassCondition::MemoryRange {
+                addr: 0x7E0000,
+                bytes: &[
+                    0x00, 0x00, 0x00, 0x00, 0x09, 0x80, 0x0F, 0x80, 0x17, 0x80, 0x21, 0x80, 0x34,
+                    0x80,
+                ],
+            },
+            PassCondition::MemoryRange {
+                addr: 0x7E000F,
+                bytes: &[0x34, 0x20, 0x55],
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn bit() {
+    TestRom {
+        name: "bit",
+        rom: "bit.sfc",
+        frames: 1,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0xC0, 0xC2, 0x42, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn per() {
+    TestRom {
+        name: "per",
+        rom: "per.sfc",
+        frames: 1,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x90, 0x00, 0x80, 0x02, 0x10, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn index_width() {
+    TestRom {
+        name: "index_width",
+        rom: "index_width.sfc",
+        frames: 1,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x34, 0x00, 0x78, 0x00, 0xCD, 0x00, 0xF0, 0x01, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn reset_stack() {
+    TestRom {
+        name: "reset_stack",
+        rom: "reset_stack.sfc",
+        frames: 3,
+        interrupts: &[(1, Interrupt::Reset)],
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0001,
+            bytes: &[0xED, 0xAA, 0xAA, 0xAA, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn xba() {
+    TestRom {
+        name: "xba",
+        rom: "xba.sfc",
+        frames: 2,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x12, 0x34, 0x00, 0x80, 0x02, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// M7A and the last byte written to M7B are multiplied as signed values
+#[test]
+fn m7_multiply() {
+    TestRom {
+        name: "m7_multiply",
+        rom: "m7_multiply.sfc",
+        frames: 2,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x80, 0x00, 0x00, 0x00, 0x40, 0x00, 0xE6, 0xF6, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Writing either half of VMADD fills the VMDATA read latch
+#[test]
+fn vram_prefetch() {
+    TestRom {
+        name: "vram_prefetch",
+        rom: "vram_prefetch.sfc",
+        frames: 1,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[
+                0xEF, 0xBE, 0xEF, 0xBE, 0xFE, 0xCA, 0xEF, 0xBE, 0xEF, 0xEF, 0xFE, 0x55,
+            ],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The WMDATA address wraps from $1FFFF to $00000 on writes and reads
+#[test]
+fn wmdata_wrap() {
+    TestRom {
+        name: "wmdata_wrap",
+        rom: "wmdata_wrap.sfc",
+        frames: 2,
+        pass: &[
+            PassCondition::MemoryRange {
+                addr: 0x7FFFFE,
+                bytes: &[0x11, 0x22],
+            },
+            PassCondition::MemoryRange {
+                addr: 0x7E0000,
+                bytes: &[0x33, 0x22, 0x33, 0x55],
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn dmap() {
+    TestRom {
+        name: "dmap",
+        rom: "dmap.sfc",
+        frames: 2,
+        pass: &[PassCondition::Memory {
+            addr: 0x7E0000,
+            value: 0x55,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn oam_dma() {
+    TestRom {
+        name: "oam_dma",
+        rom: "oam_dma.sfc",
+        frames: 1,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[
+                0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x00, 0x00, 0xAA, 0xBB, 0x55,
+            ],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn oam_reload() {
+    TestRom {
+        name: "oam_reload",
+        rom: "oam_reload.sfc",
+        frames: 4,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x11, 0x33, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn backdrop_math() {
+    TestRom {
+        name: "backdrop_math",
+        rom: "backdrop_math.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0xA5FCE5EC32723124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The plain backdrop color (red 16) everywhere, even inside the color window
+#[test]
+fn backdrop_math_disabled() {
+    TestRom {
+        name: "backdrop_math_disabled",
+        rom: "backdrop_math.sfc",
+        frames: 3,
+        setup: Some(|snes| snes.ppu.disable_color_math = true),
+        pass: &[PassCondition::FrameHash {
+            hash: 0x3C1D857820597124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Red on the left half (BG1) and green on the right half (BG3)
+#[test]
+fn bg3_priority() {
+    TestRom {
+        name: "bg3_priority",
+        rom: "bg3_priority.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0x37907F4B48BE7124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Black above line 100, the backdrop without objects on line 100 and the objects from line 101 on
+#[test]
+fn obj_forced_blank() {
+    TestRom {
+        name: "obj_forced_blank",
+        rom: "obj_forced_blank.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0xB99DBEFB7D202224,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn sprite_flags() {
+    TestRom {
+        name: "sprite_flags",
+        rom: "sprite_flags.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x40, 0xC0, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn sprite_flags_limits_disabled() {
+    TestRom {
+        name: "sprite_flags_limits_disabled",
+        rom: "sprite_flags.sfc",
+        frames: 10,
+        setup: Some(|snes| snes.ppu.disable_sprite_limits = true),
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x40, 0xC0, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The wrapped around write sets the backdrop to red 16, so the frame is the same as without color
+// math in backdrop_math
+#[test]
+fn cgdata() {
+    TestRom {
+        name: "cgdata",
+        rom: "cgdata.sfc",
+        frames: 3,
+        pass: &[
+            PassCondition::MemoryRange {
+                addr: 0x7E0000,
+                bytes: &[0x00, 0x7C, 0x10, 0x00, 0x55],
+            },
+            PassCondition::FrameHash {
+                hash: 0x3C1D857820597124,
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Alternating blue (sub screen) and red (main screen) columns
+#[test]
+fn pseudo_hires() {
+    TestRom {
+        name: "pseudo_hires",
+        rom: "pseudo_hires.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0xFE0CCD27CF07F124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn mosaic() {
+    TestRom {
+        name: "mosaic",
+        rom: "mosaic.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0x24EF77D987ED9124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// A white staircase in every 8x8 block, from tiles written through the VMAIN address translation
+#[test]
+fn vram_translation() {
+    TestRom {
+        name: "vram_translation",
+        rom: "vram_translation.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0x5A8DB399B47ED924,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// A rotated Mode 7 tile map, with the transparent and the tile 0 screen over modes
+#[test]
+fn mode7() {
+    TestRom {
+        name: "mode7",
+        rom: "mode7.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0xECB9B5E34B07E0E4,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn mode5() {
+    TestRom {
+        name: "mode5",
+        rom: "mode5.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0x5870F0D3329EB124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn offset_per_tile() {
+    TestRom {
+        name: "offset_per_tile",
+        rom: "offset_per_tile.sfc",
+        frames: 3,
+        pass: &[PassCondition::FrameHash {
+            hash: 0xABA875FF219B5024,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// BG1HOFS is $0115: bits 3-7 come from the BG2HOFS write in between, bits 0-2 from the
+// first BG1HOFS write
+#[test]
+fn scroll_latch_bg1() {
+    TestRom {
+        name: "scroll_latch_bg1",
+        rom: "scroll_latch.sfc",
+        frames: 10,
+        pass: &[
+            PassCondition::Memory {
+                addr: 0x7E0000,
+                value: 0x55,
+            },
+            PassCondition::FrameHash {
+                hash: 0x72A019444CBFA124,
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// M7HOFS is $012D, the BG2HOFS write doesn't touch the Mode 7 latch
+#[test]
+fn scroll_latch_mode7() {
+    TestRom {
+        name: "scroll_latch_mode7",
+        rom: "scroll_latch.sfc",
+        frames: 10,
+        inputs: &[(0, PRESS_B)],
+        pass: &[
+            PassCondition::Memory {
+                addr: 0x7E0000,
+                value: 0x55,
+            },
+            PassCondition::FrameHash {
+                hash: 0x336956F14533A124,
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// One band per layer, each masked by a different combination of the window selection bits
+#[test]
+fn window_select() {
+    TestRom {
+        name: "window_select",
+        rom: "window_select.sfc",
+        frames: 3,
+        pass: &[PassCondition::Screenshot {
+            reference: "window_select.png",
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// No layer is masked and the color window is empty, so the backdrop below the bands stays grey
+#[test]
+fn window_select_disabled() {
+    TestRom {
+        name: "window_select_disabled",
+        rom: "window_select.sfc",
+        frames: 3,
+        setup: Some(|snes| snes.ppu.disable_windows = true),
+        pass: &[PassCondition::FrameHash {
+            hash: 0xF3BB6F8BE22A8D24,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn ipl_handshake() {
+    TestRom {
+        name: "ipl_handshake",
+        rom: "ipl_handshake.sfc",
+        frames: 3,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x25, 0x00, 0xAA, 0xBB, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn apu_cycles() {
+    TestRom {
+        name: "apu_cycles",
+        rom: "apu_cycles.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x0F, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn spc_timers() {
+    TestRom {
+        name: "spc_timers",
+        rom: "spc_timers.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x03, 0x02, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn apu_port_mirror() {
+    TestRom {
+        name: "apu_port_mirror",
+        rom: "apu_port_mirror.sfc",
+        frames: 2,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0xAA, 0xBB, 0x00, 0x00, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn apu_port_clear() {
+    TestRom {
+        name: "apu_port_clear",
+        rom: "apu_port_clear.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x33, 0x00, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Writing anything but the power-on value to TEST stops emulation with an error
+#[test]
+fn apu_test_register() {
+    TestRom {
+        name: "apu_test_register",
+        rom: "apu_test_register.sfc",
+        frames: 10,
+        pass: &[PassCondition::Stopped {
+            result: StepResult::Unimplemented("APU TEST register"),
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn apu_x_wrap() {
+    TestRom {
+        name: "apu_x_wrap",
+        rom: "apu_x_wrap.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0xA5, 0x01, 0x5A, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn dsp_readback() {
+    TestRom {
+        name: "dsp_readback",
+        rom: "dsp_readback.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x7F, 0x01, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// A 2 kHz square wave, resampled from 32 kHz
+#[test]
+fn apu_tone() {
+    TestRom {
+        name: "apu_tone",
+        rom: "apu_tone.sfc",
+        frames: 60,
+        setup: Some(|snes| snes.set_audio_sample_rate(44100)),
+        pass: &[
+            PassCondition::Memory {
+                addr: 0x7E0000,
+                value: 0x55,
+            },
+            PassCondition::AudioSamples { count: 43796 },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The same tone at the native rate, hashed to catch any change of the DSP output
+#[test]
+fn apu_tone_hash() {
+    TestRom {
+        name: "apu_tone_hash",
+        rom: "apu_tone.sfc",
+        frames: 60,
+        pass: &[
+            PassCondition::Memory {
+                addr: 0x7E0000,
+                value: 0x55,
+            },
+            PassCondition::AudioHash {
+                hash: 0xAA445CC54EAAAD55,
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn apu_x_inc() {
+    TestRom {
+        name: "apu_x_inc",
+        rom: "apu_x_inc.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0xC3, 0x42, 0x42, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Stores leave the flags alone, and the dummy read of MOVW dp,YA clears T0OUT
+#[test]
+fn apu_mov_flags() {
+    TestRom {
+        name: "apu_mov_flags",
+        rom: "apu_mov_flags.sfc",
+        frames: 10,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x02, 0x80, 0x00, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Even rows are red and odd rows blue, every field only covers half of them
+#[test]
+fn interlace() {
+    TestRom {
+        name: "interlace",
+        rom: "interlace.sfc",
+        frames: 4,
+        pass: &[PassCondition::FrameHash {
+            hash: 0xCAB082080973F124,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn overscan() {
+    TestRom {
+        name: "overscan",
+        rom: "overscan.sfc",
+        frames: 6,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0xE1, 0x00, 0xF0, 0x00, 0xF0, 0xE1, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn hdma_overscan() {
+    TestRom {
+        name: "hdma_overscan",
+        rom: "hdma_overscan.sfc",
+        frames: 6,
+        pass: &[
+            PassCondition::MemoryRange {
+                addr: 0x7E10E0,
+                bytes: &[0x01, 0x00],
+            },
+            PassCondition::MemoryRange {
+                addr: 0x7E11EF,
+                bytes: &[0x01, 0x00],
+            },
+            PassCondition::MemoryRange {
+                addr: 0x7E0000,
+                bytes: &[0x55],
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn timeup() {
+    TestRom {
+        name: "timeup",
+        rom: "timeup.sfc",
+        frames: 6,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x80, 0x01, 0x00, 0x00, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// B is the first bit of the report and ends up in bit 7 of JOY1H
+#[test]
+fn joypad_b() {
+    TestRom {
+        name: "joypad_b",
+        rom: "joypad.sfc",
+        frames: 3,
+        inputs: &[(0, PRESS_B)],
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x80, 0x00, 0x80, 0x00, 0x01, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// JOY1 only has its final value once the automatic read is finished
+#[test]
+fn joypad_partial() {
+    TestRom {
+        name: "joypad_partial",
+        rom: "joypad_partial.sfc",
+        frames: 3,
+        inputs: &[(0, PRESS_B_R)],
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x00, 0x00, 0x01, 0x80, 0x10, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// R is the last button, followed by the four signature bits in JOY1L
+#[test]
+fn joypad_r() {
+    TestRom {
+        name: "joypad_r",
+        rom: "joypad.sfc",
+        frames: 3,
+        inputs: &[(0, PRESS_R)],
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x00, 0x10, 0x00, 0x10, 0x01, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Every controller on the multitap holds a different button
+#[test]
+fn multitap() {
+    TestRom {
+        name: "multitap",
+        rom: "multitap.sfc",
+        frames: 3,
+        multitap: &[PRESS_B, PRESS_Y, PRESS_A, PRESS_X],
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x80, 0x00, 0x40, 0x00, 0x00, 0x80, 0x00, 0x40, 0x02, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The Super Scope latches the counters when the beam passes the pixel it is aimed at
+#[test]
+fn super_scope() {
+    TestRom {
+        name: "super_scope",
+        rom: "super_scope.sfc",
+        frames: 3,
+        setup: Some(|snes| {
+            snes.set_input2(Some(Box::new(SuperScope::new(|| SuperScopeState {
+                x: 100,
+                y: 50,
+                trigger: true,
+                ..SuperScopeState::default()
+            }))))
+        }),
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[0x01, 0xFF, 0x40, 0x7A, 0x00, 0x33, 0x00, 0x55],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn runaway() {
+    TestRom {
+        name: "runaway",
+        rom: "runaway.sfc",
+        frames: 10,
+        instruction_budget: Some(1000),
+        pass: &[PassCondition::Stopped {
+            result: StepResult::BudgetExhausted,
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// The DMA to WMDATA must not trigger the watchpoint on it
+#[test]
+fn watchpoint_write() {
+    TestRom {
+        name: "watchpoint_write",
+        rom: "watchpoint.sfc",
+        frames: 2,
+        setup: Some(|snes| {
+            snes.cpu.debug.watchpoints.extend([
+                Watchpoint {
+                    start: 0x002180,
+                    end: 0x002180,
+                    read: false,
+                    write: true,
+                    value: None,
+                },
+                Watchpoint {
+                    start: 0x000010,
+                    end: 0x000010,
+                    read: false,
+                    write: true,
+                    value: None,
+                },
+            ])
+        }),
+        pass: &[PassCondition::Stopped {
+            result: StepResult::WatchpointHit(WatchpointHit {
+                addr: 0x000010,
+                value: 0x42,
+                kind: AccessKind::Write,
+            }),
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn watchpoint_read() {
+    TestRom {
+        name: "watchpoint_read",
+        rom: "watchpoint.sfc",
+        frames: 2,
+        setup: Some(|snes| {
+            snes.cpu.debug.watchpoints.push(Watchpoint {
+                start: 0x000020,
+                end: 0x00002F,
+                read: true,
+                write: false,
+                value: None,
+            })
+        }),
+        pass: &[PassCondition::Stopped {
+            result: StepResult::WatchpointHit(WatchpointHit {
+                addr: 0x000020,
+                value: 0xA5,
+                kind: AccessKind::Read,
+            }),
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn watchpoint_dma() {
+    TestRom {
+        name: "watchpoint_dma",
+        rom: "watchpoint.sfc",
+        frames: 2,
+        setup: Some(|snes| {
+            snes.cpu.debug.watch_dma = true;
+            snes.cpu.debug.watchpoints.push(Watchpoint {
+                start: 0x002180,
+                end: 0x002180,
+                read: false,
+                write: true,
+                value: None,
+            })
+        }),
+        pass: &[PassCondition::Stopped {
+            result: StepResult::WatchpointHit(WatchpointHit {
+                addr: 0x002180,
+                value: 0xA5,
+                kind: AccessKind::Write,
+            }),
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+// Holds B for 10 frames, which the ROM counts and shows as the backdrop color
+#[test]
+fn replay() {
+    TestRom {
+        name: "replay",
+        rom: "replay.sfc",
+        frames: 30,
+        inputs: &[(5, PRESS_B), (15, RELEASED)],
+        pass: &[
+            PassCondition::Memory {
+                addr: 0x7E0000,
+                value: 0x0A,
+            },
+            PassCondition::FrameHash {
+                hash: 0x88188FD290487124,
+            },
+        ],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}
+
+#[test]
+fn srtc() {
+    TestRom {
+        name: "srtc",
+        rom: "srtc.sfc",
+        frames: 80,
+        pass: &[PassCondition::MemoryRange {
+            addr: 0x7E0000,
+            bytes: &[
+                0x0F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x0A, 0x06,
+                0x0F, 0x55,
+            ],
+        }],
+        ..TestRom::DEFAULT
+    }
+    .assert_passes();
+}